@@ -1,9 +1,13 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use sha2::{Digest, Sha256};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process;
 
+mod state_dir;
+
+use state_dir::{parse_address, StateDirectory};
+
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
@@ -18,13 +22,39 @@ enum Commands {
         /// The path to the SynQ source file
         #[arg(short, long)]
         path: PathBuf,
+        /// Compile only this contract; required without --out-dir when the file defines several
+        #[arg(short, long)]
+        contract: Option<String>,
+        /// Write one artifact per contract to this directory: <Contract>.compiled.synq,
+        /// .abi.json, .srcmap and .metadata, next to the file's Solidity translation
+        #[arg(short, long)]
+        out_dir: Option<PathBuf>,
     },
-    /// Runs a compiled SynQ bytecode file
+    /// Runs a compiled SynQ bytecode file, or calls a deployed contract instance
     Run {
+        /// The path to the SynQ bytecode file or contract instance
+        #[arg(short, long)]
+        path: PathBuf,
+        /// The parameterless entry point to call; without it the call carries empty calldata,
+        /// which only a `receive` or `fallback` entry point accepts
+        #[arg(short, long)]
+        function: Option<String>,
+    },
+    /// Deploys a compiled SynQ bytecode file, running its constructor once
+    Deploy {
         /// The path to the SynQ bytecode file
         #[arg(short, long)]
         path: PathBuf,
+        /// ABI-encoded constructor arguments as hex (e.g. 0x0000...2a)
+        #[arg(short, long)]
+        args: Option<String>,
+        /// The contract instance file to write (defaults to <name>.instance)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
     },
+    /// Calls an entry point of a compiled contract against a local state directory, deploying
+    /// the contract there first if needed
+    Call(CallArgs),
     /// Verifies deterministic bytecode generation from source
     Verify {
         /// The path to the SynQ source file
@@ -36,20 +66,90 @@ enum Commands {
         /// Execute the verified bytecode after match validation
         #[arg(long, default_value_t = false)]
         run: bool,
+        /// ABI-encoded constructor arguments as hex, used when --run deploys a contract
+        #[arg(short, long)]
+        args: Option<String>,
+        /// The parameterless entry point --run calls, as for `run --function`
+        #[arg(short, long)]
+        function: Option<String>,
     },
+    /// Prints a compiled SynQ bytecode file as QuantumVM assembly (.qasm)
+    Disassemble {
+        /// The path to the SynQ bytecode file
+        #[arg(short, long)]
+        path: PathBuf,
+        /// Write the listing to this file instead of standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Assembles a QuantumVM assembly (.qasm) file into bytecode
+    Assemble {
+        /// The path to the .qasm file
+        #[arg(short, long)]
+        path: PathBuf,
+        /// The bytecode file to write (defaults to <path>.compiled.synq)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Args)]
+struct CallArgs {
+    /// The contract's bytecode, with its ABI next to it as <Contract>.abi.json (see
+    /// compile --out-dir)
+    #[arg(long)]
+    artifact: PathBuf,
+    /// The entry point to call
+    #[arg(short, long)]
+    function: String,
+    /// Arguments as a JSON array (e.g. '["0x11..", 100]')
+    #[arg(long, default_value = "[]")]
+    args: String,
+    /// The calling account, as 0x and 40 hex digits
+    #[arg(long)]
+    sender: String,
+    /// Gas limit of the call, and of the deployment when the call deploys the contract
+    #[arg(long, default_value_t = 10_000_000)]
+    gas: u64,
+    /// Directory holding the accounts and deployed contracts; created if missing
+    #[arg(long, default_value = ".synq-state")]
+    state: PathBuf,
+    /// Constructor arguments as a JSON array, used when the contract is deployed
+    #[arg(long, default_value = "[]")]
+    constructor_args: String,
+    /// Deploy a rebuilt artifact at a new address, leaving the old deployment and its state
+    #[arg(long, default_value_t = false)]
+    redeploy: bool,
+    /// Genesis file whose gas schedule the call is charged by (docs/Gas-Model.md §7); the
+    /// built-in schedule otherwise
+    #[arg(long)]
+    genesis: Option<PathBuf>,
+    /// Also write the execution receipt (status, return value, logs, gas breakdown) as JSON
+    #[arg(long)]
+    receipt: Option<PathBuf>,
 }
 
 fn main() {
     let cli = Cli::parse();
 
     let result = match &cli.command {
-        Commands::Compile { path } => compile(path),
-        Commands::Run { path } => run(path),
+        Commands::Compile {
+            path,
+            contract,
+            out_dir,
+        } => compile(path, contract.as_deref(), out_dir.as_deref()),
+        Commands::Run { path, function } => run(path, function.as_deref()),
+        Commands::Deploy { path, args, output } => deploy(path, args.as_deref(), output.as_deref()),
+        Commands::Call(args) => call(args),
         Commands::Verify {
             source,
             bytecode,
             run,
-        } => verify(source, bytecode, *run),
+            args,
+            function,
+        } => verify(source, bytecode, *run, args.as_deref(), function.as_deref()),
+        Commands::Disassemble { path, output } => disassemble(path, output.as_deref()),
+        Commands::Assemble { path, output } => assemble(path, output.as_deref()),
     };
 
     if let Err(err) = result {
@@ -58,14 +158,55 @@ fn main() {
     }
 }
 
-fn compile(path: &Path) -> Result<(), String> {
+fn compile(path: &Path, contract: Option<&str>, out_dir: Option<&Path>) -> Result<(), String> {
     println!("Compiling: {}", path.display());
-    let (bytecode, solidity_code) = compile_source(path)?;
+    let (artifacts, solidity_code) = compile_contracts(path)?;
+    let names = artifacts
+        .iter()
+        .map(|artifact| format!("`{}`", artifact.name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let selected: Vec<&compiler::ContractArtifact> = match contract {
+        Some(name) => vec![artifacts
+            .iter()
+            .find(|artifact| artifact.name == name)
+            .ok_or_else(|| {
+                format!(
+                    "{} has no contract `{name}` (it defines {names})",
+                    path.display()
+                )
+            })?],
+        None => artifacts.iter().collect(),
+    };
+    if selected.is_empty() {
+        return Err(format!("{} defines no contracts", path.display()));
+    }
 
-    // Generate .synq bytecode
-    let (synq_path, sol_path) = derive_output_paths(path);
-    fs::write(&synq_path, &bytecode).map_err(|e| format!("Failed to write .synq file: {e}"))?;
-    println!("✓ Generated: {}", synq_path.display());
+    let sol_path = match out_dir {
+        Some(dir) => {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+            for artifact in selected {
+                write_artifact(dir, artifact)?;
+            }
+            let (_, sol_path) = derive_output_paths(path);
+            dir.join(sol_path.file_name().unwrap_or_default())
+        }
+        None => {
+            let [artifact] = selected.as_slice() else {
+                return Err(format!(
+                    "{} defines {names}; choose one with --contract or write them all with --out-dir",
+                    path.display()
+                ));
+            };
+            // Generate .synq bytecode
+            let (synq_path, sol_path) = derive_output_paths(path);
+            fs::write(&synq_path, &artifact.bytecode)
+                .map_err(|e| format!("Failed to write .synq file: {e}"))?;
+            println!("✓ Generated: {}", synq_path.display());
+            sol_path
+        }
+    };
 
     // Generate .sol Solidity code
     fs::write(&sol_path, &solidity_code).map_err(|e| format!("Failed to write .sol file: {e}"))?;
@@ -75,15 +216,239 @@ fn compile(path: &Path) -> Result<(), String> {
     Ok(())
 }
 
-fn run(path: &Path) -> Result<(), String> {
+/// Writes the bytecode, ABI, runtime source map and metadata of `artifact` to `dir`.
+fn write_artifact(dir: &Path, artifact: &compiler::ContractArtifact) -> Result<(), String> {
+    let source_map = &artifact.source_map;
+    let mut srcmap = String::new();
+    if !source_map.file.is_empty() {
+        srcmap.push_str(&format!("file {}\n", source_map.file));
+    }
+    for entry in &source_map.entries {
+        srcmap.push_str(&format!(
+            "{} {}:{} {}\n",
+            entry.offset, entry.line, entry.column, source_map.functions[entry.function as usize]
+        ));
+    }
+    let metadata = &artifact.metadata;
+    let [major, minor, patch] = metadata.compiler_version;
+    let metadata = format!(
+        "contract {}\ncompiler {major}.{minor}.{patch}\nsource {}\nsource_hash 0x{}\nbytecode_sha256 0x{}\n",
+        artifact.name,
+        metadata.source_file,
+        hex(&metadata.source_hash),
+        sha256_hex(&artifact.bytecode)
+    );
+
+    let abi = artifact.abi.to_json();
+    for (extension, contents) in [
+        ("compiled.synq", artifact.bytecode.as_slice()),
+        ("abi.json", abi.as_bytes()),
+        ("srcmap", srcmap.as_bytes()),
+        ("metadata", metadata.as_bytes()),
+    ] {
+        let path = dir.join(format!("{}.{extension}", artifact.name));
+        fs::write(&path, contents)
+            .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+        println!("✓ Generated: {}", path.display());
+    }
+    Ok(())
+}
+
+fn run(path: &Path, function: Option<&str>) -> Result<(), String> {
     println!("Running: {}", path.display());
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read bytecode file: {e}"))?;
+    let source_dir = path.parent().unwrap_or(Path::new("."));
+    if quantumvm::ContractInstance::is_instance(&bytes) {
+        // Calling a deployed instance persists the storage it leaves behind.
+        let mut instance = quantumvm::ContractInstance::decode(&bytes)
+            .map_err(|e| format!("Failed to load contract instance: {e}"))?;
+        let calldata = entry_calldata(&instance.code, function)?;
+        call_instance(&mut instance, &calldata, source_dir)?;
+        fs::write(path, instance.encode())
+            .map_err(|e| format!("Failed to write contract instance: {e}"))?;
+    } else {
+        let calldata = entry_calldata(&bytes, function)?;
+        execute_bytecode(&bytes, &[], &calldata, source_dir)?;
+    }
+    println!("Execution finished successfully");
+    Ok(())
+}
+
+fn deploy(path: &Path, args: Option<&str>, output: Option<&Path>) -> Result<(), String> {
+    println!("Deploying: {}", path.display());
     let bytecode = fs::read(path).map_err(|e| format!("Failed to read bytecode file: {e}"))?;
-    execute_bytecode(&bytecode)?;
+    let args = parse_hex(args.unwrap_or_default())
+        .map_err(|e| format!("Invalid constructor arguments: {e}"))?;
+    let instance = deploy_bytecode(&bytecode, &args, path.parent().unwrap_or(Path::new(".")))?;
+    let output = output
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| derive_instance_path(path));
+    fs::write(&output, instance.encode())
+        .map_err(|e| format!("Failed to write contract instance: {e}"))?;
+    println!(
+        "✓ Deployed: {} ({} storage slots)",
+        output.display(),
+        instance.storage.len()
+    );
+    Ok(())
+}
+
+fn call(args: &CallArgs) -> Result<(), String> {
+    let CallArgs {
+        artifact,
+        function,
+        sender,
+        gas,
+        state: state_dir,
+        constructor_args,
+        redeploy,
+        receipt: receipt_path,
+        genesis,
+        ..
+    } = args;
+    let function = function.as_str();
+    let bytecode = fs::read(artifact).map_err(|e| format!("Failed to read bytecode file: {e}"))?;
+    let (contract, abi_path) = derive_abi_path(artifact);
+    let abi = fs::read_to_string(&abi_path)
+        .map_err(|e| format!("Failed to read ABI {}: {e}", abi_path.display()))
+        .and_then(|json| compiler::ContractAbi::from_json(&json))?;
+    let entry = abi.function(function).ok_or_else(|| {
+        let names: Vec<String> = abi.functions().map(|f| format!("`{}`", f.name)).collect();
+        format!(
+            "{contract} has no entry point `{function}` (it has {})",
+            names.join(", ")
+        )
+    })?;
+    let sender = parse_address(sender)?;
+    let schedule = match genesis {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read genesis file {}: {e}", path.display()))
+            .and_then(|json| {
+                quantumvm::GasSchedule::from_genesis_json(&json).map_err(|e| e.to_string())
+            })?,
+        None => quantumvm::GasSchedule::default(),
+    };
+    let calldata = entry.encode_call(&parse_json(&args.args)?)?;
+    let source_dir = artifact.parent().unwrap_or(Path::new("."));
+
+    let backend = StateDirectory::open(state_dir)?;
+    let deployed = backend.deployment(&contract)?;
+    let mut state = quantumvm::WorldState::new(backend);
+
+    // A deployment of an older build of the artifact is replaced only when asked to, since the
+    // new one starts at a new address with fresh state.
+    let runtime = quantumvm::Header::parse(&bytecode)
+        .map(|header| header.section_bytes(&bytecode, quantumvm::SectionKind::Runtime))
+        .map_err(|e| format!("Failed to load bytecode: {e}"))?;
+    let address = match deployed {
+        Some(address) if state.code(&address) == runtime => address,
+        Some(address) if !redeploy => {
+            return Err(format!(
+                "{contract} at 0x{} was deployed from a different build of {}; pass --redeploy \
+                 to deploy this build at a new address",
+                hex(&address),
+                artifact.display()
+            ))
+        }
+        deployed => {
+            if let Some(old) = deployed {
+                eprintln!(
+                    "Warning: redeploying {contract}; the deployment at 0x{} and its state are \
+                     left behind",
+                    hex(&old)
+                );
+            }
+            let args = match abi.constructor() {
+                Some(constructor) => constructor.encode_args(&parse_json(constructor_args)?)?,
+                None => Vec::new(),
+            };
+            let mut vm = quantumvm::QuantumVM::with_schedule(*gas, schedule.clone());
+            let result = vm.deploy_contract(&mut state, &sender, &bytecode, &args);
+            print_output(vm.output());
+            let address = result.map_err(|e| {
+                format!("Deployment failed: {}", execution_error(&vm, e, source_dir))
+            })?;
+            state
+                .commit()
+                .map_err(|e| format!("Failed to save state: {e}"))?;
+            state.backend().record_deployment(&contract, &address)?;
+            println!("✓ Deployed {contract} at 0x{}", hex(&address));
+            address
+        }
+    };
+
+    println!("Calling {contract}.{function}");
+    let mut vm = quantumvm::QuantumVM::with_schedule(*gas, schedule);
+    let result = vm.call_contract(&mut state, &sender, &address, &calldata, 0);
+    let receipt = vm.receipt(&result);
+    print_output(&receipt.output);
+    println!("Gas used: {}", receipt.gas_used);
+    println!("PQ gas used: {}", receipt.pqc_gas_used);
+    if let Some(path) = receipt_path {
+        fs::write(path, receipt.to_json())
+            .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+        println!("✓ Receipt: {}", path.display());
+    }
+    result.map_err(|e| execution_error(&vm, e, source_dir))?;
+
+    // The call succeeded, so its state is saved even if its return value is missing.
+    let logs = state
+        .commit()
+        .map_err(|e| format!("Failed to save state: {e}"))?;
+    if let Some(output) = entry.outputs.first() {
+        let value = receipt
+            .return_data
+            .last()
+            .ok_or_else(|| format!("{contract}.{function} returned no value"))?;
+        println!("Returned: {}", describe_value(output, value));
+    }
+    for log in logs {
+        let topic = format!("0x{:08x}", log.topic);
+        let event = abi.entries.iter().find_map(|entry| match entry {
+            compiler::AbiEntry::Event(event) if event.topic == topic => Some(event),
+            _ => None,
+        });
+        let line = match event {
+            Some(event) => {
+                let fields: Vec<String> = event
+                    .inputs
+                    .iter()
+                    .zip(&log.data)
+                    .map(|(input, value)| {
+                        format!("{}={}", input.name, describe_value(input, value))
+                    })
+                    .collect();
+                format!("{}({})", event.name, fields.join(", "))
+            }
+            None => format!("{topic} {:?}", log.data),
+        };
+        println!("Log: {line}");
+    }
     println!("Execution finished successfully");
     Ok(())
 }
 
-fn verify(source_path: &Path, bytecode_path: &Path, run_after_verify: bool) -> Result<(), String> {
+/// Renders a VM value as `param`'s ABI type, or as the raw value when it does not fit it.
+fn describe_value(param: &compiler::AbiParam, value: &quantumvm::Value) -> String {
+    param
+        .abi_type()
+        .ok()
+        .and_then(|ty| quantumvm::AbiValue::from_value(&ty, value).ok())
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| format!("{value:?}"))
+}
+
+fn parse_json(text: &str) -> Result<serde_json::Value, String> {
+    serde_json::from_str(text).map_err(|e| format!("`{text}` is not valid JSON: {e}"))
+}
+
+fn verify(
+    source_path: &Path,
+    bytecode_path: &Path,
+    run_after_verify: bool,
+    args: Option<&str>,
+    function: Option<&str>,
+) -> Result<(), String> {
     println!(
         "Verifying bytecode determinism:\n  source: {}\n  bytecode: {}",
         source_path.display(),
@@ -116,31 +481,90 @@ fn verify(source_path: &Path, bytecode_path: &Path, run_after_verify: bool) -> R
     );
 
     if run_after_verify {
-        execute_bytecode(&provided)?;
+        let args = parse_hex(args.unwrap_or_default())
+            .map_err(|e| format!("Invalid constructor arguments: {e}"))?;
+        let calldata = entry_calldata(&provided, function)?;
+        execute_bytecode(
+            &provided,
+            &args,
+            &calldata,
+            source_path.parent().unwrap_or(Path::new(".")),
+        )?;
         println!("Execution finished successfully");
     }
 
     Ok(())
 }
 
+fn disassemble(path: &Path, output: Option<&Path>) -> Result<(), String> {
+    let bytecode = fs::read(path).map_err(|e| format!("Failed to read bytecode file: {e}"))?;
+    let listing = quantumvm::disassembler::disassemble(&bytecode)
+        .map_err(|e| format!("Failed to disassemble {}: {e}", path.display()))?;
+    match output {
+        Some(output) => {
+            fs::write(output, listing).map_err(|e| format!("Failed to write .qasm file: {e}"))?;
+            println!("✓ Generated: {}", output.display());
+        }
+        None => print!("{listing}"),
+    }
+    Ok(())
+}
+
+fn assemble(path: &Path, output: Option<&Path>) -> Result<(), String> {
+    println!("Assembling: {}", path.display());
+    let source = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read assembly file {}: {e}", path.display()))?;
+    let bytecode = quantumvm::Assembler::from_qasm(&source)
+        .and_then(quantumvm::Assembler::build)
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+    let output = output
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| path.with_extension("compiled.synq"));
+    fs::write(&output, &bytecode).map_err(|e| format!("Failed to write .synq file: {e}"))?;
+    println!("✓ Generated: {}", output.display());
+    Ok(())
+}
+
 fn compile_source(path: &Path) -> Result<(Vec<u8>, String), String> {
+    let (source, ast) = parse_source(path)?;
+    let bytecode = code_generator(path, &source)
+        .generate(&ast)
+        .map_err(|e| format!("Failed to generate bytecode: {e}"))?;
+    Ok((bytecode, generate_solidity(&ast)?))
+}
+
+/// One artifact per contract in `path`, plus the Solidity translation of the whole file.
+fn compile_contracts(path: &Path) -> Result<(Vec<compiler::ContractArtifact>, String), String> {
+    let (source, ast) = parse_source(path)?;
+    let artifacts = code_generator(path, &source)
+        .generate_contracts(&ast)
+        .map_err(|e| format!("Failed to generate bytecode: {e}"))?;
+    Ok((artifacts, generate_solidity(&ast)?))
+}
+
+fn parse_source(path: &Path) -> Result<(String, Vec<compiler::ast::SourceUnit>), String> {
     let source = fs::read_to_string(path)
         .map_err(|e| format!("Failed to read source file {}: {e}", path.display()))?;
     let (_version_req, ast) = compiler::parse(&source)
         .map_err(|e| format!("Failed to parse source file {}: {e}", path.display()))?;
     compiler::analyze(&ast).map_err(|errors| format_semantic_errors(path, &errors))?;
+    Ok((source, ast))
+}
 
-    let codegen = compiler::CodeGenerator::new();
-    let bytecode = codegen
-        .generate(&ast)
-        .map_err(|e| format!("Failed to generate bytecode: {e}"))?;
-
-    let sol_gen = compiler::SolidityGenerator::new();
-    let solidity_code = sol_gen
-        .generate(&ast)
-        .map_err(|e| format!("Failed to generate Solidity code: {e}"))?;
+fn code_generator(path: &Path, source: &str) -> compiler::CodeGenerator {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    compiler::CodeGenerator::new()
+        .with_source(source)
+        .with_source_file(&file_name)
+}
 
-    Ok((bytecode, solidity_code))
+fn generate_solidity(ast: &[compiler::ast::SourceUnit]) -> Result<String, String> {
+    compiler::SolidityGenerator::new()
+        .generate(ast)
+        .map_err(|e| format!("Failed to generate Solidity code: {e}"))
 }
 
 fn derive_output_paths(path: &Path) -> (PathBuf, PathBuf) {
@@ -152,19 +576,174 @@ fn derive_output_paths(path: &Path) -> (PathBuf, PathBuf) {
     (synq_path, sol_path)
 }
 
-fn execute_bytecode(bytecode: &[u8]) -> Result<(), String> {
+/// `X.compiled.synq` deploys to `X.instance`.
+fn derive_instance_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    match name.strip_suffix(".compiled.synq") {
+        Some(stem) => path.with_file_name(format!("{stem}.instance")),
+        None => path.with_extension("instance"),
+    }
+}
+
+/// `X.compiled.synq` is contract `X`, whose ABI is `X.abi.json`.
+fn derive_abi_path(path: &Path) -> (String, PathBuf) {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let contract = name
+        .strip_suffix(".compiled.synq")
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        });
+    let abi_path = path.with_file_name(format!("{contract}.abi.json"));
+    (contract, abi_path)
+}
+
+fn parse_hex(text: &str) -> Result<Vec<u8>, String> {
+    let digits = text.strip_prefix("0x").unwrap_or(text);
+    if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!("`{text}` is not hex"));
+    }
+    if !digits.len().is_multiple_of(2) {
+        return Err(format!("`{text}` has an odd number of hex digits"));
+    }
+    Ok((0..digits.len())
+        .step_by(2)
+        .map(|at| u8::from_str_radix(&digits[at..at + 2], 16).expect("hex digits"))
+        .collect())
+}
+
+/// Calldata that calls the parameterless entry point `function`, whose selector `bytecode`
+/// lists in its metadata section; empty calldata without one.
+fn entry_calldata(bytecode: &[u8], function: Option<&str>) -> Result<Vec<u8>, String> {
+    let Some(function) = function else {
+        return Ok(Vec::new());
+    };
+    let header =
+        quantumvm::Header::parse(bytecode).map_err(|e| format!("Failed to load bytecode: {e}"))?;
+    let metadata = header.section_bytes(bytecode, quantumvm::SectionKind::Metadata);
+    for line in String::from_utf8_lossy(metadata).lines() {
+        let Some((selector, signature)) = line.split_once(' ') else {
+            continue;
+        };
+        let signature = signature.split_once('.').map_or(signature, |(_, sig)| sig);
+        if signature.split_once('(').map(|(name, _)| name) != Some(function) {
+            continue;
+        }
+        if !signature.ends_with("()") {
+            return Err(format!(
+                "Entry point `{signature}` takes arguments; pass them with `call`"
+            ));
+        }
+        let selector = parse_hex(selector)
+            .map_err(|e| format!("Invalid selector `{selector}` in metadata: {e}"))?;
+        return Ok(selector);
+    }
+    Err(format!("The bytecode has no entry point `{function}`"))
+}
+
+/// Runs `bytecode`: a deploy container is deployed with the constructor arguments `args` and
+/// its instance called once with `calldata`, anything else runs as is. On failure, quotes the
+/// failing source line when the source map names a file found in `source_dir`.
+fn execute_bytecode(
+    bytecode: &[u8],
+    args: &[u8],
+    calldata: &[u8],
+    source_dir: &Path,
+) -> Result<(), String> {
+    let header =
+        quantumvm::Header::parse(bytecode).map_err(|e| format!("Failed to load bytecode: {e}"))?;
+    if !header
+        .section_bytes(bytecode, quantumvm::SectionKind::Runtime)
+        .is_empty()
+    {
+        let mut instance = deploy_bytecode(bytecode, args, source_dir)?;
+        return call_instance(&mut instance, calldata, source_dir);
+    }
     let mut vm = quantumvm::QuantumVM::new();
-    vm.load_bytecode(&bytecode)
+    vm.load_bytecode(bytecode)
         .map_err(|e| format!("Failed to load bytecode: {e}"))?;
-    vm.execute()
-        .map_err(|e| format!("VM execution failed: {e}"))?;
-    Ok(())
+    let result = vm.execute();
+    print_output(vm.output());
+    result.map_err(|e| execution_error(&vm, e, source_dir))
+}
+
+fn deploy_bytecode(
+    bytecode: &[u8],
+    args: &[u8],
+    source_dir: &Path,
+) -> Result<quantumvm::ContractInstance, String> {
+    let mut vm = quantumvm::QuantumVM::new();
+    let result = vm.deploy(bytecode, args);
+    print_output(vm.output());
+    result.map_err(|e| format!("Deployment failed: {}", execution_error(&vm, e, source_dir)))
+}
+
+fn call_instance(
+    instance: &mut quantumvm::ContractInstance,
+    calldata: &[u8],
+    source_dir: &Path,
+) -> Result<(), String> {
+    let mut vm = quantumvm::QuantumVM::new();
+    let result = vm.call(instance, calldata);
+    print_output(vm.output());
+    result.map_err(|e| execution_error(&vm, e, source_dir))
+}
+
+// Values the program wrote with `PRINT`, one per line
+fn print_output(output: &[quantumvm::Value]) {
+    for value in output {
+        println!("{value:?}");
+    }
+}
+
+fn execution_error(
+    vm: &quantumvm::QuantumVM,
+    error: quantumvm::VMError,
+    source_dir: &Path,
+) -> String {
+    let excerpt = vm
+        .fault_location()
+        .and_then(|location| source_excerpt(source_dir, &location))
+        .unwrap_or_default();
+    format!("VM execution failed: {error}{excerpt}")
+}
+
+fn source_excerpt(source_dir: &Path, location: &quantumvm::SourceLocation) -> Option<String> {
+    if location.file.is_empty() {
+        return None;
+    }
+    let path = source_dir.join(&location.file);
+    let source = fs::read_to_string(&path).ok()?;
+    let text = source
+        .lines()
+        .nth((location.line as usize).checked_sub(1)?)?;
+    let gutter = " ".repeat(location.line.to_string().len());
+    let caret = " ".repeat((location.column as usize).saturating_sub(1));
+    Some(format!(
+        "\n{gutter}--> {}:{}:{} in {}\n{gutter} |\n{} | {text}\n{gutter} | {caret}^",
+        path.display(),
+        location.line,
+        location.column,
+        location.function,
+        location.line
+    ))
 }
 
 fn sha256_hex(bytes: &[u8]) -> String {
-    let digest = Sha256::digest(bytes);
-    let mut out = String::with_capacity(digest.len() * 2);
-    for byte in digest {
+    hex(&Sha256::digest(bytes))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
         out.push_str(&format!("{byte:02x}"));
     }
     out
@@ -0,0 +1,144 @@
+use crate::hex;
+use quantumvm::{Account, Address, ContractInstance, StateBackend, VMError};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// World state kept in a directory between CLI invocations. `accounts/<address>.account` holds
+// an account's nonce and balance (little-endian `u64`s) followed by its code and storage in the
+// contract instance encoding; `contracts/<Contract>.address` holds the `0x` address the last
+// `call` deployed that contract to.
+pub struct StateDirectory {
+    dir: PathBuf,
+    accounts: BTreeMap<Address, Account>,
+}
+
+impl StateDirectory {
+    /// Opens `dir`, creating it if needed, and loads every account in it.
+    pub fn open(dir: &Path) -> Result<Self, String> {
+        let accounts_dir = dir.join("accounts");
+        fs::create_dir_all(&accounts_dir)
+            .and_then(|()| fs::create_dir_all(dir.join("contracts")))
+            .map_err(|e| format!("Failed to create state directory {}: {e}", dir.display()))?;
+        let entries = fs::read_dir(&accounts_dir)
+            .map_err(|e| format!("Failed to read {}: {e}", accounts_dir.display()))?;
+        let mut accounts = BTreeMap::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|e| format!("Failed to read {}: {e}", accounts_dir.display()))?
+                .path();
+            let Some(address) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".account"))
+                .and_then(|name| parse_address(&format!("0x{name}")).ok())
+            else {
+                continue;
+            };
+            let bytes =
+                fs::read(&path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+            let account = decode_account(&bytes)
+                .map_err(|e| format!("Invalid account file {}: {e}", path.display()))?;
+            accounts.insert(address, account);
+        }
+        Ok(StateDirectory {
+            dir: dir.to_path_buf(),
+            accounts,
+        })
+    }
+
+    /// Address `contract` was last deployed to, if any.
+    pub fn deployment(&self, contract: &str) -> Result<Option<Address>, String> {
+        let path = self.deployment_path(contract);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let text = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        parse_address(text.trim())
+            .map(Some)
+            .map_err(|e| format!("Invalid deployment record {}: {e}", path.display()))
+    }
+
+    pub fn record_deployment(&self, contract: &str, address: &Address) -> Result<(), String> {
+        let path = self.deployment_path(contract);
+        fs::write(&path, format!("0x{}\n", hex(address)))
+            .map_err(|e| format!("Failed to write {}: {e}", path.display()))
+    }
+
+    fn deployment_path(&self, contract: &str) -> PathBuf {
+        self.dir
+            .join("contracts")
+            .join(format!("{contract}.address"))
+    }
+
+    fn account_path(&self, address: &Address) -> PathBuf {
+        self.dir
+            .join("accounts")
+            .join(format!("{}.account", hex(address)))
+    }
+}
+
+impl StateBackend for StateDirectory {
+    fn account(&self, address: &Address) -> Option<Account> {
+        self.accounts.get(address).cloned()
+    }
+
+    fn apply(&mut self, changes: BTreeMap<Address, Option<Account>>) -> Result<(), VMError> {
+        for (address, account) in changes {
+            let path = self.account_path(&address);
+            let written = match &account {
+                Some(account) => fs::write(&path, encode_account(account)),
+                None => fs::remove_file(&path).or_else(|e| match e.kind() {
+                    std::io::ErrorKind::NotFound => Ok(()),
+                    _ => Err(e),
+                }),
+            };
+            written.map_err(|e| {
+                VMError::RuntimeError(format!("Failed to write {}: {e}", path.display()))
+            })?;
+            match account {
+                Some(account) => self.accounts.insert(address, account),
+                None => self.accounts.remove(&address),
+            };
+        }
+        Ok(())
+    }
+}
+
+fn encode_account(account: &Account) -> Vec<u8> {
+    let mut bytes = account.nonce.to_le_bytes().to_vec();
+    bytes.extend_from_slice(&account.balance.to_le_bytes());
+    let instance = ContractInstance {
+        code: account.code.clone(),
+        storage: account.storage.clone(),
+    };
+    bytes.extend_from_slice(&instance.encode());
+    bytes
+}
+
+fn decode_account(bytes: &[u8]) -> Result<Account, VMError> {
+    let (Some(nonce), Some(balance), Some(instance)) =
+        (bytes.get(..8), bytes.get(8..16), bytes.get(16..))
+    else {
+        return Err(VMError::InvalidBytecode(
+            "Account file is truncated".to_string(),
+        ));
+    };
+    let instance = ContractInstance::decode(instance)?;
+    Ok(Account {
+        nonce: u64::from_le_bytes(nonce.try_into().expect("8 bytes")),
+        balance: u64::from_le_bytes(balance.try_into().expect("8 bytes")),
+        code: instance.code,
+        storage: instance.storage,
+    })
+}
+
+/// Reads an account address written as `0x` and 40 hex digits.
+pub fn parse_address(text: &str) -> Result<Address, String> {
+    crate::parse_hex(text)
+        .ok()
+        .filter(|_| text.starts_with("0x"))
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| format!("`{text}` is not an address (0x and 40 hex digits)"))
+}
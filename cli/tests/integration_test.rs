@@ -10,7 +10,7 @@ use tempfile::{tempdir, NamedTempFile};
 fn test_compile_and_run() {
     let contract = r#"
         contract MyContract {
-            function my_function() {}
+            @public function my_function() {}
         }
     "#;
 
@@ -26,19 +26,42 @@ fn test_compile_and_run() {
     assert!(bytecode_path.exists());
 
     let mut run_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
-    run_cmd.arg("run").arg("--path").arg(&bytecode_path);
+    run_cmd
+        .arg("run")
+        .arg("--path")
+        .arg(&bytecode_path)
+        .arg("--function")
+        .arg("my_function");
 
     run_cmd
         .assert()
         .success()
         .stdout(predicate::str::contains("Execution finished successfully"));
+
+    // Without a `receive` or `fallback` entry point, empty calldata reverts.
+    let mut run_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+    run_cmd.arg("run").arg("--path").arg(&bytecode_path);
+    run_cmd.assert().failure().stderr(predicate::str::contains(
+        "Reverted: No receive or fallback function accepts empty calldata",
+    ));
+
+    let mut run_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+    run_cmd
+        .arg("run")
+        .arg("--path")
+        .arg(&bytecode_path)
+        .arg("--function")
+        .arg("missing");
+    run_cmd.assert().failure().stderr(predicate::str::contains(
+        "The bytecode has no entry point `missing`",
+    ));
 }
 
 #[test]
 fn test_verify_accepts_matching_bytecode_and_executes() {
     let contract = r#"
         contract VerifyContract {
-            function noop() {}
+            @public function noop() {}
         }
     "#;
 
@@ -59,7 +82,9 @@ fn test_verify_accepts_matching_bytecode_and_executes() {
         .arg(file.path())
         .arg("--bytecode")
         .arg(&bytecode_path)
-        .arg("--run");
+        .arg("--run")
+        .arg("--function")
+        .arg("noop");
 
     verify_cmd
         .assert()
@@ -165,6 +190,139 @@ fn test_compile_all_documented_examples() {
     }
 }
 
+#[test]
+fn test_verify_deploys_all_documented_examples() {
+    use quantumvm::{AbiType, AbiValue, PqcType};
+
+    let repo_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+    let key_type = AbiType::Pqc(PqcType::MLDSAPublicKey);
+    let key = AbiValue::Bytes(vec![7; 1952]);
+    let uint = AbiType::UInt(256);
+    let examples = [
+        (
+            "1-ERC20-Token",
+            vec![
+                AbiType::String,
+                AbiType::String,
+                AbiType::UInt(8),
+                uint.clone(),
+                key_type.clone(),
+            ],
+            vec![
+                AbiValue::String("Quantum".into()),
+                AbiValue::String("QTM".into()),
+                AbiValue::uint(18),
+                AbiValue::uint(1_000_000),
+                key.clone(),
+            ],
+        ),
+        (
+            "2-MultiSig-Wallet",
+            vec![
+                AbiType::Array(Box::new(AbiType::Address), None),
+                AbiType::Array(Box::new(key_type.clone()), None),
+                uint.clone(),
+            ],
+            vec![
+                AbiValue::Array(vec![AbiValue::Address([1; 20]), AbiValue::Address([2; 20])]),
+                AbiValue::Array(vec![key.clone(), key.clone()]),
+                AbiValue::uint(2),
+            ],
+        ),
+        (
+            "3-DAO-Voting",
+            vec![
+                AbiType::Address,
+                uint.clone(),
+                uint.clone(),
+                uint.clone(),
+                key_type.clone(),
+            ],
+            vec![
+                AbiValue::Address([3; 20]),
+                AbiValue::uint(100),
+                AbiValue::uint(50),
+                AbiValue::uint(10),
+                key.clone(),
+            ],
+        ),
+        (
+            "4-NFT-Contract",
+            vec![
+                AbiType::String,
+                AbiType::String,
+                uint.clone(),
+                key_type.clone(),
+            ],
+            vec![
+                AbiValue::String("Quantum Art".into()),
+                AbiValue::String("QART".into()),
+                AbiValue::uint(1_000),
+                key.clone(),
+            ],
+        ),
+        (
+            "5-Escrow-Contract",
+            vec![key_type.clone()],
+            vec![key.clone()],
+        ),
+        (
+            "6-Staking-Contract",
+            vec![
+                AbiType::Address,
+                AbiType::Address,
+                uint.clone(),
+                uint.clone(),
+                uint.clone(),
+                key_type,
+            ],
+            vec![
+                AbiValue::Address([4; 20]),
+                AbiValue::Address([5; 20]),
+                AbiValue::uint(10),
+                AbiValue::uint(60),
+                AbiValue::uint(3_600),
+                key,
+            ],
+        ),
+    ];
+
+    for (name, types, values) in examples {
+        let source_path = repo_root.join(format!("docs/examples/{name}.synq"));
+        let compiled_path = source_path.with_extension("compiled.synq");
+        let args = quantumvm::abi::encode(&types, &values).unwrap();
+        let args: String = args.iter().map(|byte| format!("{byte:02x}")).collect();
+
+        let mut compile_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+        compile_cmd.arg("compile").arg("--path").arg(&source_path);
+        compile_cmd.assert().success();
+
+        let mut verify_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+        verify_cmd
+            .arg("verify")
+            .arg("--source")
+            .arg(&source_path)
+            .arg("--bytecode")
+            .arg(&compiled_path)
+            .arg("--args")
+            .arg(format!("0x{args}"))
+            .arg("--run");
+        // `--run` then calls the instance with empty calldata, which the entry points may
+        // reject; only loading and deploying are checked here.
+        let output = verify_cmd.output().unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stdout.contains("Verification succeeded"),
+            "{name} does not match its compiled bytecode: {stderr}"
+        );
+        assert!(
+            !stderr.contains("Failed to load bytecode") && !stderr.contains("Deployment failed"),
+            "{name} failed to load or deploy: {stderr}"
+        );
+    }
+}
+
 #[test]
 fn test_compile_fails_on_semantic_errors() {
     let invalid_contract = r#"
@@ -185,3 +343,425 @@ fn test_compile_fails_on_semantic_errors() {
         .failure()
         .stderr(predicate::str::contains("Semantic analysis failed"));
 }
+
+#[test]
+fn test_disassemble_and_assemble_round_trip_examples() {
+    let repo_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+    let dir = tempdir().unwrap();
+
+    for name in ["1-ERC20-Token", "4-NFT-Contract", "5-Escrow-Contract"] {
+        let source_path = dir.path().join(format!("{name}.synq"));
+        fs::copy(
+            repo_root.join(format!("docs/examples/{name}.synq")),
+            &source_path,
+        )
+        .unwrap();
+        let mut compile_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+        compile_cmd.arg("compile").arg("--path").arg(&source_path);
+        compile_cmd.assert().success();
+
+        let compiled_path = source_path.with_extension("compiled.synq");
+        let qasm_path = source_path.with_extension("qasm");
+        let mut disassemble_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+        disassemble_cmd
+            .arg("disassemble")
+            .arg("--path")
+            .arg(&compiled_path)
+            .arg("--output")
+            .arg(&qasm_path);
+        disassemble_cmd.assert().success();
+        let listing = fs::read_to_string(&qasm_path).unwrap();
+        assert!(listing.contains(".source_hash 0x"), "{listing}");
+        assert!(listing.contains("jumpif L"), "{listing}");
+
+        let reassembled_path = dir.path().join(format!("{name}.reassembled.synq"));
+        let mut assemble_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+        assemble_cmd
+            .arg("assemble")
+            .arg("--path")
+            .arg(&qasm_path)
+            .arg("--output")
+            .arg(&reassembled_path);
+        assemble_cmd.assert().success();
+        assert_eq!(
+            fs::read(&reassembled_path).unwrap(),
+            fs::read(&compiled_path).unwrap(),
+            "{name} should reassemble to identical bytes"
+        );
+    }
+}
+
+#[test]
+fn test_assemble_reports_line_of_undefined_label() {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "start:\n    push 1\n    jumpif missing\n").unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+    cmd.arg("assemble").arg("--path").arg(file.path());
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "line 3: undefined label `missing`",
+    ));
+}
+
+#[test]
+fn test_run_reports_source_line_and_function_of_a_runtime_error() {
+    let dir = tempdir().unwrap();
+    let source = dir.path().join("Ratio.synq");
+    fs::write(
+        &source,
+        "contract Ratio {\n    @public function run() -> Int64 {\n        Int64 parts = 0;\n        return 10 / parts;\n    }\n}\n",
+    )
+    .unwrap();
+
+    let mut compile_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+    compile_cmd.arg("compile").arg("--path").arg(&source);
+    compile_cmd.assert().success();
+
+    let mut run_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+    run_cmd
+        .arg("run")
+        .arg("--path")
+        .arg(dir.path().join("Ratio.compiled.synq"))
+        .arg("--function")
+        .arg("run");
+    run_cmd
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("(Ratio.synq:4:9 in Ratio.run)"))
+        .stderr(predicate::str::contains("4 |         return 10 / parts;"));
+}
+
+#[test]
+fn test_deploy_with_arguments_then_run_persists_storage() {
+    let dir = tempdir().unwrap();
+    let source = dir.path().join("Countdown.synq");
+    fs::write(
+        &source,
+        "contract Countdown {\n    Int64 left;\n\n    constructor(Int64 start) {\n        left = start;\n    }\n\n    @public function tick() -> Int64 {\n        left = left - 1;\n        return 10 / left;\n    }\n}\n",
+    )
+    .unwrap();
+
+    let mut compile_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+    compile_cmd.arg("compile").arg("--path").arg(&source);
+    compile_cmd.assert().success();
+
+    let mut deploy_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+    deploy_cmd
+        .arg("deploy")
+        .arg("--path")
+        .arg(dir.path().join("Countdown.compiled.synq"))
+        .arg("--args")
+        .arg(format!("0x{}03", "00".repeat(31)));
+    deploy_cmd
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Countdown.instance (1 storage slots)",
+        ));
+
+    // `left` goes 3 -> 2 -> 1 -> 0 across runs; the third divides by zero.
+    let instance = dir.path().join("Countdown.instance");
+    for _ in 0..2 {
+        let mut run_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+        run_cmd
+            .arg("run")
+            .arg("--path")
+            .arg(&instance)
+            .arg("--function")
+            .arg("tick");
+        run_cmd.assert().success();
+    }
+    let mut run_cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+    run_cmd
+        .arg("run")
+        .arg("--path")
+        .arg(&instance)
+        .arg("--function")
+        .arg("tick");
+    run_cmd.assert().failure().stderr(predicate::str::contains(
+        "(Countdown.synq:10:9 in Countdown.tick)",
+    ));
+
+    let mut bad_args = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+    bad_args
+        .arg("deploy")
+        .arg("--path")
+        .arg(dir.path().join("Countdown.compiled.synq"))
+        .arg("--args")
+        .arg("0x123");
+    bad_args.assert().failure().stderr(predicate::str::contains(
+        "Invalid constructor arguments: `0x123` has an odd number of hex digits",
+    ));
+}
+
+#[test]
+fn test_compile_writes_one_artifact_per_contract() {
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("pair.synq");
+    fs::write(
+        &source_path,
+        r#"
+        contract Token {
+            UInt256 supply;
+            @public function mint() { supply = supply + 1; }
+        }
+        contract Vault {
+            UInt256 locked;
+            @public function lock() { locked = locked + 1; }
+        }
+    "#,
+    )
+    .unwrap();
+
+    // Without an output directory only one contract can take the file's artifact name.
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+    cmd.arg("compile").arg("--path").arg(&source_path);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "defines `Token`, `Vault`; choose one with --contract",
+    ));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+    cmd.arg("compile")
+        .arg("--path")
+        .arg(&source_path)
+        .arg("--contract")
+        .arg("Vault");
+    cmd.assert().success();
+    assert!(dir.path().join("pair.compiled.synq").exists());
+
+    let out_dir = dir.path().join("out");
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+    cmd.arg("compile")
+        .arg("--path")
+        .arg(&source_path)
+        .arg("--out-dir")
+        .arg(&out_dir);
+    cmd.assert().success();
+    for name in ["Token", "Vault"] {
+        for extension in ["compiled.synq", "abi.json", "srcmap", "metadata"] {
+            let path = out_dir.join(format!("{name}.{extension}"));
+            assert!(path.exists(), "Expected artifact at {}", path.display());
+        }
+    }
+    assert!(out_dir.join("pair.sol").exists());
+    let abi = fs::read_to_string(out_dir.join("Vault.abi.json")).unwrap();
+    assert!(abi.contains("\"name\": \"lock\""), "{abi}");
+    assert!(!abi.contains("mint"), "{abi}");
+    let metadata = fs::read_to_string(out_dir.join("Vault.metadata")).unwrap();
+    assert!(
+        metadata.starts_with("contract Vault\ncompiler "),
+        "{metadata}"
+    );
+    assert!(metadata.contains("\nsource pair.synq\n"), "{metadata}");
+    let srcmap = fs::read_to_string(out_dir.join("Vault.srcmap")).unwrap();
+    assert!(srcmap.starts_with("file pair.synq\n"), "{srcmap}");
+    assert!(srcmap.contains(" Vault.lock\n"), "{srcmap}");
+
+    // The deployed artifact runs on its own.
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+    cmd.arg("run")
+        .arg("--path")
+        .arg(out_dir.join("Token.compiled.synq"))
+        .arg("--function")
+        .arg("mint");
+    cmd.assert().success();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+    cmd.arg("compile")
+        .arg("--path")
+        .arg(&source_path)
+        .arg("--contract")
+        .arg("Ledger");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "has no contract `Ledger` (it defines `Token`, `Vault`)",
+    ));
+}
+
+#[test]
+fn test_call_encodes_arguments_and_persists_state_between_calls() {
+    let dir = tempdir().unwrap();
+    let source_path = dir.path().join("tally.synq");
+    fs::write(
+        &source_path,
+        r#"
+        contract Tally {
+            Int64 total;
+            Address last;
+
+            event Added(Address indexed from, Int64 amount);
+
+            constructor(Int64 start) {
+                total = start;
+            }
+
+            function add(Address from, Int64 amount) public -> Int64 {
+                require(amount > 0, "Nothing to add");
+                total = total + amount;
+                last = from;
+                emit Added(from, amount);
+                return total;
+            }
+        }
+    "#,
+    )
+    .unwrap();
+    let out_dir = dir.path().join("out");
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+    cmd.arg("compile")
+        .arg("--path")
+        .arg(&source_path)
+        .arg("--out-dir")
+        .arg(&out_dir);
+    cmd.assert().success();
+
+    let state = dir.path().join("state");
+    let sender = format!("0x{}", "ab".repeat(20));
+    let from = format!("0x{}", "11".repeat(20));
+    let call = |amount: i64| {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+        cmd.arg("call")
+            .arg("--artifact")
+            .arg(out_dir.join("Tally.compiled.synq"))
+            .arg("--function")
+            .arg("add")
+            .arg("--args")
+            .arg(format!(r#"["{from}", {amount}]"#))
+            .arg("--sender")
+            .arg(&sender)
+            .arg("--state")
+            .arg(&state)
+            .arg("--constructor-args")
+            .arg("[100]");
+        cmd
+    };
+
+    // The gas limit applies to the deployment too; a failed deployment records nothing.
+    call(5)
+        .arg("--gas")
+        .arg("500")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Deployment failed: VM execution failed: Out of gas",
+        ));
+    assert!(!state.join("contracts/Tally.address").exists());
+
+    // The first call deploys the contract into the state directory; the second reuses it.
+    call(5)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("✓ Deployed Tally at 0x"))
+        .stdout(predicate::str::contains("Returned: 105"))
+        .stdout(predicate::str::contains(format!(
+            "Log: Added(from={from}, amount=5)"
+        )))
+        .stdout(predicate::str::contains("Gas used: "))
+        .stdout(predicate::str::contains("PQ gas used: 0"));
+    call(7)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deployed").not())
+        .stdout(predicate::str::contains("Returned: 112"));
+    assert!(state.join("contracts/Tally.address").exists());
+
+    // A rebuilt artifact is not silently deployed at a new address.
+    fs::write(
+        &source_path,
+        fs::read_to_string(&source_path)
+            .unwrap()
+            .replace("total = start;", "total = start + 1;"),
+    )
+    .unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+    cmd.arg("compile")
+        .arg("--path")
+        .arg(&source_path)
+        .arg("--out-dir")
+        .arg(&out_dir);
+    cmd.assert().success();
+    call(1).assert().failure().stderr(predicate::str::contains(
+        "was deployed from a different build of",
+    ));
+    call(1)
+        .arg("--redeploy")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Warning: redeploying Tally"))
+        .stdout(predicate::str::contains("✓ Deployed Tally at 0x"))
+        .stdout(predicate::str::contains("Returned: 102"));
+    call(5)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Returned: 107"));
+
+    // A failing call reports the revert and leaves the state as it was; its receipt is still
+    // written.
+    let receipt = dir.path().join("receipt.json");
+    call(0)
+        .arg("--receipt")
+        .arg(&receipt)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Nothing to add"));
+    let json = fs::read_to_string(&receipt).unwrap();
+    assert!(json.contains(r#""status": "reverted""#), "{json}");
+    assert!(json.contains(r#""logs": []"#), "{json}");
+    call(1)
+        .arg("--receipt")
+        .arg(&receipt)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Returned: 108"))
+        .stdout(predicate::str::contains("✓ Receipt: "));
+    let json = fs::read_to_string(&receipt).unwrap();
+    assert!(json.contains(r#""status": "success""#), "{json}");
+    assert!(json.contains(r#""revert_data": null"#), "{json}");
+    assert!(json.contains(r#""storage": "#), "{json}");
+
+    // A genesis file changes what the call is charged.
+    let genesis = dir.path().join("genesis.json");
+    fs::write(&genesis, r#"{"opcode_costs": {"sstore": 20000000}}"#).unwrap();
+    call(1)
+        .arg("--genesis")
+        .arg(&genesis)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Out of gas"));
+    fs::write(&genesis, r#"{"pqc_costs": {"verify_rsa": 1}}"#).unwrap();
+    call(1)
+        .arg("--genesis")
+        .arg(&genesis)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown PQC cost `verify_rsa`"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+    cmd.arg("call")
+        .arg("--artifact")
+        .arg(out_dir.join("Tally.compiled.synq"))
+        .arg("--function")
+        .arg("add")
+        .arg("--args")
+        .arg(r#"["0x12", 1]"#)
+        .arg("--sender")
+        .arg(&sender)
+        .arg("--state")
+        .arg(&state);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Argument `from`: `\"0x12\"` is not a valid Address",
+    ));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("cli"));
+    cmd.arg("call")
+        .arg("--artifact")
+        .arg(out_dir.join("Tally.compiled.synq"))
+        .arg("--function")
+        .arg("remove")
+        .arg("--sender")
+        .arg(&sender)
+        .arg("--state")
+        .arg(&state);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Tally has no entry point `remove` (it has `add`)",
+    ));
+}
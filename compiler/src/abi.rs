@@ -0,0 +1,404 @@
+//! JSON ABI for SynQ contracts
+//! Describes a contract's external interface: its constructor, entry points, events and
+//! custom errors, in the shape of a Solidity ABI.
+//!
+//! Types keep their SynQ names (`UInt256`, `Address`, `MLDSAPublicKey`, ...), so PQC keys and
+//! signatures stay distinguishable from plain `Bytes`. Structs become `tuple` parameters whose
+//! `components` list the fields.
+
+use crate::ast::*;
+use crate::codegen::{
+    canonical_type_name, error_selector, event_topic, function_selector, function_signature,
+};
+use quantumvm::abi::{encode, encode_call};
+use quantumvm::{AbiType, AbiValue};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+use std::collections::HashMap;
+
+/// A contract's ABI: one entry per constructor, entry point, event and error.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ContractAbi {
+    pub entries: Vec<AbiEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum AbiEntry {
+    Constructor(AbiConstructor),
+    Function(AbiFunction),
+    Event(AbiEvent),
+    Error(AbiError),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AbiConstructor {
+    pub inputs: Vec<AbiParam>,
+    /// Always `nonpayable`: deploys carry no value.
+    #[serde(rename = "stateMutability")]
+    pub state_mutability: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AbiFunction {
+    pub name: String,
+    pub inputs: Vec<AbiParam>,
+    pub outputs: Vec<AbiParam>,
+    /// `pure`, `view`, `nonpayable` or `payable`.
+    #[serde(rename = "stateMutability")]
+    pub state_mutability: String,
+    /// Dispatcher selector as `0x` and eight hex digits.
+    pub selector: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AbiEvent {
+    pub name: String,
+    /// Every input carries `indexed`.
+    pub inputs: Vec<AbiParam>,
+    /// Log topic as `0x` and eight hex digits.
+    pub topic: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AbiError {
+    pub name: String,
+    pub inputs: Vec<AbiParam>,
+    pub selector: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AbiParam {
+    pub name: String,
+    /// SynQ type name, with `tuple` standing in for a struct (`tuple[]` for an array of them).
+    #[serde(rename = "type")]
+    pub ty: String,
+    /// `struct <Name>` for struct types.
+    #[serde(
+        rename = "internalType",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub internal_type: Option<String>,
+    /// Struct fields, in layout order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<AbiParam>,
+    /// Whether an event input is indexed; absent outside events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub indexed: Option<bool>,
+}
+
+impl ContractAbi {
+    pub fn functions(&self) -> impl Iterator<Item = &AbiFunction> {
+        self.entries.iter().filter_map(|entry| match entry {
+            AbiEntry::Function(function) => Some(function),
+            _ => None,
+        })
+    }
+
+    pub fn function(&self, name: &str) -> Option<&AbiFunction> {
+        self.functions().find(|function| function.name == name)
+    }
+
+    pub fn constructor(&self) -> Option<&AbiConstructor> {
+        self.entries.iter().find_map(|entry| match entry {
+            AbiEntry::Constructor(constructor) => Some(constructor),
+            _ => None,
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("ABI entries always serialize")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| format!("Invalid ABI JSON: {}", e))
+    }
+}
+
+impl AbiFunction {
+    pub fn selector_value(&self) -> Result<u32, String> {
+        self.selector
+            .strip_prefix("0x")
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or_else(|| {
+                format!(
+                    "Function `{}` has invalid selector `{}`",
+                    self.name, self.selector
+                )
+            })
+    }
+
+    /// Calldata calling this function with `args`, a JSON array holding one value per input;
+    /// see `abi_value_from_json`.
+    pub fn encode_call(&self, args: &Json) -> Result<Vec<u8>, String> {
+        let (types, values) = json_arguments(&self.inputs, args)?;
+        encode_call(self.selector_value()?, &types, &values)
+            .map_err(|e| format!("Arguments of `{}`: {}", self.name, e))
+    }
+}
+
+impl AbiConstructor {
+    /// Constructor arguments as the init code reads them, from a JSON array of values.
+    pub fn encode_args(&self, args: &Json) -> Result<Vec<u8>, String> {
+        let (types, values) = json_arguments(&self.inputs, args)?;
+        encode(&types, &values).map_err(|e| format!("Constructor arguments: {}", e))
+    }
+}
+
+impl AbiParam {
+    /// Wire type of the parameter; `tuple` types are built from `components`.
+    pub fn abi_type(&self) -> Result<AbiType, String> {
+        let Some(suffix) = self.ty.strip_prefix("tuple") else {
+            return AbiType::parse(&self.ty).map_err(|e| e.to_string());
+        };
+        let mut ty = AbiType::Tuple(
+            self.components
+                .iter()
+                .map(AbiParam::abi_type)
+                .collect::<Result<_, _>>()?,
+        );
+        // `tuple[2][]` wraps the tuple innermost first.
+        let mut rest = suffix;
+        while let Some(dimension) = rest.strip_prefix('[') {
+            let (len, tail) = dimension
+                .split_once(']')
+                .ok_or_else(|| format!("Invalid ABI type `{}`", self.ty))?;
+            let len = match len {
+                "" => None,
+                len => Some(
+                    len.parse()
+                        .map_err(|_| format!("Invalid array length in `{}`", self.ty))?,
+                ),
+            };
+            ty = AbiType::Array(Box::new(ty), len);
+            rest = tail;
+        }
+        if !rest.is_empty() {
+            return Err(format!("Invalid ABI type `{}`", self.ty));
+        }
+        Ok(ty)
+    }
+}
+
+fn json_arguments(
+    inputs: &[AbiParam],
+    args: &Json,
+) -> Result<(Vec<AbiType>, Vec<AbiValue>), String> {
+    let args = args
+        .as_array()
+        .ok_or_else(|| format!("Arguments must be a JSON array, not `{}`", args))?;
+    if args.len() != inputs.len() {
+        return Err(format!(
+            "Expected {} arguments but got {}",
+            inputs.len(),
+            args.len()
+        ));
+    }
+    let mut types = Vec::new();
+    let mut values = Vec::new();
+    for (param, arg) in inputs.iter().zip(args) {
+        let ty = param.abi_type()?;
+        values.push(
+            abi_value_from_json(&ty, arg)
+                .map_err(|e| format!("Argument `{}`: {}", param.name, e))?,
+        );
+        types.push(ty);
+    }
+    Ok((types, values))
+}
+
+/// Reads a value of `ty` from JSON. Integers are JSON numbers, or decimal or `0x` hex strings
+/// when they do not fit in 64 bits; addresses, `BytesN`, `Bytes` and PQC keys and signatures
+/// are `0x` hex strings; arrays and tuples are JSON arrays.
+pub fn abi_value_from_json(ty: &AbiType, json: &Json) -> Result<AbiValue, String> {
+    let mismatch = || format!("`{}` is not a valid {}", json, ty);
+    let hex = || json.as_str().and_then(parse_hex).ok_or_else(mismatch);
+    let value = match ty {
+        AbiType::Address => AbiValue::Address(hex()?.try_into().map_err(|_| mismatch())?),
+        AbiType::Bool => AbiValue::Bool(json.as_bool().ok_or_else(mismatch)?),
+        AbiType::UInt(_) => match json_integer(json).ok_or_else(mismatch)? {
+            (false, magnitude) => AbiValue::UInt(magnitude),
+            (true, _) => return Err(format!("`{}` is negative but {} is unsigned", json, ty)),
+        },
+        AbiType::Int(_) => {
+            let (negative, magnitude) = json_integer(json).ok_or_else(mismatch)?;
+            let limit = U256::one() << 255;
+            if magnitude > limit || (!negative && magnitude == limit) {
+                return Err(format!("`{}` does not fit in {}", json, ty));
+            }
+            AbiValue::Int(if negative {
+                (!magnitude).overflowing_add(U256::one()).0
+            } else {
+                magnitude
+            })
+        }
+        AbiType::FixedBytes(_) => AbiValue::FixedBytes(hex()?),
+        AbiType::Bytes | AbiType::Pqc(_) => AbiValue::Bytes(hex()?),
+        AbiType::String => AbiValue::String(json.as_str().ok_or_else(mismatch)?.to_string()),
+        AbiType::Array(element, _) => AbiValue::Array(
+            json.as_array()
+                .ok_or_else(mismatch)?
+                .iter()
+                .map(|item| abi_value_from_json(element, item))
+                .collect::<Result<_, _>>()?,
+        ),
+        AbiType::Tuple(types) => {
+            let items = json.as_array().ok_or_else(mismatch)?;
+            if items.len() != types.len() {
+                return Err(mismatch());
+            }
+            AbiValue::Tuple(
+                types
+                    .iter()
+                    .zip(items)
+                    .map(|(ty, item)| abi_value_from_json(ty, item))
+                    .collect::<Result<_, _>>()?,
+            )
+        }
+    };
+    Ok(value)
+}
+
+// Sign and magnitude of a JSON integer
+fn json_integer(json: &Json) -> Option<(bool, U256)> {
+    if let Some(value) = json.as_u64() {
+        return Some((false, U256::from(value)));
+    }
+    if let Some(value) = json.as_i64() {
+        return Some((value < 0, U256::from(value.unsigned_abs())));
+    }
+    let text = json.as_str()?;
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let magnitude = match digits.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16).ok()?,
+        None if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) => {
+            U256::from_dec_str(digits).ok()?
+        }
+        None => return None,
+    };
+    Some((negative, magnitude))
+}
+
+fn parse_hex(text: &str) -> Option<Vec<u8>> {
+    let digits = text.strip_prefix("0x")?;
+    if !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|at| u8::from_str_radix(digits.get(at..at + 2)?, 16).ok())
+        .collect()
+}
+
+/// ABI of `contract`, in declaration order. Struct types resolve against the contract's own
+/// structs and the top-level ones in `units`; both are expected to be monomorphized.
+pub fn contract_abi(contract: &ContractDefinition, units: &[SourceUnit]) -> ContractAbi {
+    let mut structs: HashMap<&str, &StructDefinition> = HashMap::new();
+    for unit in units {
+        if let SourceUnit::Struct(s) = unit {
+            structs.insert(&s.name, s);
+        }
+    }
+    for part in &contract.parts {
+        if let ContractPart::Struct(s) = part {
+            structs.insert(&s.name, s);
+        }
+    }
+    let params = |params: &[Parameter], indexed: bool| -> Vec<AbiParam> {
+        params
+            .iter()
+            .map(|p| {
+                let mut param = abi_param(&p.name, &p.ty, &structs);
+                param.indexed = indexed.then_some(p.is_indexed);
+                param
+            })
+            .collect()
+    };
+
+    let mut entries = Vec::new();
+    let mut signatures = Vec::new();
+    for part in &contract.parts {
+        match part {
+            ContractPart::Constructor(constructor) => {
+                entries.push(AbiEntry::Constructor(AbiConstructor {
+                    inputs: params(&constructor.params, false),
+                    state_mutability: StateMutability::NonPayable.as_str().to_string(),
+                }))
+            }
+            ContractPart::Function(f) if f.visibility.is_entry_point() => {
+                // A redefinition shares the first definition's dispatcher entry.
+                let signature = function_signature(f);
+                if signatures.contains(&signature) {
+                    continue;
+                }
+                signatures.push(signature);
+                entries.push(AbiEntry::Function(AbiFunction {
+                    name: f.name.clone(),
+                    inputs: params(&f.params, false),
+                    outputs: f
+                        .returns
+                        .iter()
+                        .map(|ty| abi_param("", ty, &structs))
+                        .collect(),
+                    state_mutability: f.mutability.as_str().to_string(),
+                    selector: format!("0x{:08x}", function_selector(f)),
+                }))
+            }
+            ContractPart::Event(event) => entries.push(AbiEntry::Event(AbiEvent {
+                name: event.name.clone(),
+                inputs: params(&event.params, true),
+                topic: format!("0x{:08x}", event_topic(event)),
+            })),
+            ContractPart::Error(error) => entries.push(AbiEntry::Error(AbiError {
+                name: error.name.clone(),
+                inputs: params(&error.params, false),
+                selector: format!("0x{:08x}", error_selector(error)),
+            })),
+            _ => {}
+        }
+    }
+    ContractAbi { entries }
+}
+
+fn abi_param(name: &str, ty: &Type, structs: &HashMap<&str, &StructDefinition>) -> AbiParam {
+    let mut element = ty;
+    while let Type::Array(inner, _) = element {
+        element = inner;
+    }
+    let (internal_type, components) = match element {
+        Type::Struct(struct_name) => (
+            Some(format!("struct {}", struct_name)),
+            structs
+                .get(struct_name.as_str())
+                .map(|s| {
+                    s.fields
+                        .iter()
+                        .map(|field| abi_param(&field.name, &field.ty, structs))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        ),
+        _ => (None, Vec::new()),
+    };
+    AbiParam {
+        name: name.to_string(),
+        ty: abi_type_name(ty),
+        internal_type,
+        components,
+        indexed: None,
+    }
+}
+
+fn abi_type_name(ty: &Type) -> String {
+    match ty {
+        Type::Array(inner, Some(len)) => format!("{}[{}]", abi_type_name(inner), len),
+        Type::Array(inner, None) => format!("{}[]", abi_type_name(inner)),
+        Type::Struct(_) => "tuple".to_string(),
+        other => canonical_type_name(other),
+    }
+}
@@ -0,0 +1,395 @@
+//! Annotation registry for SynQ declarations
+//! Describes every annotation the compiler understands, where it may appear, and its typed arguments.
+//!
+//! Semantic analysis rejects annotations that fail [`resolve`]; later passes read the
+//! validated [`ResolvedAnnotation`] values instead of re-parsing raw annotation expressions.
+
+use crate::ast::{Annotation, Expression, Literal};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Declaration kinds an annotation can be attached to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnotationTarget {
+    Contract,
+    StateVariable,
+    Constructor,
+    Function,
+    Event,
+}
+
+impl AnnotationTarget {
+    fn singular(self) -> &'static str {
+        match self {
+            AnnotationTarget::Contract => "a contract",
+            AnnotationTarget::StateVariable => "a state variable",
+            AnnotationTarget::Constructor => "a constructor",
+            AnnotationTarget::Function => "a function",
+            AnnotationTarget::Event => "an event",
+        }
+    }
+
+    fn plural(self) -> &'static str {
+        match self {
+            AnnotationTarget::Contract => "contracts",
+            AnnotationTarget::StateVariable => "state variables",
+            AnnotationTarget::Constructor => "constructors",
+            AnnotationTarget::Function => "functions",
+            AnnotationTarget::Event => "events",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArgKind {
+    Integer,
+    String,
+}
+
+impl ArgKind {
+    fn describe(self) -> &'static str {
+        match self {
+            ArgKind::Integer => "an integer",
+            ArgKind::String => "a string",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ArgShape {
+    /// Bare marker such as `@hardware_accel`.
+    None,
+    /// Exactly one unnamed argument, e.g. `@gas_limit(100000)`.
+    Positional(ArgKind),
+    /// Named arguments; `required` keys must be present and any other key must have kind `extra`.
+    Named {
+        required: &'static [(&'static str, ArgKind)],
+        extra: Option<ArgKind>,
+    },
+}
+
+/// Registry entry for one annotation.
+#[derive(Debug)]
+pub struct AnnotationSpec {
+    pub name: &'static str,
+    pub targets: &'static [AnnotationTarget],
+    shape: ArgShape,
+}
+
+const FUNCTION_LIKE: &[AnnotationTarget] =
+    &[AnnotationTarget::Function, AnnotationTarget::Constructor];
+const FUNCTION_ONLY: &[AnnotationTarget] = &[AnnotationTarget::Function];
+
+const REGISTRY: &[AnnotationSpec] = &[
+    AnnotationSpec {
+        name: "gas_cost",
+        targets: FUNCTION_LIKE,
+        shape: ArgShape::Named {
+            required: &[("base", ArgKind::Integer)],
+            extra: Some(ArgKind::Integer),
+        },
+    },
+    AnnotationSpec {
+        name: "gas_limit",
+        targets: FUNCTION_LIKE,
+        shape: ArgShape::Positional(ArgKind::Integer),
+    },
+    AnnotationSpec {
+        name: "precompile",
+        targets: FUNCTION_ONLY,
+        shape: ArgShape::Positional(ArgKind::String),
+    },
+    AnnotationSpec {
+        name: "hardware_accel",
+        targets: FUNCTION_ONLY,
+        shape: ArgShape::None,
+    },
+    AnnotationSpec {
+        name: "optimize_gas",
+        targets: &[AnnotationTarget::Contract, AnnotationTarget::Function],
+        shape: ArgShape::None,
+    },
+    AnnotationSpec {
+        name: "deploy",
+        targets: &[AnnotationTarget::Contract],
+        shape: ArgShape::None,
+    },
+    // Visibility and mutability markers; the parser folds these into the function definition.
+    AnnotationSpec {
+        name: "public",
+        targets: FUNCTION_ONLY,
+        shape: ArgShape::None,
+    },
+    AnnotationSpec {
+        name: "external",
+        targets: FUNCTION_ONLY,
+        shape: ArgShape::None,
+    },
+    AnnotationSpec {
+        name: "internal",
+        targets: FUNCTION_ONLY,
+        shape: ArgShape::None,
+    },
+    AnnotationSpec {
+        name: "private",
+        targets: FUNCTION_ONLY,
+        shape: ArgShape::None,
+    },
+    AnnotationSpec {
+        name: "view",
+        targets: FUNCTION_ONLY,
+        shape: ArgShape::None,
+    },
+    AnnotationSpec {
+        name: "pure",
+        targets: FUNCTION_ONLY,
+        shape: ArgShape::None,
+    },
+    AnnotationSpec {
+        name: "payable",
+        targets: FUNCTION_ONLY,
+        shape: ArgShape::None,
+    },
+];
+
+/// Looks up the registry entry for an annotation name (without the leading `@`).
+pub fn lookup(name: &str) -> Option<&'static AnnotationSpec> {
+    REGISTRY.iter().find(|spec| spec.name == name)
+}
+
+/// Validated annotation data handed to code generators.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolvedAnnotation {
+    /// `@gas_cost(base: N, op: N, ...)`; extra keys are kept in source order.
+    GasCost {
+        base: u64,
+        operations: Vec<(String, u64)>,
+    },
+    GasLimit(u64),
+    Precompile(String),
+    HardwareAccel,
+    OptimizeGas,
+    Deploy,
+    Visibility(String),
+    Mutability(String),
+}
+
+impl fmt::Display for ResolvedAnnotation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResolvedAnnotation::GasCost { base, operations } => {
+                write!(f, "@gas_cost(base: {}", base)?;
+                for (name, cost) in operations {
+                    write!(f, ", {}: {}", name, cost)?;
+                }
+                write!(f, ")")
+            }
+            ResolvedAnnotation::GasLimit(limit) => write!(f, "@gas_limit({})", limit),
+            ResolvedAnnotation::Precompile(name) => write!(f, "@precompile(\"{}\")", name),
+            ResolvedAnnotation::HardwareAccel => write!(f, "@hardware_accel"),
+            ResolvedAnnotation::OptimizeGas => write!(f, "@optimize_gas"),
+            ResolvedAnnotation::Deploy => write!(f, "@deploy"),
+            ResolvedAnnotation::Visibility(name) | ResolvedAnnotation::Mutability(name) => {
+                write!(f, "@{}", name)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ArgValue {
+    Integer(u64),
+    String(String),
+}
+
+/// Validates one annotation against the registry for the given target.
+///
+/// Errors are sentence fragments meant to follow a declaration description,
+/// e.g. "Function `f` in contract `C` uses unknown annotation `@gas_cots`".
+pub fn resolve(
+    annotation: &Annotation,
+    target: AnnotationTarget,
+) -> Result<ResolvedAnnotation, String> {
+    let Some(spec) = lookup(&annotation.name) else {
+        return Err(match suggest(&annotation.name) {
+            Some(known) => format!(
+                "uses unknown annotation `@{}`; did you mean `@{}`?",
+                annotation.name, known
+            ),
+            None => format!("uses unknown annotation `@{}`", annotation.name),
+        });
+    };
+
+    if !spec.targets.contains(&target) {
+        let allowed: Vec<&str> = spec.targets.iter().map(|t| t.plural()).collect();
+        return Err(format!(
+            "places `@{}` on {}; it is only allowed on {}",
+            spec.name,
+            target.singular(),
+            allowed.join(" and ")
+        ));
+    }
+
+    let invalid = |detail: String| {
+        format!(
+            "passes invalid arguments to `@{}`: {}",
+            annotation.name, detail
+        )
+    };
+
+    match spec.shape {
+        ArgShape::None => {
+            if !annotation.args.is_empty() {
+                return Err(invalid("it takes no arguments".to_string()));
+            }
+        }
+        ArgShape::Positional(kind) => {
+            let [arg] = annotation.args.as_slice() else {
+                return Err(invalid(format!("expected {}", kind.describe())));
+            };
+            if let Some(name) = &arg.name {
+                return Err(invalid(format!(
+                    "expected {} without a name, found `{}:`",
+                    kind.describe(),
+                    name
+                )));
+            }
+            let value = expect_kind(&arg.value, kind)
+                .ok_or_else(|| invalid(format!("expected {}", kind.describe())))?;
+            return Ok(match (spec.name, value) {
+                ("gas_limit", ArgValue::Integer(limit)) => ResolvedAnnotation::GasLimit(limit),
+                ("precompile", ArgValue::String(name)) => ResolvedAnnotation::Precompile(name),
+                _ => unreachable!("registry shape and resolver disagree for `@{}`", spec.name),
+            });
+        }
+        ArgShape::Named { required, extra } => {
+            let mut seen = HashSet::new();
+            let mut values = Vec::new();
+            for arg in &annotation.args {
+                let Some(name) = &arg.name else {
+                    return Err(invalid(
+                        "arguments must be named (`key: value`)".to_string(),
+                    ));
+                };
+                if !seen.insert(name.as_str()) {
+                    return Err(invalid(format!("duplicate argument `{}`", name)));
+                }
+                let kind = required
+                    .iter()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, kind)| *kind)
+                    .or(extra)
+                    .ok_or_else(|| invalid(format!("unexpected argument `{}`", name)))?;
+                let value = expect_kind(&arg.value, kind).ok_or_else(|| {
+                    invalid(format!("argument `{}` must be {}", name, kind.describe()))
+                })?;
+                values.push((name.clone(), value));
+            }
+            if let Some((missing, _)) = required.iter().find(|(key, _)| !seen.contains(key)) {
+                return Err(invalid(format!("missing required argument `{}`", missing)));
+            }
+
+            // `gas_cost` is the only named-argument annotation and all of its values are integers.
+            let mut base = 0;
+            let mut operations = Vec::new();
+            for (name, value) in values {
+                let ArgValue::Integer(cost) = value else {
+                    unreachable!("`@gas_cost` arguments are integers");
+                };
+                if name == "base" {
+                    base = cost;
+                } else {
+                    operations.push((name, cost));
+                }
+            }
+            return Ok(ResolvedAnnotation::GasCost { base, operations });
+        }
+    }
+
+    Ok(match spec.name {
+        "hardware_accel" => ResolvedAnnotation::HardwareAccel,
+        "optimize_gas" => ResolvedAnnotation::OptimizeGas,
+        "deploy" => ResolvedAnnotation::Deploy,
+        "public" | "external" | "internal" | "private" => {
+            ResolvedAnnotation::Visibility(spec.name.to_string())
+        }
+        "view" | "pure" | "payable" => ResolvedAnnotation::Mutability(spec.name.to_string()),
+        _ => unreachable!("registry entry `@{}` has no resolver", spec.name),
+    })
+}
+
+/// Validates every annotation on one declaration, also rejecting repeated annotations.
+pub fn resolve_all(
+    annotations: &[Annotation],
+    target: AnnotationTarget,
+) -> Result<Vec<ResolvedAnnotation>, Vec<String>> {
+    let mut resolved = Vec::new();
+    let mut errors = Vec::new();
+    let mut seen = HashSet::new();
+
+    for annotation in annotations {
+        if !seen.insert(annotation.name.as_str()) {
+            errors.push(format!("repeats annotation `@{}`", annotation.name));
+            continue;
+        }
+        match resolve(annotation, target) {
+            Ok(value) => resolved.push(value),
+            Err(error) => errors.push(error),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(resolved)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Returns the validated `@gas_limit` budget of a function, if it declares one.
+pub fn gas_limit(annotations: &[Annotation]) -> Option<u64> {
+    annotations
+        .iter()
+        .filter(|annotation| annotation.name == "gas_limit")
+        .find_map(
+            |annotation| match resolve(annotation, AnnotationTarget::Function) {
+                Ok(ResolvedAnnotation::GasLimit(limit)) => Some(limit),
+                _ => None,
+            },
+        )
+}
+
+fn expect_kind(value: &Expression, kind: ArgKind) -> Option<ArgValue> {
+    match (kind, value) {
+        (ArgKind::Integer, Expression::Literal(Literal::Number(n))) => {
+            u64::try_from(*n).ok().map(ArgValue::Integer)
+        }
+        (ArgKind::String, Expression::Literal(Literal::String(s))) => {
+            Some(ArgValue::String(s.clone()))
+        }
+        _ => None,
+    }
+}
+
+/// Suggests the closest registered name within two edits, to catch typos like `@gas_cots`.
+fn suggest(name: &str) -> Option<&'static str> {
+    REGISTRY
+        .iter()
+        .map(|spec| (edit_distance(name, spec.name), spec.name))
+        .filter(|(distance, _)| *distance <= 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, known)| known)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
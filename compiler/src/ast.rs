@@ -1,10 +1,12 @@
 //! Abstract Syntax Tree (AST) for the SynQ language.
 
+pub use primitive_types::U256;
 use std::collections::HashMap;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum SourceUnit {
     Contract(ContractDefinition),
+    ContractInstance(ContractInstantiation),
     Struct(StructDefinition),
     Event(EventDefinition),
 }
@@ -12,22 +14,41 @@ pub enum SourceUnit {
 #[derive(Debug, PartialEq, Clone)]
 pub struct Annotation {
     pub name: String,
-    pub args: Vec<Expression>,
+    pub args: Vec<AnnotationArg>,
+}
+
+/// One annotation argument, either named (`base: 45000`) or positional (`100000`).
+#[derive(Debug, PartialEq, Clone)]
+pub struct AnnotationArg {
+    pub name: Option<String>,
+    pub value: Expression,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct ContractDefinition {
     pub name: String,
+    pub generic_params: Vec<String>,
     pub annotations: Vec<Annotation>,
     pub parts: Vec<ContractPart>,
 }
 
+/// Named instantiation of a generic contract, e.g. `contract TokenVault = Vault<UInt256>;`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ContractInstantiation {
+    pub name: String,
+    pub generic: String,
+    pub type_args: Vec<Type>,
+    pub annotations: Vec<Annotation>,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum ContractPart {
     StateVariable(StateVariableDeclaration),
     Constructor(ConstructorDefinition),
     Function(FunctionDefinition),
     Event(EventDefinition),
+    Error(ErrorDefinition),
+    Struct(StructDefinition),
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -43,6 +64,7 @@ pub struct ConstructorDefinition {
     pub params: Vec<Parameter>,
     pub body: Block,
     pub annotations: Vec<Annotation>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -51,13 +73,62 @@ pub struct FunctionDefinition {
     pub params: Vec<Parameter>,
     pub returns: Option<Type>,
     pub body: Block,
-    pub is_public: bool,
+    pub visibility: Visibility,
+    pub mutability: StateMutability,
     pub annotations: Vec<Annotation>,
+    pub span: Span,
+}
+
+/// Function visibility; only `Public` and `External` functions are reachable from calldata.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Visibility {
+    Public,
+    External,
+    #[default]
+    Internal,
+    Private,
+}
+
+impl Visibility {
+    pub fn is_entry_point(self) -> bool {
+        matches!(self, Visibility::Public | Visibility::External)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Visibility::Public => "public",
+            Visibility::External => "external",
+            Visibility::Internal => "internal",
+            Visibility::Private => "private",
+        }
+    }
+}
+
+/// How a function may interact with contract state, ordered from most to least restrictive.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Default)]
+pub enum StateMutability {
+    Pure,
+    View,
+    #[default]
+    NonPayable,
+    Payable,
+}
+
+impl StateMutability {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StateMutability::Pure => "pure",
+            StateMutability::View => "view",
+            StateMutability::NonPayable => "nonpayable",
+            StateMutability::Payable => "payable",
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct StructDefinition {
     pub name: String,
+    pub generic_params: Vec<String>,
     pub fields: Vec<Parameter>,
 }
 
@@ -68,6 +139,13 @@ pub struct EventDefinition {
     pub annotations: Vec<Annotation>,
 }
 
+/// Custom error declared with `error Name(params);`, described in the contract's ABI.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ErrorDefinition {
+    pub name: String,
+    pub params: Vec<Parameter>,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Parameter {
     pub name: String,
@@ -75,9 +153,18 @@ pub struct Parameter {
     pub is_indexed: bool,
 }
 
+/// 1-based line and column where a definition or statement starts in the source.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Block {
     pub statements: Vec<Statement>,
+    /// Start of each statement, parallel to `statements`.
+    pub spans: Vec<Span>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -85,6 +172,7 @@ pub enum Statement {
     Expression(Expression),
     VariableDeclaration(String, Type, Option<Expression>),
     Assignment(String, Expression),
+    ElementAssignment(Expression, Expression), // target[index] = value or target.field = value
     Return(Option<Expression>),
     Require(Expression, String),
     Revert(String),
@@ -92,11 +180,14 @@ pub enum Statement {
     For(String, Expression, Expression, Block),
     Emit(String, Vec<Expression>),
     RequirePqc(Block, Option<Box<Statement>>), // require_pqc block with optional fallback (revert/return)
+    WithGasLimit(Expression, Block), // with_gas_limit(budget) block; overrunning the budget reverts only the block
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Expression {
     Call(String, Vec<Expression>),
+    /// `receiver.method(args)` where the receiver is not a plain dotted name, e.g. `owners[id].push(x)`.
+    MethodCall(Box<Expression>, String, Vec<Expression>),
     MemberAccess(Box<Expression>, String),
     IndexAccess(Box<Expression>, Box<Expression>),
     Literal(Literal),
@@ -104,6 +195,9 @@ pub enum Expression {
     Binary(BinaryOp, Box<Expression>, Box<Expression>),
     Unary(UnaryOp, Box<Expression>),
     Ternary(Box<Expression>, Box<Expression>, Box<Expression>),
+    ArrayLiteral(Vec<Expression>),
+    /// `{ field: value, ... }`; the struct type comes from the context it is used in.
+    ObjectLiteral(Vec<(String, Expression)>),
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -148,6 +242,8 @@ pub enum Type {
     Int128,
     Bool,
     Bytes,
+    /// `Bytes1` through `Bytes32`.
+    FixedBytes(u8),
     String,
     Array(Box<Type>, Option<u32>),
     Mapping(Box<Type>, Box<Type>),
@@ -170,7 +266,7 @@ pub enum Type {
 #[derive(Debug, PartialEq, Clone)]
 pub enum Literal {
     String(String),
-    Number(u64),
+    Number(U256),
     Bool(bool),
     Address(String),
     Bytes(Vec<u8>),
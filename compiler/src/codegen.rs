@@ -1,69 +1,517 @@
 //! SynQ bytecode generator
 //! Generates .synq bytecode files for the QuantumVM
 
+use crate::abi::{contract_abi, ContractAbi};
+use crate::annotations;
 use crate::ast::*;
-use quantumvm::{Assembler, OpCode};
+use crate::semantic::{
+    gas_budget_literal, integer_bounds, monomorphize, parse_constructor_type, path_expression,
+};
+use quantumvm::{AbiType, Assembler, Header, Label, OpCode, SectionKind, SourceMap};
+use sha2::{Digest, Sha256};
+
+/// Memory slots are 32-byte words.
+const WORD: u32 = 32;
+/// Slot holding the address where the next heap allocation starts.
+const FREE_POINTER: u32 = 0x40;
+/// First named slot; everything below it is reserved.
+const STATIC_BASE: u32 = 0x60;
+
+// Which container a generator is writing
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Stage {
+    /// Init code: initializes storage and runs the constructors once.
+    Deploy,
+    /// Code installed by the deploy: the entry dispatcher and the functions.
+    Runtime,
+}
+
+/// One compiled contract: the deploy container plus what tools need to call and debug it,
+/// read back out of the container so the two never disagree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContractArtifact {
+    pub name: String,
+    /// Deploy container with the runtime embedded.
+    pub bytecode: Vec<u8>,
+    pub abi: ContractAbi,
+    /// Source map of the runtime code, which is what calls execute.
+    pub source_map: SourceMap,
+    pub metadata: ArtifactMetadata,
+}
+
+// Provenance of an artifact, as recorded in its container header
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArtifactMetadata {
+    pub compiler_version: [u32; 3],
+    /// Source file name given to `with_source_file`; empty when unknown.
+    pub source_file: String,
+    /// SHA-256 of the source text; all zero when unknown.
+    pub source_hash: [u8; 32],
+}
+
+impl ContractArtifact {
+    fn from_container(name: String, abi: ContractAbi, bytecode: Vec<u8>) -> Result<Self, String> {
+        let header = Header::parse(&bytecode).map_err(|e| e.to_string())?;
+        let runtime = header.section_bytes(&bytecode, SectionKind::Runtime);
+        let runtime_header = Header::parse(runtime).map_err(|e| e.to_string())?;
+        let source_map = match runtime_header.section_bytes(runtime, SectionKind::SourceMap) {
+            [] => SourceMap::default(),
+            bytes => SourceMap::decode(bytes).map_err(|e| e.to_string())?,
+        };
+        let metadata = ArtifactMetadata {
+            compiler_version: header.compiler_version.unwrap_or_default(),
+            source_file: source_map.file.clone(),
+            source_hash: header.source_hash.unwrap_or_default(),
+        };
+        Ok(ContractArtifact {
+            name,
+            abi,
+            bytecode,
+            source_map,
+            metadata,
+        })
+    }
+}
 
 pub struct CodeGenerator {
     assembler: Assembler,
-    function_labels: std::collections::HashMap<String, usize>,
+    stage: Stage,
+    source_hash: [u8; 32],
+    source_file: String,
+    // Entry label of every function, keyed `{Contract}_{function}`.
+    function_labels: std::collections::HashMap<String, Label>,
     current_function: Option<String>,
-    jump_patches: Vec<(usize, String)>, // (address_position, label)
-    label_positions: std::collections::HashMap<String, usize>,
+    // Declared types of names in scope; byte-string operators and conversions lower by type.
+    state_variable_types: std::collections::HashMap<String, Type>,
+    variable_types: std::collections::HashMap<String, Type>,
+    // Field names and types of every concrete struct, in declaration (and layout) order.
+    struct_layouts: std::collections::HashMap<String, Vec<(String, Type)>>,
+    // Locals of struct or array type; their slot holds the address of the data, not the data.
+    reference_variables: std::collections::HashSet<String>,
+    // Byte addresses of named slots, allocated in order from `STATIC_BASE`.
+    static_slots: std::collections::HashMap<String, u32>,
+    next_static: u32,
+    // Parameters of every function by label; a call stores its arguments in their slots.
+    function_params: std::collections::HashMap<String, Vec<Parameter>>,
+    // Labels of functions that return a value; the others return with nothing pushed.
+    value_functions: std::collections::HashSet<String>,
+    current_contract: String,
+    // Source map name of the function being generated, `{Contract}.{function}`, and the
+    // position its code is currently attributed to.
+    source_function: String,
+    source_span: Option<Span>,
+    // First storage slot of every state variable, keyed `{Contract}::{name}`, and the names in
+    // scope that still refer to one (a local or parameter of the same name shadows it).
+    storage_slots: std::collections::HashMap<String, u32>,
+    storage_variables: std::collections::HashSet<String>,
+    // Struct and array locals bound to a place in storage: their slot holds its storage slot.
+    storage_references: std::collections::HashSet<String>,
+    // Entry label of every constructor, by contract.
+    constructor_labels: std::collections::HashMap<String, Label>,
+    // Where the dispatcher enters each entry point that takes parameters, keyed like
+    // `function_labels`: code that decodes them from calldata, then falls into the function.
+    entry_labels: std::collections::HashMap<String, Label>,
+    // Functions with a `@gas_limit`, by label: where internal calls enter the body past the
+    // function's own gas scope, the budget and the return type. Each call opens its own scope.
+    budgeted_functions: std::collections::HashMap<String, (Label, u32, Option<Type>)>,
+    // Declared events by name, for their log topics.
+    events: std::collections::HashMap<String, EventDefinition>,
 }
 
 impl CodeGenerator {
     pub fn new() -> Self {
         CodeGenerator {
             assembler: Assembler::new(),
+            stage: Stage::Runtime,
+            source_hash: [0; 32],
+            source_file: String::new(),
             function_labels: std::collections::HashMap::new(),
             current_function: None,
-            jump_patches: Vec::new(),
-            label_positions: std::collections::HashMap::new(),
+            state_variable_types: std::collections::HashMap::new(),
+            variable_types: std::collections::HashMap::new(),
+            struct_layouts: std::collections::HashMap::new(),
+            reference_variables: std::collections::HashSet::new(),
+            static_slots: std::collections::HashMap::new(),
+            next_static: STATIC_BASE,
+            function_params: std::collections::HashMap::new(),
+            value_functions: std::collections::HashSet::new(),
+            current_contract: String::new(),
+            source_function: String::new(),
+            source_span: None,
+            storage_slots: std::collections::HashMap::new(),
+            storage_variables: std::collections::HashSet::new(),
+            storage_references: std::collections::HashSet::new(),
+            constructor_labels: std::collections::HashMap::new(),
+            entry_labels: std::collections::HashMap::new(),
+            budgeted_functions: std::collections::HashMap::new(),
+            events: std::collections::HashMap::new(),
+        }
+    }
+
+    /// Records the SHA-256 of `source` in the container header.
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.source_hash = Sha256::digest(source.as_bytes()).into();
+        self
+    }
+
+    /// Names the source file in the container's source map.
+    pub fn with_source_file(mut self, file: &str) -> Self {
+        self.source_file = file.to_string();
+        self
+    }
+
+    /// Compiles `ast` into a deploy container: init code that initializes storage and runs the
+    /// constructors, with the runtime container (dispatcher and functions) embedded for the VM
+    /// to install. A source defining several contracts is rejected; `generate_contracts`
+    /// compiles each of them on its own.
+    pub fn generate(self, ast: &[SourceUnit]) -> Result<Vec<u8>, String> {
+        // Generic structs and contracts are emitted as one concrete copy per instantiation.
+        let ast = monomorphize(ast).map_err(|errors| format_monomorphize_errors(&errors))?;
+        let contracts = contract_names(&ast);
+        if contracts.len() > 1 {
+            return Err(format!(
+                "Source defines {} contracts ({}); each one compiles to its own artifact",
+                contracts.len(),
+                contracts
+                    .iter()
+                    .map(|name| format!("`{}`", name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
         }
+        self.generate_container(&ast)
+    }
+
+    /// Compiles every contract in `ast` into its own artifact, in declaration order. Each
+    /// contract sees the file's structs and events but none of the other contracts.
+    pub fn generate_contracts(self, ast: &[SourceUnit]) -> Result<Vec<ContractArtifact>, String> {
+        let ast = monomorphize(ast).map_err(|errors| format_monomorphize_errors(&errors))?;
+        ast.iter()
+            .filter_map(|unit| match unit {
+                SourceUnit::Contract(c) => Some(c),
+                _ => None,
+            })
+            .map(|contract| {
+                let units: Vec<SourceUnit> = ast
+                    .iter()
+                    .filter(|unit| match unit {
+                        SourceUnit::Contract(c) => c.name == contract.name,
+                        _ => true,
+                    })
+                    .cloned()
+                    .collect();
+                let bytecode = self
+                    .generate_container(&units)
+                    .map_err(|e| format!("Contract `{}`: {}", contract.name, e))?;
+                let abi = contract_abi(contract, &units);
+                ContractArtifact::from_container(contract.name.clone(), abi, bytecode)
+            })
+            .collect()
+    }
+
+    // Builds the runtime container for `ast`, then the deploy container that embeds it.
+    fn generate_container(&self, ast: &[SourceUnit]) -> Result<Vec<u8>, String> {
+        let runtime = self.fork(Stage::Runtime).generate_stage(ast)?;
+        let mut deploy = self.fork(Stage::Deploy);
+        // Repeat the entry points so tools can read them without unpacking the runtime.
+        let header = Header::parse(&runtime).map_err(|e| e.to_string())?;
+        deploy.assembler.set_metadata(
+            header
+                .section_bytes(&runtime, SectionKind::Metadata)
+                .to_vec(),
+        );
+        deploy.assembler.set_runtime(runtime);
+        deploy.generate_stage(ast)
+    }
+
+    fn fork(&self, stage: Stage) -> Self {
+        let mut generator = CodeGenerator::new();
+        generator.stage = stage;
+        generator.source_hash = self.source_hash;
+        generator.source_file = self.source_file.clone();
+        generator
     }
 
-    pub fn generate(mut self, ast: &[SourceUnit]) -> Result<Vec<u8>, String> {
-        // First pass: collect function labels and their positions
+    fn generate_stage(mut self, ast: &[SourceUnit]) -> Result<Vec<u8>, String> {
+        let version = crate::version::get_compiler_version();
+        self.assembler
+            .set_compiler_version(version.major, version.minor, version.patch);
+        self.assembler.set_source_hash(self.source_hash);
+        self.assembler.set_source_file(&self.source_file);
+
+        for item in ast {
+            let structs: Vec<&StructDefinition> = match item {
+                SourceUnit::Struct(s) => vec![s],
+                SourceUnit::Contract(c) => c
+                    .parts
+                    .iter()
+                    .filter_map(|part| match part {
+                        ContractPart::Struct(s) => Some(s),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+            for s in structs {
+                let fields = s
+                    .fields
+                    .iter()
+                    .map(|field| (field.name.clone(), field.ty.clone()))
+                    .collect();
+                self.struct_layouts.insert(s.name.clone(), fields);
+            }
+        }
+
+        // State variables get consecutive storage slots in declaration order, as many as words
+        // in their memory layout; see `gen_storage_place`.
+        let mut next_slot = 0;
+        for item in ast {
+            let c = match item {
+                SourceUnit::Contract(c) => c,
+                SourceUnit::Event(event) => {
+                    self.events.insert(event.name.clone(), event.clone());
+                    continue;
+                }
+                _ => continue,
+            };
+            for part in &c.parts {
+                if let ContractPart::Event(event) = part {
+                    self.events.insert(event.name.clone(), event.clone());
+                }
+                if let ContractPart::StateVariable(var) = part {
+                    let key = format!("{}::{}", c.name, var.name);
+                    let count = self.slot_count(&var.ty);
+                    if let std::collections::hash_map::Entry::Vacant(entry) =
+                        self.storage_slots.entry(key)
+                    {
+                        entry.insert(next_slot);
+                        next_slot += count;
+                    }
+                }
+            }
+        }
+
+        // First pass: create a label for every function so calls can precede definitions
         for item in ast {
             self.collect_functions(item)?;
         }
 
-        // Second pass: generate code with proper label resolution
+        // The heap starts after the last named slot, which is only known once all code exists.
+        self.assembler.emit_op(OpCode::Push);
+        let heap_start = self.assembler.code_len();
+        self.assembler.emit_u32(0);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(FREE_POINTER);
+        self.assembler.emit_op(OpCode::Store);
+
+        match self.stage {
+            // Init code sets up storage and calls each constructor
+            Stage::Deploy => self.gen_init(ast)?,
+            // Entry dispatcher runs next and routes calldata to public/external functions
+            Stage::Runtime => self.gen_dispatcher(ast)?,
+        }
+
+        // Second pass: generate code, binding labels as their positions are reached
         for item in ast {
             self.gen_source_unit(item)?;
         }
 
-        // Third pass: patch jump addresses
-        self.patch_jumps()?;
+        // The assembler resolves jumps; only the heap start is patched here
+        self.assembler.patch_u32(heap_start, self.next_static)?;
 
-        Ok(self.assembler.build())
+        self.assembler.build()
     }
 
-    fn patch_jumps(&mut self) -> Result<(), String> {
-        for (patch_pos, label) in &self.jump_patches {
-            if let Some(&target_pos) = self.label_positions.get(label) {
-                let addr = target_pos as u32;
-                self.assembler.patch_u32(*patch_pos, addr)?;
-            } else {
-                return Err(format!("Undefined label: {}", label));
+    fn collect_functions(&mut self, unit: &SourceUnit) -> Result<(), String> {
+        if let SourceUnit::Contract(c) = unit {
+            for part in &c.parts {
+                if let ContractPart::Function(f) = part {
+                    let name = format!("{}_{}", c.name, f.name);
+                    self.function_params.insert(name.clone(), f.params.clone());
+                    if f.returns.is_some() {
+                        self.value_functions.insert(name.clone());
+                    }
+                    if !self.function_labels.contains_key(&name) {
+                        if let Some(limit) = gas_limit(&f.annotations)? {
+                            let body = self.assembler.new_label();
+                            self.budgeted_functions
+                                .insert(name.clone(), (body, limit, f.returns.clone()));
+                        }
+                        let label = self.assembler.new_label();
+                        self.function_labels.insert(name, label);
+                    }
+                }
             }
         }
         Ok(())
     }
 
-    fn collect_functions(&mut self, unit: &SourceUnit) -> Result<(), String> {
-        if let SourceUnit::Contract(c) = unit {
+    /// Emits the init sequence after the heap setup: every scalar state variable is written its
+    /// zero value, then each contract's constructor runs with the calldata as its arguments.
+    /// Slots of structs, arrays and mappings are left unwritten and read as zero.
+    fn gen_init(&mut self, ast: &[SourceUnit]) -> Result<(), String> {
+        for unit in ast {
+            let SourceUnit::Contract(c) = unit else {
+                continue;
+            };
             for part in &c.parts {
-                if let ContractPart::Function(f) = part {
-                    let label = format!("{}_{}", c.name, f.name);
-                    // Store current code position as function label
-                    let pos = self.assembler.code_len();
-                    self.function_labels.insert(label.clone(), pos);
-                    self.label_positions.insert(label, pos);
+                let ContractPart::StateVariable(var) = part else {
+                    continue;
+                };
+                let Some(&slot) = self.storage_slots.get(&format!("{}::{}", c.name, var.name))
+                else {
+                    continue;
+                };
+                if !self.is_scalar(&var.ty) {
+                    continue;
+                }
+                if is_byte_value(&var.ty) {
+                    self.assembler.emit_op(OpCode::LoadImm);
+                    self.assembler.emit_bytes(&[]);
+                } else {
+                    self.assembler.emit_op(OpCode::Push);
+                    self.assembler.emit_u32(0);
+                }
+                self.assembler.emit_op(OpCode::Push);
+                self.assembler.emit_u32(slot);
+                self.assembler.emit_op(OpCode::SStore);
+            }
+            if c.parts
+                .iter()
+                .any(|part| matches!(part, ContractPart::Constructor(_)))
+            {
+                let label = self.assembler.new_label();
+                self.constructor_labels.insert(c.name.clone(), label);
+                self.assembler.emit_jump(OpCode::Call, label);
+            }
+        }
+        self.assembler.emit_op(OpCode::Halt);
+        Ok(())
+    }
+
+    /// Emits the entry dispatcher after the heap setup. Calldata starts with a 4-byte function selector
+    /// followed by the ABI-encoded arguments. Empty calldata runs the `receive` entry point, or
+    /// else `fallback`; calldata without a known selector runs `fallback`. Without such an entry
+    /// the call reverts. Entry points that are not `payable` revert when the call carries value.
+    fn gen_dispatcher(&mut self, ast: &[SourceUnit]) -> Result<(), String> {
+        // (label, selector, name reported when a non-payable entry is sent value)
+        let mut entry_points: Vec<(String, u32, Option<String>)> = Vec::new();
+        // Labels of the `receive` and `fallback` entry points, if declared
+        let mut receive = None;
+        let mut fallback = None;
+        // One `0x<selector> <Contract>.<signature>` line per entry point
+        let mut metadata = String::new();
+        for unit in ast {
+            let SourceUnit::Contract(c) = unit else {
+                continue;
+            };
+            for part in &c.parts {
+                let ContractPart::Function(f) = part else {
+                    continue;
+                };
+                if !f.visibility.is_entry_point() {
+                    continue;
+                }
+                let label = format!("{}_{}", c.name, f.name);
+                let selector = function_selector(f);
+                if let Some((existing, _, _)) = entry_points.iter().find(|(_, s, _)| *s == selector)
+                {
+                    if *existing == label {
+                        // A redefinition in the same contract shares the existing entry.
+                        continue;
+                    }
+                    return Err(format!(
+                        "Functions `{}` and `{}` share dispatcher selector 0x{:08x}",
+                        existing, label, selector
+                    ));
+                }
+                if !f.params.is_empty() {
+                    let entry = self.assembler.new_label();
+                    self.entry_labels.insert(label.clone(), entry);
                 }
+                match f.name.as_str() {
+                    "receive" => receive = Some(label.clone()),
+                    "fallback" => fallback = Some(label.clone()),
+                    _ => {}
+                }
+                let value_check = (f.mutability != StateMutability::Payable)
+                    .then(|| format!("{}.{}", c.name, f.name));
+                entry_points.push((label, selector, value_check));
+                metadata.push_str(&format!(
+                    "0x{:08x} {}.{}\n",
+                    selector,
+                    c.name,
+                    function_signature(f)
+                ));
             }
         }
+        self.assembler.set_metadata(metadata.into_bytes());
+
+        // Non-payable entries are reached through a check that the call carries no value.
+        let mut value_checks = Vec::new();
+        let mut targets: std::collections::HashMap<String, Label> =
+            std::collections::HashMap::new();
+        let mut selectors = Vec::new();
+        for (label, selector, value_check) in &entry_points {
+            let function = match self.entry_labels.get(label) {
+                Some(&entry) => entry,
+                None => self.function_labels[label],
+            };
+            let target = match value_check {
+                Some(name) => {
+                    let check = self.assembler.new_label();
+                    value_checks.push((check, function, name.clone()));
+                    check
+                }
+                None => function,
+            };
+            targets.insert(label.clone(), target);
+            selectors.push((target, *selector));
+        }
+        let receive = receive.or(fallback.clone()).map(|label| targets[&label]);
+        let fallback = fallback.map(|label| targets[&label]);
+
+        let empty = self.assembler.new_label();
+        let unknown = self.assembler.new_label();
+        self.assembler.emit_op(OpCode::CallDataSize);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(0);
+        self.assembler.emit_op(OpCode::Eq);
+        self.assembler.emit_jump(OpCode::JumpIf, empty);
+        self.assembler.emit_op(OpCode::CallDataSize);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(4);
+        self.assembler.emit_op(OpCode::Lt);
+        self.assembler.emit_jump(OpCode::JumpIf, unknown);
+
+        for (target, selector) in selectors {
+            self.assembler.emit_op(OpCode::Selector);
+            self.assembler.emit_op(OpCode::Push);
+            self.assembler.emit_u32(selector);
+            self.assembler.emit_op(OpCode::Eq);
+            self.assembler.emit_jump(OpCode::JumpIf, target);
+        }
+
+        self.assembler.bind(unknown);
+        match fallback {
+            Some(target) => self.assembler.emit_jump(OpCode::Jump, target),
+            None => self.gen_revert("Calldata does not start with a known function selector")?,
+        }
+        self.assembler.bind(empty);
+        match receive {
+            Some(target) => self.assembler.emit_jump(OpCode::Jump, target),
+            None => self.gen_revert("No receive or fallback function accepts empty calldata")?,
+        }
+
+        for (check, function, name) in value_checks {
+            self.assembler.bind(check);
+            self.assembler.emit_op(OpCode::CallValue);
+            self.assembler.emit_op(OpCode::Push);
+            self.assembler.emit_u32(0);
+            self.assembler.emit_op(OpCode::Eq);
+            self.assembler.emit_jump(OpCode::JumpIf, function);
+            self.gen_revert(&format!("`{}` is not payable", name))?;
+        }
         Ok(())
     }
 
@@ -74,6 +522,10 @@ impl CodeGenerator {
                 Ok(())
             }
             SourceUnit::Contract(c) => self.gen_contract(c),
+            SourceUnit::ContractInstance(_) => {
+                // Named instantiations are expanded into contracts by monomorphization
+                Ok(())
+            }
             SourceUnit::Event(_) => {
                 // Events are metadata only
                 Ok(())
@@ -82,10 +534,28 @@ impl CodeGenerator {
     }
 
     fn gen_contract(&mut self, c: &ContractDefinition) -> Result<(), String> {
-        // Generate constructor if present
-        for part in &c.parts {
-            if let ContractPart::Constructor(ctor) = part {
-                self.gen_constructor(ctor)?;
+        self.current_contract = c.name.clone();
+        self.state_variable_types = c
+            .parts
+            .iter()
+            .filter_map(|part| match part {
+                ContractPart::StateVariable(var) => Some((var.name.clone(), var.ty.clone())),
+                _ => None,
+            })
+            .collect();
+
+        // The constructor is init code only; the first one is called by the init sequence
+        if let Some(&label) = self.constructor_labels.get(&c.name) {
+            for part in &c.parts {
+                if let ContractPart::Constructor(ctor) = part {
+                    if !self.assembler.is_bound(label) {
+                        self.assembler.bind(label);
+                    }
+                    self.enter_source_function(&format!("{}.constructor", c.name), ctor.span);
+                    self.enter_scope(&ctor.params);
+                    self.gen_calldata_params(&ctor.params, 0)?;
+                    self.gen_constructor(ctor)?;
+                }
             }
         }
 
@@ -93,7 +563,22 @@ impl CodeGenerator {
         for part in &c.parts {
             match part {
                 ContractPart::Function(f) => {
-                    self.current_function = Some(format!("{}_{}", c.name, f.name));
+                    let name = format!("{}_{}", c.name, f.name);
+                    // A redefinition keeps the first body as the entry point.
+                    let label = self.function_labels[&name];
+                    let entry = self.entry_labels.get(&name).copied();
+                    self.current_function = Some(name);
+                    self.enter_source_function(&format!("{}.{}", c.name, f.name), f.span);
+                    self.enter_scope(&f.params);
+                    if let Some(entry) = entry {
+                        if !self.assembler.is_bound(entry) {
+                            self.assembler.bind(entry);
+                            self.gen_entry_arguments(f)?;
+                        }
+                    }
+                    if !self.assembler.is_bound(label) {
+                        self.assembler.bind(label);
+                    }
                     self.gen_function(f)?;
                     self.current_function = None;
                 }
@@ -103,16 +588,382 @@ impl CodeGenerator {
         Ok(())
     }
 
+    /// Decodes the arguments of entry point `f` from the calldata after the selector, then
+    /// falls through to its body.
+    fn gen_entry_arguments(&mut self, f: &FunctionDefinition) -> Result<(), String> {
+        self.gen_calldata_params(&f.params, 4)
+            .map_err(|e| format!("Entry point `{}.{}`: {}", self.current_contract, f.name, e))
+    }
+
+    fn enter_source_function(&mut self, name: &str, span: Span) {
+        self.source_function = name.to_string();
+        self.mark_source(span);
+    }
+
+    /// Attributes the code emitted from here on to `span` in the current function.
+    fn mark_source(&mut self, span: Span) {
+        self.source_span = Some(span);
+        self.assembler
+            .mark_source(span.line as u32, span.column as u32, &self.source_function);
+    }
+
+    fn enter_scope(&mut self, params: &[Parameter]) {
+        self.variable_types = self.state_variable_types.clone();
+        self.reference_variables.clear();
+        self.variable_types.extend(
+            params
+                .iter()
+                .map(|param| (param.name.clone(), param.ty.clone())),
+        );
+        let prefix = format!("{}::", self.current_contract);
+        self.storage_variables = self
+            .storage_slots
+            .keys()
+            .filter_map(|key| key.strip_prefix(&prefix))
+            .filter(|name| !params.iter().any(|param| param.name == *name))
+            .map(str::to_string)
+            .collect();
+        self.storage_references.clear();
+    }
+
+    /// Whether a value of type `ty` fits one slot: not a struct, array or mapping.
+    fn is_scalar(&self, ty: &Type) -> bool {
+        !self.is_aggregate(ty) && !matches!(ty, Type::Mapping(_, _))
+    }
+
+    /// Storage slot `name` refers to in the current scope, if it is a scalar state variable.
+    fn storage_slot(&self, name: &str) -> Option<u32> {
+        if !self.storage_variables.contains(name)
+            || !self
+                .variable_types
+                .get(name)
+                .is_some_and(|ty| self.is_scalar(ty))
+        {
+            return None;
+        }
+        self.storage_slots
+            .get(&format!("{}::{}", self.current_contract, name))
+            .copied()
+    }
+
+    /// Binds `params` from ABI-encoded calldata starting at offset `base`: their heads follow
+    /// one another, see `gen_calldata_value` and `gen_calldata_into`.
+    fn gen_calldata_params(&mut self, params: &[Parameter], base: u32) -> Result<(), String> {
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(base);
+        let base_slot = self.stash_address();
+        let mut position = base;
+        for param in params {
+            let context = |e: String| format!("Parameter `{}`: {}", param.name, e);
+            let head_size = self.calldata_type(&param.ty).map_err(context)?.head_size() as u32;
+            self.assembler.emit_op(OpCode::Push);
+            self.assembler.emit_u32(position);
+            let head = self.stash_address();
+            if matches!(param.ty, Type::Array(_, None)) || !self.is_aggregate(&param.ty) {
+                self.gen_calldata_value(&param.ty, head, base_slot)
+                    .map_err(context)?;
+                self.emit_variable_store(&param.name);
+            } else {
+                let addr = self.variable_address(&param.name);
+                self.assembler.emit_op(OpCode::Push);
+                self.assembler.emit_u32(addr);
+                let dest = self.stash_address();
+                self.gen_calldata_into(&param.ty, head, base_slot, dest)
+                    .map_err(context)?;
+            }
+            position += head_size;
+        }
+        Ok(())
+    }
+
+    /// ABI type the calldata encoding of `ty` follows: structs are tuples of their fields.
+    fn calldata_type(&self, ty: &Type) -> Result<AbiType, String> {
+        match ty {
+            Type::Struct(name) => {
+                let fields = self
+                    .struct_layouts
+                    .get(name)
+                    .ok_or_else(|| format!("Unknown struct `{}`", name))?;
+                let fields = fields
+                    .iter()
+                    .map(|(_, field_ty)| self.calldata_type(field_ty))
+                    .collect::<Result<_, _>>()?;
+                Ok(AbiType::Tuple(fields))
+            }
+            Type::Array(element, size) => Ok(AbiType::Array(
+                Box::new(self.calldata_type(element)?),
+                size.map(|size| size as usize),
+            )),
+            Type::Mapping(_, _) => Err(format!("{:?} cannot be decoded from calldata", ty)),
+            _ => AbiType::parse(&canonical_type_name(ty)).map_err(|e| e.to_string()),
+        }
+    }
+
+    /// Copies the value of type `ty` whose head word sits at the calldata offset held in `head`
+    /// to the memory address held in `dest`. Structs and fixed arrays fill their inline slots
+    /// one component at a time; the components' heads follow one another, in a tail of their
+    /// own when any of them is dynamic. Other values are stored as `gen_calldata_value` pushes
+    /// them.
+    fn gen_calldata_into(
+        &mut self,
+        ty: &Type,
+        head: u32,
+        base: u32,
+        dest: u32,
+    ) -> Result<(), String> {
+        let components: Option<Vec<Type>> = match ty {
+            Type::Struct(name) => self.struct_layouts.get(name).map(|fields| {
+                fields
+                    .iter()
+                    .map(|(_, field_ty)| field_ty.clone())
+                    .collect()
+            }),
+            Type::Array(element, Some(size)) => Some(vec![(**element).clone(); *size as usize]),
+            _ => None,
+        };
+        let Some(components) = components else {
+            self.gen_calldata_value(ty, head, base)?;
+            self.emit_load_at(dest);
+            self.emit_store(is_byte_value(ty));
+            return Ok(());
+        };
+
+        let (head, base) = if self.calldata_type(ty)?.is_dynamic() {
+            self.emit_calldata_tail(head, base);
+            let tail = self.stash_address();
+            (tail, tail)
+        } else {
+            (head, base)
+        };
+        let mut position = 0;
+        let mut offset = 0;
+        for component in &components {
+            self.emit_load_at(head);
+            self.assembler.emit_op(OpCode::Push);
+            self.assembler.emit_u32(position);
+            self.assembler.emit_op(OpCode::Add);
+            let component_head = self.stash_address();
+            self.emit_slot_address(dest, offset);
+            let component_dest = self.stash_address();
+            self.gen_calldata_into(component, component_head, base, component_dest)?;
+            position += self.calldata_type(component)?.head_size() as u32;
+            offset += self.slot_count(component);
+        }
+        Ok(())
+    }
+
+    /// Pushes the value of type `ty` whose head word sits at the calldata offset held in `head`.
+    /// Integers and bools are right-aligned in the word, addresses fill its low 20 bytes and
+    /// fixed bytes are left-aligned. Byte strings and dynamic arrays are held elsewhere: the
+    /// head is their offset from the one held in `base`, where a length word is followed by the
+    /// bytes or by the elements' heads (element offsets count from after the length). A dynamic
+    /// array is copied to a fresh heap header, whose address is pushed; structs and fixed arrays
+    /// have no single value to push and go through `gen_calldata_into`.
+    fn gen_calldata_value(&mut self, ty: &Type, head: u32, base: u32) -> Result<(), String> {
+        let fixed = |generator: &mut Self, start: u32, end: u32| {
+            generator.emit_load_at(head);
+            generator.assembler.emit_op(OpCode::Push);
+            generator.assembler.emit_u32(WORD);
+            generator.assembler.emit_op(OpCode::CallDataSlice);
+            generator.assembler.emit_op(OpCode::Push);
+            generator.assembler.emit_u32(start);
+            generator.assembler.emit_op(OpCode::Push);
+            generator.assembler.emit_u32(end);
+            generator.assembler.emit_op(OpCode::BytesSlice);
+        };
+        match ty {
+            Type::Address => fixed(self, WORD - 20, WORD),
+            Type::FixedBytes(width) => fixed(self, 0, u32::from(*width)),
+            ty if is_byte_value(ty) => {
+                // [tail] -> [tail + 32, length] -> [bytes]
+                self.emit_calldata_tail(head, base);
+                self.assembler.emit_op(OpCode::Dup);
+                self.assembler.emit_op(OpCode::CallDataLoad);
+                self.assembler.emit_op(OpCode::Swap);
+                self.assembler.emit_op(OpCode::Push);
+                self.assembler.emit_u32(WORD);
+                self.assembler.emit_op(OpCode::Add);
+                self.assembler.emit_op(OpCode::Swap);
+                self.assembler.emit_op(OpCode::CallDataSlice);
+            }
+            Type::Array(element, None) => self.gen_calldata_array(element, head, base)?,
+            ty if self.is_aggregate(ty) || matches!(ty, Type::Mapping(_, _)) => {
+                return Err(format!("{:?} cannot be pushed from calldata", ty));
+            }
+            _ => {
+                self.emit_load_at(head);
+                self.assembler.emit_op(OpCode::CallDataLoad);
+            }
+        }
+        Ok(())
+    }
+
+    /// Pushes the calldata offset of the value whose tail offset is the head word held in `head`.
+    fn emit_calldata_tail(&mut self, head: u32, base: u32) {
+        self.emit_load_at(head);
+        self.assembler.emit_op(OpCode::CallDataLoad);
+        self.emit_load_at(base);
+        self.assembler.emit_op(OpCode::Add);
+    }
+
+    /// Copies a dynamic array of `element` values out of calldata; see `gen_calldata_value`.
+    fn gen_calldata_array(&mut self, element: &Type, head: u32, base: u32) -> Result<(), String> {
+        self.emit_calldata_tail(head, base);
+        let length_word = self.stash_address();
+        self.emit_load_at(length_word);
+        self.assembler.emit_op(OpCode::CallDataLoad);
+        let length = self.stash_address();
+        self.emit_load_at(length_word);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(WORD);
+        self.assembler.emit_op(OpCode::Add);
+        let elements = self.stash_address();
+        let element_slots = self.slot_count(element);
+        let element_head_size = self.calldata_type(element)?.head_size() as u32;
+
+        // The header is exactly full: length, capacity, then the elements' slots.
+        self.emit_load_at(length);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(element_slots);
+        self.assembler.emit_op(OpCode::Mul);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(2);
+        self.assembler.emit_op(OpCode::Add);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(WORD);
+        self.assembler.emit_op(OpCode::Mul);
+        self.emit_heap_alloc();
+        let header = self.stash_address();
+        for offset in 0..2 {
+            self.emit_load_at(length);
+            self.emit_slot_address(header, offset);
+            self.assembler.emit_op(OpCode::Store);
+        }
+
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(0);
+        let index = self.stash_address();
+        let loop_label = self.assembler.new_label();
+        let end_label = self.assembler.new_label();
+        self.assembler.bind(loop_label);
+        self.emit_load_at(index);
+        self.emit_load_at(length);
+        self.assembler.emit_op(OpCode::Ge);
+        self.assembler.emit_jump(OpCode::JumpIf, end_label);
+
+        self.emit_load_at(index);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(element_head_size);
+        self.assembler.emit_op(OpCode::Mul);
+        self.emit_load_at(elements);
+        self.assembler.emit_op(OpCode::Add);
+        let element_head = self.stash_address();
+        self.emit_load_at(index);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(element_slots * WORD);
+        self.assembler.emit_op(OpCode::Mul);
+        self.emit_slot_address(header, 2);
+        self.assembler.emit_op(OpCode::Add);
+        let element_dest = self.stash_address();
+        self.gen_calldata_into(element, element_head, elements, element_dest)?;
+
+        self.emit_load_at(index);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(1);
+        self.assembler.emit_op(OpCode::Add);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(index);
+        self.assembler.emit_op(OpCode::Store);
+        self.assembler.emit_jump(OpCode::Jump, loop_label);
+        self.assembler.bind(end_label);
+
+        self.emit_load_at(header);
+        Ok(())
+    }
+
+    /// Static type of `expr` where codegen can tell it without full inference.
+    fn expression_type(&self, expr: &Expression) -> Option<Type> {
+        match expr {
+            Expression::Literal(Literal::String(_)) => Some(Type::String),
+            Expression::Literal(Literal::Bytes(_)) => Some(Type::Bytes),
+            Expression::Literal(Literal::Address(_)) => Some(Type::Address),
+            Expression::Identifier(name) if matches!(name.as_str(), "msg.sender" | "this") => {
+                Some(Type::Address)
+            }
+            Expression::Identifier(name) => self.variable_types.get(name).cloned(),
+            Expression::Call(name, _) if name == "slice" => Some(Type::Bytes),
+            Expression::Call(name, _) => parse_constructor_type(name),
+            Expression::MemberAccess(_, _) | Expression::IndexAccess(_, _) => self.place_type(expr),
+            _ => None,
+        }
+    }
+
+    /// Whether `expr` calls the native value `transfer` builtin rather than a contract's own
+    /// `transfer` function.
+    fn is_builtin_transfer(&self, expr: &Expression) -> bool {
+        matches!(expr, Expression::Call(name, _) if name == "transfer")
+            && !self
+                .function_params
+                .contains_key(&format!("{}_transfer", self.current_contract))
+    }
+
+    /// Whether `expr` leaves a value on the stack; a builtin `transfer` and calls to functions
+    /// without a return type leave nothing behind.
+    fn leaves_value(&self, expr: &Expression) -> bool {
+        match expr {
+            Expression::Call(_, _) if self.is_builtin_transfer(expr) => false,
+            Expression::Call(name, _) => {
+                let label = format!("{}_{}", self.current_contract, name);
+                !self.function_params.contains_key(&label) || self.value_functions.contains(&label)
+            }
+            _ => true,
+        }
+    }
+
+    /// Whether `expr` evaluates to a byte string (`Value::Bytes`) rather than a number.
+    fn is_byte_sequence(&self, expr: &Expression) -> bool {
+        match expr {
+            Expression::Literal(Literal::String(_) | Literal::Bytes(_)) => true,
+            Expression::Identifier(_)
+            | Expression::Call(_, _)
+            | Expression::MemberAccess(_, _)
+            | Expression::IndexAccess(_, _) => self
+                .expression_type(expr)
+                .is_some_and(|ty| is_byte_type(&ty)),
+            Expression::Binary(BinaryOp::Add, left, right) => {
+                self.is_byte_sequence(left) || self.is_byte_sequence(right)
+            }
+            Expression::Ternary(_, then_expr, else_expr) => {
+                self.is_byte_sequence(then_expr) || self.is_byte_sequence(else_expr)
+            }
+            _ => false,
+        }
+    }
+
     fn gen_constructor(&mut self, ctor: &ConstructorDefinition) -> Result<(), String> {
         // Generate constructor bytecode
+        let handler = self.open_function_gas_scope(&ctor.annotations)?;
         self.gen_block(&ctor.body)?;
         self.assembler.emit_op(OpCode::Return);
+        let owner = format!("Constructor of `{}`", self.current_contract);
+        self.close_function_gas_scope(handler, &owner)?;
         Ok(())
     }
 
     fn gen_function(&mut self, f: &FunctionDefinition) -> Result<(), String> {
         // Generate function prologue
         // Push function parameters onto stack (simplified)
+        let handler = self.open_function_gas_scope(&f.annotations)?;
+        // Internal calls enter past the function's scope, inside one opened by the call.
+        let budgeted = self
+            .current_function
+            .as_ref()
+            .and_then(|name| self.budgeted_functions.get(name).map(|&(body, _, _)| body));
+        if let Some(body) = budgeted {
+            if !self.assembler.is_bound(body) {
+                self.assembler.bind(body);
+            }
+        }
 
         // Generate function body
         self.gen_block(&f.body)?;
@@ -124,32 +975,210 @@ impl CodeGenerator {
         } else {
             self.assembler.emit_op(OpCode::Return);
         }
+        let owner = format!("`{}.{}`", self.current_contract, f.name);
+        self.close_function_gas_scope(handler, &owner)?;
+
+        Ok(())
+    }
+
+    /// Opens a gas scope for `@gas_limit`; the VM closes it when the function returns.
+    fn open_function_gas_scope(
+        &mut self,
+        annotations: &[Annotation],
+    ) -> Result<Option<(Label, u32)>, String> {
+        let Some(limit) = gas_limit(annotations)? else {
+            return Ok(None);
+        };
+        let handler = self.assembler.new_label();
+        self.emit_gas_scope_begin(limit, handler);
+        Ok(Some((handler, limit)))
+    }
+
+    /// Overrunning the budget of an entry point or constructor fails the whole call, so its
+    /// handler reverts, naming `owner`.
+    fn close_function_gas_scope(
+        &mut self,
+        handler: Option<(Label, u32)>,
+        owner: &str,
+    ) -> Result<(), String> {
+        if let Some((handler, limit)) = handler {
+            self.assembler.bind(handler);
+            self.gen_revert(&format!("{owner} exceeded its @gas_limit of {limit}"))?;
+        }
+        Ok(())
+    }
+
+    /// Calls a `@gas_limit` function inside a gas scope of its own, so overrunning the budget
+    /// undoes only the call's effects and resumes after it with the zero value it returns.
+    fn gen_budgeted_call(&mut self, body: Label, limit: u32, returns: Option<&Type>) {
+        let failure = self.assembler.new_label();
+        let done = self.assembler.new_label();
+        self.emit_gas_scope_begin(limit, failure);
+        self.assembler.emit_jump(OpCode::Call, body);
+        self.assembler.emit_op(OpCode::GasLimitEnd);
+        self.assembler.emit_jump(OpCode::Jump, done);
+        self.assembler.bind(failure);
+        if let Some(ty) = returns {
+            self.emit_zero_value(ty);
+        }
+        self.assembler.bind(done);
+    }
+
+    /// Pushes the zero value of `ty`: `0`, an empty byte string, or a zeroed struct or array.
+    fn emit_zero_value(&mut self, ty: &Type) {
+        if self.is_aggregate(ty) {
+            let slots = self.slot_count(ty);
+            let region =
+                self.allocate_slots(&format!("$literal{}", self.assembler.code_len()), slots);
+            for slot in 0..slots {
+                self.assembler.emit_op(OpCode::Push);
+                self.assembler.emit_u32(0);
+                self.assembler.emit_op(OpCode::Push);
+                self.assembler.emit_u32(region + slot * WORD);
+                self.assembler.emit_op(OpCode::Store);
+            }
+            self.assembler.emit_op(OpCode::Push);
+            self.assembler.emit_u32(region);
+        } else {
+            // Address 0 reads as zero, and a zero pointer as an empty byte string.
+            self.assembler.emit_op(OpCode::Push);
+            self.assembler.emit_u32(0);
+            self.emit_load(is_byte_value(ty));
+        }
+    }
 
+    /// Fails the call with `msg`, undoing its storage writes and logs.
+    fn gen_revert(&mut self, msg: &str) -> Result<(), String> {
+        self.gen_literal(&Literal::String(msg.to_string()))?;
+        self.assembler.emit_op(OpCode::Revert);
         Ok(())
     }
 
+    fn emit_gas_scope_begin(&mut self, limit: u32, handler: Label) {
+        self.assembler.emit_op(OpCode::GasLimitBegin);
+        self.assembler.emit_u32(limit);
+        self.assembler.emit_label(handler);
+    }
+
     fn gen_block(&mut self, block: &Block) -> Result<(), String> {
-        for stmt in &block.statements {
+        let enclosing = self.source_span;
+        for (index, stmt) in block.statements.iter().enumerate() {
+            if let Some(span) = block.spans.get(index) {
+                self.mark_source(*span);
+            }
             self.gen_statement(stmt)?;
         }
+        // Code after a nested block (loop increments, jumps past `else`) belongs to its owner.
+        if let Some(span) = enclosing {
+            self.mark_source(span);
+        }
+        Ok(())
+    }
+
+    /// Generates the body of a `require_pqc` block, jumping to `failure` as soon as a signature
+    /// verification, bare or bound to a variable, evaluates to false.
+    fn gen_pqc_block(&mut self, block: &Block, failure: Label) -> Result<(), String> {
+        let enclosing = self.source_span;
+        for (index, stmt) in block.statements.iter().enumerate() {
+            if let Some(span) = block.spans.get(index) {
+                self.mark_source(*span);
+            }
+            match stmt {
+                Statement::Expression(expr) if is_signature_verification(expr) => {
+                    self.gen_expression(expr)?;
+                }
+                Statement::VariableDeclaration(name, _, Some(expr))
+                    if is_signature_verification(expr) =>
+                {
+                    self.gen_statement(stmt)?;
+                    self.gen_expression(&Expression::Identifier(name.clone()))?;
+                }
+                _ => {
+                    self.gen_statement(stmt)?;
+                    continue;
+                }
+            }
+            self.gen_unary_op(&UnaryOp::Not)?;
+            self.assembler.emit_jump(OpCode::JumpIf, failure);
+        }
+        if let Some(span) = enclosing {
+            self.mark_source(span);
+        }
         Ok(())
     }
 
     fn gen_statement(&mut self, stmt: &Statement) -> Result<(), String> {
         match stmt {
-            Statement::VariableDeclaration(name, _ty, expr) => {
+            Statement::VariableDeclaration(name, ty, expr) => {
+                let recorded = if !is_byte_type(ty)
+                    && expr.as_ref().is_some_and(|e| self.is_byte_sequence(e))
+                {
+                    Type::Bytes
+                } else {
+                    ty.clone()
+                };
+                self.variable_types.insert(name.clone(), recorded);
+                self.storage_variables.remove(name);
+                if self.is_aggregate(ty) {
+                    self.reference_variables.insert(name.clone());
+                    return self.gen_reference_binding(name, ty, expr.as_ref());
+                }
+                self.reference_variables.remove(name);
+                self.storage_references.remove(name);
                 if let Some(ref expr) = expr {
                     self.gen_expression(expr)?;
                 } else {
-                    // Keep declared variables addressable even when parser could not recover an initializer.
+                    // Keep declared variables addressable even when parser could not recover an
+                    // initializer; a zero word also reads as an empty byte string.
+                    let addr = self.variable_address(name);
                     self.assembler.emit_op(OpCode::Push);
                     self.assembler.emit_u32(0);
+                    self.assembler.emit_op(OpCode::Push);
+                    self.assembler.emit_u32(addr);
+                    self.assembler.emit_op(OpCode::Store);
+                    return Ok(());
                 }
                 self.emit_variable_store(name);
             }
             Statement::Assignment(name, expr) => {
-                self.gen_expression(expr)?;
-                self.emit_variable_store(name);
+                let ty = self.variable_types.get(name).cloned();
+                match ty {
+                    Some(ty) if self.reference_variables.contains(name) => {
+                        self.gen_reference_binding(name, &ty, Some(expr))?;
+                    }
+                    Some(ty) if self.storage_variables.contains(name) && !self.is_scalar(&ty) => {
+                        let slot =
+                            self.storage_slots[&format!("{}::{}", self.current_contract, name)];
+                        self.assembler.emit_op(OpCode::Push);
+                        self.assembler.emit_u32(slot);
+                        let base = self.stash_address();
+                        self.gen_storage_write(base, 0, &ty, expr)?;
+                    }
+                    Some(ty) if self.is_aggregate(&ty) => {
+                        let addr = self.variable_address(name);
+                        self.assembler.emit_op(OpCode::Push);
+                        self.assembler.emit_u32(addr);
+                        let base = self.stash_address();
+                        self.gen_write(base, 0, &ty, expr)?;
+                    }
+                    _ => {
+                        self.gen_expression(expr)?;
+                        self.emit_variable_store(name);
+                    }
+                }
+            }
+            Statement::ElementAssignment(target, expr) => {
+                if matches!(target, Expression::MemberAccess(_, member) if member == "length") {
+                    return Err("Array `length` is read-only; use push() and pop()".to_string());
+                }
+                if self.is_storage_place(target) {
+                    let ty = self.gen_storage_place(target)?;
+                    let base = self.stash_address();
+                    return self.gen_storage_write(base, 0, &ty, expr);
+                }
+                let ty = self.gen_place(target)?;
+                let base = self.stash_address();
+                self.gen_write(base, 0, &ty, expr)?;
             }
             Statement::Return(expr) => {
                 if let Some(ref expr) = expr {
@@ -157,176 +1186,99 @@ impl CodeGenerator {
                 }
                 self.assembler.emit_op(OpCode::Return);
             }
-            Statement::Require(expr, _msg) => {
+            Statement::Require(expr, msg) => {
                 self.gen_expression(expr)?;
-                // Jump to error handler if condition is false
-                let error_label = format!(
-                    "{}_require_error",
-                    self.current_function
-                        .as_ref()
-                        .unwrap_or(&"global".to_string())
-                );
-                self.assembler.emit_op(OpCode::JumpIf);
-                let patch_pos = self.assembler.code_len();
-                self.assembler.emit_u32(0);
-                self.jump_patches.push((patch_pos, error_label.clone()));
-                // Error handler would emit revert - for now, halt
-                self.label_positions
-                    .insert(error_label, self.assembler.code_len());
-                self.assembler.emit_op(OpCode::Halt);
-            }
-            Statement::Revert(_msg) => {
-                // Revert operation - halt execution
-                self.assembler.emit_op(OpCode::Halt);
+                // Continue past the failure path when the condition holds
+                let pass_label = self.assembler.new_label();
+                self.assembler.emit_jump(OpCode::JumpIf, pass_label);
+                self.gen_revert(msg)?;
+                self.assembler.bind(pass_label);
             }
+            Statement::Revert(msg) => self.gen_revert(msg)?,
             Statement::If(cond, then_block, else_block) => {
                 self.gen_expression(cond)?;
 
                 // Emit JumpIf to skip then block if condition is false
-                let else_label = format!(
-                    "{}_if_else_{}",
-                    self.current_function
-                        .as_ref()
-                        .unwrap_or(&"global".to_string()),
-                    self.assembler.code_len()
-                );
-                let end_label = format!(
-                    "{}_if_end_{}",
-                    self.current_function
-                        .as_ref()
-                        .unwrap_or(&"global".to_string()),
-                    self.assembler.code_len()
-                );
+                let else_label = self.assembler.new_label();
 
-                self.assembler.emit_op(OpCode::JumpIf);
-                let patch_pos = self.assembler.code_len();
-                self.assembler.emit_u32(0);
-                self.jump_patches.push((patch_pos, else_label.clone()));
+                // `JumpIf` branches on true, so negate to skip the then branch when false
+                self.gen_unary_op(&UnaryOp::Not)?;
+                self.assembler.emit_jump(OpCode::JumpIf, else_label);
 
                 // Generate then block
                 self.gen_block(then_block)?;
 
-                if else_block.is_some() {
+                if let Some(ref else_block) = else_block {
                     // Emit Jump to skip else block
-                    self.assembler.emit_op(OpCode::Jump);
-                    let patch_pos = self.assembler.code_len();
-                    self.assembler.emit_u32(0);
-                    self.jump_patches.push((patch_pos, end_label.clone()));
+                    let end_label = self.assembler.new_label();
+                    self.assembler.emit_jump(OpCode::Jump, end_label);
 
                     // Mark else block start
-                    self.label_positions
-                        .insert(else_label, self.assembler.code_len());
-
-                    // Generate else block
-                    if let Some(ref else_block) = else_block {
-                        self.gen_block(else_block)?;
-                    }
+                    self.assembler.bind(else_label);
+                    self.gen_block(else_block)?;
 
                     // Mark end of if statement
-                    self.label_positions
-                        .insert(end_label, self.assembler.code_len());
+                    self.assembler.bind(end_label);
                 } else {
                     // No else block, mark else label as end
-                    self.label_positions
-                        .insert(else_label, self.assembler.code_len());
+                    self.assembler.bind(else_label);
                 }
             }
             Statement::Emit(event_name, args) => {
-                // Event emission - push event name and args
-                // Push event name hash as identifier
-                use std::collections::hash_map::DefaultHasher;
-                use std::hash::{Hash, Hasher};
-                let mut hasher = DefaultHasher::new();
-                event_name.hash(&mut hasher);
-                let event_id = hasher.finish() as u32;
+                let count = u8::try_from(args.len())
+                    .map_err(|_| format!("Event `{}` has more than 255 arguments", event_name))?;
+                // Undeclared events are identified by their bare name.
+                let topic = match self.events.get(event_name) {
+                    Some(event) => event_topic(event),
+                    None => topic_of(event_name),
+                };
                 self.assembler.emit_op(OpCode::Push);
-                self.assembler.emit_u32(event_id);
-
-                // Push arguments
+                self.assembler.emit_u32(topic);
                 for arg in args {
                     self.gen_expression(arg)?;
                 }
-                // Event logging would be handled by VM runtime
+                self.assembler.emit_op(OpCode::Log);
+                self.assembler.emit_u8(count);
             }
             Statement::RequirePqc(pqc_block, fallback) => {
-                // require_pqc block: execute block, if any PQC verification fails, execute fallback
-                // The block should contain PQC verification calls
-                // If all verifications pass, continue; otherwise execute fallback (revert/return)
-
-                let success_label = format!(
-                    "{}_require_pqc_success_{}",
-                    self.current_function
-                        .as_ref()
-                        .unwrap_or(&"global".to_string()),
-                    self.assembler.code_len()
-                );
-                let failure_label = format!(
-                    "{}_require_pqc_failure_{}",
-                    self.current_function
-                        .as_ref()
-                        .unwrap_or(&"global".to_string()),
-                    self.assembler.code_len()
-                );
-                let end_label = format!(
-                    "{}_require_pqc_end_{}",
-                    self.current_function
-                        .as_ref()
-                        .unwrap_or(&"global".to_string()),
-                    self.assembler.code_len()
-                );
-
-                // Execute the PQC block
-                // Each PQC verification in the block should push a bool result
-                self.gen_block(pqc_block)?;
-
-                // After the block, check if all verifications passed
-                // For simplicity, we assume the last value on stack is the verification result
-                // In a full implementation, we'd track all verification results
-                self.assembler.emit_op(OpCode::JumpIf);
-                let patch_pos = self.assembler.code_len();
-                self.assembler.emit_u32(0);
-                self.jump_patches.push((patch_pos, failure_label.clone()));
+                // Every signature verification in the block branches to the fallback when it
+                // fails; falling off the end of the block skips the fallback.
+                let failure_label = self.assembler.new_label();
+                let end_label = self.assembler.new_label();
+                self.gen_pqc_block(pqc_block, failure_label)?;
+                self.assembler.emit_jump(OpCode::Jump, end_label);
 
-                // Success path - jump to end
-                self.label_positions
-                    .insert(success_label.clone(), self.assembler.code_len());
-                self.assembler.emit_op(OpCode::Jump);
-                let patch_pos = self.assembler.code_len();
-                self.assembler.emit_u32(0);
-                self.jump_patches.push((patch_pos, end_label.clone()));
-
-                // Failure path - execute fallback
-                self.label_positions
-                    .insert(failure_label, self.assembler.code_len());
-                if let Some(fallback_stmt) = fallback.as_deref() {
-                    match fallback_stmt {
-                        Statement::Revert(_msg) => {
-                            self.assembler.emit_op(OpCode::Halt); // Revert = halt
-                        }
-                        Statement::Return(expr) => {
-                            if let Some(ref expr) = expr {
-                                self.gen_expression(expr)?;
-                            }
-                            self.assembler.emit_op(OpCode::Return);
-                        }
-                        _ => {
-                            // Default: halt on failure
-                            self.assembler.emit_op(OpCode::Halt);
+                self.assembler.bind(failure_label);
+                match fallback.as_deref() {
+                    Some(Statement::Revert(msg)) => self.gen_revert(msg)?,
+                    Some(Statement::Return(expr)) => {
+                        if let Some(expr) = expr {
+                            self.gen_expression(expr)?;
                         }
+                        self.assembler.emit_op(OpCode::Return);
                     }
-                } else {
-                    // No fallback specified - default to halt
-                    self.assembler.emit_op(OpCode::Halt);
+                    // Without a revert or return fallback, a failed verification reverts.
+                    _ => self.gen_revert("require_pqc: signature verification failed")?,
                 }
-
-                // End label
-                self.label_positions
-                    .insert(end_label, self.assembler.code_len());
+                self.assembler.bind(end_label);
             }
             Statement::Expression(expr) => {
                 self.gen_expression(expr)?;
                 // Pop result if not used
-                self.assembler.emit_op(OpCode::Pop);
+                if self.leaves_value(expr) {
+                    self.assembler.emit_op(OpCode::Pop);
+                }
+            }
+            Statement::WithGasLimit(budget, block) => {
+                // Overrunning the budget reverts the block and resumes after it.
+                let budget = gas_budget_literal(budget).ok_or_else(|| {
+                    "with_gas_limit budget must be a positive integer literal".to_string()
+                })?;
+                let end_label = self.assembler.new_label();
+                self.emit_gas_scope_begin(budget, end_label);
+                self.gen_block(block)?;
+                self.assembler.emit_op(OpCode::GasLimitEnd);
+                self.assembler.bind(end_label);
             }
             Statement::For(iterator, start_expr, end_expr, body) => {
                 // Canonical lowering for parsed range loops:
@@ -334,42 +1286,21 @@ impl CodeGenerator {
                 self.gen_expression(start_expr)?;
                 self.emit_variable_store(iterator);
 
-                let loop_id = self.assembler.code_len();
-                let loop_check_label = format!(
-                    "{}_for_check_{}",
-                    self.current_function.as_deref().unwrap_or("global"),
-                    loop_id
-                );
-                let loop_body_label = format!(
-                    "{}_for_body_{}",
-                    self.current_function.as_deref().unwrap_or("global"),
-                    loop_id
-                );
-                let loop_end_label = format!(
-                    "{}_for_end_{}",
-                    self.current_function.as_deref().unwrap_or("global"),
-                    loop_id
-                );
+                let loop_check_label = self.assembler.new_label();
+                let loop_body_label = self.assembler.new_label();
+                let loop_end_label = self.assembler.new_label();
 
-                self.label_positions
-                    .insert(loop_check_label.clone(), self.assembler.code_len());
+                self.assembler.bind(loop_check_label);
 
                 self.emit_variable_load(iterator);
                 self.gen_expression(end_expr)?;
                 self.assembler.emit_op(OpCode::Lt);
-                self.assembler.emit_op(OpCode::JumpIf);
-                let patch_pos = self.assembler.code_len();
-                self.assembler.emit_u32(0);
-                self.jump_patches.push((patch_pos, loop_body_label.clone()));
+                self.assembler.emit_jump(OpCode::JumpIf, loop_body_label);
 
                 // Condition failed; jump to loop end.
-                self.assembler.emit_op(OpCode::Jump);
-                let patch_pos = self.assembler.code_len();
-                self.assembler.emit_u32(0);
-                self.jump_patches.push((patch_pos, loop_end_label.clone()));
+                self.assembler.emit_jump(OpCode::Jump, loop_end_label);
 
-                self.label_positions
-                    .insert(loop_body_label, self.assembler.code_len());
+                self.assembler.bind(loop_body_label);
                 self.gen_block(body)?;
 
                 // i = i + 1
@@ -379,13 +1310,9 @@ impl CodeGenerator {
                 self.assembler.emit_op(OpCode::Add);
                 self.emit_variable_store(iterator);
 
-                self.assembler.emit_op(OpCode::Jump);
-                let patch_pos = self.assembler.code_len();
-                self.assembler.emit_u32(0);
-                self.jump_patches.push((patch_pos, loop_check_label));
+                self.assembler.emit_jump(OpCode::Jump, loop_check_label);
 
-                self.label_positions
-                    .insert(loop_end_label, self.assembler.code_len());
+                self.assembler.bind(loop_end_label);
             }
         }
         Ok(())
@@ -396,9 +1323,87 @@ impl CodeGenerator {
             Expression::Literal(lit) => {
                 self.gen_literal(lit)?;
             }
+            Expression::Identifier(_)
+            | Expression::MemberAccess(_, _)
+            | Expression::IndexAccess(_, _)
+                if self.is_storage_place(expr) && self.place_type(expr).is_some() =>
+            {
+                let ty = self.gen_storage_place(expr)?;
+                if matches!(ty, Type::Mapping(_, _)) {
+                    return Err("Mappings are read one entry at a time".to_string());
+                }
+                if self.is_scalar(&ty) {
+                    self.emit_storage_load(&ty);
+                } else {
+                    // Structs and arrays are copied to a region reserved for this code site.
+                    let source = self.stash_address();
+                    let region = self.allocate_slots(
+                        &format!("$literal{}", self.assembler.code_len()),
+                        self.slot_count(&ty),
+                    );
+                    self.assembler.emit_op(OpCode::Push);
+                    self.assembler.emit_u32(region);
+                    let dest = self.stash_address();
+                    self.gen_copy_from_storage(source, dest, &ty)?;
+                    self.assembler.emit_op(OpCode::Push);
+                    self.assembler.emit_u32(region);
+                }
+            }
+            Expression::Identifier(name)
+                if self
+                    .variable_types
+                    .get(name)
+                    .is_some_and(|ty| self.is_aggregate(ty)) =>
+            {
+                // Struct and array values are passed around by address.
+                self.gen_place(expr)?;
+            }
+            Expression::Identifier(name) if !self.variable_types.contains_key(name) => {
+                match name.as_str() {
+                    "msg.sender" => self.assembler.emit_op(OpCode::Caller),
+                    "msg.value" => self.assembler.emit_op(OpCode::CallValue),
+                    "this" => self.assembler.emit_op(OpCode::Address),
+                    "msg.sender.balance" => {
+                        self.assembler.emit_op(OpCode::Caller);
+                        self.assembler.emit_op(OpCode::Balance);
+                    }
+                    _ => self.emit_variable_load(name),
+                }
+            }
             Expression::Identifier(name) => {
                 self.emit_variable_load(name);
             }
+            Expression::Call(name, _) if self.struct_layouts.contains_key(name) => {
+                self.gen_literal_region(&Type::Struct(name.clone()), expr)?;
+            }
+            Expression::Call(name, args)
+                if name.rsplit_once('.').is_some_and(|(receiver, _)| {
+                    matches!(
+                        self.place_type(&path_expression(receiver)),
+                        Some(Type::Array(_, _))
+                    )
+                }) =>
+            {
+                let (receiver, method) = name.rsplit_once('.').unwrap_or_default();
+                if !self.gen_array_method(&path_expression(receiver), method, args)? {
+                    return Err(format!(
+                        "Method `{}` is not supported on `{}`",
+                        method, receiver
+                    ));
+                }
+            }
+            Expression::Call(_, args) if self.is_builtin_transfer(expr) => {
+                // Native value sent from this contract: [to, amount] -> []
+                let [to, amount] = args.as_slice() else {
+                    return Err(format!(
+                        "`transfer` takes 2 arguments but {} were given",
+                        args.len()
+                    ));
+                };
+                self.gen_expression(to)?;
+                self.gen_expression(amount)?;
+                self.assembler.emit_op(OpCode::Transfer);
+            }
             Expression::Call(name, args) => {
                 // Generate arguments
                 for arg in args {
@@ -433,76 +1438,188 @@ impl CodeGenerator {
                             }
                         }
                     }
+                } else if name == "slice" {
+                    // Stack: [value, start, end] -> [value[start..end]]
+                    self.assembler.emit_op(OpCode::BytesSlice);
+                } else if let (Some(target), [arg]) =
+                    (parse_constructor_type(name), args.as_slice())
+                {
+                    let source = self.expression_type(arg);
+                    let from_bytes = self.is_byte_sequence(arg) || source == Some(Type::Address);
+                    self.gen_conversion(from_bytes, &target)?;
                 } else {
-                    // Regular function call
-                    self.assembler.emit_op(OpCode::Call);
-                    // Would need function address
+                    // Regular function call: the arguments move into the callee's parameter
+                    // slots, last argument first since it is on top of the stack.
+                    let label = format!("{}_{}", self.current_contract, name);
+                    let params = self
+                        .function_params
+                        .get(&label)
+                        .cloned()
+                        .ok_or_else(|| format!("Call to undefined function `{}`", name))?;
+                    if params.len() != args.len() {
+                        return Err(format!(
+                            "`{}` takes {} arguments but {} were given",
+                            name,
+                            params.len(),
+                            args.len()
+                        ));
+                    }
+                    for param in params.iter().rev() {
+                        if self.is_aggregate(&param.ty) {
+                            return Err(format!(
+                                "Passing `{}` to `{}` by value is not supported yet",
+                                param.name, name
+                            ));
+                        }
+                        let addr = self.allocate_slots_in(&label, &param.name, 1);
+                        self.assembler.emit_op(OpCode::Push);
+                        self.assembler.emit_u32(addr);
+                        self.emit_store(is_byte_value(&param.ty));
+                    }
+                    match self.budgeted_functions.get(&label).cloned() {
+                        Some((body, limit, returns)) => {
+                            self.gen_budgeted_call(body, limit, returns.as_ref())
+                        }
+                        None => self
+                            .assembler
+                            .emit_jump(OpCode::Call, self.function_labels[&label]),
+                    }
+                }
+            }
+            Expression::MethodCall(receiver, method, args) => {
+                if !self.gen_array_method(receiver, method, args)? {
+                    return Err(format!(
+                        "Method `{}` is not supported on this receiver",
+                        method
+                    ));
+                }
+            }
+            Expression::MemberAccess(obj, member)
+                if member == "length" && self.is_byte_sequence(obj) =>
+            {
+                self.gen_expression(obj)?;
+                self.assembler.emit_op(OpCode::BytesLen);
+            }
+            Expression::MemberAccess(obj, member)
+                if member == "balance"
+                    && matches!(obj.as_ref(), Expression::Identifier(name) if name == "this") =>
+            {
+                self.assembler.emit_op(OpCode::SelfBalance);
+            }
+            Expression::MemberAccess(obj, member)
+                if member == "balance" && self.expression_type(obj) == Some(Type::Address) =>
+            {
+                self.gen_expression(obj)?;
+                self.assembler.emit_op(OpCode::Balance);
+            }
+            Expression::MemberAccess(obj, member) if member == "length" => {
+                match self.place_type(obj) {
+                    Some(Type::Array(_, Some(size))) => {
+                        self.assembler.emit_op(OpCode::Push);
+                        self.assembler.emit_u32(size);
+                    }
+                    Some(Type::Array(_, None)) if self.is_storage_place(obj) => {
+                        self.gen_storage_place(obj)?;
+                        self.assembler.emit_op(OpCode::SLoad);
+                    }
+                    Some(Type::Array(_, None)) => {
+                        // A dynamic array's header starts with its length.
+                        self.gen_place(obj)?;
+                        self.assembler.emit_op(OpCode::Load);
+                        self.assembler.emit_op(OpCode::Load);
+                    }
+                    _ => {
+                        return Err("`length` needs an array, `Bytes` or `String` value".to_string())
+                    }
+                }
+            }
+            Expression::MemberAccess(_, _) | Expression::IndexAccess(_, _)
+                if self.place_type(expr).is_some() =>
+            {
+                let ty = self.gen_place(expr)?;
+                if !self.is_aggregate(&ty) {
+                    self.emit_load(is_byte_value(&ty));
                 }
             }
             Expression::MemberAccess(obj, _member) => {
+                // Members of values without a known layout (e.g. enum variants) evaluate the base.
                 self.gen_expression(obj)?;
-                // Member access - in full implementation, would load member from struct/object
-                // For now, member name is available in 'member' parameter for future implementation
+            }
+            Expression::Binary(op @ (BinaryOp::And | BinaryOp::Or), left, right) => {
+                // Short-circuit: keep the left operand when it decides the result.
+                let end_label = self.assembler.new_label();
+                self.gen_expression(left)?;
+                self.assembler.emit_op(OpCode::Dup);
+                if *op == BinaryOp::And {
+                    self.gen_unary_op(&UnaryOp::Not)?;
+                }
+                self.assembler.emit_jump(OpCode::JumpIf, end_label);
+                self.assembler.emit_op(OpCode::Pop);
+                self.gen_expression(right)?;
+                self.assembler.bind(end_label);
             }
             Expression::Binary(op, left, right) => {
                 self.gen_expression(left)?;
                 self.gen_expression(right)?;
-                self.gen_binary_op(op)?;
+                if self.is_byte_sequence(left) || self.is_byte_sequence(right) {
+                    self.gen_byte_op(op)?;
+                } else {
+                    self.gen_binary_op(op)?;
+                    if let Some(ty) = self.arithmetic_type(expr) {
+                        self.emit_range_check(&ty)?;
+                    }
+                }
             }
-            Expression::Unary(op, expr) => {
-                self.gen_expression(expr)?;
+            Expression::Unary(op, operand) => {
+                self.gen_expression(operand)?;
                 self.gen_unary_op(op)?;
+                if let Some(ty) = self.arithmetic_type(expr) {
+                    self.emit_range_check(&ty)?;
+                }
             }
             Expression::IndexAccess(obj, idx) => {
+                // Indexing a value without a layout (e.g. a byte string) leaves base and index.
                 self.gen_expression(obj)?;
                 self.gen_expression(idx)?;
-                // Index access: calculate offset and load
-                // Stack: [array, index] -> [value]
-                // For now, assume array is in memory and index is offset
-                // In full implementation, would calculate byte offset based on element size
+            }
+            Expression::ArrayLiteral(elements) => {
+                let element_ty = elements
+                    .first()
+                    .and_then(|element| self.expression_type(element))
+                    .unwrap_or(Type::UInt256);
+                let ty = Type::Array(Box::new(element_ty), Some(elements.len() as u32));
+                self.gen_literal_region(&ty, expr)?;
+            }
+            Expression::ObjectLiteral(_) => {
+                return Err(
+                    "Object literal needs a struct type from a declaration or assignment"
+                        .to_string(),
+                );
             }
             Expression::Ternary(cond, then_expr, else_expr) => {
                 // Ternary: condition ? then_expr : else_expr
-                let else_label = format!(
-                    "{}_ternary_else_{}",
-                    self.current_function
-                        .as_ref()
-                        .unwrap_or(&"global".to_string()),
-                    self.assembler.code_len()
-                );
-                let end_label = format!(
-                    "{}_ternary_end_{}",
-                    self.current_function
-                        .as_ref()
-                        .unwrap_or(&"global".to_string()),
-                    self.assembler.code_len()
-                );
+                let else_label = self.assembler.new_label();
+                let end_label = self.assembler.new_label();
 
                 self.gen_expression(cond)?;
-                self.assembler.emit_op(OpCode::JumpIf);
-                let patch_pos = self.assembler.code_len();
-                self.assembler.emit_u32(0);
-                self.jump_patches.push((patch_pos, else_label.clone()));
+                // `JumpIf` branches on true, so negate to skip the then branch when false
+                self.gen_unary_op(&UnaryOp::Not)?;
+                self.assembler.emit_jump(OpCode::JumpIf, else_label);
 
                 // Generate then expression
                 self.gen_expression(then_expr)?;
 
                 // Jump to end
-                self.assembler.emit_op(OpCode::Jump);
-                let patch_pos = self.assembler.code_len();
-                self.assembler.emit_u32(0);
-                self.jump_patches.push((patch_pos, end_label.clone()));
+                self.assembler.emit_jump(OpCode::Jump, end_label);
 
                 // Mark else label
-                self.label_positions
-                    .insert(else_label, self.assembler.code_len());
+                self.assembler.bind(else_label);
 
                 // Generate else expression
                 self.gen_expression(else_expr)?;
 
                 // Mark end label
-                self.label_positions
-                    .insert(end_label, self.assembler.code_len());
+                self.assembler.bind(end_label);
             }
         }
         Ok(())
@@ -510,20 +1627,31 @@ impl CodeGenerator {
 
     fn gen_literal(&mut self, lit: &Literal) -> Result<(), String> {
         match lit {
-            Literal::Number(n) => {
-                self.assembler.emit_op(OpCode::Push);
-                self.assembler.emit_u32(*n as u32);
-            }
+            Literal::Number(n) => match i32::try_from(*n) {
+                Ok(value) => {
+                    self.assembler.emit_op(OpCode::Push);
+                    self.assembler.emit_i32(value);
+                }
+                // Wider than the `Push` immediate: convert the big-endian constant to an `I64`.
+                Err(_) if *n <= U256::from(i64::MAX as u64) => {
+                    self.assembler.emit_const(&n.low_u64().to_be_bytes());
+                    self.assembler.emit_op(OpCode::BytesToInt);
+                }
+                Err(_) => {
+                    return Err(format!(
+                        "Integer literal {} exceeds the VM's 64-bit integer range",
+                        n
+                    ))
+                }
+            },
             Literal::Bool(b) => {
                 self.assembler.emit_op(OpCode::Push);
                 self.assembler.emit_u32(if *b { 1 } else { 0 });
             }
             Literal::String(s) => {
-                self.assembler.emit_op(OpCode::LoadImm);
-                self.assembler.emit_bytes(s.as_bytes());
+                self.assembler.emit_const(s.as_bytes());
             }
             Literal::Address(addr) => {
-                self.assembler.emit_op(OpCode::LoadImm);
                 // Parse hex address payload (without 0x prefix) into bytes.
                 let hex = addr.strip_prefix("0x").unwrap_or(addr);
                 if hex.len() % 2 == 0 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
@@ -538,14 +1666,13 @@ impl CodeGenerator {
                         out.push(byte);
                         i += 2;
                     }
-                    self.assembler.emit_bytes(&out);
+                    self.assembler.emit_const(&out);
                 } else {
                     return Err("Address literal must be hex encoded".to_string());
                 }
             }
             Literal::Bytes(bytes) => {
-                self.assembler.emit_op(OpCode::LoadImm);
-                self.assembler.emit_bytes(bytes);
+                self.assembler.emit_const(bytes);
             }
         }
         Ok(())
@@ -571,6 +1698,19 @@ impl CodeGenerator {
         Ok(())
     }
 
+    fn gen_byte_op(&mut self, op: &BinaryOp) -> Result<(), String> {
+        match op {
+            BinaryOp::Add => self.assembler.emit_op(OpCode::BytesConcat),
+            BinaryOp::Eq => self.assembler.emit_op(OpCode::BytesEq),
+            BinaryOp::Ne => {
+                self.assembler.emit_op(OpCode::BytesEq);
+                self.gen_unary_op(&UnaryOp::Not)?;
+            }
+            _ => return Err(format!("Unsupported operation {:?} on byte strings", op)),
+        }
+        Ok(())
+    }
+
     fn gen_unary_op(&mut self, op: &UnaryOp) -> Result<(), String> {
         match op {
             UnaryOp::Neg => {
@@ -581,10 +1721,9 @@ impl CodeGenerator {
                 self.assembler.emit_op(OpCode::Sub);
             }
             UnaryOp::Not => {
-                // Logical not: push 1, swap, eq
+                // Logical not: compare against false
                 self.assembler.emit_op(OpCode::Push);
-                self.assembler.emit_u32(1);
-                self.assembler.emit_op(OpCode::Swap);
+                self.assembler.emit_u32(0);
                 self.assembler.emit_op(OpCode::Eq);
             }
             _ => {
@@ -594,30 +1733,1360 @@ impl CodeGenerator {
         Ok(())
     }
 
-    fn variable_address(&self, name: &str) -> u32 {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
+    /// Byte address of `name`'s slot in the current function, allocated on first use.
+    /// Struct and fixed-array variables get all of their slots; references get one.
+    fn variable_address(&mut self, name: &str) -> u32 {
+        let slots = match self.variable_types.get(name) {
+            Some(ty) if !self.reference_variables.contains(name) => self.slot_count(ty),
+            _ => 1,
+        };
+        self.allocate_slots(name, slots)
+    }
+
+    /// Byte address of the `slots`-word region named `name` in the current function.
+    fn allocate_slots(&mut self, name: &str, slots: u32) -> u32 {
+        let function = self
+            .current_function
+            .clone()
+            .unwrap_or_else(|| "global".to_string());
+        self.allocate_slots_in(&function, name, slots)
+    }
 
-        let mut hasher = DefaultHasher::new();
-        self.current_function
-            .as_deref()
-            .unwrap_or("global")
-            .hash(&mut hasher);
-        name.hash(&mut hasher);
-        hasher.finish() as u32
+    /// Byte address of the `slots`-word region named `name` in `function`.
+    fn allocate_slots_in(&mut self, function: &str, name: &str, slots: u32) -> u32 {
+        let key = format!("{}::{}", function, name);
+        if let Some(&addr) = self.static_slots.get(&key) {
+            return addr;
+        }
+        let addr = self.next_static;
+        self.next_static += slots * WORD;
+        self.static_slots.insert(key, addr);
+        addr
+    }
+
+    /// Whether `name`'s slot holds a pointer to a byte string rather than the value itself.
+    fn holds_byte_string(&self, name: &str) -> bool {
+        !self.reference_variables.contains(name)
+            && self.variable_types.get(name).is_some_and(is_byte_value)
     }
 
     fn emit_variable_store(&mut self, name: &str) {
+        if let Some(slot) = self.storage_slot(name) {
+            self.assembler.emit_op(OpCode::Push);
+            self.assembler.emit_u32(slot);
+            self.assembler.emit_op(OpCode::SStore);
+            return;
+        }
         let addr = self.variable_address(name);
         self.assembler.emit_op(OpCode::Push);
         self.assembler.emit_u32(addr);
+        self.emit_store(self.holds_byte_string(name));
+    }
+
+    /// Stores the value under the address on top of the stack. A byte string is copied to the
+    /// heap and the slot receives a pointer to its length word.
+    fn emit_store(&mut self, byte_string: bool) {
+        if !byte_string {
+            self.assembler.emit_op(OpCode::Store);
+            return;
+        }
+        // [value, slot] -> slot = free; free = end of the copy
+        self.emit_load_at(FREE_POINTER);
+        self.assembler.emit_op(OpCode::Swap);
+        self.assembler.emit_op(OpCode::Store);
+        self.emit_load_at(FREE_POINTER);
+        self.assembler.emit_op(OpCode::StoreBytes);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(FREE_POINTER);
+        self.assembler.emit_op(OpCode::Store);
+    }
+
+    /// Loads the value at the address on top of the stack, following byte string pointers.
+    fn emit_load(&mut self, byte_string: bool) {
+        self.assembler.emit_op(OpCode::Load);
+        if byte_string {
+            self.assembler.emit_op(OpCode::LoadBytes);
+        }
+    }
+
+    /// Pushes the word stored at `addr`.
+    fn emit_load_at(&mut self, addr: u32) {
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(addr);
+        self.assembler.emit_op(OpCode::Load);
+    }
+
+    // Memory layout: memory is linear and every slot is one 32-byte word. `0x00..0x40` always
+    // reads as zero, `0x40` holds the free memory pointer and named slots follow from `0x60`;
+    // the heap starts after the last named slot. A struct occupies its fields' slots in
+    // declaration order and a fixed array `T[N]` occupies `N` consecutive elements, both inline.
+    // A byte string slot points to a heap copy: a length word followed by the bytes. A dynamic
+    // array slot points to a heap header of length and capacity words followed by the elements;
+    // a zero pointer reads as empty either way. Dynamic arrays live in variables and mapping
+    // entries only, so copying a struct or fixed array never has to follow a pointer.
+
+    fn is_aggregate(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Struct(name) => self.struct_layouts.contains_key(name),
+            Type::Array(_, _) => true,
+            _ => false,
+        }
+    }
+
+    fn is_aggregate_literal(&self, expr: &Expression) -> bool {
+        match expr {
+            Expression::ArrayLiteral(_) | Expression::ObjectLiteral(_) => true,
+            Expression::Call(name, _) => self.struct_layouts.contains_key(name),
+            _ => false,
+        }
+    }
+
+    fn slot_count(&self, ty: &Type) -> u32 {
+        match ty {
+            Type::Struct(name) => self.struct_layouts.get(name).map_or(1, |fields| {
+                fields
+                    .iter()
+                    .map(|(_, field_ty)| self.slot_count(field_ty))
+                    .sum()
+            }),
+            Type::Array(element, Some(size)) => size * self.slot_count(element),
+            _ => 1,
+        }
+    }
+
+    fn field_offset(&self, struct_name: &str, member: &str) -> Result<(u32, Type), String> {
+        let fields = self
+            .struct_layouts
+            .get(struct_name)
+            .ok_or_else(|| format!("Unknown struct `{}`", struct_name))?;
+        let mut offset = 0;
+        for (field, field_ty) in fields {
+            if field == member {
+                return Ok((offset, field_ty.clone()));
+            }
+            offset += self.slot_count(field_ty);
+        }
+        Err(format!(
+            "Struct `{}` has no member `{}`",
+            struct_name, member
+        ))
+    }
+
+    /// Type stored at `expr` when it names a variable, struct field, array element or mapping entry.
+    fn place_type(&self, expr: &Expression) -> Option<Type> {
+        match expr {
+            Expression::Identifier(name) => self.variable_types.get(name).cloned(),
+            Expression::MemberAccess(object, member) => match self.place_type(object)? {
+                Type::Struct(name) => self
+                    .field_offset(&name, member)
+                    .ok()
+                    .map(|(_, field_ty)| field_ty),
+                _ => None,
+            },
+            Expression::IndexAccess(object, _) => match self.place_type(object)? {
+                Type::Array(element, _) => Some(*element),
+                Type::Mapping(_, value) => Some(*value),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Whether `expr` is a variable or mapping entry, the places a dynamic array may live.
+    fn owns_tail(&self, expr: &Expression) -> bool {
+        match expr {
+            Expression::Identifier(_) => true,
+            Expression::IndexAccess(object, _) => {
+                matches!(self.place_type(object), Some(Type::Mapping(_, _)))
+            }
+            _ => false,
+        }
+    }
+
+    /// Pushes the memory address of `expr` and returns the type stored there.
+    fn gen_place(&mut self, expr: &Expression) -> Result<Type, String> {
+        match expr {
+            Expression::Identifier(name) => {
+                let ty = self
+                    .variable_types
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| format!("Unknown variable `{}`", name))?;
+                if self.reference_variables.contains(name) {
+                    self.emit_variable_load(name);
+                } else {
+                    let addr = self.variable_address(name);
+                    self.assembler.emit_op(OpCode::Push);
+                    self.assembler.emit_u32(addr);
+                }
+                Ok(ty)
+            }
+            Expression::MemberAccess(object, member) => {
+                let object_ty = self.gen_place(object)?;
+                let Type::Struct(name) = &object_ty else {
+                    return Err(format!(
+                        "Cannot access member `{}` of `{:?}`",
+                        member, object_ty
+                    ));
+                };
+                let (offset, field_ty) = self.field_offset(name, member)?;
+                if offset > 0 {
+                    self.assembler.emit_op(OpCode::Push);
+                    self.assembler.emit_u32(offset * WORD);
+                    self.assembler.emit_op(OpCode::Add);
+                }
+                Ok(field_ty)
+            }
+            Expression::IndexAccess(object, index) => match self.place_type(object) {
+                Some(Type::Mapping(_, value)) => {
+                    // Mapping entries are keyed by the access expression until storage hashing lands.
+                    let slots = self.slot_count(&value);
+                    let addr = self.allocate_slots(&format!("{:?}", expr), slots);
+                    self.assembler.emit_op(OpCode::Push);
+                    self.assembler.emit_u32(addr);
+                    Ok(*value)
+                }
+                Some(Type::Array(element, size)) => {
+                    if size.is_none() && !self.owns_tail(object) {
+                        return Err(
+                            "Dynamic arrays nested in a struct or array are not addressable yet"
+                                .to_string(),
+                        );
+                    }
+                    self.gen_place(object)?;
+                    if size.is_none() {
+                        // Follow the slot's pointer to the array header.
+                        self.assembler.emit_op(OpCode::Load);
+                    }
+                    let base = self.stash_address();
+                    self.gen_expression(index)?;
+                    match size {
+                        Some(size) => {
+                            self.assembler.emit_op(OpCode::Push);
+                            self.assembler.emit_u32(size);
+                        }
+                        None => {
+                            self.emit_slot_address(base, 0);
+                            self.assembler.emit_op(OpCode::Load);
+                        }
+                    }
+                    self.assembler.emit_op(OpCode::BoundsCheck);
+                    self.assembler.emit_op(OpCode::Push);
+                    self.assembler.emit_u32(self.slot_count(&element) * WORD);
+                    self.assembler.emit_op(OpCode::Mul);
+                    // Dynamic array elements start after the length and capacity words.
+                    self.emit_slot_address(base, if size.is_none() { 2 } else { 0 });
+                    self.assembler.emit_op(OpCode::Add);
+                    Ok(*element)
+                }
+                _ => Err("Only arrays and mappings can be indexed in place".to_string()),
+            },
+            _ => Err("Expression does not name a variable, field or element".to_string()),
+        }
+    }
+
+    // Storage layout: a state variable starts at its slot from `storage_slots` and takes as many
+    // consecutive slots as its memory layout has words, so struct fields and fixed array elements
+    // sit at the same offsets in both. A slot holds a value itself, byte strings included. A
+    // dynamic array's slot holds its length and its elements follow one another from
+    // `SlotHash(slot, 0)`; a mapping's slot stays empty and the entry for `key` starts at
+    // `SlotHash(slot, key)`. Entries and elements take as many slots as their value type.
+    // Derived slots are 256-bit words, so offsets are added with `SlotAdd` rather than `Add`.
+
+    /// Whether `expr` names a struct, array or mapping in storage, or a field, element or entry
+    /// of one.
+    fn is_storage_place(&self, expr: &Expression) -> bool {
+        match expr {
+            Expression::Identifier(name) => {
+                self.storage_references.contains(name)
+                    || (self.storage_variables.contains(name)
+                        && self
+                            .variable_types
+                            .get(name)
+                            .is_some_and(|ty| !self.is_scalar(ty)))
+            }
+            Expression::MemberAccess(object, _) | Expression::IndexAccess(object, _) => {
+                self.is_storage_place(object)
+            }
+            _ => false,
+        }
+    }
+
+    /// Pushes the storage slot of `expr`, a storage place, and returns the type stored there.
+    fn gen_storage_place(&mut self, expr: &Expression) -> Result<Type, String> {
+        match expr {
+            Expression::Identifier(name) => {
+                let ty = self
+                    .variable_types
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| format!("Unknown variable `{}`", name))?;
+                if self.storage_references.contains(name) {
+                    self.emit_variable_load(name);
+                } else {
+                    let slot = self.storage_slots[&format!("{}::{}", self.current_contract, name)];
+                    self.assembler.emit_op(OpCode::Push);
+                    self.assembler.emit_u32(slot);
+                }
+                Ok(ty)
+            }
+            Expression::MemberAccess(object, member) => {
+                let object_ty = self.gen_storage_place(object)?;
+                let Type::Struct(name) = &object_ty else {
+                    return Err(format!(
+                        "Cannot access member `{}` of `{:?}`",
+                        member, object_ty
+                    ));
+                };
+                let (offset, field_ty) = self.field_offset(name, member)?;
+                if offset > 0 {
+                    self.assembler.emit_op(OpCode::Push);
+                    self.assembler.emit_u32(offset);
+                    self.assembler.emit_op(OpCode::SlotAdd);
+                }
+                Ok(field_ty)
+            }
+            Expression::IndexAccess(object, index) => match self.place_type(object) {
+                Some(Type::Mapping(_, value)) => {
+                    self.gen_storage_place(object)?;
+                    self.gen_expression(index)?;
+                    self.assembler.emit_op(OpCode::SlotHash);
+                    Ok(*value)
+                }
+                Some(Type::Array(element, Some(size))) => {
+                    // [slot] -> [SlotAdd(slot, index * element slots)]
+                    self.gen_storage_place(object)?;
+                    self.gen_expression(index)?;
+                    self.assembler.emit_op(OpCode::Push);
+                    self.assembler.emit_u32(size);
+                    self.assembler.emit_op(OpCode::BoundsCheck);
+                    self.emit_element_offset(&element);
+                    self.assembler.emit_op(OpCode::SlotAdd);
+                    Ok(*element)
+                }
+                Some(Type::Array(element, None)) => {
+                    // [slot] -> [SlotAdd(SlotHash(slot, 0), index * element slots)]
+                    self.gen_storage_place(object)?;
+                    let array = self.stash_address();
+                    self.emit_load_at(array);
+                    self.assembler.emit_op(OpCode::Push);
+                    self.assembler.emit_u32(0);
+                    self.assembler.emit_op(OpCode::SlotHash);
+                    self.gen_expression(index)?;
+                    self.emit_load_at(array);
+                    self.assembler.emit_op(OpCode::SLoad);
+                    self.assembler.emit_op(OpCode::BoundsCheck);
+                    self.emit_element_offset(&element);
+                    self.assembler.emit_op(OpCode::SlotAdd);
+                    Ok(*element)
+                }
+                _ => Err("Only arrays and mappings can be indexed in place".to_string()),
+            },
+            _ => Err("Expression does not name a variable, field or element".to_string()),
+        }
+    }
+
+    /// Multiplies the index on top of the stack by the slots an `element` takes.
+    fn emit_element_offset(&mut self, element: &Type) {
+        let slots = self.slot_count(element);
+        if slots > 1 {
+            self.assembler.emit_op(OpCode::Push);
+            self.assembler.emit_u32(slots);
+            self.assembler.emit_op(OpCode::Mul);
+        }
+    }
+
+    /// Loads the value of type `ty` from the storage slot on top of the stack.
+    fn emit_storage_load(&mut self, ty: &Type) {
+        self.assembler.emit_op(if is_byte_value(ty) {
+            OpCode::SLoadBytes
+        } else {
+            OpCode::SLoad
+        });
+    }
+
+    /// Pushes the storage slot held in `base` plus `offset` slots.
+    fn emit_storage_slot(&mut self, base: u32, offset: u32) {
+        self.emit_load_at(base);
+        if offset > 0 {
+            self.assembler.emit_op(OpCode::Push);
+            self.assembler.emit_u32(offset);
+            self.assembler.emit_op(OpCode::SlotAdd);
+        }
+    }
+
+    /// Pushes the slot the elements of the dynamic array whose slot is held in `array` start at.
+    fn emit_storage_elements(&mut self, array: u32) {
+        self.emit_load_at(array);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(0);
+        self.assembler.emit_op(OpCode::SlotHash);
+    }
+
+    /// Stores `expr`, of type `ty`, at `offset` slots past the storage slot held in `base`.
+    /// Struct and array literals are written field by field; other structs and arrays are
+    /// copied from memory.
+    fn gen_storage_write(
+        &mut self,
+        base: u32,
+        offset: u32,
+        ty: &Type,
+        expr: &Expression,
+    ) -> Result<(), String> {
+        match (ty, expr) {
+            (Type::Struct(name), Expression::ObjectLiteral(values)) => {
+                let fields = self.struct_layouts.get(name).cloned().unwrap_or_default();
+                let mut field_offset = offset;
+                for (field, field_ty) in &fields {
+                    if let Some((_, value)) = values.iter().find(|(key, _)| key == field) {
+                        self.gen_storage_write(base, field_offset, field_ty, value)?;
+                    }
+                    field_offset += self.slot_count(field_ty);
+                }
+                Ok(())
+            }
+            (Type::Struct(name), Expression::Call(callee, args)) if callee == name => {
+                if let [literal @ Expression::ObjectLiteral(_)] = args.as_slice() {
+                    return self.gen_storage_write(base, offset, ty, literal);
+                }
+                let fields = self.struct_layouts.get(name).cloned().unwrap_or_default();
+                let mut field_offset = offset;
+                for ((_, field_ty), value) in fields.iter().zip(args) {
+                    self.gen_storage_write(base, field_offset, field_ty, value)?;
+                    field_offset += self.slot_count(field_ty);
+                }
+                Ok(())
+            }
+            (Type::Array(element, Some(_)), Expression::ArrayLiteral(elements)) => {
+                let element_slots = self.slot_count(element);
+                let mut element_offset = offset;
+                for value in elements {
+                    self.gen_storage_write(base, element_offset, element, value)?;
+                    element_offset += element_slots;
+                }
+                Ok(())
+            }
+            (Type::Array(element, None), Expression::ArrayLiteral(elements)) => {
+                self.assembler.emit_op(OpCode::Push);
+                self.assembler.emit_u32(elements.len() as u32);
+                self.emit_storage_slot(base, offset);
+                self.assembler.emit_op(OpCode::SStore);
+                self.emit_storage_slot(base, offset);
+                let array = self.stash_address();
+                self.emit_storage_elements(array);
+                let start = self.stash_address();
+                let element_slots = self.slot_count(element);
+                let mut element_offset = 0;
+                for value in elements {
+                    self.gen_storage_write(start, element_offset, element, value)?;
+                    element_offset += element_slots;
+                }
+                Ok(())
+            }
+            (Type::Mapping(_, _), _) => Err("Mappings cannot be assigned as a whole".to_string()),
+            _ if self.is_aggregate(ty) => {
+                if self.is_storage_place(expr) {
+                    return Err(
+                        "Copying a struct or array from storage to storage is not supported yet"
+                            .to_string(),
+                    );
+                }
+                self.gen_expression(expr)?;
+                let source = self.stash_address();
+                self.emit_storage_slot(base, offset);
+                let dest = self.stash_address();
+                self.gen_copy_to_storage(source, dest, ty)
+            }
+            _ => {
+                self.gen_expression(expr)?;
+                self.emit_storage_slot(base, offset);
+                self.assembler.emit_op(OpCode::SStore);
+                Ok(())
+            }
+        }
+    }
+
+    /// Copies the value of type `ty` at the memory address held in `source` to the storage slot
+    /// held in `dest`, one scalar at a time. A dynamic array's memory slot points to its header.
+    fn gen_copy_to_storage(&mut self, source: u32, dest: u32, ty: &Type) -> Result<(), String> {
+        match ty {
+            Type::Struct(name) => {
+                let fields = self.struct_layouts.get(name).cloned().unwrap_or_default();
+                let mut offset = 0;
+                for (_, field_ty) in &fields {
+                    self.emit_slot_address(source, offset);
+                    let field_source = self.stash_address();
+                    self.emit_storage_slot(dest, offset);
+                    let field_dest = self.stash_address();
+                    self.gen_copy_to_storage(field_source, field_dest, field_ty)?;
+                    offset += self.slot_count(field_ty);
+                }
+                Ok(())
+            }
+            Type::Array(element, Some(size)) => {
+                self.assembler.emit_op(OpCode::Push);
+                self.assembler.emit_u32(*size);
+                let length = self.stash_address();
+                self.gen_copy_elements_to_storage(length, source, dest, element)
+            }
+            Type::Array(element, None) => {
+                // [header] -> dest = length; elements from header + 2 words to SlotHash(dest, 0)
+                self.emit_load_at(source);
+                self.assembler.emit_op(OpCode::Load);
+                let header = self.stash_address();
+                self.emit_slot_address(header, 0);
+                self.assembler.emit_op(OpCode::Load);
+                let length = self.stash_address();
+                self.emit_load_at(length);
+                self.emit_load_at(dest);
+                self.assembler.emit_op(OpCode::SStore);
+                self.emit_slot_address(header, 2);
+                let elements = self.stash_address();
+                self.emit_storage_elements(dest);
+                let start = self.stash_address();
+                self.gen_copy_elements_to_storage(length, elements, start, element)
+            }
+            Type::Mapping(_, _) => Err("Mappings cannot be copied".to_string()),
+            _ => {
+                self.emit_load_at(source);
+                self.emit_load(is_byte_value(ty));
+                self.emit_load_at(dest);
+                self.assembler.emit_op(OpCode::SStore);
+                Ok(())
+            }
+        }
+    }
+
+    /// Copies the number of `element` values held in `length` from the memory address held in
+    /// `source` to consecutive storage slots from the one held in `dest`.
+    fn gen_copy_elements_to_storage(
+        &mut self,
+        length: u32,
+        source: u32,
+        dest: u32,
+        element: &Type,
+    ) -> Result<(), String> {
+        let element_slots = self.slot_count(element);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(0);
+        let index = self.stash_address();
+        let loop_label = self.assembler.new_label();
+        let end_label = self.assembler.new_label();
+        self.assembler.bind(loop_label);
+        self.emit_load_at(index);
+        self.emit_load_at(length);
+        self.assembler.emit_op(OpCode::Ge);
+        self.assembler.emit_jump(OpCode::JumpIf, end_label);
+
+        self.emit_load_at(index);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(element_slots * WORD);
+        self.assembler.emit_op(OpCode::Mul);
+        self.emit_load_at(source);
+        self.assembler.emit_op(OpCode::Add);
+        let element_source = self.stash_address();
+        self.emit_load_at(dest);
+        self.emit_load_at(index);
+        self.emit_element_offset(element);
+        self.assembler.emit_op(OpCode::SlotAdd);
+        let element_dest = self.stash_address();
+        self.gen_copy_to_storage(element_source, element_dest, element)?;
+
+        self.emit_load_at(index);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(1);
+        self.assembler.emit_op(OpCode::Add);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(index);
+        self.assembler.emit_op(OpCode::Store);
+        self.assembler.emit_jump(OpCode::Jump, loop_label);
+        self.assembler.bind(end_label);
+        Ok(())
+    }
+
+    /// Copies the value of type `ty` at the storage slot held in `source` to the memory address
+    /// held in `dest`; a dynamic array gets a fresh heap header that is exactly full.
+    fn gen_copy_from_storage(&mut self, source: u32, dest: u32, ty: &Type) -> Result<(), String> {
+        match ty {
+            Type::Struct(name) => {
+                let fields = self.struct_layouts.get(name).cloned().unwrap_or_default();
+                let mut offset = 0;
+                for (_, field_ty) in &fields {
+                    self.emit_storage_slot(source, offset);
+                    let field_source = self.stash_address();
+                    self.emit_slot_address(dest, offset);
+                    let field_dest = self.stash_address();
+                    self.gen_copy_from_storage(field_source, field_dest, field_ty)?;
+                    offset += self.slot_count(field_ty);
+                }
+                Ok(())
+            }
+            Type::Array(element, Some(size)) => {
+                self.assembler.emit_op(OpCode::Push);
+                self.assembler.emit_u32(*size);
+                let length = self.stash_address();
+                self.gen_copy_elements_from_storage(length, source, dest, element)
+            }
+            Type::Array(element, None) => {
+                self.emit_load_at(source);
+                self.assembler.emit_op(OpCode::SLoad);
+                let length = self.stash_address();
+                let element_slots = self.slot_count(element);
+                self.emit_load_at(length);
+                self.assembler.emit_op(OpCode::Push);
+                self.assembler.emit_u32(element_slots);
+                self.assembler.emit_op(OpCode::Mul);
+                self.assembler.emit_op(OpCode::Push);
+                self.assembler.emit_u32(2);
+                self.assembler.emit_op(OpCode::Add);
+                self.assembler.emit_op(OpCode::Push);
+                self.assembler.emit_u32(WORD);
+                self.assembler.emit_op(OpCode::Mul);
+                self.emit_heap_alloc();
+                self.assembler.emit_op(OpCode::Dup);
+                self.emit_load_at(dest);
+                self.assembler.emit_op(OpCode::Store);
+                let header = self.stash_address();
+                for offset in 0..2 {
+                    self.emit_load_at(length);
+                    self.emit_slot_address(header, offset);
+                    self.assembler.emit_op(OpCode::Store);
+                }
+                self.emit_storage_elements(source);
+                let start = self.stash_address();
+                self.emit_slot_address(header, 2);
+                let elements = self.stash_address();
+                self.gen_copy_elements_from_storage(length, start, elements, element)
+            }
+            Type::Mapping(_, _) => Err("Mappings cannot be copied".to_string()),
+            _ => {
+                self.emit_load_at(source);
+                self.emit_storage_load(ty);
+                self.emit_load_at(dest);
+                self.emit_store(is_byte_value(ty));
+                Ok(())
+            }
+        }
+    }
+
+    /// Copies the number of `element` values held in `length` from consecutive storage slots
+    /// from the one held in `source` to the memory address held in `dest`.
+    fn gen_copy_elements_from_storage(
+        &mut self,
+        length: u32,
+        source: u32,
+        dest: u32,
+        element: &Type,
+    ) -> Result<(), String> {
+        let element_slots = self.slot_count(element);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(0);
+        let index = self.stash_address();
+        let loop_label = self.assembler.new_label();
+        let end_label = self.assembler.new_label();
+        self.assembler.bind(loop_label);
+        self.emit_load_at(index);
+        self.emit_load_at(length);
+        self.assembler.emit_op(OpCode::Ge);
+        self.assembler.emit_jump(OpCode::JumpIf, end_label);
+
+        self.emit_load_at(source);
+        self.emit_load_at(index);
+        self.emit_element_offset(element);
+        self.assembler.emit_op(OpCode::SlotAdd);
+        let element_source = self.stash_address();
+        self.emit_load_at(index);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(element_slots * WORD);
+        self.assembler.emit_op(OpCode::Mul);
+        self.emit_load_at(dest);
+        self.assembler.emit_op(OpCode::Add);
+        let element_dest = self.stash_address();
+        self.gen_copy_from_storage(element_source, element_dest, element)?;
+
+        self.emit_load_at(index);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(1);
+        self.assembler.emit_op(OpCode::Add);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(index);
+        self.assembler.emit_op(OpCode::Store);
+        self.assembler.emit_jump(OpCode::Jump, loop_label);
+        self.assembler.bind(end_label);
+        Ok(())
+    }
+
+    /// Lowers `push(value)` and `pop()` on a dynamic array in storage; both leave the new length.
+    fn gen_storage_array_method(
+        &mut self,
+        receiver: &Expression,
+        element: &Type,
+        method: &str,
+        args: &[Expression],
+    ) -> Result<bool, String> {
+        match (method, args) {
+            ("push", [value]) => {
+                self.gen_storage_place(receiver)?;
+                let array = self.stash_address();
+                self.emit_storage_elements(array);
+                self.emit_load_at(array);
+                self.assembler.emit_op(OpCode::SLoad);
+                self.emit_element_offset(element);
+                self.assembler.emit_op(OpCode::SlotAdd);
+                let slot = self.stash_address();
+                self.gen_storage_write(slot, 0, element, value)?;
+
+                self.emit_load_at(array);
+                self.assembler.emit_op(OpCode::SLoad);
+                self.assembler.emit_op(OpCode::Push);
+                self.assembler.emit_u32(1);
+                self.assembler.emit_op(OpCode::Add);
+                self.assembler.emit_op(OpCode::Dup);
+                self.emit_load_at(array);
+                self.assembler.emit_op(OpCode::SStore);
+                Ok(true)
+            }
+            ("pop", []) => {
+                self.gen_storage_place(receiver)?;
+                let array = self.stash_address();
+                // Popping an empty array fails the same way as reading past its end.
+                self.assembler.emit_op(OpCode::Push);
+                self.assembler.emit_u32(0);
+                self.emit_load_at(array);
+                self.assembler.emit_op(OpCode::SLoad);
+                self.assembler.emit_op(OpCode::BoundsCheck);
+                self.assembler.emit_op(OpCode::Pop);
+
+                self.emit_load_at(array);
+                self.assembler.emit_op(OpCode::SLoad);
+                self.assembler.emit_op(OpCode::Push);
+                self.assembler.emit_u32(1);
+                self.assembler.emit_op(OpCode::Sub);
+                self.assembler.emit_op(OpCode::Dup);
+                self.emit_load_at(array);
+                self.assembler.emit_op(OpCode::SStore);
+
+                // Clear the removed element so a later push starts from zeroed slots.
+                self.assembler.emit_op(OpCode::Dup);
+                self.emit_element_offset(element);
+                self.emit_storage_elements(array);
+                self.assembler.emit_op(OpCode::Swap);
+                self.assembler.emit_op(OpCode::SlotAdd);
+                let slot = self.stash_address();
+                for offset in 0..self.slot_count(element) {
+                    self.assembler.emit_op(OpCode::Push);
+                    self.assembler.emit_u32(0);
+                    self.emit_storage_slot(slot, offset);
+                    self.assembler.emit_op(OpCode::SStore);
+                }
+                Ok(true)
+            }
+            ("push", _) | ("pop", _) => Err(format!(
+                "`{}` takes {} argument(s)",
+                method,
+                usize::from(method == "push")
+            )),
+            _ => Ok(false),
+        }
+    }
+
+    /// Pops an address into a fresh scratch slot and returns the slot.
+    fn stash_address(&mut self) -> u32 {
+        let slot = self.allocate_slots(&format!("$scratch{}", self.assembler.code_len()), 1);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(slot);
+        self.assembler.emit_op(OpCode::Store);
+        slot
+    }
+
+    /// Pushes the address held in `base` plus `offset` slots.
+    fn emit_slot_address(&mut self, base: u32, offset: u32) {
+        self.emit_load_at(base);
+        if offset > 0 {
+            self.assembler.emit_op(OpCode::Push);
+            self.assembler.emit_u32(offset * WORD);
+            self.assembler.emit_op(OpCode::Add);
+        }
+    }
+
+    /// Reserves `bytes` (on top of the stack) of heap and replaces them with the region's address.
+    fn emit_heap_alloc(&mut self) {
+        // [bytes] -> [free]; free = free + bytes
+        self.emit_load_at(FREE_POINTER);
+        self.assembler.emit_op(OpCode::Swap);
+        self.emit_load_at(FREE_POINTER);
+        self.assembler.emit_op(OpCode::Add);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(FREE_POINTER);
+        self.assembler.emit_op(OpCode::Store);
+    }
+
+    /// Stores `expr`, of type `ty`, at `offset` slots past the address held in `base`.
+    /// Struct and array literals are written field by field; other aggregates are copied.
+    fn gen_write(
+        &mut self,
+        base: u32,
+        offset: u32,
+        ty: &Type,
+        expr: &Expression,
+    ) -> Result<(), String> {
+        match (ty, expr) {
+            (Type::Struct(name), Expression::ObjectLiteral(values)) => {
+                let fields = self.struct_layouts.get(name).cloned().unwrap_or_default();
+                let mut field_offset = offset;
+                for (field, field_ty) in &fields {
+                    if let Some((_, value)) = values.iter().find(|(key, _)| key == field) {
+                        self.gen_write(base, field_offset, field_ty, value)?;
+                    }
+                    field_offset += self.slot_count(field_ty);
+                }
+                Ok(())
+            }
+            (Type::Struct(name), Expression::Call(callee, args)) if callee == name => {
+                if let [literal @ Expression::ObjectLiteral(_)] = args.as_slice() {
+                    return self.gen_write(base, offset, ty, literal);
+                }
+                let fields = self.struct_layouts.get(name).cloned().unwrap_or_default();
+                let mut field_offset = offset;
+                for ((_, field_ty), value) in fields.iter().zip(args) {
+                    self.gen_write(base, field_offset, field_ty, value)?;
+                    field_offset += self.slot_count(field_ty);
+                }
+                Ok(())
+            }
+            (Type::Array(element, Some(_)), Expression::ArrayLiteral(elements)) => {
+                let element_slots = self.slot_count(element);
+                let mut element_offset = offset;
+                for value in elements {
+                    self.gen_write(base, element_offset, element, value)?;
+                    element_offset += element_slots;
+                }
+                Ok(())
+            }
+            (Type::Array(element, None), Expression::ArrayLiteral(elements)) => {
+                let count = elements.len() as u32;
+                let element_slots = self.slot_count(element);
+                self.assembler.emit_op(OpCode::Push);
+                self.assembler.emit_u32((2 + count * element_slots) * WORD);
+                self.emit_heap_alloc();
+                self.assembler.emit_op(OpCode::Dup);
+                self.emit_slot_address(base, offset);
+                self.assembler.emit_op(OpCode::Store);
+                let header = self.stash_address();
+                for word in 0..2 {
+                    self.assembler.emit_op(OpCode::Push);
+                    self.assembler.emit_u32(count);
+                    self.emit_slot_address(header, word);
+                    self.assembler.emit_op(OpCode::Store);
+                }
+                let mut element_offset = 2;
+                for value in elements {
+                    self.gen_write(header, element_offset, element, value)?;
+                    element_offset += element_slots;
+                }
+                Ok(())
+            }
+            (Type::Array(element, None), _) => {
+                self.gen_expression(expr)?;
+                self.assembler.emit_op(OpCode::Load);
+                let source = self.stash_address();
+                let element_slots = self.slot_count(element);
+                self.gen_dynamic_array_copy(source, base, offset, element_slots);
+                Ok(())
+            }
+            _ if self.is_aggregate(ty) => {
+                // [source] -> MCopy(destination, source, size)
+                self.gen_expression(expr)?;
+                self.emit_slot_address(base, offset);
+                self.assembler.emit_op(OpCode::Swap);
+                self.assembler.emit_op(OpCode::Push);
+                self.assembler.emit_u32(self.slot_count(ty) * WORD);
+                self.assembler.emit_op(OpCode::MCopy);
+                Ok(())
+            }
+            _ => {
+                self.gen_expression(expr)?;
+                self.emit_slot_address(base, offset);
+                self.emit_store(is_byte_value(ty));
+                Ok(())
+            }
+        }
+    }
+
+    /// Pushes the byte size of the dynamic array header held in `header` and its elements.
+    fn emit_array_size(&mut self, header: u32, element_slots: u32) {
+        self.emit_slot_address(header, 0);
+        self.assembler.emit_op(OpCode::Load);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(element_slots);
+        self.assembler.emit_op(OpCode::Mul);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(2);
+        self.assembler.emit_op(OpCode::Add);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(WORD);
+        self.assembler.emit_op(OpCode::Mul);
+    }
+
+    /// Copies the dynamic array whose header address is held in `source` to a fresh heap region
+    /// and points the slot `offset` slots past the address held in `base` at it.
+    fn gen_dynamic_array_copy(&mut self, source: u32, base: u32, offset: u32, element_slots: u32) {
+        self.emit_array_size(source, element_slots);
+        let size = self.stash_address();
+        self.emit_load_at(size);
+        self.emit_heap_alloc();
+        self.assembler.emit_op(OpCode::Dup);
+        self.emit_slot_address(base, offset);
+        self.assembler.emit_op(OpCode::Store);
+        let copy = self.stash_address();
+
+        self.emit_load_at(copy);
+        self.emit_load_at(source);
+        self.emit_load_at(size);
+        self.assembler.emit_op(OpCode::MCopy);
+
+        // The copy is exactly full.
+        self.emit_slot_address(copy, 0);
+        self.assembler.emit_op(OpCode::Load);
+        self.emit_slot_address(copy, 1);
+        self.assembler.emit_op(OpCode::Store);
+    }
+
+    /// Writes a struct or array literal into a region reserved for this code site and pushes
+    /// the region's address.
+    fn gen_literal_region(&mut self, ty: &Type, expr: &Expression) -> Result<(), String> {
+        let slots = self.slot_count(ty);
+        let region = self.allocate_slots(&format!("$literal{}", self.assembler.code_len()), slots);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(region);
+        let base = self.stash_address();
+        self.gen_write(base, 0, ty, expr)?;
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(region);
+        Ok(())
+    }
+
+    /// Points struct or array local `name` at a literal's fresh region or at an existing place,
+    /// in memory or in storage.
+    fn gen_reference_binding(
+        &mut self,
+        name: &str,
+        ty: &Type,
+        expr: Option<&Expression>,
+    ) -> Result<(), String> {
+        if let Some(expr) = expr.filter(|expr| self.is_storage_place(expr)) {
+            self.gen_storage_place(expr)?;
+            self.storage_references.insert(name.to_string());
+            self.emit_variable_store(name);
+            return Ok(());
+        }
+        self.storage_references.remove(name);
+        match expr {
+            Some(expr) if self.is_aggregate_literal(expr) => self.gen_literal_region(ty, expr)?,
+            Some(expr) => self.gen_expression(expr)?,
+            None => {
+                let slots = self.slot_count(ty);
+                let region =
+                    self.allocate_slots(&format!("$literal{}", self.assembler.code_len()), slots);
+                self.assembler.emit_op(OpCode::Push);
+                self.assembler.emit_u32(region);
+            }
+        }
+        self.emit_variable_store(name);
+        Ok(())
+    }
+
+    /// Lowers `push(value)` and `pop()` on a dynamic array; both leave the new length.
+    fn gen_array_method(
+        &mut self,
+        receiver: &Expression,
+        method: &str,
+        args: &[Expression],
+    ) -> Result<bool, String> {
+        let Some(Type::Array(element, None)) = self.place_type(receiver) else {
+            return Ok(false);
+        };
+        if self.is_storage_place(receiver) {
+            return self.gen_storage_array_method(receiver, &element, method, args);
+        }
+        if !self.owns_tail(receiver) {
+            return Err(
+                "Dynamic arrays nested in a struct or array are not addressable yet".to_string(),
+            );
+        }
+        let element_slots = self.slot_count(&element);
+
+        match (method, args) {
+            ("push", [value]) => {
+                self.gen_place(receiver)?;
+                let slot = self.stash_address();
+                self.emit_slot_address(slot, 0);
+                self.assembler.emit_op(OpCode::Load);
+                let header = self.stash_address();
+                self.gen_array_grow(slot, header, element_slots);
+
+                self.emit_slot_address(header, 0);
+                self.assembler.emit_op(OpCode::Load);
+                self.assembler.emit_op(OpCode::Push);
+                self.assembler.emit_u32(element_slots * WORD);
+                self.assembler.emit_op(OpCode::Mul);
+                self.emit_slot_address(header, 2);
+                self.assembler.emit_op(OpCode::Add);
+                let element_address = self.stash_address();
+                self.gen_write(element_address, 0, &element, value)?;
+
+                self.emit_slot_address(header, 0);
+                self.assembler.emit_op(OpCode::Load);
+                self.assembler.emit_op(OpCode::Push);
+                self.assembler.emit_u32(1);
+                self.assembler.emit_op(OpCode::Add);
+                self.assembler.emit_op(OpCode::Dup);
+                self.emit_slot_address(header, 0);
+                self.assembler.emit_op(OpCode::Store);
+                Ok(true)
+            }
+            ("pop", []) => {
+                self.gen_place(receiver)?;
+                self.assembler.emit_op(OpCode::Load);
+                let header = self.stash_address();
+                // Popping an empty array fails the same way as reading past its end.
+                self.assembler.emit_op(OpCode::Push);
+                self.assembler.emit_u32(0);
+                self.emit_slot_address(header, 0);
+                self.assembler.emit_op(OpCode::Load);
+                self.assembler.emit_op(OpCode::BoundsCheck);
+                self.assembler.emit_op(OpCode::Pop);
+
+                self.emit_slot_address(header, 0);
+                self.assembler.emit_op(OpCode::Load);
+                self.assembler.emit_op(OpCode::Push);
+                self.assembler.emit_u32(1);
+                self.assembler.emit_op(OpCode::Sub);
+                self.assembler.emit_op(OpCode::Dup);
+                self.emit_slot_address(header, 0);
+                self.assembler.emit_op(OpCode::Store);
+
+                // Clear the removed element so a later push starts from zeroed slots.
+                self.assembler.emit_op(OpCode::Dup);
+                self.assembler.emit_op(OpCode::Push);
+                self.assembler.emit_u32(element_slots * WORD);
+                self.assembler.emit_op(OpCode::Mul);
+                self.emit_slot_address(header, 2);
+                self.assembler.emit_op(OpCode::Add);
+                let slot = self.stash_address();
+                for offset in 0..element_slots {
+                    self.assembler.emit_op(OpCode::Push);
+                    self.assembler.emit_u32(0);
+                    self.emit_slot_address(slot, offset);
+                    self.assembler.emit_op(OpCode::Store);
+                }
+                Ok(true)
+            }
+            ("push", _) | ("pop", _) => Err(format!(
+                "`{}` takes {} argument(s)",
+                method,
+                usize::from(method == "push")
+            )),
+            _ => Ok(false),
+        }
+    }
+
+    /// Moves a full dynamic array to a heap region with room for `2 * capacity + 4` elements.
+    /// `slot` holds the address of the array's slot and `header` its current header address.
+    fn gen_array_grow(&mut self, slot: u32, header: u32, element_slots: u32) {
+        let ready_label = self.assembler.new_label();
+
+        self.emit_slot_address(header, 0);
+        self.assembler.emit_op(OpCode::Load);
+        self.emit_slot_address(header, 1);
+        self.assembler.emit_op(OpCode::Load);
+        self.assembler.emit_op(OpCode::Lt);
+        self.assembler.emit_jump(OpCode::JumpIf, ready_label);
+
+        self.emit_slot_address(header, 1);
+        self.assembler.emit_op(OpCode::Load);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(2);
+        self.assembler.emit_op(OpCode::Mul);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(4);
+        self.assembler.emit_op(OpCode::Add);
+        let capacity = self.stash_address();
+
+        self.emit_load_at(capacity);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(element_slots);
+        self.assembler.emit_op(OpCode::Mul);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(2);
+        self.assembler.emit_op(OpCode::Add);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(WORD);
+        self.assembler.emit_op(OpCode::Mul);
+        self.emit_heap_alloc();
+
+        // [grown] -> MCopy(grown, header, used bytes); slot = header = grown
+        self.assembler.emit_op(OpCode::Dup);
+        self.emit_load_at(header);
+        self.emit_array_size(header, element_slots);
+        self.assembler.emit_op(OpCode::MCopy);
+        self.assembler.emit_op(OpCode::Dup);
+        self.emit_slot_address(slot, 0);
+        self.assembler.emit_op(OpCode::Store);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(header);
         self.assembler.emit_op(OpCode::Store);
+        self.emit_load_at(capacity);
+        self.emit_slot_address(header, 1);
+        self.assembler.emit_op(OpCode::Store);
+
+        self.assembler.bind(ready_label);
+    }
+
+    /// Lowers `Target(value)` with the value already on the stack.
+    fn gen_conversion(&mut self, from_bytes: bool, target: &Type) -> Result<(), String> {
+        if let Some((bits, signed)) = integer_bounds(target) {
+            if from_bytes {
+                self.assembler.emit_op(OpCode::BytesToInt);
+            }
+            // The VM holds at most 64-bit integers: narrower targets truncate, and wider unsigned
+            // ones cannot represent a negative value, so it reverts.
+            if bits < 64 {
+                self.assembler.emit_op(if signed {
+                    OpCode::SignExtend
+                } else {
+                    OpCode::Mask
+                });
+                self.assembler.emit_u8(bits as u8);
+            } else if !signed {
+                self.emit_sign_check(&format!(
+                    "Cannot convert a negative value to {}",
+                    canonical_type_name(target)
+                ))?;
+            }
+            return Ok(());
+        }
+
+        let width = match target {
+            Type::Address => 20,
+            Type::FixedBytes(width) => *width,
+            // `Bytes`, `String` and the PQC types share one byte representation.
+            _ => return Ok(()),
+        };
+        self.assembler.emit_op(if from_bytes {
+            OpCode::BytesResize
+        } else {
+            OpCode::IntToBytes
+        });
+        self.assembler.emit_u8(width);
+        Ok(())
+    }
+
+    /// Reverts with `reason` unless the integer on top of the stack is non-negative.
+    fn emit_sign_check(&mut self, reason: &str) -> Result<(), String> {
+        let ok = self.assembler.new_label();
+        self.assembler.emit_op(OpCode::Dup);
+        self.assembler.emit_op(OpCode::Push);
+        self.assembler.emit_u32(0);
+        self.assembler.emit_op(OpCode::Ge);
+        self.assembler.emit_jump(OpCode::JumpIf, ok);
+        self.gen_revert(reason)?;
+        self.assembler.bind(ok);
+        Ok(())
+    }
+
+    /// Reverts unless the arithmetic result on top of the stack fits integer type `ty`. Results
+    /// of 64-bit signed and wider types are already checked by the VM.
+    fn emit_range_check(&mut self, ty: &Type) -> Result<(), String> {
+        let Some((bits, signed)) = integer_bounds(ty) else {
+            return Ok(());
+        };
+        let reason = format!(
+            "Arithmetic overflow: the result does not fit {}",
+            canonical_type_name(ty)
+        );
+        if bits >= 64 {
+            return if signed {
+                Ok(())
+            } else {
+                self.emit_sign_check(&reason)
+            };
+        }
+        // The value fits when truncating it to the type leaves it unchanged.
+        let ok = self.assembler.new_label();
+        self.assembler.emit_op(OpCode::Dup);
+        self.assembler.emit_op(OpCode::Dup);
+        self.assembler.emit_op(if signed {
+            OpCode::SignExtend
+        } else {
+            OpCode::Mask
+        });
+        self.assembler.emit_u8(bits as u8);
+        self.assembler.emit_op(OpCode::Eq);
+        self.assembler.emit_jump(OpCode::JumpIf, ok);
+        self.gen_revert(&reason)?;
+        self.assembler.bind(ok);
+        Ok(())
+    }
+
+    /// Integer type of the arithmetic expression `expr`, taken from its typed operands;
+    /// literals adopt the type of the other side.
+    fn arithmetic_type(&self, expr: &Expression) -> Option<Type> {
+        match expr {
+            Expression::Binary(
+                BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div,
+                left,
+                right,
+            ) => self
+                .arithmetic_type(left)
+                .or_else(|| self.arithmetic_type(right)),
+            Expression::Unary(UnaryOp::Neg, operand) => self.arithmetic_type(operand),
+            _ => self
+                .expression_type(expr)
+                .filter(|ty| integer_bounds(ty).is_some()),
+        }
     }
 
     fn emit_variable_load(&mut self, name: &str) {
+        if let Some(slot) = self.storage_slot(name) {
+            self.assembler.emit_op(OpCode::Push);
+            self.assembler.emit_u32(slot);
+            self.assembler.emit_op(OpCode::SLoad);
+            return;
+        }
         let addr = self.variable_address(name);
         self.assembler.emit_op(OpCode::Push);
         self.assembler.emit_u32(addr);
-        self.assembler.emit_op(OpCode::Load);
+        self.emit_load(self.holds_byte_string(name));
     }
 }
+
+fn is_byte_type(ty: &Type) -> bool {
+    matches!(ty, Type::String | Type::Bytes | Type::FixedBytes(_))
+}
+
+/// Whether values of `ty` are byte strings at runtime.
+fn is_byte_value(ty: &Type) -> bool {
+    is_byte_type(ty)
+        || matches!(
+            ty,
+            Type::Address
+                | Type::MLDSAPublicKey
+                | Type::MLDSAKeyPair
+                | Type::MLDSASignature
+                | Type::FNDSAPublicKey
+                | Type::FNDSAKeyPair
+                | Type::FNDSASignature
+                | Type::MLKEMPublicKey
+                | Type::MLKEMKeyPair
+                | Type::MLKEMCiphertext
+                | Type::SLHDSAPublicKey
+                | Type::SLHDSAKeyPair
+                | Type::SLHDSASignature
+        )
+}
+
+/// Whether `expr` calls an ML-DSA, FN-DSA or SLH-DSA signature verification builtin.
+fn is_signature_verification(expr: &Expression) -> bool {
+    use crate::pqc_integration::PqcIntegration;
+    matches!(expr, Expression::Call(name, _)
+        if PqcIntegration::is_mldsa_verify_function(name)
+            || PqcIntegration::is_fndsa_verify_function(name)
+            || PqcIntegration::is_slhdsa_verify_function(name))
+}
+
+/// The `@gas_limit` budget in `annotations`, if any, as a gas scope limit.
+fn gas_limit(annotations: &[Annotation]) -> Result<Option<u32>, String> {
+    annotations::gas_limit(annotations)
+        .map(|limit| {
+            u32::try_from(limit)
+                .map_err(|_| format!("@gas_limit({}) exceeds the maximum of {}", limit, u32::MAX))
+        })
+        .transpose()
+}
+
+/// Canonical `name(Type,...)` signature that a function's dispatcher selector is derived from.
+pub fn function_signature(f: &FunctionDefinition) -> String {
+    let params: Vec<String> = f
+        .params
+        .iter()
+        .map(|p| canonical_type_name(&p.ty))
+        .collect();
+    format!("{}({})", f.name, params.join(","))
+}
+
+/// First four bytes of the SHA-256 digest of [`function_signature`], read big-endian.
+pub fn function_selector(f: &FunctionDefinition) -> u32 {
+    topic_of(&function_signature(f))
+}
+
+/// Canonical event signature, e.g. `Transfer(Address,Address,UInt256)`.
+pub fn event_signature(e: &EventDefinition) -> String {
+    let params: Vec<String> = e
+        .params
+        .iter()
+        .map(|p| canonical_type_name(&p.ty))
+        .collect();
+    format!("{}({})", e.name, params.join(","))
+}
+
+/// Log topic of an event: the first four bytes of the SHA-256 digest of [`event_signature`].
+pub fn event_topic(e: &EventDefinition) -> u32 {
+    topic_of(&event_signature(e))
+}
+
+/// Canonical custom error signature, e.g. `InsufficientBalance(UInt256,UInt256)`.
+pub fn error_signature(e: &ErrorDefinition) -> String {
+    let params: Vec<String> = e
+        .params
+        .iter()
+        .map(|p| canonical_type_name(&p.ty))
+        .collect();
+    format!("{}({})", e.name, params.join(","))
+}
+
+/// First four bytes of the SHA-256 digest of [`error_signature`], read big-endian.
+pub fn error_selector(e: &ErrorDefinition) -> u32 {
+    topic_of(&error_signature(e))
+}
+
+fn topic_of(text: &str) -> u32 {
+    quantumvm::abi::selector(text)
+}
+
+pub(crate) fn canonical_type_name(ty: &Type) -> String {
+    match ty {
+        Type::Array(inner, Some(len)) => format!("{}[{}]", canonical_type_name(inner), len),
+        Type::Array(inner, None) => format!("{}[]", canonical_type_name(inner)),
+        Type::Mapping(key, value) => format!(
+            "mapping({}=>{})",
+            canonical_type_name(key),
+            canonical_type_name(value)
+        ),
+        Type::Struct(name) => name.clone(),
+        Type::FixedBytes(width) => format!("Bytes{}", width),
+        Type::Generic(name, args) => {
+            let args: Vec<String> = args.iter().map(canonical_type_name).collect();
+            format!("{}<{}>", name, args.join(","))
+        }
+        other => format!("{:?}", other),
+    }
+}
+
+// Names of the contracts in `ast`, in declaration order
+fn contract_names(ast: &[SourceUnit]) -> Vec<String> {
+    ast.iter()
+        .filter_map(|unit| match unit {
+            SourceUnit::Contract(c) => Some(c.name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+pub(crate) fn format_monomorphize_errors(errors: &[SemanticError]) -> String {
+    errors
+        .iter()
+        .map(|error| error.message.as_str())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
@@ -1,6 +1,8 @@
 #[macro_use]
 extern crate pest_derive;
 
+pub mod abi;
+pub mod annotations;
 pub mod ast;
 pub mod codegen;
 pub mod parser;
@@ -9,9 +11,16 @@ pub mod semantic;
 pub mod solidity_gen;
 pub mod version;
 
-pub use codegen::CodeGenerator;
+pub use abi::{
+    abi_value_from_json, contract_abi, AbiConstructor, AbiEntry, AbiFunction, AbiParam, ContractAbi,
+};
+pub use ast::U256;
+pub use codegen::{
+    error_selector, error_signature, event_signature, event_topic, function_selector,
+    function_signature, ArtifactMetadata, CodeGenerator, ContractArtifact,
+};
 pub use parser::parse;
 pub use pqc_integration::PqcIntegration;
-pub use semantic::analyze;
+pub use semantic::{analyze, monomorphize};
 pub use solidity_gen::SolidityGenerator;
 pub use version::{get_compiler_version, Version, VersionRequirement};
@@ -1,4 +1,5 @@
 use crate::ast::*;
+use pest::error::{Error, ErrorVariant};
 use pest::iterators::Pair;
 use pest::Parser;
 
@@ -17,6 +18,16 @@ pub fn parse(
     source: &str,
 ) -> Result<(Option<VersionRequirement>, Vec<SourceUnit>), pest::error::Error<Rule>> {
     let pairs = SynQParser::parse(Rule::source_file, source)?;
+    for token in pairs.clone().flatten() {
+        let checked = match token.as_rule() {
+            Rule::NUMBER => parse_number_literal(token.as_str().trim_start_matches('-')).map(drop),
+            Rule::STRING_LITERAL => unescape_string(string_body(token.as_str())).map(drop),
+            _ => Ok(()),
+        };
+        checked.map_err(|message| {
+            Error::new_from_span(ErrorVariant::CustomError { message }, token.as_span())
+        })?;
+    }
     let mut ast = vec![];
     let mut version_req: Option<VersionRequirement> = None;
 
@@ -35,6 +46,11 @@ pub fn parse(
                     Rule::contract_definition => {
                         ast.push(SourceUnit::Contract(parse_contract(item)));
                     }
+                    Rule::contract_instantiation => {
+                        ast.push(SourceUnit::ContractInstance(parse_contract_instantiation(
+                            item,
+                        )));
+                    }
                     _ => unreachable!(),
                 }
             }
@@ -82,6 +98,7 @@ fn parse_version_requirement(pair: Pair<Rule>) -> VersionRequirement {
 
 fn parse_struct(pair: Pair<Rule>) -> StructDefinition {
     let mut name = String::new();
+    let mut generic_params = Vec::new();
     let mut fields = Vec::new();
 
     for item in pair.into_inner() {
@@ -89,6 +106,9 @@ fn parse_struct(pair: Pair<Rule>) -> StructDefinition {
             Rule::IDENT if name.is_empty() => {
                 name = item.as_str().to_string();
             }
+            Rule::generic_params => {
+                generic_params = parse_generic_params(item);
+            }
             Rule::struct_field => {
                 fields.push(parse_struct_field(item));
             }
@@ -96,7 +116,55 @@ fn parse_struct(pair: Pair<Rule>) -> StructDefinition {
         }
     }
 
-    StructDefinition { name, fields }
+    StructDefinition {
+        name,
+        generic_params,
+        fields,
+    }
+}
+
+fn parse_generic_params(pair: Pair<Rule>) -> Vec<String> {
+    pair.into_inner()
+        .filter(|p| p.as_rule() == Rule::IDENT)
+        .map(|p| p.as_str().to_string())
+        .collect()
+}
+
+fn parse_type_params(pair: Pair<Rule>) -> Vec<Type> {
+    pair.into_inner()
+        .filter(|p| p.as_rule() == Rule::type_decl)
+        .map(parse_type)
+        .collect()
+}
+
+fn parse_contract_instantiation(pair: Pair<Rule>) -> ContractInstantiation {
+    let mut name = String::new();
+    let mut generic = String::new();
+    let mut type_args = Vec::new();
+    let mut annotations = Vec::new();
+
+    for item in pair.into_inner() {
+        match item.as_rule() {
+            Rule::annotation => annotations.push(parse_annotation(item)),
+            Rule::IDENT if name.is_empty() => {
+                name = item.as_str().to_string();
+            }
+            Rule::IDENT => {
+                generic = item.as_str().to_string();
+            }
+            Rule::type_params => {
+                type_args = parse_type_params(item);
+            }
+            _ => {}
+        }
+    }
+
+    ContractInstantiation {
+        name,
+        generic,
+        type_args,
+        annotations,
+    }
 }
 
 fn parse_struct_field(pair: Pair<Rule>) -> Parameter {
@@ -114,6 +182,7 @@ fn parse_struct_field(pair: Pair<Rule>) -> Parameter {
 
 fn parse_contract(pair: Pair<Rule>) -> ContractDefinition {
     let mut name = String::new();
+    let mut generic_params = Vec::new();
     let mut parts = Vec::new();
     let mut annotations = Vec::new();
 
@@ -128,11 +197,17 @@ fn parse_contract(pair: Pair<Rule>) -> ContractDefinition {
             Rule::IDENT if name.is_empty() => {
                 name = item.as_str().to_string();
             }
+            Rule::generic_params => {
+                generic_params = parse_generic_params(item);
+            }
             Rule::contract_part => {
                 if let Some(part) = item.into_inner().next() {
                     match part.as_rule() {
-                        Rule::struct_definition | Rule::enum_definition => {
-                            // Contract-local type declarations are parse-only metadata for now.
+                        Rule::struct_definition => {
+                            parts.push(ContractPart::Struct(parse_struct(part)));
+                        }
+                        Rule::enum_definition => {
+                            // Contract-local enums are parse-only metadata for now.
                         }
                         _ => parts.push(parse_contract_part(part)),
                     }
@@ -141,7 +216,8 @@ fn parse_contract(pair: Pair<Rule>) -> ContractDefinition {
             Rule::state_variable_declaration
             | Rule::function_definition
             | Rule::constructor_definition
-            | Rule::event_definition => {
+            | Rule::event_definition
+            | Rule::error_definition => {
                 parts.push(parse_contract_part(item));
             }
             _ => {}
@@ -150,6 +226,7 @@ fn parse_contract(pair: Pair<Rule>) -> ContractDefinition {
 
     ContractDefinition {
         name,
+        generic_params,
         parts,
         annotations,
     }
@@ -161,6 +238,7 @@ fn parse_contract_part(pair: Pair<Rule>) -> ContractPart {
         Rule::function_definition => ContractPart::Function(parse_function(pair)),
         Rule::constructor_definition => ContractPart::Constructor(parse_constructor(pair)),
         Rule::event_definition => ContractPart::Event(parse_event(pair)),
+        Rule::error_definition => ContractPart::Error(parse_error(pair)),
         _ => {
             // Fallback - try to parse as function
             ContractPart::Function(parse_function(pair))
@@ -169,8 +247,12 @@ fn parse_contract_part(pair: Pair<Rule>) -> ContractPart {
 }
 
 fn parse_constructor(pair: Pair<Rule>) -> ConstructorDefinition {
+    let span = span_of(&pair);
     let mut params = Vec::new();
-    let mut body = Block { statements: vec![] };
+    let mut body = Block {
+        statements: vec![],
+        spans: vec![],
+    };
     let mut annotations = Vec::new();
 
     for item in pair.into_inner() {
@@ -186,6 +268,7 @@ fn parse_constructor(pair: Pair<Rule>) -> ConstructorDefinition {
         params,
         body,
         annotations,
+        span,
     }
 }
 
@@ -214,6 +297,23 @@ fn parse_event(pair: Pair<Rule>) -> EventDefinition {
     }
 }
 
+fn parse_error(pair: Pair<Rule>) -> ErrorDefinition {
+    let mut name = String::new();
+    let mut params = Vec::new();
+
+    for item in pair.into_inner() {
+        match item.as_rule() {
+            Rule::IDENT if name.is_empty() => {
+                name = item.as_str().to_string();
+            }
+            Rule::param => params.push(parse_param(item)),
+            _ => {}
+        }
+    }
+
+    ErrorDefinition { name, params }
+}
+
 fn parse_state_variable(pair: Pair<Rule>) -> StateVariableDeclaration {
     let mut name = String::new();
     let mut ty = Type::UInt256;
@@ -257,18 +357,28 @@ fn parse_state_variable(pair: Pair<Rule>) -> StateVariableDeclaration {
 }
 
 fn parse_function(pair: Pair<Rule>) -> FunctionDefinition {
-    let mut is_public = pair.as_str().contains("@public");
+    let span = span_of(&pair);
+    let mut visibility = Visibility::default();
+    let mut mutability = StateMutability::default();
     let mut name = String::new();
     let mut params = Vec::new();
     let mut returns: Option<Type> = None;
-    let mut body = Block { statements: vec![] };
+    let mut body = Block {
+        statements: vec![],
+        spans: vec![],
+    };
     let mut annotations = Vec::new();
 
     for item in pair.into_inner() {
         match item.as_rule() {
             Rule::annotation => {
+                // `@public`, `@view`, ... are accepted as aliases for the trailing keywords.
                 let annotation = parse_annotation(item);
-                if annotation.name != "public" {
+                if let Some(kw) = parse_visibility(&annotation.name) {
+                    visibility = kw;
+                } else if let Some(kw) = parse_mutability(&annotation.name) {
+                    mutability = kw;
+                } else {
                     annotations.push(annotation);
                 }
             }
@@ -279,8 +389,13 @@ fn parse_function(pair: Pair<Rule>) -> FunctionDefinition {
                 params.push(parse_param(item));
             }
             Rule::visibility_kw => {
-                if item.as_str() == "public" {
-                    is_public = true;
+                if let Some(kw) = parse_visibility(item.as_str()) {
+                    visibility = kw;
+                }
+            }
+            Rule::mutability_kw => {
+                if let Some(kw) = parse_mutability(item.as_str()) {
+                    mutability = kw;
                 }
             }
             Rule::return_type | Rule::tuple_type | Rule::type_decl => {
@@ -299,8 +414,29 @@ fn parse_function(pair: Pair<Rule>) -> FunctionDefinition {
         params,
         returns,
         body,
-        is_public,
+        visibility,
+        mutability,
         annotations,
+        span,
+    }
+}
+
+fn parse_visibility(text: &str) -> Option<Visibility> {
+    match text {
+        "public" => Some(Visibility::Public),
+        "external" => Some(Visibility::External),
+        "internal" => Some(Visibility::Internal),
+        "private" => Some(Visibility::Private),
+        _ => None,
+    }
+}
+
+fn parse_mutability(text: &str) -> Option<StateMutability> {
+    match text {
+        "pure" => Some(StateMutability::Pure),
+        "view" => Some(StateMutability::View),
+        "payable" => Some(StateMutability::Payable),
+        _ => None,
     }
 }
 
@@ -318,8 +454,19 @@ fn parse_param(pair: Pair<Rule>) -> Parameter {
 }
 
 fn parse_block(pair: Pair<Rule>) -> Block {
-    let statements = pair.into_inner().filter_map(parse_statement).collect();
-    Block { statements }
+    let (spans, statements) = pair
+        .into_inner()
+        .filter_map(|statement| {
+            let span = span_of(&statement);
+            parse_statement(statement).map(|parsed| (span, parsed))
+        })
+        .unzip();
+    Block { statements, spans }
+}
+
+fn span_of(pair: &Pair<Rule>) -> Span {
+    let (line, column) = pair.line_col();
+    Span { line, column }
 }
 
 fn parse_statement(pair: Pair<Rule>) -> Option<Statement> {
@@ -404,16 +551,17 @@ fn parse_statement(pair: Pair<Rule>) -> Option<Statement> {
         }
         Rule::assignment => {
             let mut name = String::new();
+            let mut target: Option<Expression> = None;
             let mut rhs_expr: Option<Expression> = None;
 
             for item in statement.into_inner() {
                 match item.as_rule() {
                     Rule::lvalue => {
-                        for lvalue_item in item.into_inner() {
-                            if lvalue_item.as_rule() == Rule::IDENT && name.is_empty() {
-                                name = lvalue_item.as_str().to_string();
-                                break;
-                            }
+                        let text = item.as_str().trim();
+                        if is_identifier(text) {
+                            name = text.to_string();
+                        } else {
+                            target = parse_expression_text(text);
                         }
                     }
                     Rule::IDENT if name.is_empty() => {
@@ -426,6 +574,10 @@ fn parse_statement(pair: Pair<Rule>) -> Option<Statement> {
                 }
             }
 
+            if let Some(target) = target {
+                return rhs_expr.map(|expr| Statement::ElementAssignment(target, expr));
+            }
+
             if name.is_empty() {
                 return None;
             }
@@ -463,9 +615,11 @@ fn parse_statement(pair: Pair<Rule>) -> Option<Statement> {
                         else_block = Some(parse_block(item));
                     }
                     Rule::if_statement => {
+                        let span = span_of(&item);
                         if let Some(nested_else_if) = parse_statement(item) {
                             else_block = Some(Block {
                                 statements: vec![nested_else_if],
+                                spans: vec![span],
                             });
                         }
                     }
@@ -505,7 +659,10 @@ fn parse_statement(pair: Pair<Rule>) -> Option<Statement> {
             Some(Statement::Emit(event_name, args))
         }
         Rule::require_pqc_block => {
-            let mut pqc_block = Block { statements: vec![] };
+            let mut pqc_block = Block {
+                statements: vec![],
+                spans: vec![],
+            };
             let mut fallback: Option<Box<Statement>> = None;
 
             for item in statement.into_inner() {
@@ -524,6 +681,23 @@ fn parse_statement(pair: Pair<Rule>) -> Option<Statement> {
 
             Some(Statement::RequirePqc(pqc_block, fallback))
         }
+        Rule::with_gas_limit_block => {
+            let mut budget = None;
+            let mut body = Block {
+                statements: vec![],
+                spans: vec![],
+            };
+
+            for item in statement.into_inner() {
+                match item.as_rule() {
+                    Rule::expression => budget = Some(parse_expression(item)),
+                    Rule::block => body = parse_block(item),
+                    _ => {}
+                }
+            }
+
+            Some(Statement::WithGasLimit(budget?, body))
+        }
         Rule::for_statement => parse_for_statement(statement),
         _ => None,
     }
@@ -543,9 +717,15 @@ fn parse_annotation(pair: Pair<Rule>) -> Annotation {
                     if arg.as_rule() != Rule::annotation_arg {
                         continue;
                     }
+                    let mut arg_name = None;
                     for arg_item in arg.into_inner() {
-                        if arg_item.as_rule() == Rule::expression {
-                            args.push(parse_expression(arg_item));
+                        match arg_item.as_rule() {
+                            Rule::IDENT => arg_name = Some(arg_item.as_str().to_string()),
+                            Rule::expression => args.push(AnnotationArg {
+                                name: arg_name.take(),
+                                value: parse_expression(arg_item),
+                            }),
+                            _ => {}
                         }
                     }
                 }
@@ -642,6 +822,12 @@ fn parse_expression(pair: Pair<Rule>) -> Expression {
 }
 
 fn parse_expression_text(raw: &str) -> Option<Expression> {
+    if raw.contains("//") || raw.contains("/*") {
+        let stripped = strip_comments(raw);
+        if stripped != raw {
+            return parse_expression_text(&stripped);
+        }
+    }
     let text = trim_wrapping_parens(raw.trim());
     if text.is_empty() {
         return None;
@@ -698,9 +884,6 @@ fn parse_expression_text(raw: &str) -> Option<Expression> {
     }
 
     if let Some(rest) = text.strip_prefix('-') {
-        if rest.chars().all(|c| c.is_ascii_digit()) {
-            return None;
-        }
         let expr = parse_expression_text(rest)?;
         return Some(Expression::Unary(UnaryOp::Neg, Box::new(expr)));
     }
@@ -709,17 +892,87 @@ fn parse_expression_text(raw: &str) -> Option<Expression> {
         return Some(Expression::Literal(literal));
     }
 
+    if text.starts_with('[') && find_last_top_level(text, '[') == Some(0) {
+        let inner = text.strip_prefix('[')?.strip_suffix(']')?;
+        return Some(Expression::ArrayLiteral(parse_expression_list(inner)));
+    }
+
+    if text.starts_with('{') && find_last_top_level(text, '{') == Some(0) {
+        let inner = text.strip_prefix('{')?.strip_suffix('}')?;
+        let mut fields = Vec::new();
+        for field in split_top_level(inner, ',') {
+            let (key, value) = field.split_once(':')?;
+            let key = key.trim();
+            if !is_identifier(key) {
+                return None;
+            }
+            fields.push((key.to_string(), parse_expression_text(value)?));
+        }
+        return Some(Expression::ObjectLiteral(fields));
+    }
+
     if let Some((callee, args)) = parse_call_expression(text) {
         return Some(Expression::Call(callee.to_string(), args));
     }
 
+    if text.ends_with(')') {
+        let open = find_last_top_level(text, '(')?;
+        let (receiver, method) = text[..open].rsplit_once('.')?;
+        let method = method.trim();
+        if !is_identifier(method) {
+            return None;
+        }
+        let args = parse_expression_list(&text[open + 1..text.len() - 1]);
+        return Some(Expression::MethodCall(
+            Box::new(parse_expression_text(receiver)?),
+            method.to_string(),
+            args,
+        ));
+    }
+
+    if text.ends_with(']') {
+        let open = find_last_top_level(text, '[')?;
+        let object = parse_expression_text(&text[..open])?;
+        let index = parse_expression_text(&text[open + 1..text.len() - 1])?;
+        return Some(Expression::IndexAccess(Box::new(object), Box::new(index)));
+    }
+
     if is_identifier(text) {
         return Some(Expression::Identifier(text.to_string()));
     }
 
+    if let Some(dot) = find_last_top_level(text, '.') {
+        let (object, member) = (&text[..dot], text[dot + 1..].trim());
+        if !is_identifier(member) {
+            return None;
+        }
+        // Environment values and `Type.Variant` references stay whole identifiers.
+        let root = object.split('.').next().unwrap_or(object).trim();
+        if text.split('.').all(is_identifier)
+            && (matches!(root, "msg" | "block")
+                || root.starts_with(|c: char| c.is_ascii_uppercase()))
+        {
+            return Some(Expression::Identifier(text.to_string()));
+        }
+        return Some(Expression::MemberAccess(
+            Box::new(parse_expression_text(object)?),
+            member.to_string(),
+        ));
+    }
+
     None
 }
 
+fn parse_expression_list(text: &str) -> Vec<Expression> {
+    split_top_level(text, ',')
+        .into_iter()
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            parse_expression_text(item).unwrap_or_else(|| Expression::Identifier(item.to_string()))
+        })
+        .collect()
+}
+
 fn parse_call_expression(text: &str) -> Option<(&str, Vec<Expression>)> {
     if !text.ends_with(')') {
         return None;
@@ -727,30 +980,15 @@ fn parse_call_expression(text: &str) -> Option<(&str, Vec<Expression>)> {
 
     let open = find_top_level_open_paren(text)?;
     let callee = text[..open].trim();
-    if !is_identifier(callee) {
+    // Member calls such as `vault.deposit(x)` keep the dotted path as the callee name.
+    if !callee.split('.').all(is_identifier) {
         return None;
     }
 
-    let args_src = &text[open + 1..text.len() - 1];
-    let args = if args_src.trim().is_empty() {
-        Vec::new()
-    } else {
-        split_top_level(args_src, ',')
-            .into_iter()
-            .filter_map(|item| {
-                let trimmed = item.trim();
-                if trimmed.is_empty() {
-                    return None;
-                }
-                Some(
-                    parse_expression_text(trimmed)
-                        .unwrap_or_else(|| Expression::Identifier(trimmed.to_string())),
-                )
-            })
-            .collect()
-    };
-
-    Some((callee, args))
+    Some((
+        callee,
+        parse_expression_list(&text[open + 1..text.len() - 1]),
+    ))
 }
 
 fn parse_literal(text: &str) -> Option<Literal> {
@@ -765,7 +1003,7 @@ fn parse_literal(text: &str) -> Option<Literal> {
     }
 
     if text.starts_with('"') && text.ends_with('"') && text.len() >= 2 {
-        return Some(Literal::String(text[1..text.len() - 1].to_string()));
+        return unescape_string(string_body(text)).ok().map(Literal::String);
     }
 
     if text.starts_with("Bytes(\"") && text.ends_with("\")") {
@@ -781,23 +1019,155 @@ fn parse_literal(text: &str) -> Option<Literal> {
         return Some(Literal::Address(text.to_string()));
     }
 
-    if text.chars().all(|c| c.is_ascii_digit()) {
-        let value = text.parse::<u64>().ok()?;
-        return Some(Literal::Number(value));
+    if text.starts_with(|c: char| c.is_ascii_digit()) {
+        return parse_number_literal(text).ok().map(Literal::Number);
     }
 
     None
 }
 
+const NUMBER_UNITS: &[(&str, u64)] = &[
+    ("wei", 1),
+    ("gwei", 1_000_000_000),
+    ("ether", 1_000_000_000_000_000_000),
+    ("seconds", 1),
+    ("minutes", 60),
+    ("hours", 3_600),
+    ("days", 86_400),
+    ("weeks", 604_800),
+];
+
+/// Evaluates a `NUMBER` token exactly: `1_000`, `0xff`, `1.5e18`, `2 ether`.
+/// Fails if the value is not a whole number or does not fit in 256 bits.
+pub(crate) fn parse_number_literal(text: &str) -> Result<U256, String> {
+    let overflow = || format!("numeric literal `{text}` does not fit in 256 bits");
+    let digits = text.replace('_', "");
+
+    if let Some(hex) = digits.strip_prefix("0x") {
+        if hex.trim_start_matches('0').len() > 64 {
+            return Err(overflow());
+        }
+        return U256::from_str_radix(hex, 16).map_err(|_| format!("invalid hex literal `{text}`"));
+    }
+
+    let (number, unit) = match digits.split_once(char::is_whitespace) {
+        Some((number, unit)) => (number, Some(unit.trim())),
+        None => (digits.as_str(), None),
+    };
+    let (mantissa, exponent) = match number.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => {
+            (mantissa, exponent.parse::<usize>().map_err(|_| overflow())?)
+        }
+        None => (number, 0),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    let mut value = U256::zero();
+    for digit in whole.chars().chain(fraction.chars()) {
+        let digit = digit
+            .to_digit(10)
+            .ok_or_else(|| format!("invalid numeric literal `{text}`"))?;
+        value = value
+            .checked_mul(U256::from(10))
+            .and_then(|v| v.checked_add(U256::from(digit)))
+            .ok_or_else(overflow)?;
+    }
+
+    let multiplier = match unit {
+        Some(unit) => NUMBER_UNITS
+            .iter()
+            .find(|(name, _)| *name == unit)
+            .map(|(_, factor)| *factor)
+            .ok_or_else(|| format!("unknown unit `{unit}` in numeric literal `{text}`"))?,
+        None => 1,
+    };
+    value = value
+        .checked_mul(U256::from(multiplier))
+        .ok_or_else(overflow)?;
+
+    // Scale by 10^(exponent - fraction digits), refusing to drop non-zero digits.
+    if exponent >= fraction.len() {
+        for _ in 0..exponent - fraction.len() {
+            value = value.checked_mul(U256::from(10)).ok_or_else(overflow)?;
+        }
+    } else {
+        for _ in 0..fraction.len() - exponent {
+            let (quotient, remainder) = value.div_mod(U256::from(10));
+            if !remainder.is_zero() {
+                return Err(format!("numeric literal `{text}` is not an integer"));
+            }
+            value = quotient;
+        }
+    }
+
+    Ok(value)
+}
+
 fn parse_string_literal(pair: Pair<Rule>) -> String {
     let value = pair.as_str().trim();
     if value.starts_with('"') && value.ends_with('"') && value.len() >= 2 {
-        value[1..value.len() - 1].to_string()
+        let body = string_body(value);
+        unescape_string(body).unwrap_or_else(|_| body.to_string())
     } else {
         value.to_string()
     }
 }
 
+fn string_body(literal: &str) -> &str {
+    &literal[1..literal.len() - 1]
+}
+
+/// Decodes the escapes of a string literal body: `\\`, `\"`, `\'`, `\n`, `\r`, `\t`, `\0`,
+/// `\xHH` (ASCII only) and `\u{H..}`.
+pub(crate) fn unescape_string(body: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(body.len());
+    let mut chars = body.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            out.push(ch);
+            continue;
+        }
+
+        let escape = chars
+            .next()
+            .ok_or_else(|| "string literal ends with a lone `\\`".to_string())?;
+        let decoded = match escape {
+            '\\' | '"' | '\'' => escape,
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            'x' => {
+                let hex: String = chars.by_ref().take(2).collect();
+                u8::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|byte| hex.len() == 2 && byte.is_ascii())
+                    .map(char::from)
+                    .ok_or_else(|| {
+                        format!("invalid escape `\\x{hex}`; expected two hex digits up to 7f")
+                    })?
+            }
+            'u' => {
+                let rest = chars.as_str();
+                let code = rest
+                    .strip_prefix('{')
+                    .and_then(|rest| rest.split_once('}'))
+                    .map(|(code, _)| code)
+                    .ok_or_else(|| "invalid escape `\\u`; expected `\\u{...}`".to_string())?;
+                let decoded = u32::from_str_radix(code, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("invalid unicode escape `\\u{{{code}}}`"))?;
+                chars = rest[code.len() + 2..].chars();
+                decoded
+            }
+            other => return Err(format!("unknown escape sequence `\\{other}`")),
+        };
+        out.push(decoded);
+    }
+    Ok(out)
+}
+
 fn trim_wrapping_parens(input: &str) -> &str {
     let mut text = input.trim();
     loop {
@@ -870,6 +1240,91 @@ fn find_top_level_open_paren(text: &str) -> Option<usize> {
     None
 }
 
+/// Replaces `//` and `/* */` comments outside strings with a space.
+fn strip_comments(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    while let Some(ch) = chars.next() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == '"' {
+                in_string = false;
+            }
+            out.push(ch);
+            continue;
+        }
+
+        match (ch, chars.peek()) {
+            ('/', Some('/')) => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        break;
+                    }
+                }
+                out.push(' ');
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = '\0';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+                out.push(' ');
+            }
+            _ => {
+                if ch == '"' {
+                    in_string = true;
+                }
+                out.push(ch);
+            }
+        }
+    }
+
+    out
+}
+
+/// Byte index of the last `target` outside strings and outside any `()`, `[]` or `{}` nesting.
+fn find_last_top_level(text: &str, target: char) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut found = None;
+
+    for (idx, ch) in text.char_indices() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        if ch == target && depth == 0 {
+            found = Some(idx);
+        }
+        match ch {
+            '"' => in_string = true,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    found
+}
+
 fn find_top_level_ternary_positions(text: &str) -> Option<(usize, usize)> {
     let mut depth_paren = 0usize;
     let mut depth_bracket = 0usize;
@@ -1108,6 +1563,8 @@ fn parse_type(pair: Pair<Rule>) -> Type {
         }
         Rule::type_decl => {
             let mut base: Option<Type> = None;
+            let mut base_name = String::new();
+            let mut type_args: Vec<Type> = Vec::new();
             let mut array_suffix: Option<String> = None;
 
             for item in pair.into_inner() {
@@ -1116,8 +1573,12 @@ fn parse_type(pair: Pair<Rule>) -> Type {
                         base = Some(parse_type(item));
                     }
                     Rule::base_type => {
+                        base_name = item.as_str().to_string();
                         base = Some(parse_base_type_name(item.as_str()));
                     }
+                    Rule::type_params => {
+                        type_args = parse_type_params(item);
+                    }
                     Rule::array_suffix => {
                         array_suffix = Some(item.as_str().to_string());
                     }
@@ -1125,7 +1586,12 @@ fn parse_type(pair: Pair<Rule>) -> Type {
                 }
             }
 
-            let mut ty = base.unwrap_or(Type::Struct("Unknown".to_string()));
+            let mut ty = if type_args.is_empty() {
+                base.unwrap_or(Type::Struct("Unknown".to_string()))
+            } else {
+                // `Name<Args>` is resolved against generic definitions during monomorphization.
+                Type::Generic(base_name, type_args)
+            };
 
             if let Some(suffix) = array_suffix {
                 let size = parse_array_suffix_size(&suffix);
@@ -1167,10 +1633,14 @@ fn parse_base_type_name(name: &str) -> Type {
         "SLHDSAPublicKey" => Type::SLHDSAPublicKey,
         "SLHDSAKeyPair" => Type::SLHDSAKeyPair,
         "SLHDSASignature" => Type::SLHDSASignature,
-        _ => {
+        _ => match name
+            .strip_prefix("Bytes")
+            .and_then(|n| n.parse::<u8>().ok())
+        {
+            Some(width @ 1..=32) => Type::FixedBytes(width),
             // Unknown type - try to parse as generic or struct
-            Type::Struct(name.to_string())
-        }
+            _ => Type::Struct(name.to_string()),
+        },
     }
 }
 
@@ -1189,7 +1659,7 @@ fn parse_array_suffix_size(suffix: &str) -> Option<u32> {
         return None;
     }
 
-    inner.parse::<u32>().ok()
+    u32::try_from(parse_number_literal(inner.trim()).ok()?).ok()
 }
 
 fn parse_return_type(pair: Pair<Rule>) -> Type {
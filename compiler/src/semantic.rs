@@ -1,18 +1,29 @@
+use crate::annotations::{self, AnnotationTarget};
 use crate::ast::{
-    BinaryOp, Block, ContractDefinition, ContractPart, Expression, FunctionDefinition, Literal,
-    SemanticError, SourceUnit, Statement, Type, UnaryOp,
+    Annotation, BinaryOp, Block, ContractDefinition, ContractInstantiation, ContractPart,
+    Expression, FunctionDefinition, Literal, Parameter, SemanticError, SourceUnit, StateMutability,
+    Statement, StructDefinition, Type, UnaryOp, Visibility, U256,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Upper bound on nested generic instantiations; deeper chains indicate a recursive definition.
+const MAX_INSTANTIATION_DEPTH: usize = 32;
 
 #[derive(Debug, Default)]
 pub struct SemanticAnalyzer {
     errors: Vec<SemanticError>,
+    /// Function signatures of every contract in the unit, for calls through contract references.
+    contracts: HashMap<String, HashMap<String, FunctionSignature>>,
+    /// Fields of top-level structs.
+    structs: HashMap<String, Vec<Parameter>>,
 }
 
 #[derive(Debug, Clone)]
 struct FunctionSignature {
     params: Vec<Type>,
     returns: Option<Type>,
+    visibility: Visibility,
+    mutability: StateMutability,
 }
 
 #[derive(Debug)]
@@ -20,6 +31,8 @@ struct ContractContext {
     name: String,
     state_variables: HashMap<String, Type>,
     functions: HashMap<String, FunctionSignature>,
+    /// Fields of structs declared inside the contract.
+    structs: HashMap<String, Vec<Parameter>>,
 }
 
 #[derive(Debug)]
@@ -27,6 +40,8 @@ struct FunctionContext<'a> {
     contract: &'a ContractContext,
     function_name: String,
     returns: Option<&'a Type>,
+    visibility: Visibility,
+    mutability: StateMutability,
     scopes: Vec<HashMap<String, Type>>,
 }
 
@@ -43,6 +58,18 @@ enum BuiltinResolution {
     NotBuiltin,
 }
 
+impl FunctionSignature {
+    /// PQC builtins are pure functions of their arguments.
+    fn builtin(params: Vec<Type>, returns: Option<Type>) -> Self {
+        Self {
+            params,
+            returns,
+            visibility: Visibility::Internal,
+            mutability: StateMutability::Pure,
+        }
+    }
+}
+
 impl InferredType {
     fn known(ty: Type) -> Self {
         Self::Known(ty)
@@ -59,7 +86,10 @@ impl InferredType {
 impl SemanticAnalyzer {
     pub fn analyze(ast: &[SourceUnit]) -> Result<(), Vec<SemanticError>> {
         let mut analyzer = Self::default();
-        analyzer.analyze_units(ast);
+        match monomorphize(ast) {
+            Ok(units) => analyzer.analyze_units(&units),
+            Err(errors) => analyzer.errors.extend(errors),
+        }
         if analyzer.errors.is_empty() {
             Ok(())
         } else {
@@ -68,6 +98,19 @@ impl SemanticAnalyzer {
     }
 
     fn analyze_units(&mut self, units: &[SourceUnit]) {
+        for unit in units {
+            match unit {
+                SourceUnit::Contract(contract) => {
+                    self.contracts
+                        .insert(contract.name.clone(), collect_function_signatures(contract));
+                }
+                SourceUnit::Struct(s) => {
+                    self.structs.insert(s.name.clone(), s.fields.clone());
+                }
+                _ => {}
+            }
+        }
+
         for unit in units {
             if let SourceUnit::Contract(contract) = unit {
                 self.analyze_contract(contract);
@@ -76,7 +119,15 @@ impl SemanticAnalyzer {
     }
 
     fn analyze_contract(&mut self, contract: &ContractDefinition) {
+        self.check_annotations(
+            &format!("Contract `{}`", contract.name),
+            &contract.annotations,
+            AnnotationTarget::Contract,
+        );
+
         let mut state_variables = HashMap::new();
+        let mut structs = HashMap::new();
+        let mut errors = HashSet::new();
         let mut constructor_count = 0usize;
 
         for part in &contract.parts {
@@ -95,6 +146,15 @@ impl SemanticAnalyzer {
                 ContractPart::Constructor(_) => {
                     constructor_count += 1;
                 }
+                ContractPart::Struct(s) => {
+                    structs.insert(s.name.clone(), s.fields.clone());
+                }
+                ContractPart::Error(error) if !errors.insert(error.name.clone()) => {
+                    self.push_error(format!(
+                        "Contract `{}` has duplicate error `{}`",
+                        contract.name, error.name
+                    ));
+                }
                 _ => {}
             }
         }
@@ -106,23 +166,40 @@ impl SemanticAnalyzer {
             ));
         }
 
-        let mut functions = HashMap::new();
-        for part in &contract.parts {
-            if let ContractPart::Function(function) = part {
-                let signature = FunctionSignature {
-                    params: function.params.iter().map(|p| p.ty.clone()).collect(),
-                    returns: function.returns.clone(),
-                };
-                functions.entry(function.name.clone()).or_insert(signature);
-            }
-        }
-
         let ctx = ContractContext {
             name: contract.name.clone(),
             state_variables,
-            functions,
+            functions: collect_function_signatures(contract),
+            structs,
         };
 
+        for part in &contract.parts {
+            let (owner, annotations, target) = match part {
+                ContractPart::StateVariable(state) => (
+                    format!("State variable `{}` in contract `{}`", state.name, ctx.name),
+                    &state.annotations,
+                    AnnotationTarget::StateVariable,
+                ),
+                ContractPart::Constructor(constructor) => (
+                    format!("Constructor in contract `{}`", ctx.name),
+                    &constructor.annotations,
+                    AnnotationTarget::Constructor,
+                ),
+                ContractPart::Function(function) => (
+                    format!("Function `{}` in contract `{}`", function.name, ctx.name),
+                    &function.annotations,
+                    AnnotationTarget::Function,
+                ),
+                ContractPart::Event(event) => (
+                    format!("Event `{}` in contract `{}`", event.name, ctx.name),
+                    &event.annotations,
+                    AnnotationTarget::Event,
+                ),
+                ContractPart::Error(_) | ContractPart::Struct(_) => continue,
+            };
+            self.check_annotations(&owner, annotations, target);
+        }
+
         for part in &contract.parts {
             match part {
                 ContractPart::Function(function) => self.analyze_function(function, &ctx),
@@ -144,6 +221,8 @@ impl SemanticAnalyzer {
                         contract: &ctx,
                         function_name: "constructor".to_string(),
                         returns: None,
+                        visibility: Visibility::Public,
+                        mutability: StateMutability::NonPayable,
                         scopes: vec![root_scope],
                     };
                     self.analyze_block(&constructor.body, &mut fn_ctx);
@@ -168,10 +247,28 @@ impl SemanticAnalyzer {
             }
         }
 
+        // The dispatcher runs these on empty or unrecognised calldata, which carries no arguments.
+        if matches!(function.name.as_str(), "receive" | "fallback") {
+            if !function.params.is_empty() {
+                self.push_error(format!(
+                    "Function `{}` in contract `{}` cannot take parameters",
+                    function.name, contract.name
+                ));
+            }
+            if !function.visibility.is_entry_point() {
+                self.push_error(format!(
+                    "Function `{}` in contract `{}` must be `public` or `external`",
+                    function.name, contract.name
+                ));
+            }
+        }
+
         let mut ctx = FunctionContext {
             contract,
             function_name: function.name.clone(),
             returns: function.returns.as_ref(),
+            visibility: function.visibility,
+            mutability: function.mutability,
             scopes: vec![root_scope],
         };
 
@@ -206,9 +303,16 @@ impl SemanticAnalyzer {
     fn analyze_statement(&mut self, statement: &Statement, ctx: &mut FunctionContext<'_>) -> bool {
         match statement {
             Statement::VariableDeclaration(name, ty, value) => {
-                let value_ty = value
-                    .as_ref()
-                    .map(|expr| self.infer_expression_type(expr, ctx));
+                let value_ty = value.as_ref().map(|expr| {
+                    // Untyped `let` declarations fall back to `UInt256` and give literals no layout.
+                    if is_aggregate_literal(expr) && *ty != Type::UInt256 {
+                        let place = format!("initializer of `{name}`");
+                        self.check_aggregate_literal(ty, expr, &place, ctx);
+                        InferredType::known(ty.clone())
+                    } else {
+                        self.infer_expression_type(expr, ctx)
+                    }
+                });
                 let effective_ty = effective_variable_type(ty, value_ty.as_ref());
 
                 if let Some(scope) = ctx.scopes.last_mut() {
@@ -222,14 +326,17 @@ impl SemanticAnalyzer {
                     }
                 }
 
-                if let Some(inferred) = value_ty {
+                if let Some(expr) = value {
+                    self.check_literal_range(ty, expr, &format!("initializer of `{name}`"), ctx);
+                }
+                if let (Some(inferred), Some(expr)) = (value_ty, value.as_ref()) {
                     if let Some(actual_ty) = inferred.as_type() {
                         if should_enforce_variable_decl_check(ty, actual_ty)
-                            && !types_compatible(ty, actual_ty)
+                            && !assignable(ty, actual_ty, expr)
                         {
                             self.push_error(format!(
-                                "Function `{}` in contract `{}` initializes `{}` with incompatible type (expected `{:?}`, found `{:?}`)",
-                                ctx.function_name, ctx.contract.name, name, ty, actual_ty
+                                "Function `{}` in contract `{}` initializes `{}` with incompatible type (expected `{:?}`, found `{:?}`){}",
+                                ctx.function_name, ctx.contract.name, name, ty, actual_ty, conversion_hint(ty, actual_ty)
                             ));
                         }
                     }
@@ -243,19 +350,85 @@ impl SemanticAnalyzer {
                         "Function `{}` in contract `{}` assigns to undefined symbol `{}`",
                         ctx.function_name, ctx.contract.name, name
                     ));
+                } else if ctx.mutability <= StateMutability::View && is_state_variable(name, ctx) {
+                    self.push_error(format!(
+                        "Function `{}` in contract `{}` is declared `{}` but writes state variable `{}`",
+                        ctx.function_name,
+                        ctx.contract.name,
+                        ctx.mutability.as_str(),
+                        name
+                    ));
+                }
+
+                if let (Some(expected), true) = (target_ty.as_ref(), is_aggregate_literal(expr)) {
+                    self.check_aggregate_literal(
+                        expected,
+                        expr,
+                        &format!("assignment to `{name}`"),
+                        ctx,
+                    );
+                    return false;
                 }
 
                 let value_ty = self.infer_expression_type(expr, ctx);
+                if let Some(expected) = target_ty.as_ref() {
+                    self.check_literal_range(
+                        expected,
+                        expr,
+                        &format!("assignment to `{name}`"),
+                        ctx,
+                    );
+                }
                 if let (Some(expected), Some(actual)) = (target_ty.as_ref(), value_ty.as_type()) {
-                    // Parser currently stores assignment lvalues as root symbols.
-                    // Skip strict type checks for container-like lvalues until full lvalue AST support lands.
-                    if is_precise_assignment_target(expected) && !types_compatible(expected, actual)
+                    if is_precise_assignment_target(expected) && !assignable(expected, actual, expr)
+                    {
+                        self.push_error(format!(
+                            "Function `{}` in contract `{}` assigns incompatible type to `{}` (expected `{:?}`, found `{:?}`){}",
+                            ctx.function_name, ctx.contract.name, name, expected, actual, conversion_hint(expected, actual)
+                        ));
+                    }
+                }
+                false
+            }
+            Statement::ElementAssignment(target, value) => {
+                let target_ty = self.infer_expression_type(target, ctx);
+                let described = describe_place(target);
+                match place_root(target) {
+                    Some(root)
+                        if ctx.mutability <= StateMutability::View
+                            && is_state_variable(root, ctx) =>
                     {
                         self.push_error(format!(
-                            "Function `{}` in contract `{}` assigns incompatible type to `{}` (expected `{:?}`, found `{:?}`)",
-                            ctx.function_name, ctx.contract.name, name, expected, actual
+                            "Function `{}` in contract `{}` is declared `{}` but writes state variable `{}`",
+                            ctx.function_name,
+                            ctx.contract.name,
+                            ctx.mutability.as_str(),
+                            root
                         ));
                     }
+                    Some(_) => {}
+                    None => self.push_error(format!(
+                        "Function `{}` in contract `{}` assigns to `{}`, which is not a variable, field or element",
+                        ctx.function_name, ctx.contract.name, described
+                    )),
+                }
+                if matches!(target, Expression::MemberAccess(_, member) if member == "length") {
+                    self.push_error(format!(
+                        "Function `{}` in contract `{}` assigns to read-only `{}`",
+                        ctx.function_name, ctx.contract.name, described
+                    ));
+                }
+
+                match target_ty.as_type() {
+                    Some(expected) => self.check_assigned_value(
+                        expected,
+                        value,
+                        &format!("assignment to `{described}`"),
+                        ctx,
+                    ),
+                    None => {
+                        self.infer_expression_type(value, ctx);
+                    }
                 }
                 false
             }
@@ -273,12 +446,17 @@ impl SemanticAnalyzer {
                 }
 
                 if let (Some(expected), Some(return_expr)) = (ctx.returns, expr.as_ref()) {
+                    if is_aggregate_literal(return_expr) {
+                        self.check_aggregate_literal(expected, return_expr, "return value", ctx);
+                        return true;
+                    }
+                    self.check_literal_range(expected, return_expr, "return value", ctx);
                     let actual = self.infer_expression_type(return_expr, ctx);
                     if let Some(actual_ty) = actual.as_type() {
-                        if !types_compatible(expected, actual_ty) {
+                        if !assignable(expected, actual_ty, return_expr) {
                             self.push_error(format!(
-                                "Function `{}` in contract `{}` returns incompatible type (expected `{:?}`, found `{:?}`)",
-                                ctx.function_name, ctx.contract.name, expected, actual_ty
+                                "Function `{}` in contract `{}` returns incompatible type (expected `{:?}`, found `{:?}`){}",
+                                ctx.function_name, ctx.contract.name, expected, actual_ty, conversion_hint(expected, actual_ty)
                             ));
                         }
                     }
@@ -351,7 +529,16 @@ impl SemanticAnalyzer {
                 ctx.scopes.pop();
                 false
             }
-            Statement::Emit(_, args) => {
+            Statement::Emit(event, args) => {
+                if ctx.mutability <= StateMutability::View {
+                    self.push_error(format!(
+                        "Function `{}` in contract `{}` is declared `{}` but emits event `{}`",
+                        ctx.function_name,
+                        ctx.contract.name,
+                        ctx.mutability.as_str(),
+                        event
+                    ));
+                }
                 for arg in args {
                     self.infer_expression_type(arg, ctx);
                 }
@@ -368,6 +555,21 @@ impl SemanticAnalyzer {
 
                 block_terminates
             }
+            Statement::WithGasLimit(budget, block) => {
+                if gas_budget_literal(budget).is_none() {
+                    self.push_error(format!(
+                        "Function `{}` in contract `{}` uses a `with_gas_limit` budget that is not a positive integer literal",
+                        ctx.function_name, ctx.contract.name
+                    ));
+                }
+
+                ctx.scopes.push(HashMap::new());
+                self.analyze_block(block, ctx);
+                ctx.scopes.pop();
+
+                // Overrunning the budget resumes after the block, so it never terminates the function.
+                false
+            }
             Statement::Expression(expr) => {
                 self.infer_expression_type(expr, ctx);
                 false
@@ -390,29 +592,125 @@ impl SemanticAnalyzer {
                 Literal::Bytes(_) => InferredType::known(Type::Bytes),
             },
             Expression::Identifier(raw) => self.infer_identifier_type(raw, ctx),
+            Expression::Call(name, args) if self.struct_fields(name, ctx).is_some() => {
+                self.check_struct_constructor(name, args, ctx)
+            }
             Expression::Call(name, args) => self.infer_call_type(name, args, ctx),
+            Expression::MethodCall(receiver, method, args) => {
+                let receiver_ty = self.infer_expression_type(receiver, ctx);
+                let arg_types: Vec<InferredType> = args
+                    .iter()
+                    .map(|arg| self.infer_expression_type(arg, ctx))
+                    .collect();
+                match receiver_ty.as_type() {
+                    Some(array_ty @ Type::Array(_, _)) => {
+                        self.check_array_method(receiver, array_ty, method, args, &arg_types, ctx)
+                    }
+                    _ => InferredType::Unknown,
+                }
+            }
             Expression::MemberAccess(object, member) => {
                 let object_ty = self.infer_expression_type(object, ctx);
                 match object_ty {
+                    InferredType::Known(Type::Address) if member == "balance" => {
+                        self.check_balance_read(&describe_place(object), ctx);
+                        InferredType::known(Type::UInt256)
+                    }
                     InferredType::Known(Type::Array(_, _))
                     | InferredType::Known(Type::Bytes)
+                    | InferredType::Known(Type::FixedBytes(_))
                     | InferredType::Known(Type::String)
                         if member == "length" =>
                     {
                         InferredType::known(Type::UInt256)
                     }
+                    InferredType::Known(Type::Struct(name)) => {
+                        // Contract references and enums share `Type::Struct` but have no fields.
+                        let Some(fields) = self.struct_fields(&name, ctx) else {
+                            return InferredType::Unknown;
+                        };
+                        match fields.iter().find(|field| field.name == *member) {
+                            Some(field) => InferredType::known(field.ty.clone()),
+                            None => {
+                                self.push_error(format!(
+                                    "Function `{}` in contract `{}` accesses unknown member `{}` of struct `{}`",
+                                    ctx.function_name, ctx.contract.name, member, name
+                                ));
+                                InferredType::Unknown
+                            }
+                        }
+                    }
+                    InferredType::Known(array_ty @ Type::Array(_, _)) => {
+                        self.push_error(format!(
+                            "Function `{}` in contract `{}` accesses unknown member `{}` of `{:?}`",
+                            ctx.function_name, ctx.contract.name, member, array_ty
+                        ));
+                        InferredType::Unknown
+                    }
                     _ => InferredType::Unknown,
                 }
             }
             Expression::IndexAccess(object, index) => {
                 let object_ty = self.infer_expression_type(object, ctx);
-                self.infer_expression_type(index, ctx);
+                let index_ty = self.infer_expression_type(index, ctx);
                 match object_ty {
-                    InferredType::Known(Type::Array(element, _)) => InferredType::known(*element),
-                    InferredType::Known(Type::Mapping(_, value)) => InferredType::known(*value),
+                    InferredType::Known(Type::Array(element, size)) => {
+                        if let Some(index_ty) = index_ty.as_type() {
+                            if !is_numeric_type(index_ty) {
+                                self.push_error(format!(
+                                    "Function `{}` in contract `{}` indexes an array with non-integer `{:?}`",
+                                    ctx.function_name, ctx.contract.name, index_ty
+                                ));
+                            }
+                        }
+                        if let (Some(size), Expression::Literal(Literal::Number(value))) =
+                            (size, index.as_ref())
+                        {
+                            if *value >= U256::from(size) {
+                                self.push_error(format!(
+                                    "Function `{}` in contract `{}` indexes `{:?}` with out-of-bounds literal `{}`",
+                                    ctx.function_name,
+                                    ctx.contract.name,
+                                    Type::Array(element.clone(), Some(size)),
+                                    value
+                                ));
+                            }
+                        }
+                        InferredType::known(*element)
+                    }
+                    InferredType::Known(Type::Mapping(key, value)) => {
+                        if let Some(index_ty) = index_ty.as_type() {
+                            if !assignable(&key, index_ty, index) {
+                                self.push_error(format!(
+                                    "Function `{}` in contract `{}` indexes a mapping with incompatible key type (expected `{:?}`, found `{:?}`)",
+                                    ctx.function_name, ctx.contract.name, key, index_ty
+                                ));
+                            }
+                        }
+                        InferredType::known(*value)
+                    }
                     _ => InferredType::Unknown,
                 }
             }
+            Expression::ArrayLiteral(elements) => {
+                let element_types: Vec<InferredType> = elements
+                    .iter()
+                    .map(|element| self.infer_expression_type(element, ctx))
+                    .collect();
+                match element_types.first().and_then(InferredType::as_type) {
+                    Some(first) => InferredType::known(Type::Array(
+                        Box::new(first.clone()),
+                        Some(elements.len() as u32),
+                    )),
+                    None => InferredType::Unknown,
+                }
+            }
+            Expression::ObjectLiteral(fields) => {
+                for (_, value) in fields {
+                    self.infer_expression_type(value, ctx);
+                }
+                InferredType::Unknown
+            }
             Expression::Binary(op, lhs, rhs) => {
                 let lhs_ty = self.infer_expression_type(lhs, ctx);
                 let rhs_ty = self.infer_expression_type(rhs, ctx);
@@ -420,7 +718,7 @@ impl SemanticAnalyzer {
                 match op {
                     BinaryOp::Eq | BinaryOp::Ne => {
                         if let (Some(left), Some(right)) = (lhs_ty.as_type(), rhs_ty.as_type()) {
-                            if !types_compatible(left, right) {
+                            if !assignable(left, right, rhs) && !assignable(right, left, lhs) {
                                 self.push_error(format!(
                                     "Function `{}` in contract `{}` compares incompatible types `{:?}` and `{:?}`",
                                     ctx.function_name, ctx.contract.name, left, right
@@ -467,11 +765,23 @@ impl SemanticAnalyzer {
                     | BinaryOp::Shl
                     | BinaryOp::Shr => {
                         if let (Some(left), Some(right)) = (lhs_ty.as_type(), rhs_ty.as_type()) {
+                            if *op == BinaryOp::Add {
+                                if let Some(joined) = concatenation_type(left, right) {
+                                    return InferredType::known(joined);
+                                }
+                            }
                             if is_numeric_type(left) && is_numeric_type(right) {
-                                if is_signed_integer(left) || is_signed_integer(right) {
-                                    InferredType::known(Type::Int256)
+                                // Operands share the wider type; a literal adopts the other side's type.
+                                if is_integer_literal(rhs) || assignable(left, right, rhs) {
+                                    InferredType::known(left.clone())
+                                } else if is_integer_literal(lhs) || assignable(right, left, lhs) {
+                                    InferredType::known(right.clone())
                                 } else {
-                                    InferredType::known(Type::UInt256)
+                                    self.push_error(format!(
+                                        "Function `{}` in contract `{}` mixes `{:?}` and `{:?}` in arithmetic; convert one operand explicitly",
+                                        ctx.function_name, ctx.contract.name, left, right
+                                    ));
+                                    InferredType::Unknown
                                 }
                             } else {
                                 self.push_error(format!(
@@ -556,9 +866,29 @@ impl SemanticAnalyzer {
         }
 
         match trimmed {
-            "msg.sender" => return InferredType::known(Type::Address),
-            "msg.value" => return InferredType::known(Type::UInt256),
-            "block.number" | "block.timestamp" => return InferredType::known(Type::UInt256),
+            "msg.sender" | "this" => {
+                self.check_environment_read(trimmed, ctx);
+                return InferredType::known(Type::Address);
+            }
+            "msg.value" => {
+                self.check_environment_read(trimmed, ctx);
+                // Internal functions may read it on behalf of a payable caller.
+                if ctx.visibility.is_entry_point() && ctx.mutability != StateMutability::Payable {
+                    self.push_error(format!(
+                        "Function `{}` in contract `{}` reads `msg.value` but is not `payable`",
+                        ctx.function_name, ctx.contract.name
+                    ));
+                }
+                return InferredType::known(Type::UInt256);
+            }
+            "msg.sender.balance" => {
+                self.check_balance_read("msg.sender", ctx);
+                return InferredType::known(Type::UInt256);
+            }
+            "block.number" | "block.timestamp" => {
+                self.check_environment_read(trimmed, ctx);
+                return InferredType::known(Type::UInt256);
+            }
             "true" | "false" => return InferredType::known(Type::Bool),
             "break" | "continue" => return InferredType::Unknown,
             _ => {}
@@ -574,6 +904,12 @@ impl SemanticAnalyzer {
         };
 
         if let Some(base_ty) = self.lookup_symbol_type(root_symbol, ctx) {
+            if ctx.mutability == StateMutability::Pure && is_state_variable(root_symbol, ctx) {
+                self.push_error(format!(
+                    "Function `{}` in contract `{}` is declared `pure` but reads state variable `{}`",
+                    ctx.function_name, ctx.contract.name, root_symbol
+                ));
+            }
             let suffix = &trimmed[root_symbol.len()..];
             if suffix.is_empty() {
                 return InferredType::known(base_ty);
@@ -623,12 +959,35 @@ impl SemanticAnalyzer {
                     name,
                     arg_types.len()
                 ));
+            } else if let Some(source) = arg_types[0].as_type() {
+                if !explicit_conversion_allowed(source, &cast_ty) {
+                    self.push_error(format!(
+                        "Function `{}` in contract `{}` cannot convert `{:?}` to `{:?}`",
+                        ctx.function_name, ctx.contract.name, source, cast_ty
+                    ));
+                }
+                // Constant integers must already fit; byte literals are resized like any other value.
+                if is_integer_literal(&args[0]) {
+                    self.check_literal_range(
+                        &cast_ty,
+                        &args[0],
+                        &format!("conversion to `{}`", name),
+                        ctx,
+                    );
+                }
             }
             return InferredType::known(cast_ty);
         }
 
         if let Some(signature) = ctx.contract.functions.get(name) {
-            self.validate_call_signature(name, signature, &arg_types, ctx);
+            if signature.visibility == Visibility::External {
+                self.push_error(format!(
+                    "Function `{}` in contract `{}` calls external function `{}` internally",
+                    ctx.function_name, ctx.contract.name, name
+                ));
+            }
+            self.validate_call_mutability(name, signature, ctx);
+            self.validate_call_signature(name, signature, args, &arg_types, ctx);
             return signature
                 .returns
                 .clone()
@@ -636,9 +995,48 @@ impl SemanticAnalyzer {
                 .unwrap_or(InferredType::Unknown);
         }
 
+        if let Some((target, signature)) = self.resolve_contract_call(name, ctx) {
+            if !signature.visibility.is_entry_point() && target != ctx.contract.name {
+                self.push_error(format!(
+                    "Function `{}` in contract `{}` calls {} function `{}` from outside contract `{}`",
+                    ctx.function_name,
+                    ctx.contract.name,
+                    signature.visibility.as_str(),
+                    name,
+                    target
+                ));
+            }
+            self.validate_call_mutability(name, &signature, ctx);
+            self.validate_call_signature(name, &signature, args, &arg_types, ctx);
+            return signature
+                .returns
+                .map(InferredType::known)
+                .unwrap_or(InferredType::Unknown);
+        }
+
+        if name == "slice" {
+            return self.infer_slice_type(&arg_types, ctx);
+        }
+
+        if let Some((receiver, method @ ("push" | "pop"))) = name.rsplit_once('.') {
+            let receiver = path_expression(receiver);
+            if let Some(root) = place_root(&receiver) {
+                if let Some(Type::Array(element, size)) = self.lookup_symbol_type(root, ctx) {
+                    let receiver_ty = self.infer_expression_type(&receiver, ctx);
+                    let array_ty = receiver_ty
+                        .as_type()
+                        .cloned()
+                        .unwrap_or(Type::Array(element, size));
+                    return self
+                        .check_array_method(&receiver, &array_ty, method, args, &arg_types, ctx);
+                }
+            }
+        }
+
         match resolve_builtin_signature(name) {
             BuiltinResolution::Supported(signature) => {
-                self.validate_call_signature(name, &signature, &arg_types, ctx);
+                self.validate_call_mutability(name, &signature, ctx);
+                self.validate_call_signature(name, &signature, args, &arg_types, ctx);
                 signature
                     .returns
                     .clone()
@@ -660,104 +1058,1040 @@ impl SemanticAnalyzer {
         }
     }
 
-    fn validate_call_signature(
+    /// Fields of struct `name`, preferring the current contract's own definitions.
+    fn struct_fields(&self, name: &str, ctx: &FunctionContext<'_>) -> Option<Vec<Parameter>> {
+        ctx.contract
+            .structs
+            .get(name)
+            .or_else(|| self.structs.get(name))
+            .cloned()
+    }
+
+    /// `Name({ field: value, ... })` or `Name(value, ...)` with values in declaration order.
+    fn check_struct_constructor(
         &mut self,
         name: &str,
-        signature: &FunctionSignature,
-        args: &[InferredType],
+        args: &[Expression],
         ctx: &FunctionContext<'_>,
-    ) {
-        if signature.params.len() != args.len() {
+    ) -> InferredType {
+        let ty = Type::Struct(name.to_string());
+        let place = format!("`{name}` constructor");
+        if let [literal @ Expression::ObjectLiteral(_)] = args {
+            self.check_aggregate_literal(&ty, literal, &place, ctx);
+            return InferredType::known(ty);
+        }
+
+        let fields = self.struct_fields(name, ctx).unwrap_or_default();
+        if args.len() != fields.len() {
             self.push_error(format!(
-                "Function `{}` in contract `{}` calls `{}` with {} arguments; expected {}",
+                "Function `{}` in contract `{}` passes {} values to {}; expected {}",
                 ctx.function_name,
                 ctx.contract.name,
-                name,
                 args.len(),
-                signature.params.len()
+                place,
+                fields.len()
             ));
-            return;
         }
+        for (field, arg) in fields.iter().zip(args) {
+            let field_place = format!("field `{}` of {}", field.name, place);
+            self.check_assigned_value(&field.ty, arg, &field_place, ctx);
+        }
+        InferredType::known(ty)
+    }
 
-        for (idx, (expected, actual)) in signature.params.iter().zip(args.iter()).enumerate() {
-            if let Some(actual_ty) = actual.as_type() {
-                if !types_compatible(expected, actual_ty) {
-                    self.push_error(format!(
-                        "Function `{}` in contract `{}` passes incompatible argument {} to `{}` (expected `{:?}`, found `{:?}`)",
-                        ctx.function_name,
-                        ctx.contract.name,
-                        idx + 1,
-                        name,
-                        expected,
-                        actual_ty
-                    ));
+    /// Checks a struct or array literal against the type it initializes, element by element.
+    fn check_aggregate_literal(
+        &mut self,
+        expected: &Type,
+        expr: &Expression,
+        place: &str,
+        ctx: &FunctionContext<'_>,
+    ) {
+        match (expected, expr) {
+            (Type::Struct(name), Expression::ObjectLiteral(values)) => {
+                let Some(fields) = self.struct_fields(name, ctx) else {
+                    self.infer_expression_type(expr, ctx);
+                    return;
+                };
+                let mut seen = HashSet::new();
+                for (key, value) in values {
+                    let Some(field) = fields.iter().find(|field| field.name == *key) else {
+                        self.push_error(format!(
+                            "Function `{}` in contract `{}` sets unknown field `{}` of struct `{}` in {}",
+                            ctx.function_name, ctx.contract.name, key, name, place
+                        ));
+                        self.infer_expression_type(value, ctx);
+                        continue;
+                    };
+                    if !seen.insert(key.as_str()) {
+                        self.push_error(format!(
+                            "Function `{}` in contract `{}` sets field `{}` more than once in {}",
+                            ctx.function_name, ctx.contract.name, key, place
+                        ));
+                    }
+                    let field_place = format!("field `{key}` of {place}");
+                    self.check_assigned_value(&field.ty, value, &field_place, ctx);
+                }
+                for field in &fields {
+                    if !seen.contains(field.name.as_str()) {
+                        self.push_error(format!(
+                            "Function `{}` in contract `{}` leaves field `{}` of struct `{}` unset in {}",
+                            ctx.function_name, ctx.contract.name, field.name, name, place
+                        ));
+                    }
+                }
+            }
+            (Type::Array(element, size), Expression::ArrayLiteral(elements)) => {
+                if let Some(size) = size {
+                    if elements.len() != *size as usize {
+                        self.push_error(format!(
+                            "Function `{}` in contract `{}` uses {} elements for `{:?}` in {}",
+                            ctx.function_name,
+                            ctx.contract.name,
+                            elements.len(),
+                            expected,
+                            place
+                        ));
+                    }
                 }
+                for (idx, value) in elements.iter().enumerate() {
+                    let element_place = format!("element {idx} of {place}");
+                    self.check_assigned_value(element, value, &element_place, ctx);
+                }
+            }
+            _ => {
+                let kind = match expr {
+                    Expression::ObjectLiteral(_) => "an object",
+                    _ => "an array",
+                };
+                self.push_error(format!(
+                    "Function `{}` in contract `{}` uses {} literal where `{:?}` is expected in {}",
+                    ctx.function_name, ctx.contract.name, kind, expected, place
+                ));
+                self.infer_expression_type(expr, ctx);
             }
         }
     }
 
-    fn lookup_symbol_type(&self, symbol: &str, ctx: &FunctionContext<'_>) -> Option<Type> {
-        for scope in ctx.scopes.iter().rev() {
-            if let Some(ty) = scope.get(symbol) {
-                return Some(ty.clone());
-            }
+    /// Type-checks `value` stored into a slot of type `expected`.
+    fn check_assigned_value(
+        &mut self,
+        expected: &Type,
+        value: &Expression,
+        place: &str,
+        ctx: &FunctionContext<'_>,
+    ) {
+        if is_aggregate_literal(value) {
+            self.check_aggregate_literal(expected, value, place, ctx);
+            return;
         }
-
-        if let Some(ty) = ctx.contract.state_variables.get(symbol) {
-            return Some(ty.clone());
+        let actual = self.infer_expression_type(value, ctx);
+        self.check_literal_range(expected, value, place, ctx);
+        if let Some(actual) = actual.as_type() {
+            if !assignable(expected, actual, value) {
+                self.push_error(format!(
+                    "Function `{}` in contract `{}` stores incompatible type in {} (expected `{:?}`, found `{:?}`){}",
+                    ctx.function_name,
+                    ctx.contract.name,
+                    place,
+                    expected,
+                    actual,
+                    conversion_hint(expected, actual)
+                ));
+            }
         }
-
-        None
-    }
-
-    fn push_error(&mut self, message: String) {
-        self.errors.push(SemanticError {
-            message,
-            line: None,
-            column: None,
-        });
     }
-}
-
-pub fn analyze(ast: &[SourceUnit]) -> Result<(), Vec<SemanticError>> {
-    SemanticAnalyzer::analyze(ast)
-}
 
-fn resolve_builtin_signature(name: &str) -> BuiltinResolution {
-    let normalized = normalize_name(name);
+    /// `push(value)` and `pop()` on dynamic arrays.
+    fn check_array_method(
+        &mut self,
+        receiver: &Expression,
+        array_ty: &Type,
+        method: &str,
+        args: &[Expression],
+        arg_types: &[InferredType],
+        ctx: &FunctionContext<'_>,
+    ) -> InferredType {
+        let Type::Array(element, size) = array_ty else {
+            return InferredType::Unknown;
+        };
+        let expected_args = match method {
+            "push" => 1,
+            "pop" => 0,
+            _ => {
+                self.push_error(format!(
+                    "Function `{}` in contract `{}` calls unknown array method `{}`",
+                    ctx.function_name, ctx.contract.name, method
+                ));
+                return InferredType::Unknown;
+            }
+        };
 
-    if normalized.starts_with("verifyslhdsa") || normalized.starts_with("slhdsa") {
-        return BuiltinResolution::Unsupported(
-            "SLH-DSA is de-scoped in the current SynQ runtime profile".to_string(),
-        );
+        let described = describe_place(receiver);
+        if size.is_some() {
+            self.push_error(format!(
+                "Function `{}` in contract `{}` calls `{}` on fixed-size array `{}`",
+                ctx.function_name, ctx.contract.name, method, described
+            ));
+        }
+        if let Some(root) = place_root(receiver) {
+            if ctx.mutability <= StateMutability::View && is_state_variable(root, ctx) {
+                self.push_error(format!(
+                    "Function `{}` in contract `{}` is declared `{}` but writes state variable `{}`",
+                    ctx.function_name,
+                    ctx.contract.name,
+                    ctx.mutability.as_str(),
+                    root
+                ));
+            }
+        }
+        if args.len() != expected_args {
+            self.push_error(format!(
+                "Function `{}` in contract `{}` calls `{}` on `{}` with {} arguments; expected {}",
+                ctx.function_name,
+                ctx.contract.name,
+                method,
+                described,
+                args.len(),
+                expected_args
+            ));
+        } else if let (Some(value), Some(actual)) = (
+            args.first(),
+            arg_types.first().and_then(InferredType::as_type),
+        ) {
+            let place = format!("`{described}.push`");
+            self.check_literal_range(element, value, &place, ctx);
+            if !assignable(element, actual, value) {
+                self.push_error(format!(
+                    "Function `{}` in contract `{}` stores incompatible type in {} (expected `{:?}`, found `{:?}`){}",
+                    ctx.function_name,
+                    ctx.contract.name,
+                    place,
+                    element,
+                    actual,
+                    conversion_hint(element, actual)
+                ));
+            }
+        }
+        InferredType::Unknown
     }
 
-    if normalized.starts_with("verifymldsa") {
-        return BuiltinResolution::Supported(FunctionSignature {
-            params: vec![Type::MLDSAPublicKey, Type::Bytes, Type::MLDSASignature],
-            returns: Some(Type::Bool),
+    /// Resolves `Contract.method` and `instance.method` calls to the callee's contract and signature.
+    fn resolve_contract_call(
+        &self,
+        name: &str,
+        ctx: &FunctionContext<'_>,
+    ) -> Option<(String, FunctionSignature)> {
+        let (receiver, method) = name.rsplit_once('.')?;
+        let target = if self.contracts.contains_key(receiver) {
+            receiver.to_string()
+        } else {
+            match self.lookup_symbol_type(receiver, ctx)? {
+                Type::Struct(contract) => contract,
+                _ => return None,
+            }
+        };
+        let signature = self.contracts.get(&target)?.get(method)?.clone();
+        Some((target, signature))
+    }
+
+    /// Balances are chain state, so `pure` functions may not read them.
+    fn check_environment_read(&mut self, name: &str, ctx: &FunctionContext<'_>) {
+        if ctx.mutability == StateMutability::Pure {
+            self.push_error(format!(
+                "Function `{}` in contract `{}` is declared `pure` but reads `{}`",
+                ctx.function_name, ctx.contract.name, name
+            ));
+        }
+    }
+
+    fn check_balance_read(&mut self, account: &str, ctx: &FunctionContext<'_>) {
+        if ctx.mutability == StateMutability::Pure {
+            self.push_error(format!(
+                "Function `{}` in contract `{}` is declared `pure` but reads the balance of `{}`",
+                ctx.function_name, ctx.contract.name, account
+            ));
+        }
+    }
+
+    fn validate_call_mutability(
+        &mut self,
+        name: &str,
+        signature: &FunctionSignature,
+        ctx: &FunctionContext<'_>,
+    ) {
+        if ctx.mutability <= StateMutability::View && signature.mutability > ctx.mutability {
+            self.push_error(format!(
+                "Function `{}` in contract `{}` is declared `{}` but calls `{}` function `{}`",
+                ctx.function_name,
+                ctx.contract.name,
+                ctx.mutability.as_str(),
+                signature.mutability.as_str(),
+                name
+            ));
+        }
+    }
+
+    fn validate_call_signature(
+        &mut self,
+        name: &str,
+        signature: &FunctionSignature,
+        arg_exprs: &[Expression],
+        args: &[InferredType],
+        ctx: &FunctionContext<'_>,
+    ) {
+        if signature.params.len() != args.len() {
+            self.push_error(format!(
+                "Function `{}` in contract `{}` calls `{}` with {} arguments; expected {}",
+                ctx.function_name,
+                ctx.contract.name,
+                name,
+                args.len(),
+                signature.params.len()
+            ));
+            return;
+        }
+
+        for (idx, (expected, actual)) in signature.params.iter().zip(args.iter()).enumerate() {
+            self.check_literal_range(
+                expected,
+                &arg_exprs[idx],
+                &format!("argument {} to `{}`", idx + 1, name),
+                ctx,
+            );
+            if let Some(actual_ty) = actual.as_type() {
+                if !assignable(expected, actual_ty, &arg_exprs[idx]) {
+                    self.push_error(format!(
+                        "Function `{}` in contract `{}` passes incompatible argument {} to `{}` (expected `{:?}`, found `{:?}`){}",
+                        ctx.function_name,
+                        ctx.contract.name,
+                        idx + 1,
+                        name,
+                        expected,
+                        actual_ty,
+                        conversion_hint(expected, actual_ty)
+                    ));
+                }
+            }
+        }
+    }
+
+    /// `slice(value, start, end)` copies `value[start..end]` out of a `String` or `Bytes` value.
+    fn infer_slice_type(
+        &mut self,
+        args: &[InferredType],
+        ctx: &FunctionContext<'_>,
+    ) -> InferredType {
+        if args.len() != 3 {
+            self.push_error(format!(
+                "Function `{}` in contract `{}` calls `slice` with {} arguments; expected 3",
+                ctx.function_name,
+                ctx.contract.name,
+                args.len()
+            ));
+            return InferredType::Unknown;
+        }
+
+        for (idx, bound) in args[1..].iter().enumerate() {
+            if let Some(ty) = bound.as_type() {
+                if !is_numeric_type(ty) {
+                    self.push_error(format!(
+                        "Function `{}` in contract `{}` passes non-numeric `{:?}` as argument {} to `slice`",
+                        ctx.function_name,
+                        ctx.contract.name,
+                        ty,
+                        idx + 2
+                    ));
+                }
+            }
+        }
+
+        match args[0].as_type() {
+            Some(Type::String) => InferredType::known(Type::String),
+            Some(Type::Bytes | Type::FixedBytes(_)) => InferredType::known(Type::Bytes),
+            Some(other) => {
+                self.push_error(format!(
+                    "Function `{}` in contract `{}` slices non-byte type `{:?}`",
+                    ctx.function_name, ctx.contract.name, other
+                ));
+                InferredType::Unknown
+            }
+            None => InferredType::Unknown,
+        }
+    }
+
+    fn check_literal_range(
+        &mut self,
+        expected: &Type,
+        expr: &Expression,
+        place: &str,
+        ctx: &FunctionContext<'_>,
+    ) {
+        if let Some(literal) = literal_out_of_range(expected, expr) {
+            self.push_error(format!(
+                "Function `{}` in contract `{}` uses literal `{}` out of range for `{:?}` in {}",
+                ctx.function_name, ctx.contract.name, literal, expected, place
+            ));
+        }
+    }
+
+    fn lookup_symbol_type(&self, symbol: &str, ctx: &FunctionContext<'_>) -> Option<Type> {
+        for scope in ctx.scopes.iter().rev() {
+            if let Some(ty) = scope.get(symbol) {
+                return Some(ty.clone());
+            }
+        }
+
+        if let Some(ty) = ctx.contract.state_variables.get(symbol) {
+            return Some(ty.clone());
+        }
+
+        None
+    }
+
+    fn check_annotations(
+        &mut self,
+        owner: &str,
+        annotations: &[Annotation],
+        target: AnnotationTarget,
+    ) {
+        if let Err(errors) = annotations::resolve_all(annotations, target) {
+            for error in errors {
+                self.push_error(format!("{} {}", owner, error));
+            }
+        }
+    }
+
+    fn push_error(&mut self, message: String) {
+        self.errors.push(SemanticError {
+            message,
+            line: None,
+            column: None,
         });
     }
+}
 
-    if normalized.starts_with("verifyfndsa") {
-        return BuiltinResolution::Supported(FunctionSignature {
-            params: vec![Type::FNDSAPublicKey, Type::Bytes, Type::FNDSASignature],
-            returns: Some(Type::Bool),
+fn collect_function_signatures(
+    contract: &ContractDefinition,
+) -> HashMap<String, FunctionSignature> {
+    let mut functions = HashMap::new();
+    for part in &contract.parts {
+        if let ContractPart::Function(function) = part {
+            let signature = FunctionSignature {
+                params: function.params.iter().map(|p| p.ty.clone()).collect(),
+                returns: function.returns.clone(),
+                visibility: function.visibility,
+                mutability: function.mutability,
+            };
+            functions.entry(function.name.clone()).or_insert(signature);
+        }
+    }
+    functions
+}
+
+/// Budget of a `with_gas_limit` block; only positive integer literals that fit the VM operand are accepted.
+pub(crate) fn gas_budget_literal(budget: &Expression) -> Option<u32> {
+    match budget {
+        Expression::Literal(Literal::Number(n)) if !n.is_zero() => u32::try_from(*n).ok(),
+        _ => None,
+    }
+}
+
+/// True when `symbol` names a state variable that is not shadowed by a local or parameter.
+fn is_aggregate_literal(expr: &Expression) -> bool {
+    matches!(
+        expr,
+        Expression::ArrayLiteral(_) | Expression::ObjectLiteral(_)
+    )
+}
+
+/// `a.b.c` callee prefix as an identifier with member accesses.
+pub(crate) fn path_expression(path: &str) -> Expression {
+    let mut segments = path.split('.');
+    let root = Expression::Identifier(segments.next().unwrap_or_default().to_string());
+    segments.fold(root, |object, member| {
+        Expression::MemberAccess(Box::new(object), member.to_string())
+    })
+}
+
+/// Variable an assignment target or array method receiver ultimately writes to.
+fn place_root(expr: &Expression) -> Option<&str> {
+    match expr {
+        Expression::Identifier(name) => extract_root_symbol(name),
+        Expression::MemberAccess(object, _) | Expression::IndexAccess(object, _) => {
+            place_root(object)
+        }
+        _ => None,
+    }
+}
+
+/// Source-like rendering of a place for diagnostics, e.g. `proposals[id].votes`.
+fn describe_place(expr: &Expression) -> String {
+    match expr {
+        Expression::Identifier(name) => name.clone(),
+        Expression::Literal(Literal::Number(value)) => value.to_string(),
+        Expression::MemberAccess(object, member) => {
+            format!("{}.{}", describe_place(object), member)
+        }
+        Expression::IndexAccess(object, index) => {
+            format!("{}[{}]", describe_place(object), describe_place(index))
+        }
+        _ => "..".to_string(),
+    }
+}
+
+fn is_state_variable(symbol: &str, ctx: &FunctionContext<'_>) -> bool {
+    ctx.contract.state_variables.contains_key(symbol)
+        && !ctx.scopes.iter().any(|scope| scope.contains_key(symbol))
+}
+
+pub fn analyze(ast: &[SourceUnit]) -> Result<(), Vec<SemanticError>> {
+    SemanticAnalyzer::analyze(ast)
+}
+
+/// Replaces generic structs and contracts with concrete copies for every instantiation used.
+///
+/// Generic definitions act as templates: they are dropped from the output and each distinct
+/// `Name<Args>` reference becomes a concrete definition named by `mangle_generic_name`.
+/// Named instantiations (`contract TokenVault = Vault<UInt256>;`) keep their declared name.
+pub fn monomorphize(units: &[SourceUnit]) -> Result<Vec<SourceUnit>, Vec<SemanticError>> {
+    let mut monomorphizer = Monomorphizer::new(units);
+    monomorphizer.run(units);
+    if monomorphizer.errors.is_empty() {
+        Ok(monomorphizer.output)
+    } else {
+        Err(monomorphizer.errors)
+    }
+}
+
+/// Builds the concrete name for a generic instantiation, e.g. `Vault<UInt256>` -> `Vault_UInt256`.
+pub fn mangle_generic_name(name: &str, args: &[Type]) -> String {
+    let mut mangled = name.to_string();
+    for arg in args {
+        mangled.push('_');
+        mangled.push_str(&type_mangle_name(arg));
+    }
+    mangled
+}
+
+enum PendingInstance<'a> {
+    Struct(&'a StructDefinition),
+    Contract(&'a ContractDefinition),
+}
+
+struct Monomorphizer<'a> {
+    struct_templates: HashMap<&'a str, &'a StructDefinition>,
+    contract_templates: HashMap<&'a str, &'a ContractDefinition>,
+    instantiated: HashSet<String>,
+    pending: VecDeque<(String, PendingInstance<'a>, Vec<Type>, usize)>,
+    output: Vec<SourceUnit>,
+    errors: Vec<SemanticError>,
+}
+
+impl<'a> Monomorphizer<'a> {
+    fn new(units: &'a [SourceUnit]) -> Self {
+        let mut struct_templates = HashMap::new();
+        let mut contract_templates = HashMap::new();
+        for unit in units {
+            match unit {
+                SourceUnit::Struct(s) if !s.generic_params.is_empty() => {
+                    struct_templates.insert(s.name.as_str(), s);
+                }
+                SourceUnit::Contract(c) if !c.generic_params.is_empty() => {
+                    contract_templates.insert(c.name.as_str(), c);
+                }
+                _ => {}
+            }
+        }
+
+        Monomorphizer {
+            struct_templates,
+            contract_templates,
+            instantiated: HashSet::new(),
+            pending: VecDeque::new(),
+            output: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn run(&mut self, units: &'a [SourceUnit]) {
+        let no_subst = HashMap::new();
+
+        for unit in units {
+            match unit {
+                SourceUnit::Struct(s) if s.generic_params.is_empty() => {
+                    let concrete = self.instantiate_struct(s, s.name.clone(), &no_subst, 0);
+                    self.output.push(SourceUnit::Struct(concrete));
+                }
+                SourceUnit::Contract(c) if c.generic_params.is_empty() => {
+                    let concrete = self.instantiate_contract(c, c.name.clone(), &no_subst, 0);
+                    self.output.push(SourceUnit::Contract(concrete));
+                }
+                SourceUnit::ContractInstance(instance) => {
+                    if let Some(concrete) = self.instantiate_named_contract(instance) {
+                        self.output.push(SourceUnit::Contract(concrete));
+                    }
+                }
+                SourceUnit::Event(event) => self.output.push(SourceUnit::Event(event.clone())),
+                // Generic templates only produce output through instantiation.
+                SourceUnit::Struct(_) | SourceUnit::Contract(_) => {}
+            }
+        }
+
+        while let Some((name, template, args, depth)) = self.pending.pop_front() {
+            match template {
+                PendingInstance::Struct(s) => {
+                    let subst = bind_type_params(&s.generic_params, &args);
+                    let concrete = self.instantiate_struct(s, name, &subst, depth);
+                    self.output.push(SourceUnit::Struct(concrete));
+                }
+                PendingInstance::Contract(c) => {
+                    let subst = bind_type_params(&c.generic_params, &args);
+                    let concrete = self.instantiate_contract(c, name, &subst, depth);
+                    self.output.push(SourceUnit::Contract(concrete));
+                }
+            }
+        }
+    }
+
+    fn instantiate_named_contract(
+        &mut self,
+        instance: &ContractInstantiation,
+    ) -> Option<ContractDefinition> {
+        let Some(template) = self
+            .contract_templates
+            .get(instance.generic.as_str())
+            .copied()
+        else {
+            self.push_error(format!(
+                "Contract `{}` instantiates `{}`, which is not a generic contract",
+                instance.name, instance.generic
+            ));
+            return None;
+        };
+
+        if !self.check_arity(
+            &instance.generic,
+            &template.generic_params,
+            &instance.type_args,
+        ) {
+            return None;
+        }
+
+        let no_subst = HashMap::new();
+        let args: Vec<Type> = instance
+            .type_args
+            .iter()
+            .map(|arg| self.resolve_type(arg, &no_subst, 0))
+            .collect();
+        let subst = bind_type_params(&template.generic_params, &args);
+        let mut concrete = self.instantiate_contract(template, instance.name.clone(), &subst, 0);
+        concrete
+            .annotations
+            .extend(instance.annotations.iter().cloned());
+        Some(concrete)
+    }
+
+    fn instantiate_struct(
+        &mut self,
+        template: &StructDefinition,
+        name: String,
+        subst: &HashMap<String, Type>,
+        depth: usize,
+    ) -> StructDefinition {
+        StructDefinition {
+            name,
+            generic_params: Vec::new(),
+            fields: self.substitute_params(&template.fields, subst, depth),
+        }
+    }
+
+    fn instantiate_contract(
+        &mut self,
+        template: &ContractDefinition,
+        name: String,
+        subst: &HashMap<String, Type>,
+        depth: usize,
+    ) -> ContractDefinition {
+        let mut parts = Vec::with_capacity(template.parts.len());
+        for part in &template.parts {
+            let part = match part {
+                ContractPart::StateVariable(state) => {
+                    let mut state = state.clone();
+                    state.ty = self.resolve_type(&state.ty, subst, depth);
+                    ContractPart::StateVariable(state)
+                }
+                ContractPart::Constructor(ctor) => {
+                    let mut ctor = ctor.clone();
+                    ctor.params = self.substitute_params(&ctor.params, subst, depth);
+                    ctor.body = self.substitute_block(&ctor.body, subst, depth);
+                    ContractPart::Constructor(ctor)
+                }
+                ContractPart::Function(function) => {
+                    let mut function = function.clone();
+                    function.params = self.substitute_params(&function.params, subst, depth);
+                    function.returns = function
+                        .returns
+                        .as_ref()
+                        .map(|ty| self.resolve_type(ty, subst, depth));
+                    function.body = self.substitute_block(&function.body, subst, depth);
+                    ContractPart::Function(function)
+                }
+                ContractPart::Event(event) => {
+                    let mut event = event.clone();
+                    event.params = self.substitute_params(&event.params, subst, depth);
+                    ContractPart::Event(event)
+                }
+                ContractPart::Error(error) => {
+                    let mut error = error.clone();
+                    error.params = self.substitute_params(&error.params, subst, depth);
+                    ContractPart::Error(error)
+                }
+                ContractPart::Struct(s) => {
+                    let name = s.name.clone();
+                    ContractPart::Struct(self.instantiate_struct(s, name, subst, depth))
+                }
+            };
+            parts.push(part);
+        }
+
+        ContractDefinition {
+            name,
+            generic_params: Vec::new(),
+            annotations: template.annotations.clone(),
+            parts,
+        }
+    }
+
+    fn substitute_params(
+        &mut self,
+        params: &[Parameter],
+        subst: &HashMap<String, Type>,
+        depth: usize,
+    ) -> Vec<Parameter> {
+        params
+            .iter()
+            .map(|param| Parameter {
+                name: param.name.clone(),
+                ty: self.resolve_type(&param.ty, subst, depth),
+                is_indexed: param.is_indexed,
+            })
+            .collect()
+    }
+
+    fn substitute_block(
+        &mut self,
+        block: &Block,
+        subst: &HashMap<String, Type>,
+        depth: usize,
+    ) -> Block {
+        Block {
+            statements: block
+                .statements
+                .iter()
+                .map(|stmt| self.substitute_statement(stmt, subst, depth))
+                .collect(),
+            spans: block.spans.clone(),
+        }
+    }
+
+    fn substitute_statement(
+        &mut self,
+        statement: &Statement,
+        subst: &HashMap<String, Type>,
+        depth: usize,
+    ) -> Statement {
+        match statement {
+            Statement::VariableDeclaration(name, ty, value) => Statement::VariableDeclaration(
+                name.clone(),
+                self.resolve_type(ty, subst, depth),
+                value
+                    .as_ref()
+                    .map(|expr| substitute_expression(expr, subst)),
+            ),
+            Statement::Assignment(name, expr) => {
+                Statement::Assignment(name.clone(), substitute_expression(expr, subst))
+            }
+            Statement::ElementAssignment(target, expr) => Statement::ElementAssignment(
+                substitute_expression(target, subst),
+                substitute_expression(expr, subst),
+            ),
+            Statement::Return(expr) => {
+                Statement::Return(expr.as_ref().map(|expr| substitute_expression(expr, subst)))
+            }
+            Statement::Require(expr, message) => {
+                Statement::Require(substitute_expression(expr, subst), message.clone())
+            }
+            Statement::Revert(message) => Statement::Revert(message.clone()),
+            Statement::If(condition, then_block, else_block) => Statement::If(
+                substitute_expression(condition, subst),
+                self.substitute_block(then_block, subst, depth),
+                else_block
+                    .as_ref()
+                    .map(|block| self.substitute_block(block, subst, depth)),
+            ),
+            Statement::For(iterator, start, end, body) => Statement::For(
+                iterator.clone(),
+                substitute_expression(start, subst),
+                substitute_expression(end, subst),
+                self.substitute_block(body, subst, depth),
+            ),
+            Statement::Emit(event, args) => Statement::Emit(
+                event.clone(),
+                args.iter()
+                    .map(|arg| substitute_expression(arg, subst))
+                    .collect(),
+            ),
+            Statement::RequirePqc(block, fallback) => Statement::RequirePqc(
+                self.substitute_block(block, subst, depth),
+                fallback
+                    .as_ref()
+                    .map(|stmt| Box::new(self.substitute_statement(stmt, subst, depth))),
+            ),
+            Statement::WithGasLimit(budget, block) => Statement::WithGasLimit(
+                substitute_expression(budget, subst),
+                self.substitute_block(block, subst, depth),
+            ),
+            Statement::Expression(expr) => {
+                Statement::Expression(substitute_expression(expr, subst))
+            }
+        }
+    }
+
+    fn resolve_type(&mut self, ty: &Type, subst: &HashMap<String, Type>, depth: usize) -> Type {
+        match ty {
+            Type::Struct(name) => subst.get(name).cloned().unwrap_or_else(|| ty.clone()),
+            Type::Array(element, size) => {
+                Type::Array(Box::new(self.resolve_type(element, subst, depth)), *size)
+            }
+            Type::Mapping(key, value) => Type::Mapping(
+                Box::new(self.resolve_type(key, subst, depth)),
+                Box::new(self.resolve_type(value, subst, depth)),
+            ),
+            Type::Generic(name, args) => {
+                let args: Vec<Type> = args
+                    .iter()
+                    .map(|arg| self.resolve_type(arg, subst, depth))
+                    .collect();
+
+                if name == "Tuple" {
+                    return Type::Generic(name.clone(), args);
+                }
+
+                let template = if let Some(s) = self.struct_templates.get(name.as_str()) {
+                    PendingInstance::Struct(s)
+                } else if let Some(c) = self.contract_templates.get(name.as_str()) {
+                    PendingInstance::Contract(c)
+                } else {
+                    self.push_error(format!(
+                        "Type `{}` is not generic but is used with {} type argument(s)",
+                        name,
+                        args.len()
+                    ));
+                    return Type::Struct(name.clone());
+                };
+
+                let params = match template {
+                    PendingInstance::Struct(s) => &s.generic_params,
+                    PendingInstance::Contract(c) => &c.generic_params,
+                };
+                if !self.check_arity(name, params, &args) {
+                    return Type::Struct(name.clone());
+                }
+
+                let mangled = mangle_generic_name(name, &args);
+                if self.instantiated.insert(mangled.clone()) {
+                    if depth + 1 > MAX_INSTANTIATION_DEPTH {
+                        self.push_error(format!(
+                            "Generic instantiation `{}` exceeds the maximum depth of {}; the definition of `{}` is likely recursive",
+                            mangled, MAX_INSTANTIATION_DEPTH, name
+                        ));
+                    } else {
+                        self.pending
+                            .push_back((mangled.clone(), template, args, depth + 1));
+                    }
+                }
+                Type::Struct(mangled)
+            }
+            _ => ty.clone(),
+        }
+    }
+
+    fn check_arity(&mut self, name: &str, params: &[String], args: &[Type]) -> bool {
+        if params.len() == args.len() {
+            return true;
+        }
+        self.push_error(format!(
+            "Generic type `{}` expects {} type argument(s), found {}",
+            name,
+            params.len(),
+            args.len()
+        ));
+        false
+    }
+
+    fn push_error(&mut self, message: String) {
+        self.errors.push(SemanticError {
+            message,
+            line: None,
+            column: None,
         });
     }
+}
 
-    if normalized.starts_with("mlkem") && normalized.contains("decapsulate") {
+fn bind_type_params(params: &[String], args: &[Type]) -> HashMap<String, Type> {
+    params.iter().cloned().zip(args.iter().cloned()).collect()
+}
+
+/// Rewrites type-parameter casts such as `T(0)` to the concrete type constructor.
+fn substitute_expression(expression: &Expression, subst: &HashMap<String, Type>) -> Expression {
+    if subst.is_empty() {
+        return expression.clone();
+    }
+
+    match expression {
+        Expression::Call(name, args) => {
+            let callee = subst
+                .get(name)
+                .map(type_mangle_name)
+                .unwrap_or_else(|| name.clone());
+            Expression::Call(
+                callee,
+                args.iter()
+                    .map(|arg| substitute_expression(arg, subst))
+                    .collect(),
+            )
+        }
+        Expression::MethodCall(receiver, method, args) => Expression::MethodCall(
+            Box::new(substitute_expression(receiver, subst)),
+            method.clone(),
+            args.iter()
+                .map(|arg| substitute_expression(arg, subst))
+                .collect(),
+        ),
+        Expression::MemberAccess(object, member) => Expression::MemberAccess(
+            Box::new(substitute_expression(object, subst)),
+            member.clone(),
+        ),
+        Expression::IndexAccess(object, index) => Expression::IndexAccess(
+            Box::new(substitute_expression(object, subst)),
+            Box::new(substitute_expression(index, subst)),
+        ),
+        Expression::Binary(op, lhs, rhs) => Expression::Binary(
+            op.clone(),
+            Box::new(substitute_expression(lhs, subst)),
+            Box::new(substitute_expression(rhs, subst)),
+        ),
+        Expression::Unary(op, expr) => {
+            Expression::Unary(op.clone(), Box::new(substitute_expression(expr, subst)))
+        }
+        Expression::Ternary(condition, then_expr, else_expr) => Expression::Ternary(
+            Box::new(substitute_expression(condition, subst)),
+            Box::new(substitute_expression(then_expr, subst)),
+            Box::new(substitute_expression(else_expr, subst)),
+        ),
+        Expression::ArrayLiteral(elements) => Expression::ArrayLiteral(
+            elements
+                .iter()
+                .map(|element| substitute_expression(element, subst))
+                .collect(),
+        ),
+        Expression::ObjectLiteral(fields) => Expression::ObjectLiteral(
+            fields
+                .iter()
+                .map(|(name, value)| (name.clone(), substitute_expression(value, subst)))
+                .collect(),
+        ),
+        Expression::Literal(_) | Expression::Identifier(_) => expression.clone(),
+    }
+}
+
+fn type_mangle_name(ty: &Type) -> String {
+    match ty {
+        Type::Address => "Address".to_string(),
+        Type::UInt256 => "UInt256".to_string(),
+        Type::UInt8 => "UInt8".to_string(),
+        Type::UInt32 => "UInt32".to_string(),
+        Type::UInt64 => "UInt64".to_string(),
+        Type::UInt128 => "UInt128".to_string(),
+        Type::Int256 => "Int256".to_string(),
+        Type::Int8 => "Int8".to_string(),
+        Type::Int32 => "Int32".to_string(),
+        Type::Int64 => "Int64".to_string(),
+        Type::Int128 => "Int128".to_string(),
+        Type::Bool => "Bool".to_string(),
+        Type::Bytes => "Bytes".to_string(),
+        Type::FixedBytes(width) => format!("Bytes{}", width),
+        Type::String => "String".to_string(),
+        Type::MLDSAPublicKey => "MLDSAPublicKey".to_string(),
+        Type::MLDSAKeyPair => "MLDSAKeyPair".to_string(),
+        Type::MLDSASignature => "MLDSASignature".to_string(),
+        Type::FNDSAPublicKey => "FNDSAPublicKey".to_string(),
+        Type::FNDSAKeyPair => "FNDSAKeyPair".to_string(),
+        Type::FNDSASignature => "FNDSASignature".to_string(),
+        Type::MLKEMPublicKey => "MLKEMPublicKey".to_string(),
+        Type::MLKEMKeyPair => "MLKEMKeyPair".to_string(),
+        Type::MLKEMCiphertext => "MLKEMCiphertext".to_string(),
+        Type::SLHDSAPublicKey => "SLHDSAPublicKey".to_string(),
+        Type::SLHDSAKeyPair => "SLHDSAKeyPair".to_string(),
+        Type::SLHDSASignature => "SLHDSASignature".to_string(),
+        Type::Struct(name) => name.clone(),
+        Type::Array(element, Some(size)) => format!("{}_array{}", type_mangle_name(element), size),
+        Type::Array(element, None) => format!("{}_array", type_mangle_name(element)),
+        Type::Mapping(key, value) => format!(
+            "mapping_{}_{}",
+            type_mangle_name(key),
+            type_mangle_name(value)
+        ),
+        Type::Generic(name, args) => mangle_generic_name(name, args),
+    }
+}
+
+fn resolve_builtin_signature(name: &str) -> BuiltinResolution {
+    if name == "transfer" {
+        // Sends native value from the running contract; a contract's own `transfer` wins.
         return BuiltinResolution::Supported(FunctionSignature {
-            params: vec![Type::MLKEMCiphertext, Type::Bytes],
-            returns: Some(Type::Bytes),
+            params: vec![Type::Address, Type::UInt256],
+            returns: None,
+            visibility: Visibility::Internal,
+            mutability: StateMutability::NonPayable,
         });
     }
 
+    let normalized = normalize_name(name);
+
+    if normalized.starts_with("verifyslhdsa") || normalized.starts_with("slhdsa") {
+        return BuiltinResolution::Unsupported(
+            "SLH-DSA is de-scoped in the current SynQ runtime profile".to_string(),
+        );
+    }
+
+    if normalized.starts_with("verifymldsa") {
+        return BuiltinResolution::Supported(FunctionSignature::builtin(
+            vec![Type::MLDSAPublicKey, Type::Bytes, Type::MLDSASignature],
+            Some(Type::Bool),
+        ));
+    }
+
+    if normalized.starts_with("verifyfndsa") {
+        return BuiltinResolution::Supported(FunctionSignature::builtin(
+            vec![Type::FNDSAPublicKey, Type::Bytes, Type::FNDSASignature],
+            Some(Type::Bool),
+        ));
+    }
+
+    if normalized.starts_with("mlkem") && normalized.contains("decapsulate") {
+        return BuiltinResolution::Supported(FunctionSignature::builtin(
+            vec![Type::MLKEMCiphertext, Type::Bytes],
+            Some(Type::Bytes),
+        ));
+    }
+
     if normalized.starts_with("hqckem") && normalized.contains("decapsulate") {
-        return BuiltinResolution::Supported(FunctionSignature {
-            params: vec![Type::Bytes, Type::Bytes],
-            returns: Some(Type::Bytes),
-        });
+        return BuiltinResolution::Supported(FunctionSignature::builtin(
+            vec![Type::Bytes, Type::Bytes],
+            Some(Type::Bytes),
+        ));
     }
 
     // Treat other PQC-like names as parse-time tolerated but semantically unknown until modeled.
@@ -772,7 +2106,7 @@ fn resolve_builtin_signature(name: &str) -> BuiltinResolution {
     BuiltinResolution::NotBuiltin
 }
 
-fn parse_constructor_type(name: &str) -> Option<Type> {
+pub(crate) fn parse_constructor_type(name: &str) -> Option<Type> {
     match name {
         "Address" => Some(Type::Address),
         "UInt256" => Some(Type::UInt256),
@@ -800,7 +2134,13 @@ fn parse_constructor_type(name: &str) -> Option<Type> {
         "SLHDSAPublicKey" => Some(Type::SLHDSAPublicKey),
         "SLHDSAKeyPair" => Some(Type::SLHDSAKeyPair),
         "SLHDSASignature" => Some(Type::SLHDSASignature),
-        _ => None,
+        _ => match name
+            .strip_prefix("Bytes")
+            .and_then(|n| n.parse::<u8>().ok())
+        {
+            Some(width @ 1..=32) => Some(Type::FixedBytes(width)),
+            _ => None,
+        },
     }
 }
 
@@ -868,11 +2208,12 @@ fn types_compatible(expected: &Type, actual: &Type) -> bool {
     }
 
     if is_numeric_type(expected) && is_numeric_type(actual) {
-        return true;
+        return widens_implicitly(actual, expected);
     }
 
     match (expected, actual) {
         (Type::MLKEMCiphertext, Type::Bytes) | (Type::Bytes, Type::MLKEMCiphertext) => true,
+        (Type::FixedBytes(_), Type::Bytes) | (Type::Bytes, Type::FixedBytes(_)) => true,
         (Type::Array(exp_elem, exp_len), Type::Array(act_elem, act_len)) => {
             (exp_len == act_len || exp_len.is_none() || act_len.is_none())
                 && types_compatible(exp_elem, act_elem)
@@ -892,6 +2233,153 @@ fn types_compatible(expected: &Type, actual: &Type) -> bool {
     }
 }
 
+/// Bit width and signedness of the integer types numeric literals are checked against.
+pub(crate) fn integer_bounds(ty: &Type) -> Option<(usize, bool)> {
+    match ty {
+        Type::UInt8 => Some((8, false)),
+        Type::UInt32 => Some((32, false)),
+        Type::UInt64 => Some((64, false)),
+        Type::UInt128 => Some((128, false)),
+        Type::UInt256 => Some((256, false)),
+        Type::Int8 => Some((8, true)),
+        Type::Int32 => Some((32, true)),
+        Type::Int64 => Some((64, true)),
+        Type::Int128 => Some((128, true)),
+        Type::Int256 => Some((256, true)),
+        _ => None,
+    }
+}
+
+/// Result type of `left + right` when both sides are byte strings of the same family.
+fn concatenation_type(left: &Type, right: &Type) -> Option<Type> {
+    match (left, right) {
+        (Type::String, Type::String) => Some(Type::String),
+        (Type::Bytes | Type::FixedBytes(_), Type::Bytes | Type::FixedBytes(_)) => Some(Type::Bytes),
+        _ => None,
+    }
+}
+
+/// Returns the literal's source form if `expr` is a (possibly negated) numeric literal that `ty` cannot hold,
+/// or a `Bytes("..")` literal whose length differs from a fixed-size `BytesN` target.
+fn literal_out_of_range(ty: &Type, expr: &Expression) -> Option<String> {
+    if let (Type::FixedBytes(width), Expression::Literal(Literal::Bytes(bytes))) = (ty, expr) {
+        return (bytes.len() != usize::from(*width)).then(|| {
+            let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+            format!("Bytes(\"{}\")", hex)
+        });
+    }
+
+    let (bits, signed) = integer_bounds(ty)?;
+    let (value, negative) = match expr {
+        Expression::Literal(Literal::Number(n)) => (*n, false),
+        Expression::Unary(UnaryOp::Neg, inner) => match inner.as_ref() {
+            Expression::Literal(Literal::Number(n)) => (*n, !n.is_zero()),
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    let fits = match (signed, negative) {
+        (false, true) => false,
+        (false, false) => value.bits() <= bits,
+        // Two's complement: magnitudes up to 2^(bits-1) below zero, 2^(bits-1) - 1 above.
+        (true, true) => value <= U256::one() << (bits - 1),
+        (true, false) => value.bits() < bits,
+    };
+    if fits {
+        None
+    } else if negative {
+        Some(format!("-{value}"))
+    } else {
+        Some(value.to_string())
+    }
+}
+
+/// Implicit integer conversions never lose information: same signedness and no narrower,
+/// or unsigned into a strictly wider signed type.
+fn widens_implicitly(from: &Type, to: &Type) -> bool {
+    let (Some((from_bits, from_signed)), Some((to_bits, to_signed))) =
+        (integer_bounds(from), integer_bounds(to))
+    else {
+        return false;
+    };
+    match (from_signed, to_signed) {
+        (false, true) => to_bits > from_bits,
+        (true, false) => false,
+        _ => to_bits >= from_bits,
+    }
+}
+
+/// Whether `expr` of type `actual` may be stored into `expected` without a cast.
+/// Integer literals take the target type and are range-checked separately.
+fn assignable(expected: &Type, actual: &Type, expr: &Expression) -> bool {
+    types_compatible(expected, actual) || (is_numeric_type(expected) && is_integer_literal(expr))
+}
+
+fn is_integer_literal(expr: &Expression) -> bool {
+    match expr {
+        Expression::Literal(Literal::Number(_)) => true,
+        Expression::Unary(UnaryOp::Neg, inner) => is_integer_literal(inner),
+        _ => false,
+    }
+}
+
+fn conversion_hint(expected: &Type, actual: &Type) -> String {
+    if explicit_conversion_allowed(actual, expected) {
+        format!(
+            "; use an explicit `{}(...)` conversion",
+            type_mangle_name(expected)
+        )
+    } else {
+        String::new()
+    }
+}
+
+/// Conversions allowed through a `Type(value)` call:
+/// - any integer to any integer (truncating to the target width, then reinterpreting the sign)
+/// - `Address` to and from `UInt256` and `Bytes20`
+/// - `BytesN` to and from integers of exactly `N * 8` bits
+/// - between `Bytes` and `BytesN` of any size (truncating or zero-padding on the right)
+/// - between `Bytes` and `String`, and between `Bytes` and the PQC key/signature types
+fn explicit_conversion_allowed(from: &Type, to: &Type) -> bool {
+    if from == to || (is_numeric_type(from) && is_numeric_type(to)) {
+        return true;
+    }
+
+    let fixed_matches_integer = |width: u8, int: &Type| {
+        integer_bounds(int).is_some_and(|(bits, _)| bits == usize::from(width) * 8)
+    };
+    match (from, to) {
+        (Type::Address, Type::UInt256 | Type::FixedBytes(20))
+        | (Type::UInt256 | Type::FixedBytes(20), Type::Address) => true,
+        (Type::FixedBytes(width), int) | (int, Type::FixedBytes(width)) if is_numeric_type(int) => {
+            fixed_matches_integer(*width, int)
+        }
+        (Type::Bytes | Type::FixedBytes(_), Type::Bytes | Type::FixedBytes(_)) => true,
+        (Type::Bytes, Type::String) | (Type::String, Type::Bytes) => true,
+        (Type::Bytes, other) | (other, Type::Bytes) => is_pqc_byte_type(other),
+        _ => false,
+    }
+}
+
+fn is_pqc_byte_type(ty: &Type) -> bool {
+    matches!(
+        ty,
+        Type::MLDSAPublicKey
+            | Type::MLDSAKeyPair
+            | Type::MLDSASignature
+            | Type::FNDSAPublicKey
+            | Type::FNDSAKeyPair
+            | Type::FNDSASignature
+            | Type::MLKEMPublicKey
+            | Type::MLKEMKeyPair
+            | Type::MLKEMCiphertext
+            | Type::SLHDSAPublicKey
+            | Type::SLHDSAKeyPair
+            | Type::SLHDSASignature
+    )
+}
+
 fn is_precise_assignment_target(ty: &Type) -> bool {
     !matches!(
         ty,
@@ -943,13 +2431,6 @@ fn is_numeric_type(ty: &Type) -> bool {
     )
 }
 
-fn is_signed_integer(ty: &Type) -> bool {
-    matches!(
-        ty,
-        Type::Int8 | Type::Int32 | Type::Int64 | Type::Int128 | Type::Int256
-    )
-}
-
 fn normalize_name(name: &str) -> String {
     let mut normalized = String::with_capacity(name.len());
     for ch in name.chars() {
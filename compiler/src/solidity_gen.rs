@@ -1,7 +1,10 @@
 //! Solidity code generator for SynQ contracts
 //! Generates Solidity-compatible output from SynQ AST
 
+use crate::annotations::{self, AnnotationTarget};
 use crate::ast::*;
+use crate::codegen::format_monomorphize_errors;
+use crate::semantic::monomorphize;
 
 pub struct SolidityGenerator {
     output: String,
@@ -17,6 +20,8 @@ impl SolidityGenerator {
     }
 
     pub fn generate(mut self, ast: &[SourceUnit]) -> Result<String, String> {
+        let ast = monomorphize(ast).map_err(|errors| format_monomorphize_errors(&errors))?;
+
         // Add SPDX license identifier
         self.writeln("// SPDX-License-Identifier: MIT");
         self.writeln("pragma solidity ^0.8.0;");
@@ -34,7 +39,7 @@ impl SolidityGenerator {
         self.writeln("");
 
         // Generate structs first
-        for item in ast {
+        for item in &ast {
             if let SourceUnit::Struct(s) = item {
                 self.gen_struct(s)?;
                 self.writeln("");
@@ -42,7 +47,7 @@ impl SolidityGenerator {
         }
 
         // Generate contracts
-        for item in ast {
+        for item in &ast {
             if let SourceUnit::Contract(c) = item {
                 self.gen_contract(c)?;
                 self.writeln("");
@@ -69,13 +74,19 @@ impl SolidityGenerator {
 
     fn gen_contract(&mut self, c: &ContractDefinition) -> Result<(), String> {
         // Generate annotations as comments
-        for ann in &c.annotations {
-            self.writeln(&format!("// @{}", ann.name));
-        }
+        self.gen_annotation_comments(&c.annotations, AnnotationTarget::Contract);
 
         self.writeln(&format!("contract {} {{", c.name));
         self.indent();
 
+        // Generate contract-local structs
+        for part in &c.parts {
+            if let ContractPart::Struct(s) = part {
+                self.gen_struct(s)?;
+                self.writeln("");
+            }
+        }
+
         // Generate state variables
         for part in &c.parts {
             if let ContractPart::StateVariable(v) = part {
@@ -104,6 +115,19 @@ impl SolidityGenerator {
             self.writeln("");
         }
 
+        // Generate custom errors
+        for part in &c.parts {
+            if let ContractPart::Error(e) = part {
+                self.write(&format!("error {}(", e.name));
+                self.gen_params(&e.params)?;
+                self.writeln(");");
+            }
+        }
+
+        if c.parts.iter().any(|p| matches!(p, ContractPart::Error(_))) {
+            self.writeln("");
+        }
+
         // Generate functions
         for part in &c.parts {
             if let ContractPart::Function(f) = part {
@@ -118,9 +142,7 @@ impl SolidityGenerator {
     }
 
     fn gen_state_variable(&mut self, v: &StateVariableDeclaration) -> Result<(), String> {
-        for ann in &v.annotations {
-            self.writeln(&format!("// @{}", ann.name));
-        }
+        self.gen_annotation_comments(&v.annotations, AnnotationTarget::StateVariable);
 
         let visibility = if v.is_public { "public" } else { "internal" };
         self.writeln(&format!(
@@ -133,14 +155,11 @@ impl SolidityGenerator {
     }
 
     fn gen_constructor(&mut self, ctor: &ConstructorDefinition) -> Result<(), String> {
-        for ann in &ctor.annotations {
-            self.writeln(&format!("// @{}", ann.name));
-        }
+        self.gen_annotation_comments(&ctor.annotations, AnnotationTarget::Constructor);
 
         self.write("constructor(");
         self.gen_params(&ctor.params)?;
         self.write(") ");
-        self.gen_gas_annotation(&ctor.annotations);
         self.writeln("{");
         self.indent();
         self.gen_block(&ctor.body)?;
@@ -150,24 +169,22 @@ impl SolidityGenerator {
     }
 
     fn gen_function(&mut self, f: &FunctionDefinition) -> Result<(), String> {
-        for ann in &f.annotations {
-            self.writeln(&format!("// @{}", ann.name));
-        }
+        self.gen_annotation_comments(&f.annotations, AnnotationTarget::Function);
 
-        let visibility = if f.is_public { "public" } else { "internal" };
         self.write(&format!("function {}(", f.name));
         self.gen_params(&f.params)?;
         self.write(") ");
+        self.write(&format!("{} ", f.visibility.as_str()));
+
+        // Solidity leaves the default (nonpayable) mutability implicit.
+        if f.mutability != StateMutability::NonPayable {
+            self.write(&format!("{} ", f.mutability.as_str()));
+        }
 
         if let Some(ref ret_ty) = f.returns {
-            self.write(&format!(
-                "external returns ({}) ",
-                self.type_to_solidity(ret_ty)
-            ));
+            self.write(&format!("returns ({}) ", self.type_to_solidity(ret_ty)));
         }
 
-        self.write(&format!("{} ", visibility));
-        self.gen_gas_annotation(&f.annotations);
         self.writeln("{");
         self.indent();
         self.gen_block(&f.body)?;
@@ -177,9 +194,7 @@ impl SolidityGenerator {
     }
 
     fn gen_event(&mut self, e: &EventDefinition) -> Result<(), String> {
-        for ann in &e.annotations {
-            self.writeln(&format!("// @{}", ann.name));
-        }
+        self.gen_annotation_comments(&e.annotations, AnnotationTarget::Event);
 
         self.write(&format!("event {}((", e.name));
         self.gen_params(&e.params)?;
@@ -225,6 +240,12 @@ impl SolidityGenerator {
                 self.gen_expression(expr)?;
                 self.writeln(";");
             }
+            Statement::ElementAssignment(target, expr) => {
+                self.gen_expression(target)?;
+                self.write(" = ");
+                self.gen_expression(expr)?;
+                self.writeln(";");
+            }
             Statement::Return(expr) => {
                 self.write("return");
                 if let Some(ref expr) = expr {
@@ -237,12 +258,12 @@ impl SolidityGenerator {
                 self.write("require(");
                 self.gen_expression(expr)?;
                 if !msg.is_empty() {
-                    self.write(&format!(r#", "{}""#, msg));
+                    self.write(&format!(", {}", solidity_string_literal(msg)));
                 }
                 self.writeln(");");
             }
             Statement::Revert(msg) => {
-                self.write(&format!(r#"revert("{}");"#, msg));
+                self.write(&format!("revert({});", solidity_string_literal(msg)));
                 self.writeln("");
             }
             Statement::If(cond, then_block, else_block) => {
@@ -309,6 +330,16 @@ impl SolidityGenerator {
                 self.dedent();
                 self.writeln("}");
             }
+            Statement::WithGasLimit(budget, block) => {
+                self.write("// with_gas_limit(");
+                self.gen_expression(budget)?;
+                self.writeln(")");
+                self.writeln("{");
+                self.indent();
+                self.gen_block(block)?;
+                self.dedent();
+                self.writeln("}");
+            }
         }
         Ok(())
     }
@@ -324,12 +355,13 @@ impl SolidityGenerator {
             Expression::Call(name, args) => {
                 self.write(name);
                 self.write("(");
-                for (i, arg) in args.iter().enumerate() {
-                    if i > 0 {
-                        self.write(", ");
-                    }
-                    self.gen_expression(arg)?;
-                }
+                self.gen_expression_list(args)?;
+                self.write(")");
+            }
+            Expression::MethodCall(receiver, method, args) => {
+                self.gen_expression(receiver)?;
+                self.write(&format!(".{}(", method));
+                self.gen_expression_list(args)?;
                 self.write(")");
             }
             Expression::MemberAccess(obj, member) => {
@@ -359,6 +391,32 @@ impl SolidityGenerator {
                 self.write(" : ");
                 self.gen_expression(else_expr)?;
             }
+            Expression::ArrayLiteral(elements) => {
+                self.write("[");
+                self.gen_expression_list(elements)?;
+                self.write("]");
+            }
+            Expression::ObjectLiteral(fields) => {
+                self.write("{");
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        self.write(", ");
+                    }
+                    self.write(&format!("{}: ", name));
+                    self.gen_expression(value)?;
+                }
+                self.write("}");
+            }
+        }
+        Ok(())
+    }
+
+    fn gen_expression_list(&mut self, exprs: &[Expression]) -> Result<(), String> {
+        for (i, expr) in exprs.iter().enumerate() {
+            if i > 0 {
+                self.write(", ");
+            }
+            self.gen_expression(expr)?;
         }
         Ok(())
     }
@@ -378,6 +436,7 @@ impl SolidityGenerator {
             Type::Int8 => "int8".to_string(),
             Type::Bool => "bool".to_string(),
             Type::Bytes => "bytes".to_string(),
+            Type::FixedBytes(width) => format!("bytes{}", width),
             Type::String => "string".to_string(),
             Type::MLDSAPublicKey => "bytes memory".to_string(), // PQC types as bytes
             Type::MLDSAKeyPair => "bytes memory".to_string(),
@@ -413,7 +472,7 @@ impl SolidityGenerator {
     fn literal_to_solidity(&self, lit: &Literal) -> String {
         match lit {
             Literal::Number(n) => n.to_string(),
-            Literal::String(s) => format!("\"{}\"", s),
+            Literal::String(s) => solidity_string_literal(s),
             Literal::Bool(b) => b.to_string(),
             Literal::Address(addr) => addr.clone(),
             Literal::Bytes(bytes) => {
@@ -452,16 +511,12 @@ impl SolidityGenerator {
         }
     }
 
-    fn gen_gas_annotation(&mut self, annotations: &[Annotation]) {
+    /// Carries annotations over as comments, with arguments as validated by the registry.
+    fn gen_annotation_comments(&mut self, annotations: &[Annotation], target: AnnotationTarget) {
         for ann in annotations {
-            if ann.name == "gas_cost" {
-                self.write("// @gas_cost");
-                if !ann.args.is_empty() {
-                    self.write("(");
-                    // Format gas cost args
-                    self.write(")");
-                }
-                self.write(" ");
+            match annotations::resolve(ann, target) {
+                Ok(resolved) => self.writeln(&format!("// {}", resolved)),
+                Err(_) => self.writeln(&format!("// @{}", ann.name)),
             }
         }
     }
@@ -498,3 +553,21 @@ impl SolidityGenerator {
         }
     }
 }
+
+/// Quotes and re-escapes a decoded string; non-ASCII text needs Solidity's `unicode"..."` form.
+fn solidity_string_literal(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    for ch in value.chars() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_ascii_control() => escaped.push_str(&format!("\\x{:02x}", c as u8)),
+            c => escaped.push(c),
+        }
+    }
+    let prefix = if value.is_ascii() { "" } else { "unicode" };
+    format!("{}\"{}\"", prefix, escaped)
+}
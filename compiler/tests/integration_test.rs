@@ -1,7 +1,15 @@
-use compiler::ast::{ContractPart, Expression, Literal, SourceUnit, Statement};
-use compiler::{parser, CodeGenerator};
+use compiler::annotations::{self, AnnotationTarget, ResolvedAnnotation};
+use compiler::ast::{
+    ContractPart, Expression, Literal, SourceUnit, StateMutability, Statement, Type, Visibility,
+};
+use compiler::{
+    error_selector, error_signature, function_selector, monomorphize, parser, CodeGenerator,
+    ContractAbi, SolidityGenerator, U256,
+};
 use pqsynq::{Kem, KeyEncapsulation};
-use quantumvm::{OpCode, QuantumVM};
+use quantumvm::{
+    Address, Header, MemoryBackend, OpCode, QuantumVM, SectionKind, StorageKey, VMError, WorldState,
+};
 use std::fs;
 use std::path::PathBuf;
 
@@ -25,7 +33,7 @@ fn test_parse_simple_contract_with_function_params() {
 
 fn expect_number_literal(expr: &Expression, expected: u64) {
     match expr {
-        Expression::Literal(Literal::Number(value)) => assert_eq!(*value, expected),
+        Expression::Literal(Literal::Number(value)) => assert_eq!(*value, U256::from(expected)),
         other => panic!("Expected numeric literal {expected}, got {other:?}"),
     }
 }
@@ -77,7 +85,7 @@ contract AnnotatedContract {
             _ => None,
         })
         .expect("function should exist");
-    assert!(function.is_public);
+    assert_eq!(function.visibility, Visibility::Public);
     assert_eq!(function.annotations.len(), 1);
     assert_eq!(function.annotations[0].name, "gas");
 
@@ -97,6 +105,93 @@ contract AnnotatedContract {
     assert_eq!(loop_stmt.3.statements.len(), 1);
 }
 
+fn literal_value(source_expr: &str) -> U256 {
+    let source =
+        format!("contract Literals {{ function f() {{ let x: UInt256 = {source_expr}; }} }}");
+    let (_, units) = parser::parse(&source).expect("literal should parse");
+    let SourceUnit::Contract(contract) = &units[0] else {
+        panic!("expected contract");
+    };
+    let ContractPart::Function(function) = &contract.parts[0] else {
+        panic!("expected function");
+    };
+    match &function.body.statements[0] {
+        Statement::VariableDeclaration(_, _, Some(Expression::Literal(Literal::Number(n)))) => *n,
+        other => panic!("expected numeric literal, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_parser_evaluates_numeric_literal_forms_exactly() {
+    assert_eq!(literal_value("100_000"), U256::from(100_000u64));
+    assert_eq!(literal_value("0xff_ff"), U256::from(0xffffu64));
+    assert_eq!(literal_value("1e18"), U256::from(10u64).pow(U256::from(18)));
+    assert_eq!(literal_value("2.5e3"), U256::from(2_500u64));
+    assert_eq!(
+        literal_value("1.5 ether"),
+        U256::from(1_500_000_000_000_000_000u64)
+    );
+    assert_eq!(literal_value("3 gwei"), U256::from(3_000_000_000u64));
+    assert_eq!(literal_value("2 days"), U256::from(172_800u64));
+    assert_eq!(literal_value("1e77"), U256::from(10u64).pow(U256::from(77)));
+    assert_eq!(
+        literal_value("0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff"),
+        U256::MAX
+    );
+}
+
+#[test]
+fn test_parser_rejects_inexact_or_oversized_numeric_literals() {
+    for (literal, expected) in [
+        ("1.5", "is not an integer"),
+        ("1e78", "does not fit in 256 bits"),
+        (
+            "0x1_0000000000000000000000000000000000000000000000000000000000000000",
+            "does not fit in 256 bits",
+        ),
+    ] {
+        let source = format!("contract Bad {{ function f() {{ let x: UInt256 = {literal}; }} }}");
+        let err = parser::parse(&source).expect_err("literal must be rejected");
+        assert!(
+            err.to_string().contains(expected),
+            "`{literal}`: expected `{expected}` in {err}"
+        );
+    }
+}
+
+#[test]
+fn test_parser_decodes_string_escapes_and_fixed_bytes_types() {
+    let source = r#"
+contract Escapes {
+    digest: Bytes32;
+
+    function f(tag: Bytes4) {
+        let s: String = "a \"quoted\" word\n\t\\ \x41\u{e9}";
+    }
+}
+"#;
+    let (_, units) = parser::parse(source).expect("escapes should parse");
+    let SourceUnit::Contract(contract) = &units[0] else {
+        panic!("expected contract");
+    };
+    let ContractPart::StateVariable(digest) = &contract.parts[0] else {
+        panic!("expected state variable");
+    };
+    assert_eq!(digest.ty, Type::FixedBytes(32));
+    let function = function_by_name(&units, "f");
+    assert_eq!(function.params[0].ty, Type::FixedBytes(4));
+    match &function.body.statements[0] {
+        Statement::VariableDeclaration(_, _, Some(Expression::Literal(Literal::String(s)))) => {
+            assert_eq!(s, "a \"quoted\" word\n\t\\ A\u{e9}");
+        }
+        other => panic!("expected string literal, got {other:?}"),
+    }
+
+    let err = parser::parse(r#"contract Bad { function f() { let s: String = "\q"; } }"#)
+        .expect_err("unknown escape must be rejected");
+    assert!(err.to_string().contains("unknown escape sequence `\\q`"));
+}
+
 #[test]
 fn test_parser_lowers_c_style_for_loop_to_for_statement() {
     let source = r#"
@@ -156,9 +251,7 @@ contract LoopRuntime {
 
     let bytecode = compile_source(source);
     let mut vm = QuantumVM::new();
-    vm.load_bytecode(&bytecode)
-        .expect("VM should load compiled loop bytecode");
-    vm.execute()
+    deploy_and_call(&mut vm, &bytecode, &selector_calldata(source, "count"))
         .expect("VM should execute loop without runtime errors");
 
     let top = vm
@@ -170,6 +263,579 @@ contract LoopRuntime {
     assert_eq!(top, 3);
 }
 
+#[test]
+fn test_parser_reads_visibility_and_mutability_keywords() {
+    let source = r#"
+contract Modifiers {
+    function a() external view -> UInt256 { return 1; }
+    @private @pure function b() -> UInt256 { return 2; }
+    function c() payable {}
+    function d() {}
+}
+"#;
+
+    let (_, units) = parser::parse(source).expect("source should parse");
+    let SourceUnit::Contract(contract) = &units[0] else {
+        panic!("Expected contract source unit");
+    };
+    let specifiers: Vec<(Visibility, StateMutability, usize)> = contract
+        .parts
+        .iter()
+        .filter_map(|part| match part {
+            ContractPart::Function(f) => Some((f.visibility, f.mutability, f.annotations.len())),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(
+        specifiers,
+        vec![
+            (Visibility::External, StateMutability::View, 0),
+            (Visibility::Private, StateMutability::Pure, 0),
+            (Visibility::Internal, StateMutability::Payable, 0),
+            (Visibility::Internal, StateMutability::NonPayable, 0),
+        ]
+    );
+}
+
+#[test]
+fn test_annotation_registry_exposes_validated_arguments() {
+    let source = r#"
+contract Metered {
+    @gas_cost(base: 45000, mldsa_verify: 35000)
+    @gas_limit(100000)
+    @public function submit() {}
+}
+"#;
+
+    let (_, units) = parser::parse(source).expect("source should parse");
+    let SourceUnit::Contract(contract) = &units[0] else {
+        panic!("Expected contract source unit");
+    };
+    let ContractPart::Function(function) = &contract.parts[0] else {
+        panic!("Expected function part");
+    };
+
+    let resolved = annotations::resolve_all(&function.annotations, AnnotationTarget::Function)
+        .expect("annotations should validate");
+    assert_eq!(
+        resolved,
+        vec![
+            ResolvedAnnotation::GasCost {
+                base: 45000,
+                operations: vec![("mldsa_verify".to_string(), 35000)],
+            },
+            ResolvedAnnotation::GasLimit(100000),
+        ]
+    );
+    assert_eq!(annotations::gas_limit(&function.annotations), Some(100000));
+
+    let solidity = SolidityGenerator::new()
+        .generate(&units)
+        .expect("solidity generation should succeed");
+    assert!(solidity.contains("// @gas_cost(base: 45000, mldsa_verify: 35000)"));
+    assert!(solidity.contains("// @gas_limit(100000)"));
+}
+
+const DISPATCH_SOURCE: &str = r#"
+contract Dispatch {
+    @public function first() -> UInt256 {
+        return 1;
+    }
+
+    function second() external -> UInt256 {
+        return 2;
+    }
+
+    function hidden() -> UInt256 {
+        return 3;
+    }
+}
+"#;
+
+fn run_with_calldata(bytecode: &[u8], calldata: &[u8]) -> QuantumVM {
+    let mut vm = QuantumVM::new();
+    deploy_and_call(&mut vm, bytecode, calldata)
+        .expect("VM should execute dispatcher without runtime errors");
+    vm
+}
+
+fn function_by_name(units: &[SourceUnit], name: &str) -> compiler::ast::FunctionDefinition {
+    units
+        .iter()
+        .find_map(|unit| match unit {
+            SourceUnit::Contract(c) => c.parts.iter().find_map(|part| match part {
+                ContractPart::Function(f) if f.name == name => Some(f.clone()),
+                _ => None,
+            }),
+            _ => None,
+        })
+        .expect("function should exist")
+}
+
+/// Calldata that calls the parameterless entry point `name` declared in `source`.
+fn selector_calldata(source: &str, name: &str) -> [u8; 4] {
+    let (_, units) = parser::parse(source).expect("source should parse");
+    function_selector(&function_by_name(&units, name)).to_be_bytes()
+}
+
+#[test]
+fn test_dispatcher_routes_selectors_to_entry_points_only() {
+    let (_, units) = parser::parse(DISPATCH_SOURCE).expect("source should parse");
+    let bytecode = compile_source(DISPATCH_SOURCE);
+
+    let top = |vm: &QuantumVM| vm.stack.last().map(|v| v.as_i64().unwrap());
+
+    let first = function_selector(&function_by_name(&units, "first"));
+    assert_eq!(
+        top(&run_with_calldata(&bytecode, &first.to_be_bytes())),
+        Some(1)
+    );
+    let second = function_selector(&function_by_name(&units, "second"));
+    assert_eq!(
+        top(&run_with_calldata(&bytecode, &second.to_be_bytes())),
+        Some(2)
+    );
+
+    // Internal functions have no dispatcher entry, so their selector is unknown. Without a
+    // `receive` or `fallback` entry point, unknown, short and empty calldata all revert.
+    let hidden = function_selector(&function_by_name(&units, "hidden"));
+    let calls: [(&[u8], &str); 3] = [
+        (
+            &hidden.to_be_bytes(),
+            "Calldata does not start with a known function selector",
+        ),
+        (
+            &second.to_be_bytes()[..3],
+            "Calldata does not start with a known function selector",
+        ),
+        (
+            &[],
+            "No receive or fallback function accepts empty calldata",
+        ),
+    ];
+    for (calldata, reason) in calls {
+        let mut vm = QuantumVM::new();
+        let err = deploy_and_call(&mut vm, &bytecode, calldata).unwrap_err();
+        assert!(
+            err.to_string().starts_with(&format!("Reverted: {reason}")),
+            "{err}"
+        );
+    }
+}
+
+#[test]
+fn test_dispatcher_runs_receive_on_empty_calldata_and_fallback_otherwise() {
+    let source = r#"
+contract Wallet {
+    @public function receive() -> UInt256 {
+        return 10;
+    }
+
+    @public function fallback() -> UInt256 {
+        return 20;
+    }
+
+    @public function id() -> UInt256 {
+        return 30;
+    }
+}
+"#;
+    let bytecode = compile_source(source);
+    let top = |vm: &QuantumVM| vm.stack.last().map(|v| v.as_i64().unwrap());
+    assert_eq!(top(&run_with_calldata(&bytecode, &[])), Some(10));
+    assert_eq!(top(&run_with_calldata(&bytecode, &[0xAB, 0xCD])), Some(20));
+    assert_eq!(
+        top(&run_with_calldata(&bytecode, &[0xDE, 0xAD, 0xBE, 0xEF])),
+        Some(20)
+    );
+    assert_eq!(
+        top(&run_with_calldata(
+            &bytecode,
+            &selector_calldata(source, "id")
+        )),
+        Some(30)
+    );
+
+    // Without `receive`, empty calldata also runs `fallback`.
+    let source = source.replace("receive", "topUp");
+    let bytecode = compile_source(&source);
+    assert_eq!(top(&run_with_calldata(&bytecode, &[])), Some(20));
+}
+
+fn run_gas_scoped(budget: u64) -> Option<i64> {
+    let source = format!(
+        r#"
+contract Scoped {{
+    @public function run() -> UInt256 {{
+        let x: UInt256 = 1;
+        with_gas_limit({budget}) {{
+            for (i in 0..20) {{
+                x = x + 1;
+            }}
+        }}
+        return x;
+    }}
+}}
+"#
+    );
+    let vm = run_with_calldata(&compile_source(&source), &selector_calldata(&source, "run"));
+    vm.stack.last().map(|v| v.as_i64().unwrap())
+}
+
+#[test]
+fn test_with_gas_limit_block_reverts_on_overrun_and_continues() {
+    assert_eq!(run_gas_scoped(100_000), Some(21));
+    // The loop overruns the budget: its writes are discarded and execution resumes after the block.
+    assert_eq!(run_gas_scoped(50), Some(1));
+}
+
+#[test]
+fn test_gas_limit_annotation_fails_entry_point_on_overrun() {
+    let source = r#"
+contract Metered {
+    Int64 spins;
+
+    @gas_limit(50)
+    @public function spin() -> UInt256 {
+        spins = spins + 1;
+        let x: UInt256 = 0;
+        for (i in 0..20) {
+            x = x + 1;
+        }
+        return x;
+    }
+}
+"#;
+
+    let mut vm = QuantumVM::new();
+    let mut instance = vm.deploy(&compile_source(source), &[]).unwrap();
+    let result = vm.call(&mut instance, &selector_calldata(source, "spin"));
+    let receipt = vm.receipt(&result);
+    assert_eq!(receipt.status, quantumvm::ExecutionStatus::Reverted);
+    assert!(receipt.return_data.is_empty());
+    let err = result.unwrap_err().to_string();
+    assert!(
+        err.starts_with("Reverted: `Metered.spin` exceeded its @gas_limit of 50"),
+        "{err}"
+    );
+    assert_eq!(instance.storage[&StorageKey::from(0)].as_i64().unwrap(), 0);
+    assert!(vm.consumed_gas() > 50);
+}
+
+fn run_nested_budget(budget: u64) -> (quantumvm::Storage, usize) {
+    let source = format!(
+        r#"
+contract Nested {{
+    Int64 before;
+    Int64 inner;
+    Int64 after;
+    Int64 result;
+    String label;
+
+    event Spun(Int64 rounds);
+
+    @gas_limit({budget})
+    function spin() -> Int64 {{
+        inner = 1;
+        emit Spun(20);
+        let x: Int64 = 0;
+        for (i in 0..200) {{
+            x = x + 1;
+        }}
+        return x;
+    }}
+
+    @gas_limit(1000000)
+    function name() -> String {{
+        spin();
+        return "spun";
+    }}
+
+    @public function run() {{
+        before = 1;
+        result = spin() + 100;
+        label = name();
+        after = 1;
+    }}
+}}
+"#
+    );
+    let mut vm = QuantumVM::new();
+    let mut instance = vm.deploy(&compile_source(&source), &[]).unwrap();
+    vm.call(&mut instance, &selector_calldata(&source, "run"))
+        .expect("run should succeed");
+    (instance.storage, vm.logs().len())
+}
+
+#[test]
+fn test_budgeted_internal_call_fails_alone_and_caller_continues() {
+    let (storage, logs) = run_nested_budget(1_000_000);
+    assert_eq!(storage[&StorageKey::from(1)].as_i64().unwrap(), 1);
+    assert_eq!(storage[&StorageKey::from(3)].as_i64().unwrap(), 300);
+    assert_eq!(storage[&StorageKey::from(4)].as_bytes().unwrap(), b"spun");
+    assert_eq!(logs, 2);
+
+    // Each call to `spin` overruns its budget: its storage write and event are undone, and the
+    // caller resumes with its zero value, in `run` and in `name` alike.
+    let (storage, logs) = run_nested_budget(3_000);
+    assert_eq!(storage[&StorageKey::from(0)].as_i64().unwrap(), 1);
+    assert_eq!(storage[&StorageKey::from(1)].as_i64().unwrap(), 0);
+    assert_eq!(storage[&StorageKey::from(2)].as_i64().unwrap(), 1);
+    assert_eq!(storage[&StorageKey::from(3)].as_i64().unwrap(), 100);
+    assert_eq!(storage[&StorageKey::from(4)].as_bytes().unwrap(), b"spun");
+    assert_eq!(logs, 0);
+}
+
+#[test]
+fn test_string_concatenation_comparison_length_and_slice_execute() {
+    let source = r#"
+contract Strings {
+    @public function run() -> UInt256 {
+        let greeting: String = "say \"hi\"";
+        let full: String = greeting + "\n";
+        if (full != "say \"hi\"\n") {
+            return 0;
+        }
+        if (slice(full, 0, 3) == "say") {
+            return full.length;
+        }
+        return 1;
+    }
+}
+"#;
+
+    let vm = run_with_calldata(&compile_source(source), &selector_calldata(source, "run"));
+    assert_eq!(vm.stack.last().map(|v| v.as_i64().unwrap()), Some(9));
+}
+
+fn run_conversion(body: &str) -> Result<Option<i64>, String> {
+    let source = format!(
+        r#"
+contract Conversions {{
+    @public function run() -> Int64 {{
+        {body}
+    }}
+}}
+"#
+    );
+    let mut vm = QuantumVM::new();
+    deploy_and_call(
+        &mut vm,
+        &compile_source(&source),
+        &selector_calldata(&source, "run"),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(vm.stack.last().map(|v| v.as_i64().unwrap()))
+}
+
+#[test]
+fn test_explicit_conversions_truncate_and_sign_extend() {
+    assert_eq!(
+        run_conversion("let wide: UInt32 = 300; return Int64(UInt8(wide));"),
+        Ok(Some(44))
+    );
+    assert_eq!(
+        run_conversion("let wide: UInt32 = 200; return Int64(Int8(wide));"),
+        Ok(Some(-56))
+    );
+    assert_eq!(
+        run_conversion(
+            "let tag: Bytes2 = Bytes2(Bytes(\"6162\")); return Int64(UInt32(Bytes4(tag)));"
+        ),
+        Ok(Some(0x6162_0000))
+    );
+}
+
+#[test]
+fn test_unsigned_conversions_and_narrow_arithmetic_revert_out_of_range() {
+    let reverts = |body: &str, reason: &str| {
+        let err = run_conversion(body).expect_err(body);
+        assert!(err.starts_with(&format!("Reverted: {reason}")), "{err}");
+    };
+    // A negative value has no unsigned 64-bit or wider representation.
+    reverts(
+        "let x: Int64 = -1; return Int64(UInt64(x));",
+        "Cannot convert a negative value to UInt64",
+    );
+    reverts(
+        "let x: Int64 = -1; return Int64(UInt256(Int256(x)));",
+        "Cannot convert a negative value to UInt256",
+    );
+    assert_eq!(
+        run_conversion("let x: Int64 = 7; return Int64(UInt256(x));"),
+        Ok(Some(7))
+    );
+
+    reverts(
+        "let a: UInt8 = 150; let b: UInt8 = 150; return Int64(a + b);",
+        "Arithmetic overflow: the result does not fit UInt8",
+    );
+    assert_eq!(
+        run_conversion("let a: UInt8 = 150; let b: UInt8 = 105; return Int64(a + b);"),
+        Ok(Some(255))
+    );
+    reverts(
+        "let a: UInt256 = 1; let b: UInt256 = 2; return Int64(a - b);",
+        "Arithmetic overflow: the result does not fit UInt256",
+    );
+    reverts(
+        "let a: Int8 = 100; return Int64(a * 2);",
+        "Arithmetic overflow: the result does not fit Int8",
+    );
+    reverts(
+        "let a: Int8 = -128; return Int64(-a);",
+        "Arithmetic overflow: the result does not fit Int8",
+    );
+    assert_eq!(
+        run_conversion("let a: Int8 = -100; let b: Int8 = 28; return Int64(a - b);"),
+        Ok(Some(-128))
+    );
+}
+
+#[test]
+fn test_integer_literals_wider_than_push_execute() {
+    let source = r#"
+contract Wide {
+    UInt256 total;
+
+    @public function run() -> UInt256 {
+        UInt256 x = 3_000_000_000;
+        total = x + 1 gwei;
+        return total * 2 + 1e18;
+    }
+}
+"#;
+    let vm = run_with_calldata(&compile_source(source), &selector_calldata(source, "run"));
+    assert_eq!(
+        vm.stack.last().map(|v| v.as_i64().unwrap()),
+        Some(1_000_000_008_000_000_000)
+    );
+
+    let source = "contract Wide { @public function run() -> UInt256 { return 1e19; } }";
+    let (_version_req, ast) = parser::parse(source).expect("Fixture should parse");
+    let err = CodeGenerator::new().generate(&ast).unwrap_err();
+    assert!(
+        err.contains("exceeds the VM's 64-bit integer range"),
+        "{err}"
+    );
+}
+
+fn run_layout(body: &str) -> Result<Option<i64>, String> {
+    let source = format!(
+        r#"
+struct Point {{
+    x: Int64;
+    y: Int64;
+}}
+
+contract Layout {{
+    struct Segment {{
+        Point start;
+        Point end;
+    }}
+
+    Int64[] values;
+
+    @public function run() -> Int64 {{
+        {body}
+    }}
+}}
+"#
+    );
+    let (_version_req, ast) = parser::parse(&source).expect("Fixture should parse");
+    compiler::analyze(&ast).map_err(|errors| format!("{errors:?}"))?;
+    let bytecode = CodeGenerator::new().generate(&ast)?;
+    let mut vm = QuantumVM::new();
+    deploy_and_call(&mut vm, &bytecode, &selector_calldata(&source, "run"))
+        .map_err(|e| e.to_string())?;
+    Ok(vm.stack.last().map(|v| v.as_i64().unwrap()))
+}
+
+#[test]
+fn test_struct_fields_and_nested_structs_are_laid_out_inline() {
+    assert_eq!(
+        run_layout("let p: Point = {x: 3, y: 4}; p.y = p.y + 10; return p.x * p.y;"),
+        Ok(Some(42))
+    );
+    assert_eq!(
+        run_layout(
+            "let s: Segment = Segment({start: Point(1, 2), end: {x: 7, y: 9}}); \
+             s.end.x = s.start.y; \
+             let q: Point = s.end; \
+             q.y = 100; \
+             return s.end.x + s.end.y;"
+        ),
+        Ok(Some(102))
+    );
+}
+
+#[test]
+fn test_fixed_arrays_index_and_bounds_check() {
+    assert_eq!(
+        run_layout(
+            "let a: Int64[3] = [5, 6, 7]; let i: UInt32 = 2; a[i] = a[0] + a[1]; \
+             return a[2] + Int64(a.length);"
+        ),
+        Ok(Some(14))
+    );
+    let err = run_layout("let a: Int64[3] = [5, 6, 7]; let i: UInt32 = 3; return a[i];")
+        .expect_err("index 3 is past the end");
+    assert!(err.contains("Index 3 out of bounds for length 3"), "{err}");
+}
+
+#[test]
+fn test_dynamic_arrays_push_pop_and_copy() {
+    assert_eq!(
+        run_layout(
+            "values.push(4); values.push(5); values.push(6); values.pop(); \
+             values[0] = values[0] * 10; \
+             return values[0] + values[1] + Int64(values.length);"
+        ),
+        Ok(Some(47))
+    );
+    assert_eq!(
+        run_layout(
+            "let copy: Int64[] = [1, 2, 3]; values = copy; values.push(4); \
+             return Int64(values.length) * 100 + values[3];"
+        ),
+        Ok(Some(404))
+    );
+    let err = run_layout("values.pop(); return 0;").expect_err("empty pop must fail");
+    assert!(err.contains("out of bounds for length 0"), "{err}");
+}
+
+#[test]
+fn test_memory_is_compact_and_grows_arrays_and_strings_on_the_heap() {
+    assert_eq!(
+        run_layout(
+            "for (i in 0..10) { values.push(Int64(i) * 3); } values.pop(); \
+             return values[8] + Int64(values.length) * 100;"
+        ),
+        Ok(Some(924))
+    );
+    assert_eq!(
+        run_layout(
+            "let greeting: String = \"post-\"; let word: String = greeting + \"quantum\"; \
+             greeting = \"\"; return Int64(word.length) * 10 + Int64(greeting.length);"
+        ),
+        Ok(Some(120))
+    );
+
+    let source = r#"
+contract Compact {
+    @public function run() -> Int64 {
+        let a: Int64 = 1;
+        let b: Int64 = a + 2;
+        return b;
+    }
+}
+"#;
+    let vm = run_with_calldata(&compile_source(source), &selector_calldata(source, "run"));
+    assert_eq!(vm.stack.last().unwrap().as_i64().unwrap(), 3);
+    assert!(vm.memory().len() <= 0x100, "{} bytes", vm.memory().len());
+}
+
 #[test]
 fn test_codegen_recognizes_camel_case_mldsa_verify_builtin() {
     let source = r#"
@@ -189,6 +855,124 @@ contract VerifyAlias {
     );
 }
 
+#[test]
+fn test_require_pqc_without_fallback_reverts_on_failed_verification() {
+    let source = r#"
+contract Guarded {
+    Int64 count;
+
+    @public function run() {
+        count = 1;
+        require_pqc {
+            verifyMLDSASignature("key", "message", "signature");
+        }
+        count = 2;
+    }
+}
+"#;
+
+    let mut vm = QuantumVM::new();
+    let mut instance = vm.deploy(&compile_source(source), &[]).unwrap();
+    let result = vm.call(&mut instance, &selector_calldata(source, "run"));
+    assert_eq!(
+        vm.receipt(&result).status,
+        quantumvm::ExecutionStatus::Reverted
+    );
+    let err = result.unwrap_err().to_string();
+    assert!(
+        err.starts_with("Reverted: require_pqc: signature verification failed"),
+        "{err}"
+    );
+    assert_eq!(instance.storage[&StorageKey::from(0)].as_i64().unwrap(), 0);
+}
+
+const GENERIC_VAULT_SOURCE: &str = r#"
+struct Pair<A, B> {
+    first: A;
+    second: B;
+}
+
+contract Vault<T> {
+    total: T;
+    last: Pair<T, Address>;
+
+    @public function deposit(amount: T) -> T {
+        total = total + amount;
+        return total;
+    }
+}
+
+contract TokenVault = Vault<UInt256>;
+contract SmallVault = Vault<UInt64>;
+"#;
+
+#[test]
+fn test_monomorphize_emits_concrete_generic_instances() {
+    let (_, units) = parser::parse(GENERIC_VAULT_SOURCE).expect("generic source should parse");
+    let concrete = monomorphize(&units).expect("generic source should monomorphize");
+
+    let contract_names: Vec<&str> = concrete
+        .iter()
+        .filter_map(|unit| match unit {
+            SourceUnit::Contract(c) => Some(c.name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(contract_names, vec!["TokenVault", "SmallVault"]);
+
+    let small_vault = concrete
+        .iter()
+        .find_map(|unit| match unit {
+            SourceUnit::Contract(c) if c.name == "SmallVault" => Some(c),
+            _ => None,
+        })
+        .expect("SmallVault should be instantiated");
+    let total_ty = small_vault
+        .parts
+        .iter()
+        .find_map(|part| match part {
+            ContractPart::StateVariable(state) if state.name == "total" => Some(&state.ty),
+            _ => None,
+        })
+        .expect("total should exist");
+    assert_eq!(total_ty, &Type::UInt64);
+
+    let struct_names: Vec<&str> = concrete
+        .iter()
+        .filter_map(|unit| match unit {
+            SourceUnit::Struct(s) => Some(s.name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        struct_names,
+        vec!["Pair_UInt256_Address", "Pair_UInt64_Address"]
+    );
+}
+
+#[test]
+fn test_generators_emit_monomorphized_copies() {
+    let (_, units) = parser::parse(GENERIC_VAULT_SOURCE).expect("generic source should parse");
+
+    let solidity = SolidityGenerator::new()
+        .generate(&units)
+        .expect("Solidity generation should succeed");
+    assert!(solidity.contains("contract TokenVault {"));
+    assert!(solidity.contains("contract SmallVault {"));
+    assert!(solidity.contains("struct Pair_UInt64_Address {"));
+    assert!(solidity.contains("uint64 internal total;"));
+    assert!(!solidity.contains("contract Vault"));
+
+    let artifacts = CodeGenerator::new()
+        .generate_contracts(&units)
+        .expect("each instantiation should compile");
+    let names: Vec<&str> = artifacts.iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(names, ["TokenVault", "SmallVault"]);
+    for artifact in &artifacts {
+        assert!(!code_section(&artifact.bytecode).is_empty());
+    }
+}
+
 fn encode_hex(bytes: &[u8]) -> String {
     let mut out = String::with_capacity(bytes.len() * 2);
     for byte in bytes {
@@ -209,6 +993,100 @@ fn render_fixture_source(file_name: &str, ciphertext: &[u8], private_key: &[u8])
         .replace("{{PRIVATE_KEY_HEX}}", &encode_hex(private_key))
 }
 
+#[test]
+fn test_require_continues_when_true_and_branches_get_distinct_labels() {
+    let passing = run_layout(
+        r#"Int64 a = 5;
+        require(a > 1, "small");
+        require(a < 10, "big");
+        if (a > 3) { a = a > 4 ? a + 1 : a; }
+        Int64 none = 0;
+        return a > 5 ? a : none;"#,
+    )
+    .unwrap();
+    assert_eq!(passing, Some(6));
+
+    let failing = run_layout(
+        r#"Int64 a = 5;
+        require(a > 9, "small");
+        return 1;"#,
+    )
+    .unwrap_err();
+    assert!(failing.starts_with("Reverted: small at PC"), "{failing}");
+}
+
+#[test]
+fn test_internal_calls_bind_arguments_and_pass_the_verifier() {
+    let source = r#"
+contract Calls {
+    @public function run() -> Int64 {
+        return combine(40, "ab");
+    }
+
+    function combine(Int64 base, String tag) -> Int64 {
+        return base + Int64(tag.length);
+    }
+}
+"#;
+    let (_version_req, ast) = parser::parse(source).unwrap();
+    compiler::analyze(&ast).unwrap();
+    let bytecode = CodeGenerator::new().generate(&ast).unwrap();
+    let code = code_section(&bytecode);
+    quantumvm::verifier::verify(code).expect("compiled code should verify");
+
+    let mut vm = QuantumVM::new();
+    deploy_and_call(&mut vm, &bytecode, &selector_calldata(source, "run")).unwrap();
+    assert_eq!(vm.stack.last().unwrap().as_i64().unwrap(), 42);
+}
+
+#[test]
+fn test_container_records_compiler_version_source_hash_and_entry_points() {
+    use sha2::{Digest, Sha256};
+
+    let source = r#"
+contract Greeter {
+    function greet() public -> String {
+        String a = "hello";
+        String b = "hello";
+        return a + b;
+    }
+}
+"#;
+    let (_version_req, ast) = parser::parse(source).unwrap();
+    let bytecode = CodeGenerator::new()
+        .with_source(source)
+        .generate(&ast)
+        .unwrap();
+
+    let header = Header::parse(&bytecode).unwrap();
+    let version = compiler::get_compiler_version();
+    assert_eq!(
+        header.compiler_version,
+        Some([version.major, version.minor, version.patch])
+    );
+    let hash: [u8; 32] = Sha256::digest(source.as_bytes()).into();
+    assert_eq!(header.source_hash, Some(hash));
+    // Both literals share one constant pool entry.
+    assert_eq!(
+        header.section_bytes(&bytecode, SectionKind::Data),
+        [&5u32.to_le_bytes()[..], b"hello"].concat()
+    );
+    let greet = ast
+        .iter()
+        .find_map(|unit| match unit {
+            SourceUnit::Contract(c) => c.parts.iter().find_map(|part| match part {
+                ContractPart::Function(f) => Some(f),
+                _ => None,
+            }),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(
+        header.section_bytes(&bytecode, SectionKind::Metadata),
+        format!("0x{:08x} Greeter.greet()\n", function_selector(greet)).as_bytes()
+    );
+}
+
 fn compile_source(source: &str) -> Vec<u8> {
     let (_version_req, ast) = parser::parse(source).expect("Fixture should parse");
     CodeGenerator::new()
@@ -217,10 +1095,14 @@ fn compile_source(source: &str) -> Vec<u8> {
 }
 
 fn code_section(bytecode: &[u8]) -> &[u8] {
-    let header_length = u16::from_le_bytes([bytecode[5], bytecode[6]]) as usize;
-    let code_length =
-        u32::from_le_bytes([bytecode[7], bytecode[8], bytecode[9], bytecode[10]]) as usize;
-    &bytecode[header_length..header_length + code_length]
+    let header = Header::parse(bytecode).expect("compiled container should parse");
+    header.section_bytes(bytecode, SectionKind::Code)
+}
+
+/// Deploys `bytecode` without constructor arguments and calls the instance once.
+fn deploy_and_call(vm: &mut QuantumVM, bytecode: &[u8], calldata: &[u8]) -> Result<(), VMError> {
+    let mut instance = vm.deploy(bytecode, &[])?;
+    vm.call(&mut instance, calldata)
 }
 
 fn run_hqckem_fixture(fixture_file: &str, opcode: OpCode, kem: Kem) {
@@ -240,9 +1122,7 @@ fn run_hqckem_fixture(fixture_file: &str, opcode: OpCode, kem: Kem) {
     );
 
     let mut vm = QuantumVM::new();
-    vm.load_bytecode(&bytecode)
-        .expect("VM should load compiled fixture");
-    vm.execute()
+    deploy_and_call(&mut vm, &bytecode, &selector_calldata(&source, "run"))
         .expect("VM should execute generated HQC fixture");
 
     // Expression statement pops decapsulation result; successful cryptographic execution
@@ -270,9 +1150,7 @@ fn run_hqckem_fixture_expect_failure(
     let bytecode = compile_source(&source);
 
     let mut vm = QuantumVM::new();
-    vm.load_bytecode(&bytecode)
-        .expect("VM should load compiled fixture");
-    let execution = vm.execute();
+    let execution = deploy_and_call(&mut vm, &bytecode, &selector_calldata(&source, "run"));
     assert!(
         execution.is_err(),
         "Fixture with mismatched or corrupted key material must fail"
@@ -326,3 +1204,638 @@ fn test_hqckem256_contract_fixture_rejects_mismatched_private_key() {
     let (_other_pk, other_sk) = kem.keygen().expect("secondary keygen should succeed");
     run_hqckem_fixture_expect_failure("hqckem256-decap-fixture.synq", kem, other_sk);
 }
+
+#[test]
+fn test_source_map_points_runtime_errors_at_statements_and_functions() {
+    let source = r#"
+contract Ratio {
+    @public function run() -> Int64 {
+        Int64 total = 10;
+        for(i in 0..2) {
+            total = total + 1;
+        }
+        return split(total, 0);
+    }
+
+    function split(Int64 value, Int64 parts) -> Int64 {
+        return value / parts;
+    }
+}
+"#;
+    let (_version_req, ast) = parser::parse(source).unwrap();
+    compiler::analyze(&ast).unwrap();
+    let run = function_by_name(&ast, "run");
+    assert_eq!((run.span.line, run.body.spans[0].line), (3, 4));
+
+    let bytecode = CodeGenerator::new()
+        .with_source_file("Ratio.synq")
+        .generate(&ast)
+        .unwrap();
+    let mut vm = QuantumVM::new();
+    let err = deploy_and_call(&mut vm, &bytecode, &selector_calldata(source, "run"))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("(Ratio.synq:12:9 in Ratio.split)"), "{err}");
+
+    // The loop increment and back jump belong to the `for` statement, not its body.
+    let header = Header::parse(&bytecode).unwrap();
+    let map = quantumvm::SourceMap::decode(header.section_bytes(&bytecode, SectionKind::SourceMap))
+        .unwrap();
+    let lines: Vec<(u32, &str)> = map
+        .entries
+        .iter()
+        .map(|entry| (entry.line, map.functions[entry.function as usize].as_str()))
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            (4, "Ratio.run"),
+            (5, "Ratio.run"),
+            (6, "Ratio.run"),
+            (5, "Ratio.run"),
+            (8, "Ratio.run"),
+            (3, "Ratio.run"),
+            (12, "Ratio.split"),
+            (11, "Ratio.split"),
+        ]
+    );
+}
+
+/// Right-aligns `value` in a 32-byte ABI word.
+fn abi_word(value: u64) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+#[test]
+fn test_constructor_runs_once_with_abi_arguments_and_storage_persists() {
+    let source = r#"
+contract Counter {
+    String public label;
+    Int64 public count;
+    Address[] public owners;
+    Int64 public ownerCount;
+    Address public lastOwner;
+
+    constructor(Int64 start, String _label, Address[] _owners) {
+        count = start;
+        label = _label;
+        ownerCount = _owners.length;
+        lastOwner = _owners[1];
+    }
+
+    @public function bump() -> Int64 {
+        count = count + 1;
+        return count;
+    }
+}
+"#;
+    let bytecode = compile_source(source);
+    let header = Header::parse(&bytecode).unwrap();
+    let runtime = header.section_bytes(&bytecode, SectionKind::Runtime);
+    assert!(!runtime.is_empty(), "deploy container should embed runtime");
+
+    // start = 7, label = "tally", owners = [0x11.., 0x22..]
+    let mut args = Vec::new();
+    args.extend_from_slice(&abi_word(7));
+    args.extend_from_slice(&abi_word(96));
+    args.extend_from_slice(&abi_word(160));
+    args.extend_from_slice(&abi_word(5));
+    let mut label = [0u8; 32];
+    label[..5].copy_from_slice(b"tally");
+    args.extend_from_slice(&label);
+    args.extend_from_slice(&abi_word(2));
+    for byte in [0x11u8, 0x22] {
+        let mut owner = [0u8; 32];
+        owner[12..].fill(byte);
+        args.extend_from_slice(&owner);
+    }
+
+    let mut vm = QuantumVM::new();
+    let mut instance = vm.deploy(&bytecode, &args).expect("deploy should succeed");
+    assert_eq!(instance.code, runtime);
+    // Slots follow declaration order; the unset array keeps its length slot 2.
+    let slot = |instance: &quantumvm::ContractInstance, n: u32| {
+        instance.storage[&StorageKey::from(n)].clone()
+    };
+    assert_eq!(slot(&instance, 0).as_bytes().unwrap(), b"tally");
+    assert_eq!(slot(&instance, 1).as_i64().unwrap(), 7);
+    assert!(!instance.storage.contains_key(&StorageKey::from(2)));
+    assert_eq!(slot(&instance, 3).as_i64().unwrap(), 2);
+    assert_eq!(slot(&instance, 4).as_bytes().unwrap(), [0x22; 20]);
+
+    // Each call sees the storage the previous one left; the constructor does not run again.
+    for expected in [8, 9] {
+        vm.call(&mut instance, &selector_calldata(source, "bump"))
+            .expect("call should succeed");
+        assert_eq!(vm.stack.last().unwrap().as_i64().unwrap(), expected);
+    }
+    assert_eq!(slot(&instance, 1).as_i64().unwrap(), 9);
+    assert_eq!(slot(&instance, 0).as_bytes().unwrap(), b"tally");
+}
+
+#[test]
+fn test_revert_undoes_storage_writes_and_events_of_the_failing_call() {
+    let source = r#"
+contract Vault {
+    Int64 total;
+    Int64 spent;
+    event Deposited(Int64 amount, String memo);
+
+    @public function deposit(Int64 amount) {
+        total = total + 5;
+        emit Deposited(5, "tip");
+        with_gas_limit(150) {
+            spent = spent + 1;
+            spent = spent + 1;
+        }
+        require(total < 12, "vault full");
+    }
+}
+"#;
+    let (_, units) = parser::parse(source).unwrap();
+    let SourceUnit::Contract(vault) = &units[0] else {
+        panic!("Expected contract source unit");
+    };
+    let event = vault
+        .parts
+        .iter()
+        .find_map(|part| match part {
+            ContractPart::Event(event) => Some(event.clone()),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(compiler::event_signature(&event), "Deposited(Int64,String)");
+
+    let bytecode = compile_source(source);
+    let deposit = [&selector_calldata(source, "deposit")[..], &[0u8; 32][..]].concat();
+    let mut vm = QuantumVM::new();
+    let mut instance = vm.deploy(&bytecode, &[]).unwrap();
+    for expected in [5, 10] {
+        vm.call(&mut instance, &deposit).unwrap();
+        assert_eq!(
+            instance.storage[&StorageKey::from(0)].as_i64().unwrap(),
+            expected
+        );
+        let log = &vm.logs()[0];
+        assert_eq!(log.topic, compiler::event_topic(&event));
+        assert_eq!(log.data[0].as_i64().unwrap(), 5);
+        assert_eq!(log.data[1].as_bytes().unwrap(), b"tip");
+    }
+    // The gas-limited block overran both times, so its writes never landed.
+    assert_eq!(instance.storage[&StorageKey::from(1)].as_i64().unwrap(), 0);
+
+    // The third deposit fails its `require` after writing and emitting.
+    let err = vm.call(&mut instance, &deposit).unwrap_err();
+    assert!(err.to_string().starts_with("Reverted: vault full"), "{err}");
+    assert!(vm.logs().is_empty());
+    assert_eq!(vm.storage()[&StorageKey::from(0)].as_i64().unwrap(), 10);
+    assert_eq!(instance.storage[&StorageKey::from(0)].as_i64().unwrap(), 10);
+}
+
+#[test]
+fn test_payable_entry_points_accept_value_and_transfer_moves_balances() {
+    let source = r#"
+contract Bank {
+    Address owner;
+    UInt256 deposits;
+    UInt256 held;
+    UInt256 ownerFunds;
+
+    constructor() {
+        owner = msg.sender;
+    }
+
+    @public @payable function deposit() {
+        deposits = deposits + msg.value;
+    }
+
+    @public function withdraw() {
+        require(msg.sender == owner, "not owner");
+        transfer(msg.sender, this.balance);
+    }
+
+    @public function audit() {
+        held = this.balance;
+        ownerFunds = owner.balance;
+    }
+}
+"#;
+    let (_, units) = parser::parse(source).unwrap();
+    let selector = |name: &str| function_selector(&function_by_name(&units, name)).to_be_bytes();
+    let bytecode = compile_source(source);
+
+    let alice: Address = [0xA1; 20];
+    let bob: Address = [0xB0; 20];
+    let mut state = WorldState::new(MemoryBackend::default());
+    state.set_balance(&alice, 1_000);
+    state.set_balance(&bob, 1_000);
+    let mut vm = QuantumVM::new();
+    let bank = vm
+        .deploy_contract(&mut state, &alice, &bytecode, &[])
+        .unwrap();
+
+    vm.call_contract(&mut state, &bob, &bank, &selector("deposit"), 300)
+        .unwrap();
+    assert_eq!(state.balance(&bob), 700);
+    assert_eq!(state.balance(&bank), 300);
+    assert_eq!(state.storage(&bank, 1).as_i64().unwrap(), 300);
+
+    // Non-payable entry points refuse value before running any code.
+    let err = vm
+        .call_contract(&mut state, &bob, &bank, &selector("audit"), 1)
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("Reverted: `Bank.audit` is not payable"),
+        "{err}"
+    );
+    assert_eq!(state.balance(&bob), 700);
+
+    let withdraw = selector("withdraw");
+    let err = vm
+        .call_contract(&mut state, &bob, &bank, &withdraw, 0)
+        .unwrap_err();
+    assert!(err.to_string().starts_with("Reverted: not owner"), "{err}");
+    vm.call_contract(&mut state, &alice, &bank, &withdraw, 0)
+        .unwrap();
+    assert_eq!(state.balance(&alice), 1_300);
+    assert_eq!(state.balance(&bank), 0);
+
+    vm.call_contract(&mut state, &bob, &bank, &selector("deposit"), 50)
+        .unwrap();
+    vm.call_contract(&mut state, &bob, &bank, &selector("audit"), 0)
+        .unwrap();
+    assert_eq!(state.storage(&bank, 1).as_i64().unwrap(), 350);
+    assert_eq!(state.storage(&bank, 2).as_i64().unwrap(), 50);
+    assert_eq!(state.storage(&bank, 3).as_i64().unwrap(), 1_300);
+}
+
+#[test]
+fn test_state_structs_arrays_and_mappings_persist_across_calls() {
+    let source = r#"
+contract Ledger {
+    struct Entry {
+        amount: Int64;
+        memo: String;
+    }
+
+    mapping(Address => Int64) balanceOf;
+    Int64[] history;
+    Entry last;
+    Int64 seen;
+    Int64 count;
+    Int64 total;
+    String memo;
+
+    constructor() {
+        balanceOf[msg.sender] = 100;
+    }
+
+    @public function record() {
+        balanceOf[msg.sender] = balanceOf[msg.sender] + 5;
+        history.push(5);
+        history.push(9);
+        last = Entry({amount: 9, memo: "rent"});
+    }
+
+    @public function read() {
+        seen = balanceOf[msg.sender];
+        let copy: Int64[] = history;
+        count = Int64(copy.length);
+        total = copy[0] + copy[1] + last.amount;
+        memo = last.memo;
+    }
+}
+"#;
+    let (_, units) = parser::parse(source).unwrap();
+    let selector = |name: &str| function_selector(&function_by_name(&units, name)).to_be_bytes();
+    let bytecode = compile_source(source);
+
+    let alice: Address = [0xA1; 20];
+    let mut state = WorldState::new(MemoryBackend::default());
+    let mut vm = QuantumVM::new();
+    let ledger = vm
+        .deploy_contract(&mut state, &alice, &bytecode, &[])
+        .unwrap();
+
+    // Slots: mapping 0, history 1, last 2 and 3, then seen, count, total and memo.
+    vm.call_contract(&mut state, &alice, &ledger, &selector("record"), 0)
+        .unwrap();
+    vm.call_contract(&mut state, &alice, &ledger, &selector("read"), 0)
+        .unwrap();
+    assert_eq!(state.storage(&ledger, 4).as_i64().unwrap(), 105);
+    assert_eq!(state.storage(&ledger, 5).as_i64().unwrap(), 2);
+    assert_eq!(state.storage(&ledger, 6).as_i64().unwrap(), 23);
+    assert_eq!(state.storage(&ledger, 7).as_bytes().unwrap(), b"rent");
+}
+
+#[test]
+fn test_each_contract_compiles_to_its_own_deployable_artifact() {
+    use sha2::{Digest, Sha256};
+
+    let source = r#"
+contract Token {
+    UInt256 supply;
+
+    event Minted(UInt256 amount);
+
+    constructor() {
+        supply = 1000;
+    }
+
+    @public function mint() {
+        supply = supply + 5;
+        emit Minted(5);
+    }
+}
+
+contract Vault {
+    UInt256 locked;
+
+    event Locked(UInt256 amount);
+
+    @public function lock() {
+        locked = locked + 7;
+        emit Locked(7);
+    }
+}
+"#;
+    let (_, units) = parser::parse(source).unwrap();
+    let err = CodeGenerator::new().generate(&units).unwrap_err();
+    assert_eq!(
+        err,
+        "Source defines 2 contracts (`Token`, `Vault`); each one compiles to its own artifact"
+    );
+
+    let artifacts = CodeGenerator::new()
+        .with_source(source)
+        .with_source_file("pair.synq")
+        .generate_contracts(&units)
+        .unwrap();
+    let [token, vault] = artifacts.as_slice() else {
+        panic!("expected two artifacts, got {}", artifacts.len());
+    };
+    assert_eq!(token.name, "Token");
+    assert_eq!(vault.name, "Vault");
+    let mint = function_selector(&function_by_name(&units, "mint"));
+    let lock = function_selector(&function_by_name(&units, "lock"));
+    assert_eq!(token.abi.functions().count(), 1);
+    assert_eq!(
+        token.abi.function("mint").unwrap().selector,
+        format!("0x{:08x}", mint)
+    );
+    assert_eq!(vault.abi.functions().count(), 1);
+    assert_eq!(
+        vault.abi.function("lock").unwrap().selector,
+        format!("0x{:08x}", lock)
+    );
+    assert_eq!(vault.source_map.file, "pair.synq");
+    assert!(vault
+        .source_map
+        .functions
+        .contains(&"Vault.lock".to_string()));
+    assert!(!vault
+        .source_map
+        .functions
+        .iter()
+        .any(|f| f.starts_with("Token.")));
+    assert_eq!(token.metadata.source_file, "pair.synq");
+    assert_eq!(
+        token.metadata.source_hash,
+        <[u8; 32]>::from(Sha256::digest(source.as_bytes()))
+    );
+
+    // Each contract owns its storage from slot 0 and only dispatches its own entry points.
+    let deployer: Address = [0xD0; 20];
+    let mut state = WorldState::new(MemoryBackend::default());
+    let mut vm = QuantumVM::new();
+    let token_address = vm
+        .deploy_contract(&mut state, &deployer, &token.bytecode, &[])
+        .unwrap();
+    let vault_address = vm
+        .deploy_contract(&mut state, &deployer, &vault.bytecode, &[])
+        .unwrap();
+    assert_eq!(state.storage(&token_address, 0).as_i64().unwrap(), 1000);
+
+    vm.call_contract(
+        &mut state,
+        &deployer,
+        &token_address,
+        &mint.to_be_bytes(),
+        0,
+    )
+    .unwrap();
+    vm.call_contract(
+        &mut state,
+        &deployer,
+        &vault_address,
+        &lock.to_be_bytes(),
+        0,
+    )
+    .unwrap();
+    vm.call_contract(
+        &mut state,
+        &deployer,
+        &vault_address,
+        &mint.to_be_bytes(),
+        0,
+    )
+    .unwrap_err();
+    assert_eq!(state.storage(&token_address, 0).as_i64().unwrap(), 1005);
+    assert_eq!(state.storage(&vault_address, 0).as_i64().unwrap(), 7);
+}
+
+#[test]
+fn test_abi_describes_entry_points_events_errors_and_constructor() {
+    let source = r#"
+contract Registry {
+    struct Entry {
+        owner: Address;
+        key: MLDSAPublicKey;
+    }
+
+    UInt256 count;
+
+    event Registered(Address indexed owner, MLDSAPublicKey key);
+
+    error NotOwner(Address caller);
+
+    constructor(UInt256 start) {
+        count = start;
+    }
+
+    function register(Entry[] entries, MLDSASignature proof) public payable -> Bool {
+        return true;
+    }
+
+    function total() public view -> UInt256 {
+        return count;
+    }
+
+    function bump() internal {
+        count = count + 1;
+    }
+}
+"#;
+    let (_, units) = parser::parse(source).unwrap();
+    let artifacts = CodeGenerator::new().generate_contracts(&units).unwrap();
+    let abi = &artifacts[0].abi;
+    let json: serde_json::Value = serde_json::from_str(&abi.to_json()).unwrap();
+    let register = function_by_name(&units, "register");
+    let not_owner = units
+        .iter()
+        .find_map(|unit| match unit {
+            SourceUnit::Contract(c) => c.parts.iter().find_map(|part| match part {
+                ContractPart::Error(error) => Some(error.clone()),
+                _ => None,
+            }),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(error_signature(&not_owner), "NotOwner(Address)");
+
+    let entry = serde_json::json!({"name": "entries", "type": "tuple[]",
+    "internalType": "struct Entry", "components": [
+        {"name": "owner", "type": "Address"},
+        {"name": "key", "type": "MLDSAPublicKey"},
+    ]});
+    assert_eq!(
+        json,
+        serde_json::json!([
+            {"type": "event", "name": "Registered", "topic": "0xee80f677", "inputs": [
+                {"name": "owner", "type": "Address", "indexed": true},
+                {"name": "key", "type": "MLDSAPublicKey", "indexed": false},
+            ]},
+            {"type": "error", "name": "NotOwner",
+                "selector": format!("0x{:08x}", error_selector(&not_owner)),
+                "inputs": [{"name": "caller", "type": "Address"}]},
+            {"type": "constructor", "stateMutability": "nonpayable",
+                "inputs": [{"name": "start", "type": "UInt256"}]},
+            {"type": "function", "name": "register", "stateMutability": "payable",
+                "selector": format!("0x{:08x}", function_selector(&register)),
+                "inputs": [entry, {"name": "proof", "type": "MLDSASignature"}],
+                "outputs": [{"name": "", "type": "Bool"}]},
+            {"type": "function", "name": "total", "stateMutability": "view",
+                "selector": format!("0x{:08x}", function_selector(&function_by_name(&units, "total"))),
+                "inputs": [], "outputs": [{"name": "", "type": "UInt256"}]},
+        ])
+    );
+    assert_eq!(ContractAbi::from_json(&abi.to_json()).unwrap(), *abi);
+
+    // JSON arguments encode through the ABI's wire types.
+    let register = abi.function("register").unwrap();
+    assert_eq!(
+        register.inputs[0].abi_type().unwrap().to_string(),
+        "(Address,MLDSAPublicKey)[]"
+    );
+    let key = format!("0x{}", "07".repeat(1952));
+    let proof = format!("0x{}", "09".repeat(3309));
+    let calldata = register
+        .encode_call(&serde_json::json!([
+            [[format!("0x{}", "11".repeat(20)), key]],
+            proof
+        ]))
+        .unwrap();
+    assert_eq!(
+        calldata[..4],
+        function_selector(&function_by_name(&units, "register")).to_be_bytes()
+    );
+    let err = register
+        .encode_call(&serde_json::json!([[], "0x1234"]))
+        .unwrap_err();
+    assert!(
+        err.contains("MLDSASignature must be 3309 bytes but got 2"),
+        "{err}"
+    );
+    let err = abi
+        .constructor()
+        .unwrap()
+        .encode_args(&serde_json::json!([-1]))
+        .unwrap_err();
+    assert!(
+        err.contains("`-1` is negative but UInt256 is unsigned"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_dispatcher_decodes_struct_array_and_string_arguments() {
+    use quantumvm::abi::{encode_call, AbiType, AbiValue};
+
+    let source = r#"
+struct Point {
+    x: Int64;
+    y: Int64;
+}
+
+contract Ledger {
+    struct Tag {
+        id: Int64;
+        label: String;
+    }
+
+    Address public payer;
+    String public memo;
+    Int64 public lastY;
+    Int64 public pairSum;
+    Int64 public pathSum;
+    String public tagLabel;
+
+    function ping() public -> Int64 {
+        return 1;
+    }
+
+    function record(Address who, Point p, String note, Int64[2] pair, Point[] path, Tag tag) public {
+        payer = who;
+        memo = note;
+        lastY = p.y;
+        pairSum = pair[0] + pair[1];
+        pathSum = path[0].x + path[1].y + Int64(path.length) + tag.id;
+        tagLabel = tag.label;
+    }
+}
+"#;
+    let (_, units) = parser::parse(source).unwrap();
+    let bytecode = CodeGenerator::new().generate(&units).unwrap();
+    let point = AbiType::Tuple(vec![AbiType::Int(64), AbiType::Int(64)]);
+    let types = [
+        AbiType::Address,
+        point.clone(),
+        AbiType::String,
+        AbiType::parse("Int64[2]").unwrap(),
+        AbiType::Array(Box::new(point), None),
+        AbiType::Tuple(vec![AbiType::Int(64), AbiType::String]),
+    ];
+    let point = |x: i128, y: i128| AbiValue::Tuple(vec![AbiValue::int(x), AbiValue::int(y)]);
+    let values = [
+        AbiValue::Address([0x42; 20]),
+        point(3, -4),
+        AbiValue::String("hello".to_string()),
+        AbiValue::Array(vec![AbiValue::int(20), AbiValue::int(22)]),
+        AbiValue::Array(vec![point(100, 1), point(2, 30), point(5, 5)]),
+        AbiValue::Tuple(vec![
+            AbiValue::int(7),
+            AbiValue::String("tagged".to_string()),
+        ]),
+    ];
+    let selector = function_selector(&function_by_name(&units, "record"));
+    let calldata = encode_call(selector, &types, &values).unwrap();
+
+    let mut vm = QuantumVM::new();
+    let mut instance = vm.deploy(&bytecode, &[]).unwrap();
+    vm.call(&mut instance, &calldata)
+        .expect("record should succeed");
+    let slot = |n: u32| instance.storage[&StorageKey::from(n)].clone();
+    assert_eq!(slot(0).as_bytes().unwrap(), [0x42; 20]);
+    assert_eq!(slot(1).as_bytes().unwrap(), b"hello");
+    assert_eq!(slot(2).as_i64().unwrap(), -4);
+    assert_eq!(slot(3).as_i64().unwrap(), 42);
+    assert_eq!(slot(4).as_i64().unwrap(), 100 + 30 + 3 + 7);
+    assert_eq!(slot(5).as_bytes().unwrap(), b"tagged");
+
+    vm.call(&mut instance, &selector_calldata(source, "ping"))
+        .unwrap();
+    assert_eq!(vm.stack.last().unwrap().as_i64().unwrap(), 1);
+}
@@ -243,3 +243,589 @@ contract DeadCode {
         .iter()
         .any(|e| e.message.contains("contains unreachable statement")));
 }
+
+#[test]
+fn semantic_accepts_generic_contract_and_struct_instantiations() {
+    let source = r#"
+struct Pair<A, B> {
+    first: A;
+    second: B;
+}
+
+contract Vault<T> {
+    total: T;
+    last: Pair<T, Address>;
+
+    function deposit(amount: T) -> T {
+        total = total + amount;
+        return total;
+    }
+}
+
+contract TokenVault = Vault<UInt256>;
+contract SmallVault = Vault<UInt64>;
+"#;
+
+    assert!(analyze_source(source).is_ok());
+}
+
+#[test]
+fn semantic_checks_generic_bodies_after_substitution() {
+    let source = r#"
+contract Vault<T> {
+    total: T;
+
+    function deposit(amount: T) {
+        total = total + amount;
+    }
+}
+
+contract FlagVault = Vault<Bool>;
+"#;
+
+    let errors = analyze_source(source).expect_err("Bool does not support arithmetic");
+    assert!(errors.iter().any(|e| e
+        .message
+        .contains("applies arithmetic operation to non-numeric types")));
+}
+
+#[test]
+fn semantic_rejects_generic_arity_mismatch() {
+    let source = r#"
+struct Pair<A, B> {
+    first: A;
+    second: B;
+}
+
+contract Holder {
+    pair: Pair<UInt256>;
+}
+"#;
+
+    let errors = analyze_source(source).expect_err("wrong type argument count must fail");
+    assert!(errors.iter().any(|e| e
+        .message
+        .contains("`Pair` expects 2 type argument(s), found 1")));
+}
+
+#[test]
+fn semantic_rejects_instantiation_of_non_generic_contract() {
+    let source = r#"
+contract Plain {
+    function noop() {}
+}
+
+contract Alias = Plain<UInt256>;
+"#;
+
+    let errors = analyze_source(source).expect_err("non-generic instantiation must fail");
+    assert!(errors.iter().any(|e| e
+        .message
+        .contains("`Plain`, which is not a generic contract")));
+}
+
+#[test]
+fn semantic_rejects_state_write_in_view_function() {
+    let source = r#"
+contract Counter {
+    counter: UInt256;
+
+    function peek() view -> UInt256 {
+        counter = counter + 1;
+        return counter;
+    }
+}
+"#;
+
+    let errors = analyze_source(source).expect_err("view state writes must fail");
+    assert!(errors.iter().any(|e| e
+        .message
+        .contains("is declared `view` but writes state variable `counter`")));
+}
+
+#[test]
+fn semantic_rejects_state_read_in_pure_function() {
+    let source = r#"
+contract Counter {
+    counter: UInt256;
+
+    function double(x: UInt256) pure -> UInt256 {
+        return x + counter;
+    }
+
+    function shadowed(counter: UInt256) pure -> UInt256 {
+        return counter;
+    }
+}
+"#;
+
+    let errors = analyze_source(source).expect_err("pure state reads must fail");
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0]
+        .message
+        .contains("is declared `pure` but reads state variable `counter`"));
+}
+
+#[test]
+fn semantic_rejects_environment_read_in_pure_function() {
+    let source = r#"
+contract Caller {
+    function who() pure -> Address {
+        return msg.sender;
+    }
+
+    function self_address() pure -> Address {
+        return this;
+    }
+
+    function when() pure -> UInt256 {
+        return block.timestamp;
+    }
+
+    function visible() view -> Address {
+        return msg.sender;
+    }
+}
+"#;
+
+    let errors = analyze_source(source).expect_err("pure environment reads must fail");
+    let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+    assert_eq!(messages.len(), 3, "{messages:?}");
+    for (function, read) in [
+        ("who", "msg.sender"),
+        ("self_address", "this"),
+        ("when", "block.timestamp"),
+    ] {
+        let expected = format!(
+            "Function `{function}` in contract `Caller` is declared `pure` but reads `{read}`"
+        );
+        assert!(
+            messages.iter().any(|m| m.contains(&expected)),
+            "{messages:?}"
+        );
+    }
+}
+
+#[test]
+fn semantic_rejects_view_function_calling_state_modifying_function() {
+    let source = r#"
+contract Counter {
+    counter: UInt256;
+
+    function bump() {
+        counter = counter + 1;
+    }
+
+    function peek() view -> UInt256 {
+        bump();
+        return counter;
+    }
+}
+"#;
+
+    let errors = analyze_source(source).expect_err("view calling nonpayable must fail");
+    assert!(errors
+        .iter()
+        .any(|e| e.message.contains("calls `nonpayable` function `bump`")));
+}
+
+#[test]
+fn semantic_rejects_private_call_from_another_contract() {
+    let source = r#"
+contract Vault {
+    function sweep() private {}
+    @public function deposit() {}
+}
+
+contract Caller {
+    vault: Vault;
+
+    @public function run() {
+        vault.deposit();
+        vault.sweep();
+    }
+}
+"#;
+
+    let errors = analyze_source(source).expect_err("private cross-contract call must fail");
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0]
+        .message
+        .contains("calls private function `vault.sweep` from outside contract `Vault`"));
+}
+
+#[test]
+fn semantic_accepts_registered_annotations() {
+    let source = r#"
+@optimize_gas
+contract Annotated {
+    @gas_cost(base: 45000, mldsa_verify: 35000)
+    @gas_limit(100000)
+    @public function submit() {}
+
+    @precompile("fndsa_batch_verify")
+    @hardware_accel
+    function batch() {}
+}
+"#;
+
+    assert!(analyze_source(source).is_ok());
+}
+
+#[test]
+fn semantic_rejects_misspelled_annotation_with_suggestion() {
+    let source = r#"
+contract Typo {
+    @gas_cots(base: 45000)
+    function run() {}
+}
+"#;
+
+    let errors = analyze_source(source).expect_err("unknown annotation must fail");
+    assert!(errors.iter().any(|e| e.message
+        == "Function `run` in contract `Typo` uses unknown annotation `@gas_cots`; did you mean `@gas_cost`?"));
+}
+
+#[test]
+fn semantic_rejects_misplaced_annotation() {
+    let source = r#"
+@gas_limit(100000)
+contract Misplaced {
+    function run() {}
+}
+"#;
+
+    let errors = analyze_source(source).expect_err("misplaced annotation must fail");
+    assert!(errors.iter().any(|e| e.message.contains(
+        "places `@gas_limit` on a contract; it is only allowed on functions and constructors"
+    )));
+}
+
+#[test]
+fn semantic_rejects_badly_typed_annotation_arguments() {
+    let source = r#"
+contract BadArgs {
+    @gas_cost(base: "cheap")
+    function a() {}
+
+    @gas_cost(mldsa_verify: 35000)
+    function b() {}
+
+    @gas_limit(limit: 100000)
+    function c() {}
+
+    @hardware_accel(true)
+    function d() {}
+}
+"#;
+
+    let errors = analyze_source(source).expect_err("bad annotation arguments must fail");
+    let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+    for expected in [
+        "argument `base` must be an integer",
+        "missing required argument `base`",
+        "expected an integer without a name, found `limit:`",
+        "`@hardware_accel`: it takes no arguments",
+    ] {
+        assert!(
+            messages.iter().any(|m| m.contains(expected)),
+            "missing `{expected}` in {messages:?}"
+        );
+    }
+}
+
+#[test]
+fn semantic_rejects_non_literal_gas_budget() {
+    let source = r#"
+contract Budgeted {
+    function run(limit: UInt256) {
+        with_gas_limit(limit) {
+            let x: UInt256 = 1;
+        }
+    }
+}
+"#;
+
+    let errors = analyze_source(source).expect_err("dynamic gas budget must fail");
+    assert!(errors.iter().any(|e| e
+        .message
+        .contains("budget that is not a positive integer literal")));
+}
+
+#[test]
+fn semantic_range_checks_literals_against_target_type() {
+    let source = r#"
+contract Ranges {
+    small: UInt8;
+
+    function take(x: UInt32) {}
+
+    function run() -> UInt64 {
+        let a: UInt8 = 255;
+        let b: UInt8 = 256;
+        let c: Int8 = -128;
+        let d: Int8 = 128;
+        let e: UInt32 = -1;
+        let f: UInt256 = 2 ether;
+        small = 0x1_00;
+        take(5 weeks);
+        take(1e10);
+        return 1e20;
+    }
+}
+"#;
+
+    let errors = analyze_source(source).expect_err("out-of-range literals must fail");
+    let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+    assert_eq!(messages.len(), 6, "{messages:?}");
+    for expected in [
+        "literal `256` out of range for `UInt8` in initializer of `b`",
+        "literal `128` out of range for `Int8` in initializer of `d`",
+        "literal `-1` out of range for `UInt32` in initializer of `e`",
+        "literal `256` out of range for `UInt8` in assignment to `small`",
+        "literal `10000000000` out of range for `UInt32` in argument 1 to `take`",
+        "literal `100000000000000000000` out of range for `UInt64` in return value",
+    ] {
+        assert!(
+            messages.iter().any(|m| m.contains(expected)),
+            "missing `{expected}` in {messages:?}"
+        );
+    }
+}
+
+#[test]
+fn semantic_types_string_and_bytes_operations() {
+    let source = r#"
+contract Text {
+    tag: Bytes4;
+
+    function run(name: String, data: Bytes) -> UInt256 {
+        let joined: String = "hello " + name;
+        let raw: Bytes = data + tag;
+        let head: Bytes = slice(raw, 0, 2);
+        require(joined != name, "same");
+        tag = Bytes("cafebabe");
+        return joined.length + head.length;
+    }
+
+    function bad(name: String, data: Bytes) {
+        let mixed: String = name + data;
+        let n: UInt256 = slice(name, "0", 1).length;
+        tag = Bytes("cafe");
+    }
+}
+"#;
+
+    let errors = analyze_source(source).expect_err("mixed byte operations must fail");
+    let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+    assert!(
+        messages.iter().all(|m| m.contains("Function `bad`")),
+        "{messages:?}"
+    );
+    for expected in [
+        "applies arithmetic operation to non-numeric types `String` and `Bytes`",
+        "passes non-numeric `String` as argument 2 to `slice`",
+        "literal `Bytes(\"cafe\")` out of range for `FixedBytes(4)`",
+    ] {
+        assert!(
+            messages.iter().any(|m| m.contains(expected)),
+            "missing `{expected}` in {messages:?}"
+        );
+    }
+}
+
+#[test]
+fn semantic_requires_explicit_conversions_to_narrow() {
+    let source = r#"
+contract Casts {
+    owner: Address;
+
+    function run(amount: UInt256, small: UInt8, delta: Int32) -> UInt64 {
+        let widened: UInt256 = small;
+        let signed: Int64 = delta;
+        let narrow: UInt8 = UInt8(amount);
+        let id: UInt256 = UInt256(owner);
+        let tag: Bytes4 = Bytes4(UInt32(amount));
+        return UInt64(amount) + small;
+    }
+
+    function bad(amount: UInt256, delta: Int32, flag: Bool) -> UInt8 {
+        let narrow: UInt8 = amount;
+        let unsigned: UInt64 = delta;
+        let word: UInt64 = UInt64(flag);
+        let tag: Bytes4 = Bytes4(amount);
+        let c: UInt8 = UInt8(300);
+        return amount;
+    }
+}
+"#;
+
+    let errors = analyze_source(source).expect_err("implicit narrowing must fail");
+    let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+    assert!(
+        messages.iter().all(|m| m.contains("Function `bad`")),
+        "{messages:?}"
+    );
+    for expected in [
+        "initializes `narrow` with incompatible type (expected `UInt8`, found `UInt256`); use an explicit `UInt8(...)` conversion",
+        "initializes `unsigned` with incompatible type (expected `UInt64`, found `Int32`)",
+        "cannot convert `Bool` to `UInt64`",
+        "cannot convert `UInt256` to `FixedBytes(4)`",
+        "literal `300` out of range for `UInt8` in conversion to `UInt8`",
+        "returns incompatible type (expected `UInt8`, found `UInt256`)",
+    ] {
+        assert!(
+            messages.iter().any(|m| m.contains(expected)),
+            "missing `{expected}` in {messages:?}"
+        );
+    }
+}
+
+#[test]
+fn semantic_checks_struct_fields_and_array_methods() {
+    let source = r#"
+contract Layout {
+    struct Point {
+        x: Int64;
+        y: Int64;
+    }
+
+    Int64[4] slots;
+    Int64[] values;
+
+    function good(p: Point) {
+        values.push(p.x);
+        slots[3] = p.y;
+        let q: Point = {x: 1, y: 2};
+    }
+
+    function bad(p: Point) {
+        let z: Int64 = p.z;
+        slots.push(1);
+        slots[4] = 0;
+        values.length = 0;
+        let q: Point = {x: 1};
+        let r: Point = {x: 1, y: 2, w: 3};
+        let s: Int64[2] = [1, 2, 3];
+    }
+
+    @view function peek() {
+        values.push(1);
+    }
+}
+"#;
+
+    let errors = analyze_source(source).expect_err("invalid layouts must fail");
+    let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+    assert!(
+        !messages.iter().any(|m| m.contains("Function `good`")),
+        "{messages:?}"
+    );
+    for expected in [
+        "accesses unknown member `z` of struct `Point`",
+        "calls `push` on fixed-size array `slots`",
+        "indexes `Array(Int64, Some(4))` with out-of-bounds literal `4`",
+        "assigns to read-only `values.length`",
+        "leaves field `y` of struct `Point` unset in initializer of `q`",
+        "sets unknown field `w` of struct `Point` in initializer of `r`",
+        "uses 3 elements for `Array(Int64, Some(2))` in initializer of `s`",
+        "Function `peek` in contract `Layout` is declared `view` but writes state variable `values`",
+    ] {
+        assert!(
+            messages.iter().any(|m| m.contains(expected)),
+            "missing `{expected}` in {messages:?}"
+        );
+    }
+}
+
+#[test]
+fn semantic_checks_payable_value_reads_and_balance_builtins() {
+    let source = r#"
+contract Till {
+    total: UInt256;
+    owner: Address;
+
+    @public @payable function pay() {
+        total = total + msg.value;
+    }
+
+    function credit() {
+        total = total + msg.value;
+    }
+
+    @public @view function funds() -> UInt256 {
+        return this.balance + owner.balance + msg.sender.balance;
+    }
+
+    @public function refund() {
+        transfer(owner, msg.value);
+    }
+
+    @external function tip() {
+        total = msg.value;
+    }
+
+    @public @view function sweep() {
+        transfer(owner, 1);
+    }
+
+    @pure function peek(a: Address) -> UInt256 {
+        return a.balance;
+    }
+}
+"#;
+
+    let errors = analyze_source(source).expect_err("value misuse must fail");
+    let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+    for fine in ["`pay`", "`credit`", "`funds`"] {
+        assert!(
+            !messages.iter().any(|m| m.contains(fine)),
+            "{fine} should pass: {messages:?}"
+        );
+    }
+    for expected in [
+        "Function `refund` in contract `Till` reads `msg.value` but is not `payable`",
+        "Function `tip` in contract `Till` reads `msg.value` but is not `payable`",
+        "Function `sweep` in contract `Till` is declared `view` but calls `nonpayable` function `transfer`",
+        "Function `peek` in contract `Till` is declared `pure` but reads the balance of `a`",
+    ] {
+        assert!(
+            messages.iter().any(|m| m.contains(expected)),
+            "missing `{expected}` in {messages:?}"
+        );
+    }
+}
+
+#[test]
+fn semantic_rejects_duplicate_custom_errors() {
+    let source = r#"
+contract Errors {
+    error Unauthorized(Address caller);
+    error Unauthorized(UInt256 code);
+}
+"#;
+
+    let errors = analyze_source(source).expect_err("duplicate errors must fail");
+    assert!(errors
+        .iter()
+        .any(|e| e.message == "Contract `Errors` has duplicate error `Unauthorized`"));
+}
+
+#[test]
+fn semantic_rejects_receive_and_fallback_that_calldata_cannot_reach() {
+    let source = r#"
+contract Wallet {
+    @public function receive(amount: UInt256) {}
+
+    function fallback() {}
+}
+"#;
+
+    let errors = analyze_source(source).expect_err("receive and fallback signatures must fail");
+    assert!(errors.iter().any(|e| e
+        .message
+        .contains("`receive` in contract `Wallet` cannot take parameters")));
+    assert!(errors.iter().any(|e| e
+        .message
+        .contains("`fallback` in contract `Wallet` must be `public` or `external`")));
+}
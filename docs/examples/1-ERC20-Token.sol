@@ -28,7 +28,7 @@ decimals = _decimals;
 owner = msg.sender;
 governanceKey = _governanceKey;
 totalSupply = _initialSupply;
-balanceOf = _initialSupply;
+balanceOf[msg.sender] = _initialSupply;
 emit Transfer((Address(0), msg.sender, _initialSupply));
 }
 
@@ -38,62 +38,62 @@ event Mint((address to, uint256 amount));
 event Burn((address from, uint256 amount));
 event GovernanceKeyUpdated((bytes memory newKey));
 
-function totalSupply() external returns (uint256) public {
+function totalSupply() public returns (uint256) {
 return totalSupply;
 }
 
-function balanceOf(address account) external returns (uint256) public {
+function balanceOf(address account) public returns (uint256) {
 return balanceOf[account];
 }
 
-function transfer(address to, uint256 amount) external returns (bool) public {
+function transfer(address to, uint256 amount) public returns (bool) {
 require(to != Address(0), "Transfer to zero address");
 require(balanceOf[msg.sender] >= amount, "Insufficient balance");
-balanceOf = balanceOf[msg.sender] - amount;
-balanceOf = balanceOf[to] + amount;
+balanceOf[msg.sender] = balanceOf[msg.sender] - amount;
+balanceOf[to] = balanceOf[to] + amount;
 emit Transfer((msg.sender, to, amount));
 return true;
 }
 
-function transferFrom(address from, address to, uint256 amount) external returns (bool) public {
+function transferFrom(address from, address to, uint256 amount) public returns (bool) {
 require(to != Address(0), "Transfer to zero address");
 require(balanceOf[from] >= amount, "Insufficient balance");
 require(allowance[from][msg.sender] >= amount, "Insufficient allowance");
-balanceOf = balanceOf[from] - amount;
-balanceOf = balanceOf[to] + amount;
-allowance = allowance[from][msg.sender] - amount;
+balanceOf[from] = balanceOf[from] - amount;
+balanceOf[to] = balanceOf[to] + amount;
+allowance[from][msg.sender] = allowance[from][msg.sender] - amount;
 emit Transfer((from, to, amount));
 return true;
 }
 
-function approve(address spender, uint256 amount) external returns (bool) public {
+function approve(address spender, uint256 amount) public returns (bool) {
 require(spender != Address(0), "Approve to zero address");
-allowance = amount;
+allowance[msg.sender][spender] = amount;
 emit Approval((msg.sender, spender, amount));
 return true;
 }
 
-function allowance(address owner, address spender) external returns (uint256) public {
+function allowance(address owner, address spender) public returns (uint256) {
 return allowance[owner][spender];
 }
 
-function increaseAllowance(address spender, uint256 addedValue) external returns (bool) public {
+function increaseAllowance(address spender, uint256 addedValue) public returns (bool) {
 require(spender != Address(0), "Approve to zero address");
-allowance = allowance[msg.sender][spender] + addedValue;
+allowance[msg.sender][spender] = allowance[msg.sender][spender] + addedValue;
 emit Approval((msg.sender, spender, allowance[msg.sender][spender]));
 return true;
 }
 
-function decreaseAllowance(address spender, uint256 subtractedValue) external returns (bool) public {
+function decreaseAllowance(address spender, uint256 subtractedValue) public returns (bool) {
 require(spender != Address(0), "Approve to zero address");
 require(allowance[msg.sender][spender] >= subtractedValue, "Decreased allowance below zero");
-allowance = allowance[msg.sender][spender] - subtractedValue;
+allowance[msg.sender][spender] = allowance[msg.sender][spender] - subtractedValue;
 emit Approval((msg.sender, spender, allowance[msg.sender][spender]));
 return true;
 }
 
-// @gas_cost
-function mint(address to, uint256 amount, bytes messageToSign, bytes memory signature) public // @gas_cost() {
+// @gas_cost(base: 100000, mldsa_verify: 35000)
+function mint(address to, uint256 amount, bytes messageToSign, bytes memory signature) public {
 require(to != Address(0), "Mint to zero address");
 require(amount > 0, "Mint amount must be positive");
 {
@@ -106,7 +106,7 @@ revert("PQC verification failed");
 }
 }
 totalSupply = totalSupply + amount;
-balanceOf = balanceOf[to] + amount;
+balanceOf[to] = balanceOf[to] + amount;
 emit Mint((to, amount));
 emit Transfer((Address(0), to, amount));
 }
@@ -114,7 +114,7 @@ emit Transfer((Address(0), to, amount));
 function burn(uint256 amount) public {
 require(balanceOf[msg.sender] >= amount, "Insufficient balance to burn");
 require(amount > 0, "Burn amount must be positive");
-balanceOf = balanceOf[msg.sender] - amount;
+balanceOf[msg.sender] = balanceOf[msg.sender] - amount;
 totalSupply = totalSupply - amount;
 emit Burn((msg.sender, amount));
 emit Transfer((msg.sender, Address(0), amount));
@@ -124,15 +124,15 @@ function burnFrom(address from, uint256 amount) public {
 require(balanceOf[from] >= amount, "Insufficient balance to burn");
 require(allowance[from][msg.sender] >= amount, "Insufficient allowance");
 require(amount > 0, "Burn amount must be positive");
-balanceOf = balanceOf[from] - amount;
-allowance = allowance[from][msg.sender] - amount;
+balanceOf[from] = balanceOf[from] - amount;
+allowance[from][msg.sender] = allowance[from][msg.sender] - amount;
 totalSupply = totalSupply - amount;
 emit Burn((from, amount));
 emit Transfer((from, Address(0), amount));
 }
 
-// @gas_cost
-function pause(bytes messageToSign, bytes memory signature) public // @gas_cost() {
+// @gas_cost(base: 75000, mldsa_verify: 35000)
+function pause(bytes messageToSign, bytes memory signature) public {
 {
 // SynQ require_pqc compatibility block
 bool __synq_pqc_ok = true;
@@ -145,8 +145,8 @@ revert("PQC verification failed");
 paused = true;
 }
 
-// @gas_cost
-function unpause(bytes messageToSign, bytes memory signature) public // @gas_cost() {
+// @gas_cost(base: 75000, mldsa_verify: 35000)
+function unpause(bytes messageToSign, bytes memory signature) public {
 {
 // SynQ require_pqc compatibility block
 bool __synq_pqc_ok = true;
@@ -159,18 +159,18 @@ revert("PQC verification failed");
 paused = false;
 }
 
-function transfer(address to, uint256 amount) external returns (bool) public {
+function transfer(address to, uint256 amount) public returns (bool) {
 require(!paused, "Token transfers are paused");
 require(to != Address(0), "Transfer to zero address");
 require(balanceOf[msg.sender] >= amount, "Insufficient balance");
-balanceOf = balanceOf[msg.sender] - amount;
-balanceOf = balanceOf[to] + amount;
+balanceOf[msg.sender] = balanceOf[msg.sender] - amount;
+balanceOf[to] = balanceOf[to] + amount;
 emit Transfer((msg.sender, to, amount));
 return true;
 }
 
-// @gas_cost
-function updateGovernanceKey(bytes memory newKey, bytes messageToSign, bytes memory signature) public // @gas_cost() {
+// @gas_cost(base: 100000, mldsa_verify: 35000)
+function updateGovernanceKey(bytes memory newKey, bytes messageToSign, bytes memory signature) public {
 {
 // SynQ require_pqc compatibility block
 bool __synq_pqc_ok = true;
@@ -184,8 +184,8 @@ governanceKey = newKey;
 emit GovernanceKeyUpdated((newKey));
 }
 
-// @gas_cost
-function batchTransfer(address[] recipients, uint256[] amounts) external returns (bool) public // @gas_cost() {
+// @gas_cost(base: 50000, per_transfer: 21000)
+function batchTransfer(address[] recipients, uint256[] amounts) public returns (bool) {
 require(recipients.length == amounts.length, "Array length mismatch");
 require(recipients.length > 0, "Empty arrays");
 require(recipients.length <= 100, "Too many recipients");
@@ -196,14 +196,14 @@ totalAmount = totalAmount + amounts[i];
 require(balanceOf[msg.sender] >= totalAmount, "Insufficient balance for batch");
 for (uint256 i = 0; i < recipients.length; i++) {
 require(recipients[i] != Address(0), "Invalid recipient address");
-balanceOf = balanceOf[msg.sender] - amounts[i];
-balanceOf = balanceOf[recipients[i]] + amounts[i];
+balanceOf[msg.sender] = balanceOf[msg.sender] - amounts[i];
+balanceOf[recipients[i]] = balanceOf[recipients[i]] + amounts[i];
 emit Transfer((msg.sender, recipients[i], amounts[i]));
 }
 return true;
 }
 
-function getTokenInfo() external returns (Tuple) public {
+function getTokenInfo() public returns (Tuple) {
 return (name, symbol, decimals, totalSupply);
 }
 
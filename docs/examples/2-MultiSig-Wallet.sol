@@ -11,6 +11,14 @@ pragma solidity ^0.8.0;
 // import "@synq/pqc/SLH-DSA.sol";
 
 contract PQCMultiSigWallet {
+struct Transaction {
+address to;
+uint256 value;
+bytes data;
+bool executed;
+uint256 confirmations;
+}
+
 address[] public owners;
 uint256 public requiredSignatures;
 uint256 public nonce;
@@ -29,7 +37,7 @@ requiredSignatures = _requiredSignatures;
 nonce = 0;
 transactionCount = 0;
 for (uint256 i = 0; i < _owners.length; i++) {
-ownerKeys = _ownerKeys[i];
+ownerKeys[_owners[i]] = _ownerKeys[i];
 }
 }
 
@@ -41,7 +49,7 @@ event OwnerAdded((address owner, bytes memory key));
 event OwnerRemoved((address owner));
 event RequiredSignaturesChanged((uint256 oldRequired, uint256 newRequired));
 
-function isOwner(address addr) external returns (bool) internal {
+function isOwner(address addr) private returns (bool) {
 for (uint256 i = 0; i < owners.length; i++) {
 if (owners[i] == addr) {
 return true;
@@ -50,28 +58,22 @@ return true;
 return false;
 }
 
-function deposit() public {
+function deposit() public payable {
 emit Deposit((msg.sender, msg.value));
 }
 
-function submitTransaction(address to, uint256 value, bytes data) external returns (uint256) public {
+function submitTransaction(address to, uint256 value, bytes data) public returns (uint256) {
 require(isOwner(msg.sender), "Not an owner");
 require(to != Address(0), "Invalid recipient");
 uint256 txId = transactionCount;
 transactionCount = transactionCount + 1;
-transactions = Transaction(        {
-                    to: to,
-                    value: value,
-                    data: data,
-                    executed: false,
-                    confirmations: 0
-                });
+transactions[txId] = Transaction({to: to, value: value, data: data, executed: false, confirmations: 0});
 emit TransactionSubmitted((txId, to, value));
 return txId;
 }
 
-// @gas_cost
-function confirmTransaction(uint256 txId, bytes messageToSign, bytes memory signature) public // @gas_cost() {
+// @gas_cost(base: 75000, dilithium_verify: 35000)
+function confirmTransaction(uint256 txId, bytes messageToSign, bytes memory signature) public {
 require(isOwner(msg.sender), "Not an owner");
 require(transactions[txId].to != Address(0), "Transaction does not exist");
 require(!transactions[txId].executed, "Transaction already executed");
@@ -85,19 +87,19 @@ if (!__synq_pqc_ok) {
 revert("PQC verification failed");
 }
 }
-confirmations = true;
-transactions = transactions[txId].confirmations + 1;
+confirmations[txId][msg.sender] = true;
+transactions[txId].confirmations = transactions[txId].confirmations + 1;
 emit TransactionConfirmed((txId, msg.sender));
 if (transactions[txId].confirmations >= requiredSignatures) {
 executeTransaction(txId);
 }
 }
 
-function executeTransaction(uint256 txId) internal {
+function executeTransaction(uint256 txId) private {
 require(!transactions[txId].executed, "Transaction already executed");
 require(transactions[txId].confirmations >= requiredSignatures, "Not enough confirmations");
 Transaction tx = transactions[txId];
-tx = true;
+tx.executed = true;
 emit TransactionExecuted((txId));
 }
 
@@ -106,12 +108,12 @@ require(isOwner(msg.sender), "Not an owner");
 require(transactions[txId].to != Address(0), "Transaction does not exist");
 require(!transactions[txId].executed, "Transaction already executed");
 require(confirmations[txId][msg.sender], "Transaction not confirmed");
-confirmations = false;
-transactions = transactions[txId].confirmations - 1;
+confirmations[txId][msg.sender] = false;
+transactions[txId].confirmations = transactions[txId].confirmations - 1;
 }
 
-// @gas_cost
-function addOwner(address newOwner, bytes memory newOwnerKey, bytes messageToSign, bytes memory[] signatures) public // @gas_cost() {
+// @gas_cost(base: 100000, dilithium_verify: 35000)
+function addOwner(address newOwner, bytes memory newOwnerKey, bytes messageToSign, bytes memory[] signatures) public {
 require(!isOwner(newOwner), "Already an owner");
 require(newOwner != Address(0), "Invalid owner address");
 require(signatures.length >= requiredSignatures, "Not enough signatures");
@@ -133,12 +135,12 @@ revert("PQC verification failed");
 }
 }
 owners.push(newOwner);
-ownerKeys = newOwnerKey;
+ownerKeys[newOwner] = newOwnerKey;
 emit OwnerAdded((newOwner, newOwnerKey));
 }
 
-// @gas_cost
-function removeOwner(address ownerToRemove, bytes messageToSign, bytes memory[] signatures) public // @gas_cost() {
+// @gas_cost(base: 100000, dilithium_verify: 35000)
+function removeOwner(address ownerToRemove, bytes messageToSign, bytes memory[] signatures) public {
 require(isOwner(ownerToRemove), "Not an owner");
 require(owners.length - 1 >= requiredSignatures, "Would violate required signatures");
 require(signatures.length >= requiredSignatures, "Not enough signatures");
@@ -161,7 +163,7 @@ revert("PQC verification failed");
 }
 for (uint256 i = 0; i < owners.length; i++) {
 if (owners[i] == ownerToRemove) {
-owners = owners[owners.length - 1];
+owners[i] = owners[owners.length - 1];
 owners.pop();
 break;
 }
@@ -169,8 +171,8 @@ break;
 emit OwnerRemoved((ownerToRemove));
 }
 
-// @gas_cost
-function replaceOwner(address oldOwner, address newOwner, bytes memory newOwnerKey, bytes messageToSign, bytes memory[] signatures) public // @gas_cost() {
+// @gas_cost(base: 100000, dilithium_verify: 35000)
+function replaceOwner(address oldOwner, address newOwner, bytes memory newOwnerKey, bytes messageToSign, bytes memory[] signatures) public {
 require(isOwner(oldOwner), "Old owner not found");
 require(!isOwner(newOwner), "New owner already exists");
 require(newOwner != Address(0), "Invalid new owner");
@@ -194,8 +196,8 @@ revert("PQC verification failed");
 }
 for (uint256 i = 0; i < owners.length; i++) {
 if (owners[i] == oldOwner) {
-owners = newOwner;
-ownerKeys = newOwnerKey;
+owners[i] = newOwner;
+ownerKeys[newOwner] = newOwnerKey;
 break;
 }
 }
@@ -203,8 +205,8 @@ emit OwnerRemoved((oldOwner));
 emit OwnerAdded((newOwner, newOwnerKey));
 }
 
-// @gas_cost
-function changeRequiredSignatures(uint256 newRequired, bytes messageToSign, bytes memory[] signatures) public // @gas_cost() {
+// @gas_cost(base: 100000, dilithium_verify: 35000)
+function changeRequiredSignatures(uint256 newRequired, bytes messageToSign, bytes memory[] signatures) public {
 require(newRequired > 0, "Required must be > 0");
 require(newRequired <= owners.length, "Too many required");
 require(signatures.length >= requiredSignatures, "Not enough signatures");
@@ -230,20 +232,20 @@ requiredSignatures = newRequired;
 emit RequiredSignaturesChanged((oldRequired, newRequired));
 }
 
-function getTransaction(uint256 txId) external returns (Tuple) public {
+function getTransaction(uint256 txId) public returns (Tuple) {
 Transaction tx = transactions[txId];
 return (tx.to, tx.value, tx.data, tx.executed, tx.confirmations);
 }
 
-function isConfirmedBy(uint256 txId, address owner) external returns (bool) public {
+function isConfirmedBy(uint256 txId, address owner) public returns (bool) {
 return confirmations[txId][owner];
 }
 
-function getOwners() external returns (address[]) public {
+function getOwners() public returns (address[]) {
 return owners;
 }
 
-function getOwnerCount() external returns (uint256) public {
+function getOwnerCount() public returns (uint256) {
 return owners.length;
 }
 
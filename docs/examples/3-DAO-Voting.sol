@@ -11,6 +11,21 @@ pragma solidity ^0.8.0;
 // import "@synq/pqc/SLH-DSA.sol";
 
 contract PQCGovernanceDAO {
+struct Proposal {
+uint256 id;
+address proposer;
+string description;
+uint256 startBlock;
+uint256 endBlock;
+uint256 forVotes;
+uint256 againstVotes;
+uint256 abstainVotes;
+bool executed;
+bool canceled;
+bytes calldata;
+address target;
+}
+
 address public tokenContract;
 uint256 public proposalThreshold;
 uint256 public votingPeriod;
@@ -37,25 +52,12 @@ event ProposalCanceled((uint256 id));
 event GovernanceKeyUpdated((bytes memory newKey));
 event QuorumUpdated((uint256 oldQuorum, uint256 newQuorum));
 
-function propose(string description, address target, bytes calldata) external returns (uint256) public {
+function propose(string description, address target, bytes calldata) public returns (uint256) {
 uint256 proposalId = proposalCount;
 proposalCount = proposalCount + 1;
 uint256 startBlock = block.number;
 uint256 endBlock = startBlock + votingPeriod;
-proposals = Proposal(        {
-                    id: proposalId,
-                    proposer: msg.sender,
-                    description: description,
-                    startBlock: startBlock,
-                    endBlock: endBlock,
-                    forVotes: 0,
-                    againstVotes: 0,
-                    abstainVotes: 0,
-                    executed: false,
-                    canceled: false,
-                    calldata: calldata,
-                    target: target
-                });
+proposals[proposalId] = Proposal({id: proposalId, proposer: msg.sender, description: description, startBlock: startBlock, endBlock: endBlock, forVotes: 0, againstVotes: 0, abstainVotes: 0, executed: false, canceled: false, calldata: calldata, target: target});
 emit ProposalCreated((proposalId, msg.sender, description, startBlock, endBlock));
 return proposalId;
 }
@@ -69,22 +71,22 @@ require(block.number >= proposals[proposalId].startBlock, "Voting not started");
 require(block.number <= proposals[proposalId].endBlock, "Voting period ended");
 require(!hasVoted[proposalId][msg.sender], "Already voted");
 uint256 weight = 1;
-hasVoted = true;
-votes = support;
+hasVoted[proposalId][msg.sender] = true;
+votes[proposalId][msg.sender] = support;
 if (support == 1) {
-proposals = proposals[proposalId].forVotes + weight;
+proposals[proposalId].forVotes = proposals[proposalId].forVotes + weight;
 } else {
 if (support == 0) {
-proposals = proposals[proposalId].againstVotes + weight;
+proposals[proposalId].againstVotes = proposals[proposalId].againstVotes + weight;
 } else {
-proposals = proposals[proposalId].abstainVotes + weight;
+proposals[proposalId].abstainVotes = proposals[proposalId].abstainVotes + weight;
 }
 }
 emit VoteCast((proposalId, msg.sender, support, weight));
 }
 
-// @gas_cost
-function castVoteWithSignature(uint256 proposalId, uint8 support, address voter, bytes memory voterKey, bytes messageToSign, bytes memory signature) public // @gas_cost() {
+// @gas_cost(base: 75000, dilithium_verify: 35000)
+function castVoteWithSignature(uint256 proposalId, uint8 support, address voter, bytes memory voterKey, bytes messageToSign, bytes memory signature) public {
 require(support <= 2, "Invalid vote type");
 require(proposals[proposalId].id == proposalId, "Proposal does not exist");
 require(!proposals[proposalId].canceled, "Proposal is canceled");
@@ -102,22 +104,22 @@ revert("PQC verification failed");
 }
 }
 uint256 weight = 1;
-hasVoted = true;
-votes = support;
+hasVoted[proposalId][voter] = true;
+votes[proposalId][voter] = support;
 if (support == 1) {
-proposals = proposals[proposalId].forVotes + weight;
+proposals[proposalId].forVotes = proposals[proposalId].forVotes + weight;
 } else {
 if (support == 0) {
-proposals = proposals[proposalId].againstVotes + weight;
+proposals[proposalId].againstVotes = proposals[proposalId].againstVotes + weight;
 } else {
-proposals = proposals[proposalId].abstainVotes + weight;
+proposals[proposalId].abstainVotes = proposals[proposalId].abstainVotes + weight;
 }
 }
 emit VoteCast((proposalId, voter, support, weight));
 }
 
-// @gas_cost
-function executeProposal(uint256 proposalId, bytes messageToSign, bytes memory signature) public // @gas_cost() {
+// @gas_cost(base: 100000, dilithium_verify: 35000)
+function executeProposal(uint256 proposalId, bytes messageToSign, bytes memory signature) public {
 Proposal proposal = proposals[proposalId];
 require(proposal.id == proposalId, "Proposal does not exist");
 require(!proposal.executed, "Proposal already executed");
@@ -135,7 +137,7 @@ if (!__synq_pqc_ok) {
 revert("PQC verification failed");
 }
 }
-proposal = true;
+proposal.executed = true;
 emit ProposalExecuted((proposalId));
 }
 
@@ -145,12 +147,12 @@ require(proposal.id == proposalId, "Proposal does not exist");
 require(!proposal.executed, "Proposal already executed");
 require(!proposal.canceled, "Proposal already canceled");
 require(msg.sender == proposal.proposer || msg.sender == Address(0), "Not authorized to cancel");
-proposal = true;
+proposal.canceled = true;
 emit ProposalCanceled((proposalId));
 }
 
-// @gas_cost
-function updateGovernanceKey(bytes memory newKey, bytes messageToSign, bytes memory signature) public // @gas_cost() {
+// @gas_cost(base: 100000, dilithium_verify: 35000)
+function updateGovernanceKey(bytes memory newKey, bytes messageToSign, bytes memory signature) public {
 {
 // SynQ require_pqc compatibility block
 bool __synq_pqc_ok = true;
@@ -164,8 +166,8 @@ governanceKey = newKey;
 emit GovernanceKeyUpdated((newKey));
 }
 
-// @gas_cost
-function updateQuorum(uint256 newQuorum, bytes messageToSign, bytes memory signature) public // @gas_cost() {
+// @gas_cost(base: 100000, dilithium_verify: 35000)
+function updateQuorum(uint256 newQuorum, bytes messageToSign, bytes memory signature) public {
 {
 // SynQ require_pqc compatibility block
 bool __synq_pqc_ok = true;
@@ -180,7 +182,7 @@ quorum = newQuorum;
 emit QuorumUpdated((oldQuorum, newQuorum));
 }
 
-function getProposal(uint256 proposalId) external returns (Tuple) public {
+function getProposal(uint256 proposalId) public returns (Tuple) {
 Proposal proposal = proposals[proposalId];
 return         (
                     proposal.id,
@@ -196,15 +198,15 @@ return         (
                 );
 }
 
-function hasVotedOn(uint256 proposalId, address voter) external returns (bool) public {
+function hasVotedOn(uint256 proposalId, address voter) public returns (bool) {
 return hasVoted[proposalId][voter];
 }
 
-function getVote(uint256 proposalId, address voter) external returns (uint8) public {
+function getVote(uint256 proposalId, address voter) public returns (uint8) {
 return votes[proposalId][voter];
 }
 
-function getProposalState(uint256 proposalId) external returns (string) public {
+function getProposalState(uint256 proposalId) public returns (string) {
 Proposal proposal = proposals[proposalId];
 if (proposal.canceled) {
 return "Canceled";
@@ -221,7 +223,7 @@ return "Active";
 if (proposal.forVotes <= proposal.againstVotes) {
 return "Defeated";
 }
-if ((proposal.forVotes + proposal.againstVotes + proposal.abstainVotes) < quorum) {
+if (proposal.forVotes + proposal.againstVotes + proposal.abstainVotes < quorum) {
 return "QuorumNotMet";
 }
 return "Succeeded";
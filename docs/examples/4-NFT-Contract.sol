@@ -43,16 +43,16 @@ event Mint((address to, uint256 tokenId, string tokenURI));
 event Burn((uint256 tokenId));
 event RoyaltyUpdated((uint256 tokenId, address recipient, uint256 percentage));
 
-function totalSupply() external returns (uint256) public {
+function totalSupply() public returns (uint256) {
 return totalSupply;
 }
 
-function balanceOf(address owner) external returns (uint256) public {
+function balanceOf(address owner) public returns (uint256) {
 require(owner != Address(0), "Balance query for zero address");
 return balanceOf[owner];
 }
 
-function ownerOf(uint256 tokenId) external returns (address) public {
+function ownerOf(uint256 tokenId) public returns (address) {
 address owner = ownerOf[tokenId];
 require(owner != Address(0), "Token does not exist");
 return owner;
@@ -62,37 +62,35 @@ function approve(address to, uint256 tokenId) public {
 address owner = ownerOf[tokenId];
 require(owner == msg.sender || operatorApprovals[owner][msg.sender], "Not authorized");
 require(to != owner, "Approval to current owner");
-tokenApprovals = to;
+tokenApprovals[tokenId] = to;
 emit Approval((owner, to, tokenId));
 }
 
-function getApproved(uint256 tokenId) external returns (address) public {
+function getApproved(uint256 tokenId) public returns (address) {
 require(ownerOf[tokenId] != Address(0), "Token does not exist");
 return tokenApprovals[tokenId];
 }
 
 function setApprovalForAll(address operator, bool approved) public {
 require(operator != msg.sender, "Approve to caller");
-operatorApprovals = approved;
+operatorApprovals[msg.sender][operator] = approved;
 emit ApprovalForAll((msg.sender, operator, approved));
 }
 
-function isApprovedForAll(address owner, address operator) external returns (bool) public {
+function isApprovedForAll(address owner, address operator) public returns (bool) {
 return operatorApprovals[owner][operator];
 }
 
 function transferFrom(address from, address to, uint256 tokenId) public {
 require(ownerOf[tokenId] == from, "Transfer from incorrect owner");
 require(to != Address(0), "Transfer to zero address");
-require(        msg.sender == from ||
-                    msg.sender == tokenApprovals[tokenId] ||
-                    operatorApprovals[from][msg.sender], "Transfer not authorized");
+require(msg.sender == from || msg.sender == tokenApprovals[tokenId] || operatorApprovals[from][msg.sender], "Transfer not authorized");
 if (tokenApprovals[tokenId] != Address(0)) {
-tokenApprovals = Address(0);
+tokenApprovals[tokenId] = Address(0);
 }
-balanceOf = balanceOf[from] - 1;
-balanceOf = balanceOf[to] + 1;
-ownerOf = to;
+balanceOf[from] = balanceOf[from] - 1;
+balanceOf[to] = balanceOf[to] + 1;
+ownerOf[tokenId] = to;
 emit Transfer((from, to, tokenId));
 }
 
@@ -100,8 +98,8 @@ function safeTransferFrom(address from, address to, uint256 tokenId, bytes data)
 transferFrom(from, to, tokenId);
 }
 
-// @gas_cost
-function mint(address to, string _tokenURI, string _tokenName, string _tokenDescription, bytes messageToSign, bytes memory signature) external returns (uint256) public // @gas_cost() {
+// @gas_cost(base: 100000, mldsa_verify: 35000)
+function mint(address to, string _tokenURI, string _tokenName, string _tokenDescription, bytes messageToSign, bytes memory signature) public returns (uint256) {
 require(to != Address(0), "Mint to zero address");
 require(totalSupply < maxSupply, "Max supply reached");
 {
@@ -115,34 +113,34 @@ revert("PQC verification failed");
 }
 uint256 tokenId = totalSupply;
 totalSupply = totalSupply + 1;
-ownerOf = to;
-balanceOf = balanceOf[to] + 1;
-tokenURI = _tokenURI;
-tokenName = _tokenName;
-tokenDescription = _tokenDescription;
+ownerOf[tokenId] = to;
+balanceOf[to] = balanceOf[to] + 1;
+tokenURI[tokenId] = _tokenURI;
+tokenName[tokenId] = _tokenName;
+tokenDescription[tokenId] = _tokenDescription;
 emit Mint((to, tokenId, _tokenURI));
 emit Transfer((Address(0), to, tokenId));
 return tokenId;
 }
 
-function publicMint(address to, string _tokenURI, string _tokenName, string _tokenDescription) external returns (uint256) public {
+function publicMint(address to, string _tokenURI, string _tokenName, string _tokenDescription) public returns (uint256) {
 require(publicMintingEnabled, "Public minting disabled");
 require(to != Address(0), "Mint to zero address");
 require(totalSupply < maxSupply, "Max supply reached");
 uint256 tokenId = totalSupply;
 totalSupply = totalSupply + 1;
-ownerOf = to;
-balanceOf = balanceOf[to] + 1;
-tokenURI = _tokenURI;
-tokenName = _tokenName;
-tokenDescription = _tokenDescription;
+ownerOf[tokenId] = to;
+balanceOf[to] = balanceOf[to] + 1;
+tokenURI[tokenId] = _tokenURI;
+tokenName[tokenId] = _tokenName;
+tokenDescription[tokenId] = _tokenDescription;
 emit Mint((to, tokenId, _tokenURI));
 emit Transfer((Address(0), to, tokenId));
 return tokenId;
 }
 
-// @gas_cost
-function batchMint(address[] recipients, string[] tokenURIs, string[] tokenNames, string[] tokenDescriptions, bytes messageToSign, bytes memory signature) public // @gas_cost() {
+// @gas_cost(base: 150000, mldsa_verify: 35000, per_token: 50000)
+function batchMint(address[] recipients, string[] tokenURIs, string[] tokenNames, string[] tokenDescriptions, bytes messageToSign, bytes memory signature) public {
 require(recipients.length == tokenURIs.length, "Array length mismatch");
 require(recipients.length == tokenNames.length, "Array length mismatch");
 require(recipients.length == tokenDescriptions.length, "Array length mismatch");
@@ -161,11 +159,11 @@ for (uint256 i = 0; i < recipients.length; i++) {
 require(recipients[i] != Address(0), "Invalid recipient");
 uint256 tokenId = totalSupply;
 totalSupply = totalSupply + 1;
-ownerOf = recipients[i];
-balanceOf = balanceOf[recipients[i]] + 1;
-tokenURI = tokenURIs[i];
-tokenName = tokenNames[i];
-tokenDescription = tokenDescriptions[i];
+ownerOf[tokenId] = recipients[i];
+balanceOf[recipients[i]] = balanceOf[recipients[i]] + 1;
+tokenURI[tokenId] = tokenURIs[i];
+tokenName[tokenId] = tokenNames[i];
+tokenDescription[tokenId] = tokenDescriptions[i];
 emit Mint((recipients[i], tokenId, tokenURIs[i]));
 emit Transfer((Address(0), recipients[i], tokenId));
 }
@@ -175,10 +173,10 @@ function burn(uint256 tokenId) public {
 address owner = ownerOf[tokenId];
 require(owner == msg.sender || operatorApprovals[owner][msg.sender], "Not authorized");
 if (tokenApprovals[tokenId] != Address(0)) {
-tokenApprovals = Address(0);
+tokenApprovals[tokenId] = Address(0);
 }
-balanceOf = balanceOf[owner] - 1;
-ownerOf = Address(0);
+balanceOf[owner] = balanceOf[owner] - 1;
+ownerOf[tokenId] = Address(0);
 totalSupply = totalSupply - 1;
 emit Transfer((owner, Address(0), tokenId));
 emit Burn((tokenId));
@@ -186,22 +184,22 @@ emit Burn((tokenId));
 
 function setTokenURI(uint256 tokenId, string _tokenURI) public {
 require(ownerOf[tokenId] == msg.sender, "Not token owner");
-tokenURI = _tokenURI;
+tokenURI[tokenId] = _tokenURI;
 }
 
 function setRoyalty(uint256 tokenId, address recipient, uint256 percentage) public {
 require(ownerOf[tokenId] == msg.sender, "Not token owner");
 require(percentage <= 10000, "Royalty too high");
-royaltyRecipient = recipient;
-royaltyPercentage = percentage;
+royaltyRecipient[tokenId] = recipient;
+royaltyPercentage[tokenId] = percentage;
 emit RoyaltyUpdated((tokenId, recipient, percentage));
 }
 
-function getRoyalty(uint256 tokenId) external returns (Tuple) public {
+function getRoyalty(uint256 tokenId) public returns (Tuple) {
 return (royaltyRecipient[tokenId], royaltyPercentage[tokenId]);
 }
 
-function calculateRoyalty(uint256 tokenId, uint256 salePrice) external returns (uint256) public {
+function calculateRoyalty(uint256 tokenId, uint256 salePrice) public returns (uint256) {
 uint256 percentage = royaltyPercentage[tokenId];
 if (percentage == 0) {
 return 0;
@@ -209,8 +207,8 @@ return 0;
 return salePrice * percentage / 10000;
 }
 
-// @gas_cost
-function setPublicMinting(bool enabled, bytes messageToSign, bytes memory signature) public // @gas_cost() {
+// @gas_cost(base: 75000, mldsa_verify: 35000)
+function setPublicMinting(bool enabled, bytes messageToSign, bytes memory signature) public {
 {
 // SynQ require_pqc compatibility block
 bool __synq_pqc_ok = true;
@@ -223,8 +221,8 @@ revert("PQC verification failed");
 publicMintingEnabled = enabled;
 }
 
-// @gas_cost
-function updateMintingKey(bytes memory newKey, bytes messageToSign, bytes memory signature) public // @gas_cost() {
+// @gas_cost(base: 100000, mldsa_verify: 35000)
+function updateMintingKey(bytes memory newKey, bytes messageToSign, bytes memory signature) public {
 {
 // SynQ require_pqc compatibility block
 bool __synq_pqc_ok = true;
@@ -237,12 +235,12 @@ revert("PQC verification failed");
 mintingKey = newKey;
 }
 
-function getTokenMetadata(uint256 tokenId) external returns (Tuple) public {
+function getTokenMetadata(uint256 tokenId) public returns (Tuple) {
 require(ownerOf[tokenId] != Address(0), "Token does not exist");
 return (tokenName[tokenId], tokenDescription[tokenId], tokenURI[tokenId]);
 }
 
-function tokensOfOwner(address owner) external returns (uint256[]) public {
+function tokensOfOwner(address owner) public returns (uint256[]) {
 return [];
 }
 
@@ -11,6 +11,19 @@ pragma solidity ^0.8.0;
 // import "@synq/pqc/SLH-DSA.sol";
 
 contract PQCEscrow {
+struct Escrow {
+uint256 id;
+address buyer;
+address seller;
+uint256 amount;
+string description;
+uint256 createdAt;
+uint256 expiresAt;
+EscrowStatus status;
+bytes releaseData;
+bytes memory releaseKey;
+}
+
 mapping(uint256 => Escrow) public escrows;
 mapping(address => uint256[]) public buyerEscrows;
 mapping(address => uint256[]) public sellerEscrows;
@@ -31,7 +44,7 @@ event EscrowDisputed((uint256 id, address raisedBy));
 event EscrowExpired((uint256 id));
 event DisputeResolved((uint256 id, bool favorBuyer));
 
-function createEscrow(address seller, string description, uint256 duration, bytes memory releaseKey) external returns (uint256) public {
+function createEscrow(address seller, string description, uint256 duration, bytes memory releaseKey) public payable returns (uint256) {
 require(seller != Address(0), "Invalid seller");
 require(seller != msg.sender, "Cannot escrow to self");
 require(duration > 0, "Invalid duration");
@@ -39,26 +52,15 @@ uint256 id = escrowCount;
 escrowCount = escrowCount + 1;
 uint256 createdAt = block.number;
 uint256 expiresAt = createdAt + duration;
-escrows = Escrow(        {
-                    id: id,
-                    buyer: msg.sender,
-                    seller: seller,
-                    amount: msg.value, // In real implementation, would transfer tokens
-                    description: description,
-                    createdAt: createdAt,
-                    expiresAt: expiresAt,
-                    status: EscrowStatus.Pending,
-                    releaseData: Bytes(""),
-                    releaseKey: releaseKey
-                });
+escrows[id] = Escrow({id: id, buyer: msg.sender, seller: seller, amount: msg.value, description: description, createdAt: createdAt, expiresAt: expiresAt, status: EscrowStatus.Pending, releaseData: hex"", releaseKey: releaseKey});
 buyerEscrows[msg.sender].push(id);
 sellerEscrows[seller].push(id);
 emit EscrowCreated((id, msg.sender, seller, msg.value));
 return id;
 }
 
-// @gas_cost
-function releaseEscrow(uint256 escrowId, bytes messageToSign, bytes memory signature) public // @gas_cost() {
+// @gas_cost(base: 100000, dilithium_verify: 35000)
+function releaseEscrow(uint256 escrowId, bytes messageToSign, bytes memory signature) public {
 Escrow escrow = escrows[escrowId];
 require(escrow.id == escrowId, "Escrow does not exist");
 require(escrow.status == EscrowStatus.Pending, "Escrow not pending");
@@ -74,7 +76,7 @@ if (!__synq_pqc_ok) {
 revert("PQC verification failed");
 }
 }
-escrow = EscrowStatus.Released;
+escrow.status = EscrowStatus.Released;
 emit EscrowReleased((escrowId, escrow.seller));
 }
 
@@ -85,7 +87,7 @@ require(escrow.status == EscrowStatus.Pending, "Escrow not pending");
 require(msg.sender == escrow.buyer, "Only buyer can refund");
 require(block.number > escrow.expiresAt, "Escrow not expired");
 require(!disputeResolved[escrowId], "Escrow under dispute");
-escrow = EscrowStatus.Refunded;
+escrow.status = EscrowStatus.Refunded;
 emit EscrowRefunded((escrowId, escrow.buyer));
 }
 
@@ -95,13 +97,13 @@ require(escrow.id == escrowId, "Escrow does not exist");
 require(escrow.status == EscrowStatus.Pending, "Escrow not pending");
 require(msg.sender == escrow.buyer || msg.sender == escrow.seller, "Not party to escrow");
 require(!disputeResolved[escrowId], "Dispute already resolved");
-escrow = EscrowStatus.Disputed;
-disputeRaisedBy = msg.sender;
+escrow.status = EscrowStatus.Disputed;
+disputeRaisedBy[escrowId] = msg.sender;
 emit EscrowDisputed((escrowId, msg.sender));
 }
 
-// @gas_cost
-function resolveDispute(uint256 escrowId, bool favorBuyer, bytes messageToSign, bytes memory signature) public // @gas_cost() {
+// @gas_cost(base: 100000, dilithium_verify: 35000)
+function resolveDispute(uint256 escrowId, bool favorBuyer, bytes messageToSign, bytes memory signature) public {
 Escrow escrow = escrows[escrowId];
 require(escrow.id == escrowId, "Escrow does not exist");
 require(escrow.status == EscrowStatus.Disputed, "Escrow not disputed");
@@ -115,12 +117,12 @@ if (!__synq_pqc_ok) {
 revert("PQC verification failed");
 }
 }
-disputeResolved = true;
+disputeResolved[escrowId] = true;
 if (favorBuyer) {
-escrow = EscrowStatus.Refunded;
+escrow.status = EscrowStatus.Refunded;
 emit EscrowRefunded((escrowId, escrow.buyer));
 } else {
-escrow = EscrowStatus.Released;
+escrow.status = EscrowStatus.Released;
 emit EscrowReleased((escrowId, escrow.seller));
 }
 emit DisputeResolved((escrowId, favorBuyer));
@@ -131,11 +133,11 @@ Escrow escrow = escrows[escrowId];
 require(escrow.id == escrowId, "Escrow does not exist");
 require(escrow.status == EscrowStatus.Pending, "Escrow not pending");
 require(block.number > escrow.expiresAt, "Escrow not expired");
-escrow = EscrowStatus.Expired;
+escrow.status = EscrowStatus.Expired;
 emit EscrowExpired((escrowId));
 }
 
-function getEscrow(uint256 escrowId) external returns (Tuple) public {
+function getEscrow(uint256 escrowId) public returns (Tuple) {
 Escrow escrow = escrows[escrowId];
 return         (
                     escrow.id,
@@ -149,16 +151,16 @@ return         (
                 );
 }
 
-function getBuyerEscrows(address buyer) external returns (uint256[]) public {
+function getBuyerEscrows(address buyer) public returns (uint256[]) {
 return buyerEscrows[buyer];
 }
 
-function getSellerEscrows(address seller) external returns (uint256[]) public {
+function getSellerEscrows(address seller) public returns (uint256[]) {
 return sellerEscrows[seller];
 }
 
-// @gas_cost
-function updateArbitratorKey(bytes memory newKey, bytes messageToSign, bytes memory signature) public // @gas_cost() {
+// @gas_cost(base: 100000, dilithium_verify: 35000)
+function updateArbitratorKey(bytes memory newKey, bytes messageToSign, bytes memory signature) public {
 {
 // SynQ require_pqc compatibility block
 bool __synq_pqc_ok = true;
@@ -171,7 +173,7 @@ revert("PQC verification failed");
 arbitratorKey = newKey;
 }
 
-function timeRemaining(uint256 escrowId) external returns (uint256) public {
+function timeRemaining(uint256 escrowId) public returns (uint256) {
 Escrow escrow = escrows[escrowId];
 if (block.number >= escrow.expiresAt) {
 return 0;
@@ -179,7 +181,7 @@ return 0;
 return escrow.expiresAt - block.number;
 }
 
-function isExpired(uint256 escrowId) external returns (bool) public {
+function isExpired(uint256 escrowId) public returns (bool) {
 Escrow escrow = escrows[escrowId];
 return block.number > escrow.expiresAt;
 }
@@ -11,6 +11,14 @@ pragma solidity ^0.8.0;
 // import "@synq/pqc/SLH-DSA.sol";
 
 contract PQCStaking {
+struct Staker {
+uint256 stakedAmount;
+uint256 rewardDebt;
+uint256 lastStakeBlock;
+uint256 totalEarned;
+bool active;
+}
+
 address public stakingToken;
 address public rewardToken;
 uint256 public rewardRate;
@@ -44,33 +52,33 @@ event RewardClaimed((address staker, uint256 amount));
 event RewardRateUpdated((uint256 oldRate, uint256 newRate));
 event WithdrawalKeyUpdated((bytes memory newKey));
 
-function updateReward() internal {
+function updateReward() private {
 if (totalStaked == 0) {
 lastUpdateBlock = block.number;
 return;
 }
 uint256 blocksSinceUpdate = block.number - lastUpdateBlock;
-uint256 newRewards = (blocksSinceUpdate * rewardRate * 1e18) / totalStaked;
+uint256 newRewards = blocksSinceUpdate * rewardRate * 1000000000000000000 / totalStaked;
 rewardPerTokenStored = rewardPerTokenStored + newRewards;
 lastUpdateBlock = block.number;
 }
 
-function rewardPerToken() external returns (uint256) public {
+function rewardPerToken() public returns (uint256) {
 if (totalStaked == 0) {
 return rewardPerTokenStored;
 }
 uint256 blocksSinceUpdate = block.number - lastUpdateBlock;
-uint256 newRewards = (blocksSinceUpdate * rewardRate * 1e18) / totalStaked;
+uint256 newRewards = blocksSinceUpdate * rewardRate * 1000000000000000000 / totalStaked;
 return rewardPerTokenStored + newRewards;
 }
 
-function earned(address staker) external returns (uint256) public {
+function earned(address staker) public returns (uint256) {
 Staker s = stakers[staker];
 if (s.stakedAmount == 0) {
 return 0;
 }
 uint256 currentRewardPerToken = rewardPerToken();
-uint256 reward = (s.stakedAmount * (currentRewardPerToken - s.rewardDebt)) / 1e18;
+uint256 reward = s.stakedAmount * currentRewardPerToken - s.rewardDebt / 1000000000000000000;
 return reward;
 }
 
@@ -83,19 +91,19 @@ Staker s = stakers[msg.sender];
 if (s.stakedAmount > 0) {
 uint256 pendingReward = earned(msg.sender);
 if (pendingReward > 0) {
-s = s.totalEarned + pendingReward;
+s.totalEarned = s.totalEarned + pendingReward;
 }
 }
 if (!s.active) {
-s = true;
+s.active = true;
 stakerList.push(msg.sender);
 }
-s = s.stakedAmount + amount;
-s = (s.stakedAmount * rewardPerTokenStored) / 1e18;
-s = block.number;
+s.stakedAmount = s.stakedAmount + amount;
+s.rewardDebt = s.stakedAmount * rewardPerTokenStored / 1000000000000000000;
+s.lastStakeBlock = block.number;
 uint256 newLockUntil = block.number + lockPeriod;
 if (lockUntil[msg.sender] < newLockUntil) {
-lockUntil = newLockUntil;
+lockUntil[msg.sender] = newLockUntil;
 }
 totalStaked = totalStaked + amount;
 emit Staked((msg.sender, amount, lockUntil[msg.sender]));
@@ -109,14 +117,14 @@ require(block.number >= lockUntil[msg.sender], "Stake still locked");
 updateReward();
 uint256 pendingReward = earned(msg.sender);
 if (pendingReward > 0) {
-s = s.totalEarned + pendingReward;
+s.totalEarned = s.totalEarned + pendingReward;
 emit RewardClaimed((msg.sender, pendingReward));
 }
-s = s.stakedAmount - amount;
-s = (s.stakedAmount * rewardPerTokenStored) / 1e18;
+s.stakedAmount = s.stakedAmount - amount;
+s.rewardDebt = s.stakedAmount * rewardPerTokenStored / 1000000000000000000;
 totalStaked = totalStaked - amount;
 if (s.stakedAmount == 0) {
-s = false;
+s.active = false;
 }
 emit Unstaked((msg.sender, amount));
 }
@@ -127,13 +135,13 @@ require(s.stakedAmount > 0, "No staked amount");
 updateReward();
 uint256 pendingReward = earned(msg.sender);
 require(pendingReward > 0, "No rewards to claim");
-s = s.totalEarned + pendingReward;
-s = (s.stakedAmount * rewardPerTokenStored) / 1e18;
+s.totalEarned = s.totalEarned + pendingReward;
+s.rewardDebt = s.stakedAmount * rewardPerTokenStored / 1000000000000000000;
 emit RewardClaimed((msg.sender, pendingReward));
 }
 
-// @gas_cost
-function emergencyWithdraw(uint256 amount, bytes messageToSign, bytes memory signature) public // @gas_cost() {
+// @gas_cost(base: 100000, dilithium_verify: 35000)
+function emergencyWithdraw(uint256 amount, bytes messageToSign, bytes memory signature) public {
 require(!withdrawalsEnabled, "Normal withdrawals enabled");
 Staker s = stakers[msg.sender];
 require(s.stakedAmount >= amount, "Insufficient staked amount");
@@ -146,16 +154,16 @@ if (!__synq_pqc_ok) {
 revert("PQC verification failed");
 }
 }
-s = s.stakedAmount - amount;
+s.stakedAmount = s.stakedAmount - amount;
 totalStaked = totalStaked - amount;
 if (s.stakedAmount == 0) {
-s = false;
+s.active = false;
 }
 emit Unstaked((msg.sender, amount));
 }
 
-// @gas_cost
-function updateRewardRate(uint256 newRate, bytes messageToSign, bytes memory signature) public // @gas_cost() {
+// @gas_cost(base: 75000, dilithium_verify: 35000)
+function updateRewardRate(uint256 newRate, bytes messageToSign, bytes memory signature) public {
 {
 // SynQ require_pqc compatibility block
 bool __synq_pqc_ok = true;
@@ -171,8 +179,8 @@ rewardRate = newRate;
 emit RewardRateUpdated((oldRate, newRate));
 }
 
-// @gas_cost
-function updateWithdrawalKey(bytes memory newKey, bytes messageToSign, bytes memory signature) public // @gas_cost() {
+// @gas_cost(base: 100000, dilithium_verify: 35000)
+function updateWithdrawalKey(bytes memory newKey, bytes messageToSign, bytes memory signature) public {
 {
 // SynQ require_pqc compatibility block
 bool __synq_pqc_ok = true;
@@ -186,8 +194,8 @@ withdrawalKey = newKey;
 emit WithdrawalKeyUpdated((newKey));
 }
 
-// @gas_cost
-function setWithdrawalsEnabled(bool enabled, bytes messageToSign, bytes memory signature) public // @gas_cost() {
+// @gas_cost(base: 50000, dilithium_verify: 35000)
+function setWithdrawalsEnabled(bool enabled, bytes messageToSign, bytes memory signature) public {
 {
 // SynQ require_pqc compatibility block
 bool __synq_pqc_ok = true;
@@ -200,7 +208,7 @@ revert("PQC verification failed");
 withdrawalsEnabled = enabled;
 }
 
-function getStakerInfo(address staker) external returns (Tuple) public {
+function getStakerInfo(address staker) public returns (Tuple) {
 Staker s = stakers[staker];
 return         (
                     s.stakedAmount,
@@ -212,15 +220,15 @@ return         (
                 );
 }
 
-function getStakerCount() external returns (uint256) public {
+function getStakerCount() public returns (uint256) {
 return stakerList.length;
 }
 
-function isLocked(address staker) external returns (bool) public {
+function isLocked(address staker) public returns (bool) {
 return block.number < lockUntil[staker];
 }
 
-function timeUntilUnlock(address staker) external returns (uint256) public {
+function timeUntilUnlock(address staker) public returns (uint256) {
 if (block.number >= lockUntil[staker]) {
 return 0;
 }
@@ -233,9 +241,9 @@ require(s.stakedAmount > 0, "No staked amount");
 updateReward();
 uint256 pendingReward = earned(msg.sender);
 require(pendingReward > 0, "No rewards to compound");
-s = s.totalEarned + pendingReward;
-s = ((s.stakedAmount + pendingReward) * rewardPerTokenStored) / 1e18;
-s = s.stakedAmount + pendingReward;
+s.totalEarned = s.totalEarned + pendingReward;
+s.rewardDebt = s.stakedAmount + pendingReward * rewardPerTokenStored / 1000000000000000000;
+s.stakedAmount = s.stakedAmount + pendingReward;
 totalStaked = totalStaked + pendingReward;
 emit Staked((msg.sender, pendingReward, lockUntil[msg.sender]));
 }
@@ -0,0 +1,626 @@
+use crate::opcode::VMError;
+use crate::state::hex;
+use crate::vm::Value;
+use primitive_types::U256;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::ops::RangeInclusive;
+
+// The SynQ ABI: how call arguments, constructor arguments and return values are laid out as
+// bytes. Every value starts with a 32-byte head word in its enclosing tuple:
+//
+// - integers are big-endian and right-aligned (signed ones sign-extended), bools are 0 or 1,
+//   addresses fill the low 20 bytes and `BytesN` values are left-aligned;
+// - dynamic values (byte strings, PQC keys and signatures, `T[]`, and arrays or tuples holding
+//   a dynamic value) put in their head the offset of their tail from the start of the enclosing
+//   heads. A byte string's tail is a length word followed by the bytes, zero-padded to a whole
+//   word; a `T[]` tail is a length word followed by the elements encoded as a tuple;
+// - static arrays and tuples of static values are encoded inline, one head after another.
+//
+// Calldata is a 4-byte selector followed by the encoded arguments, whose offsets count from
+// just after the selector.
+
+/// Size of one head word.
+pub const WORD: usize = 32;
+
+/// Post-quantum key and signature types; each is a byte string whose length depends on the
+/// algorithm the VM runs for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PqcType {
+    MLDSAPublicKey,
+    MLDSAKeyPair,
+    MLDSASignature,
+    FNDSAPublicKey,
+    FNDSAKeyPair,
+    FNDSASignature,
+    MLKEMPublicKey,
+    MLKEMKeyPair,
+    MLKEMCiphertext,
+    SLHDSAPublicKey,
+    SLHDSAKeyPair,
+    SLHDSASignature,
+}
+
+impl PqcType {
+    pub const ALL: [PqcType; 12] = [
+        PqcType::MLDSAPublicKey,
+        PqcType::MLDSAKeyPair,
+        PqcType::MLDSASignature,
+        PqcType::FNDSAPublicKey,
+        PqcType::FNDSAKeyPair,
+        PqcType::FNDSASignature,
+        PqcType::MLKEMPublicKey,
+        PqcType::MLKEMKeyPair,
+        PqcType::MLKEMCiphertext,
+        PqcType::SLHDSAPublicKey,
+        PqcType::SLHDSAKeyPair,
+        PqcType::SLHDSASignature,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PqcType::MLDSAPublicKey => "MLDSAPublicKey",
+            PqcType::MLDSAKeyPair => "MLDSAKeyPair",
+            PqcType::MLDSASignature => "MLDSASignature",
+            PqcType::FNDSAPublicKey => "FNDSAPublicKey",
+            PqcType::FNDSAKeyPair => "FNDSAKeyPair",
+            PqcType::FNDSASignature => "FNDSASignature",
+            PqcType::MLKEMPublicKey => "MLKEMPublicKey",
+            PqcType::MLKEMKeyPair => "MLKEMKeyPair",
+            PqcType::MLKEMCiphertext => "MLKEMCiphertext",
+            PqcType::SLHDSAPublicKey => "SLHDSAPublicKey",
+            PqcType::SLHDSAKeyPair => "SLHDSAKeyPair",
+            PqcType::SLHDSASignature => "SLHDSASignature",
+        }
+    }
+
+    /// Byte lengths a value may have, for the parameter sets the VM verifies with: ML-DSA-65,
+    /// FN-DSA-512 (whose signatures vary in length) and ML-KEM-768. Key pairs, and SLH-DSA,
+    /// which no build enables yet, are not checked.
+    pub fn lengths(self) -> Option<RangeInclusive<usize>> {
+        match self {
+            PqcType::MLDSAPublicKey => Some(1952..=1952),
+            PqcType::MLDSASignature => Some(3309..=3309),
+            PqcType::FNDSAPublicKey => Some(897..=897),
+            PqcType::FNDSASignature => Some(1..=752),
+            PqcType::MLKEMPublicKey => Some(1184..=1184),
+            PqcType::MLKEMCiphertext => Some(1088..=1088),
+            PqcType::MLDSAKeyPair
+            | PqcType::FNDSAKeyPair
+            | PqcType::MLKEMKeyPair
+            | PqcType::SLHDSAPublicKey
+            | PqcType::SLHDSAKeyPair
+            | PqcType::SLHDSASignature => None,
+        }
+    }
+
+    fn check_length(self, len: usize) -> Result<(), VMError> {
+        match self.lengths() {
+            Some(lengths) if !lengths.contains(&len) => Err(abi_error(format!(
+                "{} must be {} bytes but got {}",
+                self.name(),
+                if lengths.start() == lengths.end() {
+                    lengths.start().to_string()
+                } else {
+                    format!("{} to {}", lengths.start(), lengths.end())
+                },
+                len
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AbiType {
+    Address,
+    Bool,
+    /// `UInt8` through `UInt256`, by bit width.
+    UInt(u16),
+    /// `Int8` through `Int256`, by bit width.
+    Int(u16),
+    /// `Bytes1` through `Bytes32`.
+    FixedBytes(u8),
+    Bytes,
+    String,
+    Pqc(PqcType),
+    /// `T[N]` with a length, `T[]` without.
+    Array(Box<AbiType>, Option<usize>),
+    /// A struct's fields, in layout order.
+    Tuple(Vec<AbiType>),
+}
+
+impl AbiType {
+    /// Parses a type name as it appears in signatures and JSON ABIs, e.g. `UInt256`,
+    /// `Bytes32`, `MLDSAPublicKey` or `Address[3][]`. Tuples have no name of their own and are
+    /// built from their components.
+    pub fn parse(name: &str) -> Result<AbiType, VMError> {
+        if let Some(element) = name.strip_suffix("[]") {
+            return Ok(AbiType::Array(Box::new(AbiType::parse(element)?), None));
+        }
+        if let Some((element, len)) = name
+            .strip_suffix(']')
+            .and_then(|rest| rest.rsplit_once('['))
+        {
+            let len = len
+                .parse()
+                .map_err(|_| abi_error(format!("Invalid array length in `{}`", name)))?;
+            return Ok(AbiType::Array(
+                Box::new(AbiType::parse(element)?),
+                Some(len),
+            ));
+        }
+        let width = |digits: &str, max: u16| {
+            digits
+                .parse::<u16>()
+                .ok()
+                .filter(|width| *width > 0 && *width <= max)
+        };
+        let ty = match name {
+            "Address" => AbiType::Address,
+            "Bool" => AbiType::Bool,
+            "Bytes" => AbiType::Bytes,
+            "String" => AbiType::String,
+            _ => {
+                if let Some(pqc) = PqcType::ALL.iter().find(|pqc| pqc.name() == name) {
+                    AbiType::Pqc(*pqc)
+                } else if let Some(bits) = name.strip_prefix("UInt").and_then(|d| width(d, 256)) {
+                    AbiType::UInt(bits)
+                } else if let Some(bits) = name.strip_prefix("Int").and_then(|d| width(d, 256)) {
+                    AbiType::Int(bits)
+                } else if let Some(bytes) = name.strip_prefix("Bytes").and_then(|d| width(d, 32)) {
+                    AbiType::FixedBytes(bytes as u8)
+                } else {
+                    return Err(abi_error(format!("Unknown ABI type `{}`", name)));
+                }
+            }
+        };
+        Ok(ty)
+    }
+
+    /// Whether the value is stored in a tail, behind an offset in its head.
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            AbiType::Bytes | AbiType::String | AbiType::Pqc(_) | AbiType::Array(_, None) => true,
+            AbiType::Array(element, Some(_)) => element.is_dynamic(),
+            AbiType::Tuple(types) => types.iter().any(AbiType::is_dynamic),
+            _ => false,
+        }
+    }
+
+    /// Bytes the value takes in the heads of its enclosing tuple.
+    pub fn head_size(&self) -> usize {
+        match self {
+            _ if self.is_dynamic() => WORD,
+            AbiType::Array(element, Some(len)) => element.head_size() * len,
+            AbiType::Tuple(types) => types.iter().map(AbiType::head_size).sum(),
+            _ => WORD,
+        }
+    }
+}
+
+impl fmt::Display for AbiType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AbiType::Address => write!(f, "Address"),
+            AbiType::Bool => write!(f, "Bool"),
+            AbiType::UInt(bits) => write!(f, "UInt{}", bits),
+            AbiType::Int(bits) => write!(f, "Int{}", bits),
+            AbiType::FixedBytes(len) => write!(f, "Bytes{}", len),
+            AbiType::Bytes => write!(f, "Bytes"),
+            AbiType::String => write!(f, "String"),
+            AbiType::Pqc(pqc) => write!(f, "{}", pqc.name()),
+            AbiType::Array(element, Some(len)) => write!(f, "{}[{}]", element, len),
+            AbiType::Array(element, None) => write!(f, "{}[]", element),
+            AbiType::Tuple(types) => {
+                write!(f, "(")?;
+                for (index, ty) in types.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", ty)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// A value of some `AbiType`. PQC keys and signatures are `Bytes`; their type decides the
+/// lengths allowed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AbiValue {
+    Address([u8; 20]),
+    Bool(bool),
+    UInt(U256),
+    /// A signed integer as its two's-complement 256-bit word.
+    Int(U256),
+    FixedBytes(Vec<u8>),
+    Bytes(Vec<u8>),
+    String(String),
+    Array(Vec<AbiValue>),
+    Tuple(Vec<AbiValue>),
+}
+
+impl AbiValue {
+    pub fn int(value: i128) -> AbiValue {
+        AbiValue::Int(twos_complement(value))
+    }
+
+    pub fn uint(value: u128) -> AbiValue {
+        AbiValue::UInt(U256::from(value))
+    }
+
+    /// Reads a VM value, e.g. a return value or log field, as a value of `ty`. Arrays and
+    /// tuples never fit in one VM value.
+    pub fn from_value(ty: &AbiType, value: &Value) -> Result<AbiValue, VMError> {
+        let mismatch = || abi_error(format!("{:?} is not a valid {}", value, ty));
+        let abi_value = match (ty, value) {
+            (AbiType::Bool, Value::Bool(_) | Value::I32(_)) => AbiValue::Bool(value.as_bool()?),
+            (AbiType::UInt(bits), Value::I32(_) | Value::I64(_)) => {
+                // A negative integer is read as its low `bits` bits, as `Mask` would keep them.
+                let word = twos_complement(i128::from(value.as_i64()?));
+                AbiValue::UInt(if *bits >= 256 {
+                    word
+                } else {
+                    word & ((U256::one() << *bits) - U256::one())
+                })
+            }
+            (AbiType::Int(_), Value::I32(_) | Value::I64(_)) => {
+                AbiValue::int(i128::from(value.as_i64()?))
+            }
+            (AbiType::Address, Value::Bytes(bytes)) => {
+                AbiValue::Address(bytes.as_slice().try_into().map_err(|_| mismatch())?)
+            }
+            (AbiType::FixedBytes(len), Value::Bytes(bytes)) if bytes.len() == usize::from(*len) => {
+                AbiValue::FixedBytes(bytes.clone())
+            }
+            (AbiType::Bytes | AbiType::Pqc(_), Value::Bytes(bytes)) => {
+                AbiValue::Bytes(bytes.clone())
+            }
+            (AbiType::String, Value::Bytes(bytes)) => {
+                AbiValue::String(String::from_utf8(bytes.clone()).map_err(|_| mismatch())?)
+            }
+            _ => return Err(mismatch()),
+        };
+        Ok(abi_value)
+    }
+}
+
+impl fmt::Display for AbiValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = |f: &mut fmt::Formatter, values: &[AbiValue], open, close| {
+            write!(f, "{}", open)?;
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}", value)?;
+            }
+            write!(f, "{}", close)
+        };
+        match self {
+            AbiValue::Address(address) => write!(f, "0x{}", hex(address)),
+            AbiValue::Bool(value) => write!(f, "{}", value),
+            AbiValue::UInt(value) => write!(f, "{}", value),
+            AbiValue::Int(word) if word.bit(255) => {
+                write!(f, "-{}", (!*word).overflowing_add(U256::one()).0)
+            }
+            AbiValue::Int(word) => write!(f, "{}", word),
+            AbiValue::FixedBytes(bytes) | AbiValue::Bytes(bytes) => write!(f, "0x{}", hex(bytes)),
+            AbiValue::String(text) => write!(f, "{:?}", text),
+            AbiValue::Array(values) => list(f, values, "[", "]"),
+            AbiValue::Tuple(values) => list(f, values, "(", ")"),
+        }
+    }
+}
+
+/// Selector of a canonical signature such as `transfer(Address,UInt256)`: the first four
+/// bytes of its SHA-256 digest, read big-endian. Event topics and error selectors are derived
+/// the same way.
+pub fn selector(signature: &str) -> u32 {
+    let digest = Sha256::digest(signature.as_bytes());
+    u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]])
+}
+
+/// Encodes `values` as a tuple of `types`, e.g. constructor arguments or return values.
+pub fn encode(types: &[AbiType], values: &[AbiValue]) -> Result<Vec<u8>, VMError> {
+    if types.len() != values.len() {
+        return Err(abi_error(format!(
+            "Expected {} values but got {}",
+            types.len(),
+            values.len()
+        )));
+    }
+    let heads: usize = types.iter().map(AbiType::head_size).sum();
+    let mut head = Vec::with_capacity(heads);
+    let mut tail = Vec::new();
+    for (index, (ty, value)) in types.iter().zip(values).enumerate() {
+        let encoded = encode_value(ty, value).map_err(|e| e.with_context(&at(index)))?;
+        if ty.is_dynamic() {
+            head.extend_from_slice(&usize_word(heads + tail.len()));
+            tail.extend_from_slice(&encoded);
+        } else {
+            head.extend_from_slice(&encoded);
+        }
+    }
+    head.extend_from_slice(&tail);
+    Ok(head)
+}
+
+/// Calldata for the entry point with `selector`: the selector, then the encoded arguments.
+pub fn encode_call(
+    selector: u32,
+    types: &[AbiType],
+    values: &[AbiValue],
+) -> Result<Vec<u8>, VMError> {
+    let mut calldata = selector.to_be_bytes().to_vec();
+    calldata.extend_from_slice(&encode(types, values)?);
+    Ok(calldata)
+}
+
+/// Decodes a tuple of `types` from `data`, checking every value against its type.
+pub fn decode(types: &[AbiType], data: &[u8]) -> Result<Vec<AbiValue>, VMError> {
+    decode_tuple(types, data, 0)
+}
+
+/// Splits calldata into its selector and decoded arguments.
+pub fn decode_call(types: &[AbiType], calldata: &[u8]) -> Result<(u32, Vec<AbiValue>), VMError> {
+    let selector = calldata
+        .get(..4)
+        .map(|bytes| u32::from_be_bytes(bytes.try_into().expect("4 bytes")))
+        .ok_or_else(|| {
+            abi_error(format!(
+                "Calldata of {} bytes is too short for a selector",
+                calldata.len()
+            ))
+        })?;
+    Ok((selector, decode(types, &calldata[4..])?))
+}
+
+fn encode_value(ty: &AbiType, value: &AbiValue) -> Result<Vec<u8>, VMError> {
+    let mismatch = || abi_error(format!("{} is not a valid {}", value, ty));
+    let mut word = [0u8; WORD];
+    match (ty, value) {
+        (AbiType::Address, AbiValue::Address(address)) => word[12..].copy_from_slice(address),
+        (AbiType::Bool, AbiValue::Bool(value)) => word[31] = u8::from(*value),
+        (AbiType::UInt(bits), AbiValue::UInt(value)) => {
+            if *bits < 256 && value.bits() > usize::from(*bits) {
+                return Err(abi_error(format!("{} does not fit in UInt{}", value, bits)));
+            }
+            value.to_big_endian(&mut word);
+        }
+        (AbiType::Int(bits), AbiValue::Int(value)) => {
+            if !fits_signed(value, *bits) {
+                return Err(abi_error(format!("{} does not fit in Int{}", value, bits)));
+            }
+            value.to_big_endian(&mut word);
+        }
+        (AbiType::FixedBytes(len), AbiValue::FixedBytes(bytes)) => {
+            if bytes.len() != usize::from(*len) {
+                return Err(abi_error(format!(
+                    "Bytes{} must be {} bytes but got {}",
+                    len,
+                    len,
+                    bytes.len()
+                )));
+            }
+            word[..bytes.len()].copy_from_slice(bytes);
+        }
+        (AbiType::Bytes, AbiValue::Bytes(bytes)) => return Ok(encode_bytes(bytes)),
+        (AbiType::String, AbiValue::String(text)) => return Ok(encode_bytes(text.as_bytes())),
+        (AbiType::Pqc(pqc), AbiValue::Bytes(bytes)) => {
+            pqc.check_length(bytes.len())?;
+            return Ok(encode_bytes(bytes));
+        }
+        (AbiType::Array(element, len), AbiValue::Array(values)) => {
+            let types = vec![(**element).clone(); values.len()];
+            return match len {
+                Some(len) if *len != values.len() => Err(abi_error(format!(
+                    "{} takes {} elements but got {}",
+                    ty,
+                    len,
+                    values.len()
+                ))),
+                Some(_) => encode(&types, values),
+                None => {
+                    let mut out = usize_word(values.len()).to_vec();
+                    out.extend_from_slice(&encode(&types, values)?);
+                    Ok(out)
+                }
+            };
+        }
+        (AbiType::Tuple(types), AbiValue::Tuple(values)) => return encode(types, values),
+        _ => return Err(mismatch()),
+    }
+    Ok(word.to_vec())
+}
+
+fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    let mut out = usize_word(bytes.len()).to_vec();
+    out.extend_from_slice(bytes);
+    out.resize(WORD + bytes.len().div_ceil(WORD) * WORD, 0);
+    out
+}
+
+// Decodes a tuple whose heads start at `start`; tail offsets count from there too.
+fn decode_tuple(types: &[AbiType], data: &[u8], start: usize) -> Result<Vec<AbiValue>, VMError> {
+    let mut values = Vec::with_capacity(types.len());
+    let mut head = start;
+    for (index, ty) in types.iter().enumerate() {
+        let value = if ty.is_dynamic() {
+            read_usize(data, head)
+                .and_then(|offset| {
+                    let at = start.checked_add(offset).ok_or_else(|| {
+                        abi_error(format!("Offset {} at byte {} overflows", offset, head))
+                    })?;
+                    decode_value(ty, data, at)
+                })
+                .map_err(|e| e.with_context(&at(index)))?
+        } else {
+            decode_value(ty, data, head).map_err(|e| e.with_context(&at(index)))?
+        };
+        values.push(value);
+        head += ty.head_size();
+    }
+    Ok(values)
+}
+
+fn decode_value(ty: &AbiType, data: &[u8], at: usize) -> Result<AbiValue, VMError> {
+    let invalid = |what: &str| abi_error(format!("Word at byte {} is not a valid {}", at, what));
+    let value = match ty {
+        AbiType::Address => {
+            let word = read_word(data, at)?;
+            if word[..12].iter().any(|byte| *byte != 0) {
+                return Err(invalid("Address"));
+            }
+            AbiValue::Address(word[12..].try_into().expect("20 bytes"))
+        }
+        AbiType::Bool => match read_word(data, at)? {
+            word if word[..31].iter().any(|byte| *byte != 0) => return Err(invalid("Bool")),
+            word => match word[31] {
+                0 => AbiValue::Bool(false),
+                1 => AbiValue::Bool(true),
+                _ => return Err(invalid("Bool")),
+            },
+        },
+        AbiType::UInt(bits) => {
+            let value = U256::from_big_endian(read_word(data, at)?);
+            if value.bits() > usize::from(*bits) {
+                return Err(invalid(&ty.to_string()));
+            }
+            AbiValue::UInt(value)
+        }
+        AbiType::Int(bits) => {
+            let value = U256::from_big_endian(read_word(data, at)?);
+            if !fits_signed(&value, *bits) {
+                return Err(invalid(&ty.to_string()));
+            }
+            AbiValue::Int(value)
+        }
+        AbiType::FixedBytes(len) => {
+            let word = read_word(data, at)?;
+            let (bytes, padding) = word.split_at(usize::from(*len));
+            if padding.iter().any(|byte| *byte != 0) {
+                return Err(invalid(&ty.to_string()));
+            }
+            AbiValue::FixedBytes(bytes.to_vec())
+        }
+        AbiType::Bytes => AbiValue::Bytes(read_bytes(data, at)?.to_vec()),
+        AbiType::String => AbiValue::String(
+            String::from_utf8(read_bytes(data, at)?.to_vec())
+                .map_err(|_| abi_error(format!("String at byte {} is not UTF-8", at)))?,
+        ),
+        AbiType::Pqc(pqc) => {
+            let bytes = read_bytes(data, at)?;
+            pqc.check_length(bytes.len())?;
+            AbiValue::Bytes(bytes.to_vec())
+        }
+        AbiType::Array(element, Some(len)) => {
+            check_count(data, at, *len, element.head_size())?;
+            AbiValue::Array(decode_tuple(&vec![(**element).clone(); *len], data, at)?)
+        }
+        AbiType::Array(element, None) => {
+            let len = read_usize(data, at)?;
+            check_count(data, at + WORD, len, element.head_size())?;
+            AbiValue::Array(decode_tuple(
+                &vec![(**element).clone(); len],
+                data,
+                at + WORD,
+            )?)
+        }
+        AbiType::Tuple(types) => AbiValue::Tuple(decode_tuple(types, data, at)?),
+    };
+    Ok(value)
+}
+
+fn read_word(data: &[u8], at: usize) -> Result<&[u8], VMError> {
+    at.checked_add(WORD)
+        .and_then(|end| data.get(at..end))
+        .ok_or_else(|| {
+            abi_error(format!(
+                "Word at byte {} is past the end of the {}-byte data",
+                at,
+                data.len()
+            ))
+        })
+}
+
+fn read_usize(data: &[u8], at: usize) -> Result<usize, VMError> {
+    let value = U256::from_big_endian(read_word(data, at)?);
+    if value > U256::from(data.len()) {
+        return Err(abi_error(format!(
+            "Offset or length {} at byte {} is past the end of the {}-byte data",
+            value,
+            at,
+            data.len()
+        )));
+    }
+    Ok(value.as_usize())
+}
+
+fn read_bytes(data: &[u8], at: usize) -> Result<&[u8], VMError> {
+    let len = read_usize(data, at)?;
+    let start = at + WORD;
+    start
+        .checked_add(len)
+        .and_then(|end| data.get(start..end))
+        .ok_or_else(|| {
+            abi_error(format!(
+                "{} bytes at byte {} run past the end of the {}-byte data",
+                len,
+                start,
+                data.len()
+            ))
+        })
+}
+
+// Rejects element counts whose heads could not fit in the data, before allocating for them.
+fn check_count(data: &[u8], at: usize, count: usize, head_size: usize) -> Result<(), VMError> {
+    let fits = count
+        .checked_mul(head_size)
+        .and_then(|size| at.checked_add(size))
+        .is_some_and(|end| end <= data.len());
+    if !fits {
+        return Err(abi_error(format!(
+            "{} elements at byte {} do not fit in the {}-byte data",
+            count,
+            at,
+            data.len()
+        )));
+    }
+    Ok(())
+}
+
+// `value` as a two's-complement 256-bit word
+fn twos_complement(value: i128) -> U256 {
+    let magnitude = U256::from(value.unsigned_abs());
+    if value < 0 {
+        (!magnitude).overflowing_add(U256::one()).0
+    } else {
+        magnitude
+    }
+}
+
+// Whether the two's-complement `word` is a sign extension of a `bits`-wide integer.
+fn fits_signed(word: &U256, bits: u16) -> bool {
+    if bits >= 256 {
+        return true;
+    }
+    let magnitude = if word.bit(255) { !*word } else { *word };
+    magnitude.bits() < usize::from(bits)
+}
+
+fn usize_word(value: usize) -> [u8; WORD] {
+    let mut word = [0u8; WORD];
+    word[WORD - 8..].copy_from_slice(&(value as u64).to_be_bytes());
+    word
+}
+
+fn at(index: usize) -> String {
+    format!("value {}", index)
+}
+
+fn abi_error(message: String) -> VMError {
+    VMError::AbiError(message)
+}
@@ -1,10 +1,24 @@
-use crate::opcode::OpCode;
-use crate::vm::Header;
+use crate::opcode::{Immediate, OpCode};
+use crate::source_map::SourceMap;
+use crate::vm::{Header, SectionKind};
+
+/// A code position that jumps can refer to before it is bound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Label(usize);
 
 // Assembler for creating bytecode
 pub struct Assembler {
     code: Vec<u8>,
-    data: Vec<u8>,
+    // Every position each label was bound at; exactly one is valid.
+    labels: Vec<Vec<u32>>,
+    // Code positions of `u32` label references, patched by `build`.
+    relocations: Vec<(usize, Label)>,
+    constants: Vec<Vec<u8>>,
+    metadata: Vec<u8>,
+    compiler_version: [u32; 3],
+    source_hash: [u8; 32],
+    source_map: SourceMap,
+    runtime: Vec<u8>,
 }
 
 impl Default for Assembler {
@@ -17,7 +31,14 @@ impl Assembler {
     pub fn new() -> Self {
         Assembler {
             code: Vec::new(),
-            data: Vec::new(),
+            labels: Vec::new(),
+            relocations: Vec::new(),
+            constants: Vec::new(),
+            metadata: Vec::new(),
+            compiler_version: [0; 3],
+            source_hash: [0; 32],
+            source_map: SourceMap::default(),
+            runtime: Vec::new(),
         }
     }
 
@@ -25,6 +46,10 @@ impl Assembler {
         self.code.push(op as u8);
     }
 
+    pub fn emit_u8(&mut self, value: u8) {
+        self.code.push(value);
+    }
+
     pub fn emit_i32(&mut self, value: i32) {
         self.code.extend_from_slice(&value.to_le_bytes());
     }
@@ -38,6 +63,80 @@ impl Assembler {
         self.code.extend_from_slice(bytes);
     }
 
+    pub fn new_label(&mut self) -> Label {
+        self.labels.push(Vec::new());
+        Label(self.labels.len() - 1)
+    }
+
+    /// Binds `label` to the current end of the code.
+    pub fn bind(&mut self, label: Label) {
+        let position = self.code.len() as u32;
+        self.labels[label.0].push(position);
+    }
+
+    pub fn is_bound(&self, label: Label) -> bool {
+        !self.labels[label.0].is_empty()
+    }
+
+    /// Emits `op` with `label` as its `u32` target.
+    pub fn emit_jump(&mut self, op: OpCode, label: Label) {
+        self.emit_op(op);
+        self.emit_label(label);
+    }
+
+    /// Emits a `u32` placeholder that `build` replaces with `label`'s position.
+    pub fn emit_label(&mut self, label: Label) {
+        self.relocations.push((self.code.len(), label));
+        self.emit_u32(0);
+    }
+
+    /// Index of `bytes` in the constant pool, adding it if it is not there yet.
+    pub fn add_constant(&mut self, bytes: &[u8]) -> u32 {
+        let index = match self.constants.iter().position(|c| c == bytes) {
+            Some(index) => index,
+            None => {
+                self.constants.push(bytes.to_vec());
+                self.constants.len() - 1
+            }
+        };
+        index as u32
+    }
+
+    /// Emits `LoadConst` for `bytes`, pooling it.
+    pub fn emit_const(&mut self, bytes: &[u8]) {
+        let index = self.add_constant(bytes);
+        self.emit_op(OpCode::LoadConst);
+        self.emit_u32(index);
+    }
+
+    pub fn set_metadata(&mut self, metadata: Vec<u8>) {
+        self.metadata = metadata;
+    }
+
+    pub fn set_compiler_version(&mut self, major: u32, minor: u32, patch: u32) {
+        self.compiler_version = [major, minor, patch];
+    }
+
+    pub fn set_source_hash(&mut self, hash: [u8; 32]) {
+        self.source_hash = hash;
+    }
+
+    /// Embeds `container` as the runtime code a deploy installs, making this code init code.
+    pub fn set_runtime(&mut self, container: Vec<u8>) {
+        self.runtime = container;
+    }
+
+    /// Names the source file recorded in the source map.
+    pub fn set_source_file(&mut self, file: &str) {
+        self.source_map.file = file.to_string();
+    }
+
+    /// Attributes the code emitted from here on to a source position in `function`.
+    pub fn mark_source(&mut self, line: u32, column: u32, function: &str) {
+        let offset = self.code.len() as u32;
+        self.source_map.record(offset, line, column, function);
+    }
+
     pub fn code_len(&self) -> usize {
         self.code.len()
     }
@@ -51,20 +150,403 @@ impl Assembler {
         Ok(())
     }
 
-    pub fn build(self) -> Vec<u8> {
+    /// Resolves label references and writes a v2 container. The data, metadata, source map and
+    /// runtime sections are omitted when empty. Fails if a label is bound more than once or referenced but never
+    /// bound.
+    pub fn build(mut self) -> Result<Vec<u8>, String> {
+        for (index, positions) in self.labels.iter().enumerate() {
+            if let [first, second, ..] = positions[..] {
+                return Err(format!(
+                    "Label {} is bound twice, at offsets {} and {}",
+                    index, first, second
+                ));
+            }
+        }
+        for (position, label) in std::mem::take(&mut self.relocations) {
+            let Some(&target) = self.labels[label.0].first() else {
+                return Err(format!(
+                    "Label {} is referenced by the operand at offset {} but never bound",
+                    label.0, position
+                ));
+            };
+            self.patch_u32(position, target)?;
+        }
+
+        let mut data = Vec::new();
+        for constant in &self.constants {
+            data.extend_from_slice(&(constant.len() as u32).to_le_bytes());
+            data.extend_from_slice(constant);
+        }
+
+        let mut sections = vec![(SectionKind::Code, self.code)];
+        if !data.is_empty() {
+            sections.push((SectionKind::Data, data));
+        }
+        if !self.metadata.is_empty() {
+            sections.push((SectionKind::Metadata, self.metadata));
+        }
+        if !self.source_map.is_empty() {
+            sections.push((SectionKind::SourceMap, self.source_map.encode()));
+        }
+        if !self.runtime.is_empty() {
+            sections.push((SectionKind::Runtime, self.runtime));
+        }
+
         let mut bytecode = Vec::new();
 
         // Header
+        let header_length = Header::V2_FIXED_LENGTH + sections.len() * 5;
         bytecode.extend_from_slice(&Header::MAGIC.to_le_bytes());
-        bytecode.push(1); // version
-        bytecode.extend_from_slice(&15u16.to_le_bytes()); // header length
-        bytecode.extend_from_slice(&(self.code.len() as u32).to_le_bytes());
-        bytecode.extend_from_slice(&(self.data.len() as u32).to_le_bytes());
+        bytecode.push(Header::VERSION_2);
+        bytecode.extend_from_slice(&(header_length as u16).to_le_bytes());
+        for part in self.compiler_version {
+            bytecode.extend_from_slice(&part.to_le_bytes());
+        }
+        bytecode.extend_from_slice(&self.source_hash);
+        bytecode.push(sections.len() as u8);
+        for (kind, bytes) in &sections {
+            bytecode.push(*kind as u8);
+            bytecode.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        }
+
+        // Sections
+        for (_, bytes) in sections {
+            bytecode.extend_from_slice(&bytes);
+        }
+
+        Ok(bytecode)
+    }
+}
+
+// Textual assembly (`.qasm`)
+//
+// One instruction, label or directive per line; `;` starts a comment. Mnemonics are opcode names
+// in any case (`push 1`, `JumpIf done`). Operands are separated by commas: integers (decimal or
+// `0x` hex), labels for jump, call and gas-scope handler targets, and byte strings as `"text"`
+// (with `\n`, `\t`, `\\`, `\"` and `\xNN` escapes) or `0x` hex. `loadconst` takes a pool index
+// or a byte string, which is pooled. Directives: `.version 1.2.3`, `.source_hash 0x<32 bytes>`,
+// `.metadata <bytes>`, `.const <bytes>`, which appends the next constant pool entry, and the
+// source map's `.file "<name>"` and `.loc <line>, <column>, "<function>"`, which attributes the
+// instructions that follow. Lines between `.runtime` and `.end` are a nested program, assembled on
+// its own and embedded as the runtime container of a deploy.
+impl Assembler {
+    pub fn from_qasm(source: &str) -> Result<Assembler, String> {
+        let mut assembler = Assembler::new();
+        // Label by name, with the first line that referenced it.
+        let mut labels: std::collections::HashMap<String, (Label, Option<usize>)> =
+            std::collections::HashMap::new();
+
+        let lines: Vec<&str> = source.lines().collect();
+        let mut index = 0;
+        while index < lines.len() {
+            let line_number = index + 1;
+            let at_line = |message: String| format!("line {}: {}", line_number, message);
+            let mut line = strip_qasm_comment(lines[index]).trim();
+            index += 1;
 
-        // Code and data
-        bytecode.extend_from_slice(&self.code);
-        bytecode.extend_from_slice(&self.data);
+            // Labels, possibly followed by an instruction on the same line.
+            while let Some((name, rest)) = line.split_once(':') {
+                let name = name.trim();
+                if !is_label_name(name) {
+                    break;
+                }
+                let (label, _) = *labels
+                    .entry(name.to_string())
+                    .or_insert_with(|| (assembler.new_label(), None));
+                if assembler.is_bound(label) {
+                    return Err(at_line(format!("label `{}` is defined twice", name)));
+                }
+                assembler.bind(label);
+                line = rest.trim();
+            }
+            if line.is_empty() {
+                continue;
+            }
 
-        bytecode
+            let (word, rest) = line
+                .split_once(char::is_whitespace)
+                .map_or((line, ""), |(word, rest)| (word, rest.trim()));
+            let operands = split_qasm_operands(rest);
+
+            if let Some(directive) = word.strip_prefix('.') {
+                if directive == "runtime" {
+                    let end = runtime_block_end(&lines, index)
+                        .ok_or_else(|| at_line("`.runtime` has no matching `.end`".to_string()))?;
+                    // Blank out the enclosing lines so nested errors keep their line numbers.
+                    let nested: Vec<&str> = (0..end)
+                        .map(|i| if i < index { "" } else { lines[i] })
+                        .collect();
+                    let runtime = Assembler::from_qasm(&nested.join("\n"))?.build()?;
+                    assembler.set_runtime(runtime);
+                    index = end + 1;
+                    continue;
+                }
+                if directive == "end" {
+                    return Err(at_line("`.end` without `.runtime`".to_string()));
+                }
+                if directive == "loc" {
+                    let [line, column, function] = operands.as_slice() else {
+                        return Err(at_line(
+                            "`.loc` takes a line, a column and a function".to_string(),
+                        ));
+                    };
+                    let position = |text: &str| {
+                        parse_qasm_integer(text)
+                            .and_then(|value| u32::try_from(value).ok())
+                            .ok_or_else(|| at_line(format!("invalid source position `{}`", text)))
+                    };
+                    let function = parse_qasm_bytes(function).map_err(&at_line)?;
+                    let function = String::from_utf8(function)
+                        .map_err(|_| at_line("function name is not UTF-8".to_string()))?;
+                    assembler.mark_source(position(line)?, position(column)?, &function);
+                    continue;
+                }
+                let [operand] = operands.as_slice() else {
+                    return Err(at_line(format!("`.{}` takes one operand", directive)));
+                };
+                match directive {
+                    "version" => {
+                        let parts: Vec<u32> = operand
+                            .split('.')
+                            .map(|part| part.parse::<u32>())
+                            .collect::<Result<_, _>>()
+                            .map_err(|_| at_line(format!("invalid version `{}`", operand)))?;
+                        let [major, minor, patch] = parts[..] else {
+                            return Err(at_line(format!("invalid version `{}`", operand)));
+                        };
+                        assembler.set_compiler_version(major, minor, patch);
+                    }
+                    "source_hash" => {
+                        let hash = parse_qasm_bytes(operand).map_err(&at_line)?;
+                        let hash: [u8; 32] = hash.try_into().map_err(|hash: Vec<u8>| {
+                            at_line(format!("source hash has {} bytes, not 32", hash.len()))
+                        })?;
+                        assembler.set_source_hash(hash);
+                    }
+                    "file" => {
+                        let file = parse_qasm_bytes(operand).map_err(&at_line)?;
+                        let file = String::from_utf8(file)
+                            .map_err(|_| at_line("file name is not UTF-8".to_string()))?;
+                        assembler.set_source_file(&file);
+                    }
+                    "metadata" => {
+                        assembler.set_metadata(parse_qasm_bytes(operand).map_err(&at_line)?);
+                    }
+                    "const" => {
+                        let bytes = parse_qasm_bytes(operand).map_err(&at_line)?;
+                        let expected = assembler.constants.len() as u32;
+                        if assembler.add_constant(&bytes) != expected {
+                            return Err(at_line(format!("constant {} is already pooled", operand)));
+                        }
+                    }
+                    _ => return Err(at_line(format!("unknown directive `.{}`", directive))),
+                }
+                continue;
+            }
+
+            let opcode = OpCode::from_mnemonic(word)
+                .ok_or_else(|| at_line(format!("unknown mnemonic `{}`", word)))?;
+            let expected = match opcode.immediate() {
+                Immediate::None => 0,
+                Immediate::GasScope => 2,
+                _ => 1,
+            };
+            if operands.len() != expected {
+                return Err(at_line(format!(
+                    "`{}` takes {} operands but {} were given",
+                    opcode.mnemonic(),
+                    expected,
+                    operands.len()
+                )));
+            }
+            let mut target =
+                |assembler: &mut Assembler, operand: &str| match parse_qasm_integer(operand) {
+                    Some(offset) => assembler.emit_u32(offset as u32),
+                    None => {
+                        let (label, first_use) = labels
+                            .entry(operand.to_string())
+                            .or_insert_with(|| (assembler.new_label(), None));
+                        first_use.get_or_insert(line_number);
+                        assembler.emit_label(*label);
+                    }
+                };
+            match opcode.immediate() {
+                Immediate::None => assembler.emit_op(opcode),
+                Immediate::U8 => {
+                    let value = parse_qasm_integer(operands[0])
+                        .and_then(|value| u8::try_from(value).ok())
+                        .ok_or_else(|| at_line(format!("`{}` is not a byte", operands[0])))?;
+                    assembler.emit_op(opcode);
+                    assembler.emit_u8(value);
+                }
+                Immediate::I32 => {
+                    let value = parse_qasm_integer(operands[0])
+                        .filter(|value| {
+                            *value >= i64::from(i32::MIN) && *value <= i64::from(u32::MAX)
+                        })
+                        .ok_or_else(|| {
+                            at_line(format!("`{}` is not a 32-bit integer", operands[0]))
+                        })?;
+                    assembler.emit_op(opcode);
+                    assembler.emit_u32(value as u32);
+                }
+                Immediate::U32 => {
+                    let index = match parse_qasm_integer(operands[0]) {
+                        Some(index) => u32::try_from(index)
+                            .map_err(|_| at_line(format!("invalid index `{}`", operands[0])))?,
+                        None => {
+                            let bytes = parse_qasm_bytes(operands[0]).map_err(&at_line)?;
+                            assembler.add_constant(&bytes)
+                        }
+                    };
+                    assembler.emit_op(opcode);
+                    assembler.emit_u32(index);
+                }
+                Immediate::Target => {
+                    assembler.emit_op(opcode);
+                    target(&mut assembler, operands[0]);
+                }
+                Immediate::GasScope => {
+                    let limit = parse_qasm_integer(operands[0])
+                        .and_then(|limit| u32::try_from(limit).ok())
+                        .ok_or_else(|| at_line(format!("invalid gas limit `{}`", operands[0])))?;
+                    assembler.emit_op(opcode);
+                    assembler.emit_u32(limit);
+                    target(&mut assembler, operands[1]);
+                }
+                Immediate::Bytes => {
+                    let bytes = parse_qasm_bytes(operands[0]).map_err(&at_line)?;
+                    assembler.emit_op(opcode);
+                    assembler.emit_bytes(&bytes);
+                }
+            }
+        }
+
+        let mut undefined: Vec<(usize, &String)> = labels
+            .iter()
+            .filter(|(_, (label, _))| !assembler.is_bound(*label))
+            .filter_map(|(name, (_, first_use))| first_use.map(|line| (line, name)))
+            .collect();
+        undefined.sort();
+        if let Some((line_number, name)) = undefined.first() {
+            return Err(format!("line {}: undefined label `{}`", line_number, name));
+        }
+        Ok(assembler)
+    }
+}
+
+/// Index of the `.end` closing a `.runtime` block whose body starts at `start`.
+fn runtime_block_end(lines: &[&str], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (index, line) in lines.iter().enumerate().skip(start) {
+        match strip_qasm_comment(line).trim() {
+            ".runtime" => depth += 1,
+            ".end" if depth == 0 => return Some(index),
+            ".end" => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn strip_qasm_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            ';' if !in_string => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn split_qasm_operands(text: &str) -> Vec<&str> {
+    if text.is_empty() {
+        return Vec::new();
+    }
+    let mut operands = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            ',' if !in_string => {
+                operands.push(text[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    operands.push(text[start..].trim());
+    operands
+}
+
+fn is_label_name(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_qasm_integer(text: &str) -> Option<i64> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let value = match digits.strip_prefix("0x") {
+        Some(hex) => i64::from_str_radix(hex, 16).ok()?,
+        None => digits.parse::<i64>().ok()?,
+    };
+    Some(if negative { -value } else { value })
+}
+
+fn parse_qasm_bytes(text: &str) -> Result<Vec<u8>, String> {
+    if let Some(hex) = text.strip_prefix("0x") {
+        if hex.len() % 2 != 0 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("`{}` is not an even number of hex digits", text));
+        }
+        return (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|e| e.to_string()))
+            .collect();
+    }
+    let inner = text
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .ok_or_else(|| format!("expected a quoted string or 0x hex, found `{}`", text))?;
+    let mut bytes = Vec::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buffer = [0u8; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('n') => bytes.push(b'\n'),
+            Some('t') => bytes.push(b'\t'),
+            Some('\\') => bytes.push(b'\\'),
+            Some('"') => bytes.push(b'"'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                let byte = u8::from_str_radix(&hex, 16)
+                    .map_err(|_| format!("invalid escape `\\x{}`", hex))?;
+                bytes.push(byte);
+            }
+            other => {
+                return Err(format!(
+                    "invalid escape `\\{}`",
+                    other.map(String::from).unwrap_or_default()
+                ))
+            }
+        }
     }
+    Ok(bytes)
 }
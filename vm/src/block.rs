@@ -0,0 +1,209 @@
+use crate::gas_schedule::GasSchedule;
+use crate::receipt::{serialize_optional_bytes, ExecutionReceipt};
+use crate::state::{Address, StateBackend, WorldState};
+use crate::vm::QuantumVM;
+use serde::Serialize;
+
+/// What a transaction does.
+#[derive(Debug, Clone)]
+pub enum TransactionAction {
+    /// Deploys a deploy container, running its init code with `constructor_args`.
+    Deploy {
+        bytecode: Vec<u8>,
+        constructor_args: Vec<u8>,
+    },
+    /// Calls the contract at `contract`, sending `value` from the sender's balance.
+    Call {
+        contract: Address,
+        calldata: Vec<u8>,
+        value: u64,
+    },
+}
+
+/// One transaction of a block, with the gas its sender allows it to use.
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    pub sender: Address,
+    pub gas_limit: u64,
+    pub action: TransactionAction,
+}
+
+impl Transaction {
+    pub fn deploy(
+        sender: Address,
+        bytecode: Vec<u8>,
+        constructor_args: Vec<u8>,
+        gas_limit: u64,
+    ) -> Self {
+        Transaction {
+            sender,
+            gas_limit,
+            action: TransactionAction::Deploy {
+                bytecode,
+                constructor_args,
+            },
+        }
+    }
+
+    pub fn call(
+        sender: Address,
+        contract: Address,
+        calldata: Vec<u8>,
+        value: u64,
+        gas_limit: u64,
+    ) -> Self {
+        Transaction {
+            sender,
+            gas_limit,
+            action: TransactionAction::Call {
+                contract,
+                calldata,
+                value,
+            },
+        }
+    }
+}
+
+/// Result of one transaction of a block.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum TransactionReceipt {
+    /// The transaction ran, successfully or not; either way its gas counts toward the block.
+    Included {
+        #[serde(flatten)]
+        execution: ExecutionReceipt,
+        /// Address a successful deploy created.
+        #[serde(serialize_with = "serialize_optional_bytes")]
+        contract_address: Option<Address>,
+        /// Block gas and PQ gas used up to and including this transaction.
+        cumulative_gas_used: u64,
+        cumulative_pqc_gas_used: u64,
+    },
+    /// The transaction's gas limit is more than the block has left; it had no effect.
+    Rejected { reason: String },
+}
+
+impl TransactionReceipt {
+    /// Execution receipt of an included transaction.
+    pub fn execution(&self) -> Option<&ExecutionReceipt> {
+        match self {
+            TransactionReceipt::Included { execution, .. } => Some(execution),
+            TransactionReceipt::Rejected { .. } => None,
+        }
+    }
+}
+
+/// Receipts of a block's transactions, in order, and the block's totals.
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockReceipt {
+    pub transactions: Vec<TransactionReceipt>,
+    pub gas_used: u64,
+    pub pqc_gas_used: u64,
+}
+
+/// Runs the transactions of a block in order against one world state, within the block's gas
+/// limit and the schedule's `max_pqc_gas_per_block`.
+///
+/// A transaction whose gas limit is more than the block has left is rejected before it runs;
+/// rejected transactions change nothing, not even the sender's nonce. Its PQ gas limit is the
+/// schedule's `max_pqc_gas_per_tx` or the PQ gas the block has left, whichever is lower, so one
+/// that needs more runs out of PQ gas and is included as failed. Changes stay in the world state
+/// until the caller commits it.
+pub struct BlockExecutor {
+    schedule: GasSchedule,
+    gas_limit: u64,
+}
+
+impl BlockExecutor {
+    /// Block gas limit of `BlockExecutor::new`.
+    pub const DEFAULT_GAS_LIMIT: u64 = 30_000_000;
+
+    pub fn new(schedule: GasSchedule) -> Self {
+        BlockExecutor::with_gas_limit(schedule, Self::DEFAULT_GAS_LIMIT)
+    }
+
+    pub fn with_gas_limit(schedule: GasSchedule, gas_limit: u64) -> Self {
+        BlockExecutor {
+            schedule,
+            gas_limit,
+        }
+    }
+
+    pub fn gas_limit(&self) -> u64 {
+        self.gas_limit
+    }
+
+    pub fn execute<B: StateBackend>(
+        &self,
+        state: &mut WorldState<B>,
+        transactions: &[Transaction],
+    ) -> BlockReceipt {
+        let mut block = BlockReceipt {
+            transactions: Vec::with_capacity(transactions.len()),
+            gas_used: 0,
+            pqc_gas_used: 0,
+        };
+        for transaction in transactions {
+            let receipt = self.execute_transaction(state, transaction, &block);
+            if let TransactionReceipt::Included {
+                cumulative_gas_used,
+                cumulative_pqc_gas_used,
+                ..
+            } = receipt
+            {
+                block.gas_used = cumulative_gas_used;
+                block.pqc_gas_used = cumulative_pqc_gas_used;
+            }
+            block.transactions.push(receipt);
+        }
+        block
+    }
+
+    fn execute_transaction<B: StateBackend>(
+        &self,
+        state: &mut WorldState<B>,
+        transaction: &Transaction,
+        block: &BlockReceipt,
+    ) -> TransactionReceipt {
+        let gas_left = self.gas_limit - block.gas_used;
+        if transaction.gas_limit > gas_left {
+            return TransactionReceipt::Rejected {
+                reason: format!(
+                    "Gas limit {} exceeds the {} gas left in the block",
+                    transaction.gas_limit, gas_left
+                ),
+            };
+        }
+
+        let mut schedule = self.schedule.clone();
+        let pqc_gas_left = schedule.max_pqc_gas_per_block - block.pqc_gas_used;
+        schedule.max_pqc_gas_per_tx = schedule.max_pqc_gas_per_tx.min(pqc_gas_left);
+        let mut vm = QuantumVM::with_schedule(transaction.gas_limit, schedule);
+        let (execution, contract_address) = match &transaction.action {
+            TransactionAction::Deploy {
+                bytecode,
+                constructor_args,
+            } => {
+                let result =
+                    vm.deploy_contract(state, &transaction.sender, bytecode, constructor_args);
+                (vm.receipt(&result), result.ok())
+            }
+            TransactionAction::Call {
+                contract,
+                calldata,
+                value,
+            } => {
+                let result =
+                    vm.call_contract(state, &transaction.sender, contract, calldata, *value);
+                (vm.receipt(&result), None)
+            }
+        };
+
+        TransactionReceipt::Included {
+            cumulative_gas_used: block.gas_used + execution.gas_used,
+            cumulative_pqc_gas_used: block.pqc_gas_used + execution.pqc_gas_used,
+            execution,
+            contract_address,
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use crate::opcode::VMError;
+use crate::source_map::SourceMap;
+use crate::verifier::{decode, Operand};
+use crate::vm::{Header, SectionKind};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+/// Renders a container as `.qasm` text that `Assembler::from_qasm` reads back into the same
+/// bytes: header directives, the constant pool, then one instruction per line with its offset.
+/// Jump, call and handler targets become `L<offset>` labels, and source map rows `.loc` lines.
+/// A deploy container's runtime follows as a nested `.runtime` ... `.end` listing.
+pub fn disassemble(bytecode: &[u8]) -> Result<String, VMError> {
+    let header = Header::parse(bytecode)?;
+    let code = header.section_bytes(bytecode, SectionKind::Code);
+    let data = header.section_bytes(bytecode, SectionKind::Data);
+    let instructions = decode(code)?;
+
+    let mut out = String::new();
+    // Writing to a `String` cannot fail.
+    let _ = writeln!(
+        out,
+        "; QuantumVM container v{}, {} bytes of code",
+        header.version,
+        code.len()
+    );
+    if let Some([major, minor, patch]) = header.compiler_version {
+        let _ = writeln!(out, ".version {}.{}.{}", major, minor, patch);
+    }
+    if let Some(hash) = header.source_hash {
+        let _ = writeln!(out, ".source_hash 0x{}", hex(&hash));
+    }
+    let metadata = header.section_bytes(bytecode, SectionKind::Metadata);
+    if !metadata.is_empty() {
+        let _ = writeln!(out, ".metadata {}", format_literal(metadata));
+    }
+    let source_map = match header.section_bytes(bytecode, SectionKind::SourceMap) {
+        [] => SourceMap::default(),
+        bytes => SourceMap::decode(bytes)?,
+    };
+    if !source_map.file.is_empty() {
+        let _ = writeln!(out, ".file {}", format_literal(source_map.file.as_bytes()));
+    }
+
+    let constants = if header.version == Header::VERSION_1 {
+        if !data.is_empty() {
+            let _ = writeln!(out, "; data section: 0x{}", hex(data));
+        }
+        Vec::new()
+    } else {
+        Header::parse_constants(data)?
+    };
+    for (index, constant) in constants.iter().enumerate() {
+        let _ = writeln!(out, ".const {} ; #{}", format_literal(constant), index);
+    }
+
+    let targets: BTreeSet<usize> = instructions
+        .iter()
+        .filter_map(|instruction| match instruction.operand {
+            Operand::Target(target) => Some(target as usize),
+            Operand::GasScope { handler, .. } => Some(handler as usize),
+            _ => None,
+        })
+        .collect();
+
+    // Source map rows go before the first instruction at or after their offset.
+    let mut rows = source_map.entries.iter().peekable();
+    let mut write_rows = |out: &mut String, offset: usize| {
+        while let Some(row) = rows.next_if(|row| row.offset as usize <= offset) {
+            let function = &source_map.functions[row.function as usize];
+            let _ = writeln!(
+                out,
+                ".loc {}, {}, {}",
+                row.line,
+                row.column,
+                format_literal(function.as_bytes())
+            );
+        }
+    };
+
+    out.push('\n');
+    for instruction in &instructions {
+        if targets.contains(&instruction.offset) {
+            let _ = writeln!(out, "{}:", label(instruction.offset as u32));
+        }
+        write_rows(&mut out, instruction.offset);
+        let mnemonic = instruction.opcode.mnemonic();
+        let (operands, note) = match &instruction.operand {
+            Operand::None => (String::new(), None),
+            Operand::U8(value) => (value.to_string(), None),
+            Operand::I32(value) => (value.to_string(), None),
+            Operand::U32(index) => (
+                index.to_string(),
+                constants
+                    .get(*index as usize)
+                    .map(|constant| format_literal(constant)),
+            ),
+            Operand::Target(target) => (label(*target), None),
+            Operand::GasScope { limit, handler } => {
+                (format!("{}, {}", limit, label(*handler)), None)
+            }
+            Operand::Bytes(bytes) => (format_literal(bytes), None),
+        };
+        let text = if operands.is_empty() {
+            mnemonic
+        } else {
+            format!("{} {}", mnemonic, operands)
+        };
+        let _ = match note {
+            Some(note) => writeln!(out, "    {:<32} ; {} {}", text, instruction.offset, note),
+            None => writeln!(out, "    {:<32} ; {}", text, instruction.offset),
+        };
+    }
+    // A gas scope handler may point just past the last instruction.
+    if targets.contains(&code.len()) {
+        let _ = writeln!(out, "{}:", label(code.len() as u32));
+    }
+    write_rows(&mut out, usize::MAX);
+
+    let runtime = header.section_bytes(bytecode, SectionKind::Runtime);
+    if !runtime.is_empty() {
+        let _ = write!(out, "\n.runtime\n{}.end\n", disassemble(runtime)?);
+    }
+    Ok(out)
+}
+
+fn label(offset: u32) -> String {
+    format!("L{}", offset)
+}
+
+/// A quoted string when the bytes are printable UTF-8, otherwise `0x` hex.
+pub fn format_literal(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text)
+            if text
+                .chars()
+                .all(|c| !c.is_control() || matches!(c, '\n' | '\t')) =>
+        {
+            let mut out = String::from("\"");
+            for c in text.chars() {
+                match c {
+                    '"' => out.push_str("\\\""),
+                    '\\' => out.push_str("\\\\"),
+                    '\n' => out.push_str("\\n"),
+                    '\t' => out.push_str("\\t"),
+                    _ => out.push(c),
+                }
+            }
+            out.push('"');
+            out
+        }
+        _ => format!("0x{}", hex(bytes)),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
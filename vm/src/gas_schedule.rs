@@ -0,0 +1,378 @@
+use crate::opcode::{OpCode, VMError};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Gas an opcode charges: `base` before it runs, then `per_byte` for each byte and `per_word`
+/// for each 32-byte word of the operands it reads or writes, plus a fixed `compute` cost.
+/// PQC operations charge all four together, as PQ gas, once their operands are known.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OpCost {
+    pub base: u64,
+    pub per_byte: u64,
+    pub per_word: u64,
+    pub compute: u64,
+}
+
+impl OpCost {
+    /// Operand-dependent part of the cost for `bytes` bytes.
+    pub fn data_cost(&self, bytes: usize) -> u64 {
+        let bytes = bytes as u64;
+        self.per_byte
+            .saturating_mul(bytes)
+            .saturating_add(self.per_word.saturating_mul(bytes.div_ceil(32)))
+            .saturating_add(self.compute)
+    }
+
+    pub fn total(&self, bytes: usize) -> u64 {
+        self.base.saturating_add(self.data_cost(bytes))
+    }
+}
+
+/// Gas costs of every opcode and the chain's PQ gas limits. Schedules are numbered so blocks
+/// replay with the schedule that was active when they were produced (see
+/// `GasScheduleHistory`); version 1 is the built-in one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GasSchedule {
+    pub version: u32,
+    /// Charged for every instruction on top of its own base cost.
+    pub instruction: u64,
+    pub stack_push: u64,
+    pub stack_pop: u64,
+    /// Storage writes pay this per KB of the value written, on top of `SSTORE`'s cost.
+    pub storage_cost_per_kb: u64,
+    /// Part of `TRANSFER`'s compute cost forwarded to the recipient. Recipients never run
+    /// code, so it is always returned unused.
+    pub transfer_stipend: u64,
+    pub max_pqc_gas_per_tx: u64,
+    pub max_pqc_gas_per_block: u64,
+    costs: [OpCost; 256],
+}
+
+// Genesis names of the PQC operations; `mlkem_encapsulate_768` is the documented name of the
+// ML-KEM key exchange, which decapsulates.
+const PQC_COST_NAMES: [(&str, OpCode); 8] = [
+    ("verify_mldsa_65", OpCode::MLDSAVerify),
+    ("verify_fndsa_512", OpCode::FNDSAVerify),
+    ("verify_slhdsa", OpCode::SLHDSAVerify),
+    ("mlkem_decapsulate_768", OpCode::MLKEMKeyExchange),
+    ("mlkem_encapsulate_768", OpCode::MLKEMKeyExchange),
+    ("hqckem_decapsulate_128", OpCode::HQCKEM128KeyExchange),
+    ("hqckem_decapsulate_192", OpCode::HQCKEM192KeyExchange),
+    ("hqckem_decapsulate_256", OpCode::HQCKEM256KeyExchange),
+];
+
+impl Default for GasSchedule {
+    fn default() -> Self {
+        GasSchedule::v1()
+    }
+}
+
+impl GasSchedule {
+    /// The built-in schedule.
+    pub fn v1() -> Self {
+        let byte_op = OpCost {
+            base: 3,
+            per_word: 1,
+            ..OpCost::default()
+        };
+        let fixed = |base| OpCost {
+            base,
+            ..OpCost::default()
+        };
+        let pqc = |base, per_byte, compute| OpCost {
+            base,
+            per_byte,
+            per_word: 0,
+            compute,
+        };
+        let mut schedule = GasSchedule {
+            version: 1,
+            instruction: 1,
+            stack_push: 1,
+            stack_pop: 1,
+            storage_cost_per_kb: 50_000,
+            transfer_stipend: 2_300,
+            max_pqc_gas_per_tx: 300_000,
+            max_pqc_gas_per_block: 2_000_000,
+            costs: [OpCost::default(); 256],
+        };
+        for (opcode, cost) in [
+            (OpCode::Div, fixed(5)),
+            (OpCode::Jump, fixed(2)),
+            (OpCode::JumpIf, fixed(2)),
+            (OpCode::Call, fixed(10)),
+            (OpCode::Return, fixed(3)),
+            (
+                OpCode::GasLimitBegin,
+                OpCost {
+                    base: 2,
+                    per_word: 1,
+                    ..OpCost::default()
+                },
+            ),
+            (OpCode::GasLimitEnd, fixed(1)),
+            (OpCode::Load, fixed(3)),
+            (OpCode::Store, fixed(5)),
+            (OpCode::Load8, fixed(3)),
+            (OpCode::Store8, fixed(3)),
+            (OpCode::MCopy, byte_op),
+            (OpCode::LoadBytes, byte_op),
+            (OpCode::StoreBytes, byte_op),
+            (OpCode::BytesConcat, byte_op),
+            (OpCode::BytesSlice, byte_op),
+            (OpCode::BytesEq, byte_op),
+            (OpCode::SLoad, fixed(100)),
+            (OpCode::SStore, fixed(100)),
+            (OpCode::SlotHash, fixed(30)),
+            (
+                OpCode::SLoadBytes,
+                OpCost {
+                    base: 100,
+                    per_word: 1,
+                    ..OpCost::default()
+                },
+            ),
+            (OpCode::MLDSAVerify, pqc(6_000, 9, 20_000)),
+            (OpCode::MLKEMKeyExchange, pqc(5_000, 6, 14_000)),
+            (OpCode::FNDSAVerify, pqc(4_000, 6, 10_000)),
+            (OpCode::HQCKEM128KeyExchange, pqc(6_500, 7, 22_000)),
+            (OpCode::HQCKEM192KeyExchange, pqc(7_000, 7, 26_000)),
+            (OpCode::HQCKEM256KeyExchange, pqc(7_500, 7, 32_000)),
+            (
+                OpCode::Log,
+                OpCost {
+                    base: 375,
+                    per_byte: 8,
+                    ..OpCost::default()
+                },
+            ),
+            (OpCode::CallDataLoad, fixed(3)),
+            (OpCode::CallDataSlice, byte_op),
+            (OpCode::Balance, fixed(100)),
+            (
+                OpCode::Transfer,
+                OpCost {
+                    compute: 9_000,
+                    ..OpCost::default()
+                },
+            ),
+        ] {
+            schedule.set_cost(opcode, cost);
+        }
+        schedule
+    }
+
+    pub fn cost(&self, opcode: OpCode) -> OpCost {
+        self.costs[opcode as usize]
+    }
+
+    pub fn set_cost(&mut self, opcode: OpCode, cost: OpCost) {
+        self.costs[opcode as usize] = cost;
+    }
+
+    /// Size-dependent cost of writing a `bytes`-byte value to storage.
+    pub fn storage_cost(&self, bytes: usize) -> u64 {
+        (bytes as u64).saturating_mul(self.storage_cost_per_kb) / 1024
+    }
+
+    /// Cost of a `TRANSFER` of `amount` beyond its base; moving nothing costs nothing extra.
+    pub fn transfer_cost(&self, amount: u64) -> u64 {
+        if amount == 0 {
+            0
+        } else {
+            self.cost(OpCode::Transfer)
+                .data_cost(0)
+                .saturating_sub(self.transfer_stipend)
+        }
+    }
+
+    /// Reads the gas fields of a genesis file (see `docs/Gas-Model.md` §7). Other fields are
+    /// ignored; costs it does not set keep their values from the built-in schedule.
+    pub fn from_genesis_json(json: &str) -> Result<GasSchedule, VMError> {
+        let genesis: GenesisSchedule = serde_json::from_str(json)
+            .map_err(|e| VMError::InvalidGasSchedule(format!("Malformed genesis file: {e}")))?;
+        genesis.apply(GasSchedule::v1(), 1)
+    }
+}
+
+/// Gas schedules by the block they take effect at.
+#[derive(Debug, Clone)]
+pub struct GasScheduleHistory {
+    schedules: BTreeMap<u64, GasSchedule>,
+}
+
+impl GasScheduleHistory {
+    /// History whose first schedule is active from block 0.
+    pub fn new(genesis: GasSchedule) -> Self {
+        GasScheduleHistory {
+            schedules: BTreeMap::from([(0, genesis)]),
+        }
+    }
+
+    /// Activates `schedule` from `block` on. It must come after the latest schedule, with a
+    /// higher version.
+    pub fn upgrade(&mut self, block: u64, schedule: GasSchedule) -> Result<(), VMError> {
+        let (&latest_block, latest) = self.latest();
+        if block <= latest_block || schedule.version <= latest.version {
+            return Err(VMError::InvalidGasSchedule(format!(
+                "Schedule version {} at block {} does not follow version {} at block {}",
+                schedule.version, block, latest.version, latest_block
+            )));
+        }
+        self.schedules.insert(block, schedule);
+        Ok(())
+    }
+
+    /// Schedule that block `number` executes under.
+    pub fn at(&self, number: u64) -> &GasSchedule {
+        self.schedules
+            .range(..=number)
+            .next_back()
+            .map(|(_, schedule)| schedule)
+            .expect("a history always starts at block 0")
+    }
+
+    fn latest(&self) -> (&u64, &GasSchedule) {
+        self.schedules
+            .last_key_value()
+            .expect("a history always starts at block 0")
+    }
+
+    /// Reads the genesis schedule and the `gas_schedule_upgrades` of a genesis file. Each
+    /// upgrade names its `activation_block` and changes the schedule before it.
+    pub fn from_genesis_json(json: &str) -> Result<GasScheduleHistory, VMError> {
+        let genesis: GenesisSchedule = serde_json::from_str(json)
+            .map_err(|e| VMError::InvalidGasSchedule(format!("Malformed genesis file: {e}")))?;
+        let upgrades = genesis.gas_schedule_upgrades.clone();
+        let mut history = GasScheduleHistory::new(genesis.apply(GasSchedule::v1(), 1)?);
+        for upgrade in upgrades {
+            let (_, latest) = history.latest();
+            let version = latest.version + 1;
+            let schedule = upgrade.schedule.apply(latest.clone(), version)?;
+            history.upgrade(upgrade.activation_block, schedule)?;
+        }
+        Ok(history)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GenesisSchedule {
+    gas_schedule_version: Option<u32>,
+    #[serde(default)]
+    pqc_costs: BTreeMap<String, GenesisCost>,
+    #[serde(default)]
+    opcode_costs: BTreeMap<String, GenesisCost>,
+    storage_cost_per_kb: Option<u64>,
+    max_pqc_gas_per_tx: Option<u64>,
+    max_pqc_gas_per_block: Option<u64>,
+    #[serde(default)]
+    gas_schedule_upgrades: Vec<GenesisUpgrade>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GenesisUpgrade {
+    activation_block: u64,
+    #[serde(flatten)]
+    schedule: GenesisSchedule,
+}
+
+// A flat cost, or the components to change
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum GenesisCost {
+    Flat(u64),
+    Components {
+        base: Option<u64>,
+        per_byte: Option<u64>,
+        per_word: Option<u64>,
+        compute: Option<u64>,
+    },
+}
+
+impl GenesisCost {
+    fn apply(&self, cost: OpCost) -> OpCost {
+        match *self {
+            GenesisCost::Flat(base) => OpCost {
+                base,
+                ..OpCost::default()
+            },
+            GenesisCost::Components {
+                base,
+                per_byte,
+                per_word,
+                compute,
+            } => OpCost {
+                base: base.unwrap_or(cost.base),
+                per_byte: per_byte.unwrap_or(cost.per_byte),
+                per_word: per_word.unwrap_or(cost.per_word),
+                compute: compute.unwrap_or(cost.compute),
+            },
+        }
+    }
+}
+
+impl GenesisSchedule {
+    // Applies these fields to `schedule`, numbering the result `default_version` unless the
+    // file gives a version.
+    fn apply(
+        &self,
+        mut schedule: GasSchedule,
+        default_version: u32,
+    ) -> Result<GasSchedule, VMError> {
+        schedule.version = self.gas_schedule_version.unwrap_or(default_version);
+        let mut set = BTreeMap::new();
+        for (name, cost) in &self.pqc_costs {
+            let (_, opcode) = PQC_COST_NAMES
+                .iter()
+                .find(|(known, _)| known == name)
+                .ok_or_else(|| {
+                    let known: Vec<&str> = PQC_COST_NAMES.iter().map(|(name, _)| *name).collect();
+                    VMError::InvalidGasSchedule(format!(
+                        "Unknown PQC cost `{name}` (expected one of {})",
+                        known.join(", ")
+                    ))
+                })?;
+            set_cost(&mut schedule, &mut set, name, *opcode, cost)?;
+        }
+        for (name, cost) in &self.opcode_costs {
+            let opcode = OpCode::from_mnemonic(name).ok_or_else(|| {
+                VMError::InvalidGasSchedule(format!("Unknown opcode `{name}` in opcode_costs"))
+            })?;
+            set_cost(&mut schedule, &mut set, name, opcode, cost)?;
+        }
+        if let Some(cost) = self.storage_cost_per_kb {
+            schedule.storage_cost_per_kb = cost;
+        }
+        if let Some(limit) = self.max_pqc_gas_per_tx {
+            schedule.max_pqc_gas_per_tx = limit;
+        }
+        if let Some(limit) = self.max_pqc_gas_per_block {
+            schedule.max_pqc_gas_per_block = limit;
+        }
+        if schedule.max_pqc_gas_per_tx > schedule.max_pqc_gas_per_block {
+            return Err(VMError::InvalidGasSchedule(format!(
+                "max_pqc_gas_per_tx {} exceeds max_pqc_gas_per_block {}",
+                schedule.max_pqc_gas_per_tx, schedule.max_pqc_gas_per_block
+            )));
+        }
+        Ok(schedule)
+    }
+}
+
+// Sets one opcode's cost, rejecting a second entry for the same opcode
+fn set_cost<'a>(
+    schedule: &mut GasSchedule,
+    set: &mut BTreeMap<u8, &'a str>,
+    name: &'a str,
+    opcode: OpCode,
+    cost: &GenesisCost,
+) -> Result<(), VMError> {
+    if let Some(previous) = set.insert(opcode as u8, name) {
+        return Err(VMError::InvalidGasSchedule(format!(
+            "`{previous}` and `{name}` both set the cost of {}",
+            opcode.mnemonic()
+        )));
+    }
+    schedule.set_cost(opcode, cost.apply(schedule.cost(opcode)));
+    Ok(())
+}
@@ -0,0 +1,176 @@
+use crate::opcode::VMError;
+use crate::vm::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Persistent contract storage: one value per slot. Slots never written read as zero.
+pub type Storage = BTreeMap<StorageKey, Value>;
+
+/// A storage slot: a 256-bit number, kept as its big-endian word. State variables take small
+/// slots numbered from zero; mapping entries and dynamic array elements live at slots derived
+/// by SHA-256 (see `derived_slot`), so the two never share a slot in practice.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StorageKey(pub [u8; 32]);
+
+impl StorageKey {
+    /// The slot `count` slots past this one, wrapping around at 2^256.
+    pub fn offset(self, count: u64) -> StorageKey {
+        let mut word = self.0;
+        let mut carry = u128::from(count);
+        for byte in word.iter_mut().rev() {
+            if carry == 0 {
+                break;
+            }
+            let sum = u128::from(*byte) + (carry & 0xFF);
+            *byte = sum as u8;
+            carry = (carry >> 8) + (sum >> 8);
+        }
+        StorageKey(word)
+    }
+
+    /// The slot as an `i64`, if it is small enough to be one.
+    pub fn as_i64(&self) -> Option<i64> {
+        let (high, low) = self.0.split_at(24);
+        let value = i64::from_be_bytes(low.try_into().expect("8-byte tail"));
+        (value >= 0 && high.iter().all(|byte| *byte == 0)).then_some(value)
+    }
+}
+
+impl From<u32> for StorageKey {
+    fn from(slot: u32) -> Self {
+        StorageKey::default().offset(u64::from(slot))
+    }
+}
+
+impl fmt::Display for StorageKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.as_i64() {
+            Some(slot) => write!(f, "{}", slot),
+            None => {
+                write!(f, "0x")?;
+                self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+            }
+        }
+    }
+}
+
+/// Slot `SLOTHASH` derives for `key` under slot `base`: SHA-256 over `base`'s 32-byte word
+/// followed by `key`. Integer keys are hashed as their 32-byte word.
+pub fn derived_slot(base: StorageKey, key: &[u8]) -> StorageKey {
+    let mut hasher = Sha256::new();
+    hasher.update(base.0);
+    hasher.update(key);
+    StorageKey(hasher.finalize().into())
+}
+
+// A deployed contract: the runtime container and the storage its calls read and update
+#[derive(Debug, Clone)]
+pub struct ContractInstance {
+    pub code: Vec<u8>,
+    pub storage: Storage,
+}
+
+// Instance file layout: magic, version (u8), runtime container (u32 length, bytes), slot count
+// (u32), then per slot its 32-byte word, a value tag (u8) and the value: `i32` or `i64`
+// little-endian, a bool byte, or a `u32` length and bytes.
+impl ContractInstance {
+    pub const MAGIC: u32 = 0x51564D49; // QVMI
+    pub const VERSION: u8 = 2;
+
+    const TAG_I32: u8 = 0;
+    const TAG_I64: u8 = 1;
+    const TAG_BYTES: u8 = 2;
+    const TAG_BOOL: u8 = 3;
+
+    /// Whether `bytes` start like an encoded instance rather than a bytecode container.
+    pub fn is_instance(bytes: &[u8]) -> bool {
+        bytes.starts_with(&Self::MAGIC.to_le_bytes())
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Self::MAGIC.to_le_bytes().to_vec();
+        bytes.push(Self::VERSION);
+        bytes.extend_from_slice(&(self.code.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.code);
+        bytes.extend_from_slice(&(self.storage.len() as u32).to_le_bytes());
+        for (slot, value) in &self.storage {
+            bytes.extend_from_slice(&slot.0);
+            match value {
+                Value::I32(v) => {
+                    bytes.push(Self::TAG_I32);
+                    bytes.extend_from_slice(&v.to_le_bytes());
+                }
+                Value::I64(v) => {
+                    bytes.push(Self::TAG_I64);
+                    bytes.extend_from_slice(&v.to_le_bytes());
+                }
+                Value::Bytes(v) => {
+                    bytes.push(Self::TAG_BYTES);
+                    bytes.extend_from_slice(&(v.len() as u32).to_le_bytes());
+                    bytes.extend_from_slice(v);
+                }
+                Value::Bool(v) => {
+                    bytes.push(Self::TAG_BOOL);
+                    bytes.push(u8::from(*v));
+                }
+            }
+        }
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, VMError> {
+        let truncated = || VMError::InvalidBytecode("Contract instance is truncated".to_string());
+        if !Self::is_instance(bytes) {
+            return Err(VMError::InvalidBytecode(
+                "Not a contract instance".to_string(),
+            ));
+        }
+        if bytes.get(4) != Some(&Self::VERSION) {
+            return Err(VMError::InvalidBytecode(format!(
+                "Unsupported contract instance version {}",
+                bytes.get(4).copied().unwrap_or_default()
+            )));
+        }
+        let mut rest = &bytes[5..];
+        let mut take = |len: usize| -> Result<&[u8], VMError> {
+            if rest.len() < len {
+                return Err(truncated());
+            }
+            let (head, tail) = rest.split_at(len);
+            rest = tail;
+            Ok(head)
+        };
+        let u32_of = |b: &[u8]| u32::from_le_bytes(b.try_into().expect("4 bytes"));
+
+        let code_len = u32_of(take(4)?) as usize;
+        let code = take(code_len)?.to_vec();
+        let slots = u32_of(take(4)?);
+        let mut storage = Storage::new();
+        for _ in 0..slots {
+            let slot = StorageKey(take(32)?.try_into().expect("32 bytes"));
+            let value = match take(1)?[0] {
+                Self::TAG_I32 => Value::I32(i32::from_le_bytes(take(4)?.try_into().unwrap())),
+                Self::TAG_I64 => Value::I64(i64::from_le_bytes(take(8)?.try_into().unwrap())),
+                Self::TAG_BYTES => {
+                    let len = u32_of(take(4)?) as usize;
+                    Value::Bytes(take(len)?.to_vec())
+                }
+                Self::TAG_BOOL => Value::Bool(take(1)?[0] != 0),
+                tag => {
+                    return Err(VMError::InvalidBytecode(format!(
+                        "Storage slot {} has unknown value tag {}",
+                        slot, tag
+                    )))
+                }
+            };
+            storage.insert(slot, value);
+        }
+        if !rest.is_empty() {
+            return Err(VMError::InvalidBytecode(
+                "Trailing bytes after the contract instance".to_string(),
+            ));
+        }
+        Ok(ContractInstance { code, storage })
+    }
+}
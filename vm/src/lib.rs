@@ -1,8 +1,27 @@
+pub mod abi;
 pub mod assembler;
+pub mod block;
+pub mod disassembler;
+pub mod gas_schedule;
+pub mod instance;
 pub mod opcode;
+pub mod receipt;
+pub mod source_map;
+pub mod state;
+pub mod verifier;
 pub mod vm;
 
 // Re-export for convenience
-pub use assembler::Assembler;
+pub use abi::{AbiType, AbiValue, PqcType};
+pub use assembler::{Assembler, Label};
+pub use block::{BlockExecutor, BlockReceipt, Transaction, TransactionAction, TransactionReceipt};
+pub use gas_schedule::{GasSchedule, GasScheduleHistory, OpCost};
+pub use instance::{derived_slot, ContractInstance, Storage, StorageKey};
 pub use opcode::{OpCode, VMError};
-pub use vm::{QuantumVM, Value};
+pub use receipt::{ExecutionReceipt, ExecutionStatus, GasClass};
+pub use source_map::{SourceLocation, SourceMap};
+pub use state::{
+    contract_address, Account, Address, Balances, Checkpoint, LogEntry, MemoryBackend,
+    StateBackend, WorldState,
+};
+pub use vm::{Header, QuantumVM, SectionKind, Value};
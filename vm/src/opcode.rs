@@ -1,3 +1,4 @@
+use crate::receipt::GasClass;
 use std::fmt;
 
 // Error types
@@ -11,6 +12,12 @@ pub enum VMError {
     CryptoError(String),
     RuntimeError(String),
     OutOfGas(String),
+    /// Execution reverted with a message; its state changes are undone.
+    Revert(String),
+    /// Bytes or values that do not match their ABI types.
+    AbiError(String),
+    /// A gas schedule or genesis file that cannot be used.
+    InvalidGasSchedule(String),
 }
 
 impl fmt::Display for VMError {
@@ -28,12 +35,35 @@ impl fmt::Display for VMError {
             VMError::CryptoError(msg) => write!(f, "Cryptographic operation failed: {}", msg),
             VMError::RuntimeError(msg) => write!(f, "Runtime error: {}", msg),
             VMError::OutOfGas(msg) => write!(f, "Out of gas: {}", msg),
+            VMError::Revert(msg) => write!(f, "Reverted: {}", msg),
+            VMError::AbiError(msg) => write!(f, "ABI error: {}", msg),
+            VMError::InvalidGasSchedule(msg) => write!(f, "Invalid gas schedule: {}", msg),
         }
     }
 }
 
 impl std::error::Error for VMError {}
 
+impl VMError {
+    /// Appends ` (context)` to the message, keeping the variant.
+    pub fn with_context(self, context: &str) -> VMError {
+        let annotate = |msg: String| format!("{} ({})", msg, context);
+        match self {
+            VMError::InvalidBytecode(msg) => VMError::InvalidBytecode(annotate(msg)),
+            VMError::StackUnderflow(msg) => VMError::StackUnderflow(annotate(msg)),
+            VMError::StackOverflow(msg) => VMError::StackOverflow(annotate(msg)),
+            VMError::InvalidInstruction(op) => VMError::InvalidInstruction(op),
+            VMError::InvalidAddress(msg) => VMError::InvalidAddress(annotate(msg)),
+            VMError::CryptoError(msg) => VMError::CryptoError(annotate(msg)),
+            VMError::RuntimeError(msg) => VMError::RuntimeError(annotate(msg)),
+            VMError::OutOfGas(msg) => VMError::OutOfGas(annotate(msg)),
+            VMError::Revert(msg) => VMError::Revert(annotate(msg)),
+            VMError::AbiError(msg) => VMError::AbiError(annotate(msg)),
+            VMError::InvalidGasSchedule(msg) => VMError::InvalidGasSchedule(annotate(msg)),
+        }
+    }
+}
+
 // Instruction opcodes
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[repr(u8)]
@@ -63,11 +93,44 @@ pub enum OpCode {
     JumpIf = 0x31,
     Call = 0x32,
     Return = 0x33,
+    GasLimitBegin = 0x34,
+    GasLimitEnd = 0x35,
 
-    // Memory operations
+    // Memory operations (linear, byte-addressed; `Load`/`Store` move 32-byte words)
     Load = 0x40,
     Store = 0x41,
     LoadImm = 0x42,
+    BoundsCheck = 0x43,
+    Load8 = 0x44,
+    Store8 = 0x45,
+    MCopy = 0x46,
+    MSize = 0x47,
+    LoadBytes = 0x48,
+    StoreBytes = 0x49,
+    LoadConst = 0x4A,
+
+    // Byte string operations
+    BytesConcat = 0x50,
+    BytesSlice = 0x51,
+    BytesEq = 0x52,
+    BytesLen = 0x53,
+
+    // Conversion operations (one-byte width operand)
+    Mask = 0x60,
+    SignExtend = 0x61,
+    IntToBytes = 0x62,
+    BytesToInt = 0x63,
+    BytesResize = 0x64,
+
+    // Persistent storage, keyed by 256-bit slot number
+    SLoad = 0x70,
+    SStore = 0x71,
+    /// Slot of a mapping entry or of a dynamic array's elements; see `instance::derived_slot`.
+    SlotHash = 0x72,
+    /// `SLoad` of a byte string: a slot never written, or holding zero, reads as empty.
+    SLoadBytes = 0x73,
+    /// Slot a number of slots past another, wrapping at 2^256; `Add` for slots.
+    SlotAdd = 0x74,
 
     // PQC operations
     MLDSAVerify = 0x80,
@@ -78,8 +141,26 @@ pub enum OpCode {
     HQCKEM192KeyExchange = 0x85,
     HQCKEM256KeyExchange = 0x86,
 
+    // Event logs
+    Log = 0xA0,
+
+    // Environment
+    Address = 0xB0,
+    Caller = 0xB1,
+    CallDataSize = 0xB3,
+    Selector = 0xB4,
+    CallDataLoad = 0xB5,
+    CallDataSlice = 0xB6,
+
+    // Native value
+    CallValue = 0xB7,
+    Balance = 0xB8,
+    SelfBalance = 0xB9,
+    Transfer = 0xBA,
+
     // Utility
     Print = 0xF0,
+    Revert = 0xFD,
     Halt = 0xFF,
 }
 
@@ -106,9 +187,33 @@ impl TryFrom<u8> for OpCode {
             0x31 => Ok(OpCode::JumpIf),
             0x32 => Ok(OpCode::Call),
             0x33 => Ok(OpCode::Return),
+            0x34 => Ok(OpCode::GasLimitBegin),
+            0x35 => Ok(OpCode::GasLimitEnd),
             0x40 => Ok(OpCode::Load),
             0x41 => Ok(OpCode::Store),
             0x42 => Ok(OpCode::LoadImm),
+            0x43 => Ok(OpCode::BoundsCheck),
+            0x44 => Ok(OpCode::Load8),
+            0x45 => Ok(OpCode::Store8),
+            0x46 => Ok(OpCode::MCopy),
+            0x47 => Ok(OpCode::MSize),
+            0x48 => Ok(OpCode::LoadBytes),
+            0x49 => Ok(OpCode::StoreBytes),
+            0x4A => Ok(OpCode::LoadConst),
+            0x50 => Ok(OpCode::BytesConcat),
+            0x51 => Ok(OpCode::BytesSlice),
+            0x52 => Ok(OpCode::BytesEq),
+            0x53 => Ok(OpCode::BytesLen),
+            0x60 => Ok(OpCode::Mask),
+            0x61 => Ok(OpCode::SignExtend),
+            0x62 => Ok(OpCode::IntToBytes),
+            0x63 => Ok(OpCode::BytesToInt),
+            0x64 => Ok(OpCode::BytesResize),
+            0x70 => Ok(OpCode::SLoad),
+            0x71 => Ok(OpCode::SStore),
+            0x72 => Ok(OpCode::SlotHash),
+            0x73 => Ok(OpCode::SLoadBytes),
+            0x74 => Ok(OpCode::SlotAdd),
             0x80 => Ok(OpCode::MLDSAVerify),
             0x81 => Ok(OpCode::MLKEMKeyExchange),
             0x82 => Ok(OpCode::FNDSAVerify),
@@ -116,9 +221,150 @@ impl TryFrom<u8> for OpCode {
             0x84 => Ok(OpCode::HQCKEM128KeyExchange),
             0x85 => Ok(OpCode::HQCKEM192KeyExchange),
             0x86 => Ok(OpCode::HQCKEM256KeyExchange),
+            0xB0 => Ok(OpCode::Address),
+            0xB1 => Ok(OpCode::Caller),
+            0xB3 => Ok(OpCode::CallDataSize),
+            0xB4 => Ok(OpCode::Selector),
+            0xB5 => Ok(OpCode::CallDataLoad),
+            0xA0 => Ok(OpCode::Log),
+            0xB6 => Ok(OpCode::CallDataSlice),
+            0xB7 => Ok(OpCode::CallValue),
+            0xB8 => Ok(OpCode::Balance),
+            0xB9 => Ok(OpCode::SelfBalance),
+            0xBA => Ok(OpCode::Transfer),
             0xF0 => Ok(OpCode::Print),
+            0xFD => Ok(OpCode::Revert),
             0xFF => Ok(OpCode::Halt),
             _ => Err(VMError::InvalidInstruction(value)),
         }
     }
 }
+
+// Operand encoding that follows an opcode in the code section
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Immediate {
+    None,
+    U8,
+    I32,
+    U32,
+    /// `u32` code offset of a jump or call target.
+    Target,
+    /// `u32` gas limit, then the `u32` code offset of the overrun handler.
+    GasScope,
+    /// `u32` length, then that many bytes.
+    Bytes,
+}
+
+impl OpCode {
+    /// Lower-case `.qasm` name, e.g. `jumpif`.
+    pub fn mnemonic(self) -> String {
+        format!("{:?}", self).to_ascii_lowercase()
+    }
+
+    pub fn from_mnemonic(name: &str) -> Option<Self> {
+        (0..=u8::MAX)
+            .filter_map(|byte| OpCode::try_from(byte).ok())
+            .find(|op| op.mnemonic().eq_ignore_ascii_case(name))
+    }
+
+    pub fn immediate(self) -> Immediate {
+        match self {
+            OpCode::Push => Immediate::I32,
+            OpCode::Jump | OpCode::JumpIf | OpCode::Call => Immediate::Target,
+            OpCode::GasLimitBegin => Immediate::GasScope,
+            OpCode::LoadImm => Immediate::Bytes,
+            OpCode::LoadConst => Immediate::U32,
+            OpCode::Mask
+            | OpCode::SignExtend
+            | OpCode::IntToBytes
+            | OpCode::BytesResize
+            | OpCode::Log => Immediate::U8,
+            _ => Immediate::None,
+        }
+    }
+
+    /// Group the gas an instruction charges is reported under.
+    pub fn gas_class(self) -> GasClass {
+        match self as u8 {
+            0x00..=0x0F => GasClass::Stack,
+            0x10..=0x1F => GasClass::Arithmetic,
+            0x20..=0x2F => GasClass::Comparison,
+            0x30..=0x3F => GasClass::Control,
+            0x40..=0x4F => GasClass::Memory,
+            0x50..=0x5F => GasClass::Bytes,
+            0x60..=0x6F => GasClass::Conversion,
+            0x70..=0x7F => GasClass::Storage,
+            0x80..=0x8F => GasClass::Pqc,
+            0xA0..=0xAF => GasClass::Log,
+            0xB7..=0xBF => GasClass::Value,
+            0xB0..=0xB6 => GasClass::Environment,
+            _ => GasClass::Utility,
+        }
+    }
+
+    /// Values popped and pushed by one execution of the instruction. `Log` also pops the
+    /// number of values its operand names; see `verifier::Instruction::stack_effect`.
+    pub fn stack_effect(self) -> (usize, usize) {
+        match self {
+            OpCode::Push
+            | OpCode::LoadImm
+            | OpCode::LoadConst
+            | OpCode::MSize
+            | OpCode::CallDataSize
+            | OpCode::Selector
+            | OpCode::Address
+            | OpCode::Caller
+            | OpCode::CallValue
+            | OpCode::SelfBalance => (0, 1),
+            OpCode::Pop | OpCode::JumpIf | OpCode::Print | OpCode::Log | OpCode::Revert => (1, 0),
+            OpCode::Dup => (1, 2),
+            OpCode::Swap => (2, 2),
+            OpCode::Add
+            | OpCode::Sub
+            | OpCode::Mul
+            | OpCode::Div
+            | OpCode::Eq
+            | OpCode::Ne
+            | OpCode::Lt
+            | OpCode::Le
+            | OpCode::Gt
+            | OpCode::Ge
+            | OpCode::BoundsCheck
+            | OpCode::StoreBytes
+            | OpCode::BytesConcat
+            | OpCode::BytesEq
+            | OpCode::CallDataSlice
+            | OpCode::SlotHash
+            | OpCode::SlotAdd
+            | OpCode::MLKEMKeyExchange
+            | OpCode::HQCKEM128KeyExchange
+            | OpCode::HQCKEM192KeyExchange
+            | OpCode::HQCKEM256KeyExchange => (2, 1),
+            OpCode::Store | OpCode::Store8 | OpCode::SStore | OpCode::Transfer => (2, 0),
+            OpCode::MCopy => (3, 0),
+            OpCode::BytesSlice
+            | OpCode::MLDSAVerify
+            | OpCode::FNDSAVerify
+            | OpCode::SLHDSAVerify => (3, 1),
+            OpCode::Load
+            | OpCode::Load8
+            | OpCode::LoadBytes
+            | OpCode::BytesLen
+            | OpCode::Mask
+            | OpCode::SignExtend
+            | OpCode::IntToBytes
+            | OpCode::BytesToInt
+            | OpCode::BytesResize
+            | OpCode::SLoad
+            | OpCode::SLoadBytes
+            | OpCode::CallDataLoad
+            | OpCode::Balance => (1, 1),
+            OpCode::Jump
+            | OpCode::Call
+            | OpCode::Return
+            | OpCode::GasLimitBegin
+            | OpCode::GasLimitEnd
+            | OpCode::Halt => (0, 0),
+        }
+    }
+}
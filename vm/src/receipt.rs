@@ -0,0 +1,116 @@
+use crate::opcode::VMError;
+use crate::state::{hex, LogEntry};
+use crate::vm::Value;
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
+
+/// Opcode groups gas is reported by, following the opcode table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GasClass {
+    Stack,
+    Arithmetic,
+    Comparison,
+    Control,
+    Memory,
+    Bytes,
+    Conversion,
+    Storage,
+    Pqc,
+    Log,
+    Environment,
+    Value,
+    Utility,
+}
+
+/// How a deploy, call or execution ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionStatus {
+    Success,
+    /// The code ran `REVERT`.
+    Reverted,
+    OutOfGas,
+    /// Any other error: invalid bytecode, a failed check, a bad operand, ...
+    Failed,
+}
+
+/// Outcome of one deploy, call or execution, from `QuantumVM::receipt`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecutionReceipt {
+    pub status: ExecutionStatus,
+    /// Values left on the stack, bottom first; an entry point's return value is the last.
+    /// Empty unless the run succeeded.
+    pub return_data: Vec<Value>,
+    /// Message passed to `REVERT`, when the run reverted.
+    #[serde(serialize_with = "serialize_optional_bytes")]
+    pub revert_data: Option<Vec<u8>>,
+    /// Error of a run that did not succeed.
+    pub error: Option<String>,
+    /// Logs emitted, in order; empty unless the run succeeded.
+    pub logs: Vec<LogEntry>,
+    pub gas_used: u64,
+    /// Part of `gas_used` charged by PQC operations.
+    pub pqc_gas_used: u64,
+    /// `gas_used` split by the class of the opcode that charged it.
+    pub gas_breakdown: BTreeMap<GasClass, u64>,
+    /// Values written by `PRINT`, in order.
+    pub output: Vec<Value>,
+}
+
+impl ExecutionReceipt {
+    pub fn is_success(&self) -> bool {
+        self.status == ExecutionStatus::Success
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("receipts always serialize")
+    }
+}
+
+impl ExecutionStatus {
+    pub fn of<T>(result: &Result<T, VMError>) -> ExecutionStatus {
+        match result {
+            Ok(_) => ExecutionStatus::Success,
+            Err(VMError::Revert(_)) => ExecutionStatus::Reverted,
+            Err(VMError::OutOfGas(_)) => ExecutionStatus::OutOfGas,
+            Err(_) => ExecutionStatus::Failed,
+        }
+    }
+}
+
+// Integers and bools serialize as JSON numbers and booleans, byte strings as `0x` hex.
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::I32(value) => serializer.serialize_i32(*value),
+            Value::I64(value) => serializer.serialize_i64(*value),
+            Value::Bool(value) => serializer.serialize_bool(*value),
+            Value::Bytes(bytes) => serialize_bytes(bytes, serializer),
+        }
+    }
+}
+
+pub(crate) fn serialize_bytes<S: Serializer>(
+    bytes: &[u8],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("0x{}", hex(bytes)))
+}
+
+pub(crate) fn serialize_topic<S: Serializer>(
+    topic: &u32,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("0x{:08x}", topic))
+}
+
+pub(crate) fn serialize_optional_bytes<T: AsRef<[u8]>, S: Serializer>(
+    bytes: &Option<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match bytes {
+        Some(bytes) => serialize_bytes(bytes.as_ref(), serializer),
+        None => serializer.serialize_none(),
+    }
+}
@@ -0,0 +1,171 @@
+use crate::opcode::VMError;
+use std::fmt;
+
+// One source map row: code from `offset` up to the next row belongs to this line and function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceMapEntry {
+    pub offset: u32,
+    pub line: u32,
+    pub column: u32,
+    /// Index into `SourceMap::functions`.
+    pub function: u32,
+}
+
+// Debug information mapping code offsets back to the source they were compiled from
+//
+// Section layout: the source file name and a table of function names, each a `u32` length
+// followed by the bytes (the table is prefixed by its `u32` count), then entries of
+// (offset, line, column, function index) as four `u32`s in ascending offset order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceMap {
+    pub file: String,
+    pub functions: Vec<String>,
+    pub entries: Vec<SourceMapEntry>,
+}
+
+/// Source position of a code offset, rendered as `file:line:column in function`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+    pub function: String,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.file.is_empty() {
+            write!(f, "{}:", self.file)?;
+        }
+        write!(f, "{}:{} in {}", self.line, self.column, self.function)
+    }
+}
+
+impl SourceMap {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Attributes code from `offset` onwards to `line`, `column` and `function`. A row at the
+    /// same offset is replaced, and a row repeating the previous position is dropped.
+    pub fn record(&mut self, offset: u32, line: u32, column: u32, function: &str) {
+        let function = match self.functions.iter().position(|name| name == function) {
+            Some(index) => index,
+            None => {
+                self.functions.push(function.to_string());
+                self.functions.len() - 1
+            }
+        } as u32;
+        let entry = SourceMapEntry {
+            offset,
+            line,
+            column,
+            function,
+        };
+        match self.entries.last_mut() {
+            Some(last) if last.offset == offset => *last = entry,
+            Some(last) if (last.line, last.column, last.function) == (line, column, function) => {}
+            _ => self.entries.push(entry),
+        }
+    }
+
+    /// Position of the code at `offset`, if any row covers it.
+    pub fn locate(&self, offset: usize) -> Option<SourceLocation> {
+        let row = self
+            .entries
+            .partition_point(|entry| entry.offset as usize <= offset)
+            .checked_sub(1)?;
+        let entry = &self.entries[row];
+        Some(SourceLocation {
+            file: self.file.clone(),
+            line: entry.line,
+            column: entry.column,
+            function: self.functions[entry.function as usize].clone(),
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let push_str = |bytes: &mut Vec<u8>, text: &str| {
+            bytes.extend_from_slice(&(text.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(text.as_bytes());
+        };
+        push_str(&mut bytes, &self.file);
+        bytes.extend_from_slice(&(self.functions.len() as u32).to_le_bytes());
+        for function in &self.functions {
+            push_str(&mut bytes, function);
+        }
+        for entry in &self.entries {
+            for field in [entry.offset, entry.line, entry.column, entry.function] {
+                bytes.extend_from_slice(&field.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, VMError> {
+        let invalid =
+            |message: String| VMError::InvalidBytecode(format!("Source map: {}", message));
+        let mut rest = bytes;
+        let read_u32 = |rest: &mut &[u8], what: &str| -> Result<u32, VMError> {
+            let (value, tail) = rest
+                .split_first_chunk::<4>()
+                .ok_or_else(|| invalid(format!("truncated {}", what)))?;
+            *rest = tail;
+            Ok(u32::from_le_bytes(*value))
+        };
+        let read_str = |rest: &mut &[u8], what: &str| -> Result<String, VMError> {
+            let len = read_u32(rest, what)? as usize;
+            if rest.len() < len {
+                return Err(invalid(format!("truncated {}", what)));
+            }
+            let (text, tail) = rest.split_at(len);
+            *rest = tail;
+            String::from_utf8(text.to_vec()).map_err(|_| invalid(format!("{} is not UTF-8", what)))
+        };
+
+        let file = read_str(&mut rest, "file name")?;
+        let count = read_u32(&mut rest, "function count")?;
+        let functions = (0..count)
+            .map(|_| read_str(&mut rest, "function name"))
+            .collect::<Result<Vec<_>, _>>()?;
+        if !rest.len().is_multiple_of(16) {
+            return Err(invalid(format!(
+                "{} bytes of entries is not a whole number of 16-byte rows",
+                rest.len()
+            )));
+        }
+
+        let mut entries: Vec<SourceMapEntry> = Vec::with_capacity(rest.len() / 16);
+        for row in rest.chunks_exact(16) {
+            let field = |at: usize| u32::from_le_bytes(row[at..at + 4].try_into().unwrap());
+            let entry = SourceMapEntry {
+                offset: field(0),
+                line: field(4),
+                column: field(8),
+                function: field(12),
+            };
+            if entry.function >= count {
+                return Err(invalid(format!(
+                    "row at offset {} names function {} of {}",
+                    entry.offset, entry.function, count
+                )));
+            }
+            if entries
+                .last()
+                .is_some_and(|last| last.offset >= entry.offset)
+            {
+                return Err(invalid(format!(
+                    "row at offset {} is out of order",
+                    entry.offset
+                )));
+            }
+            entries.push(entry);
+        }
+        Ok(SourceMap {
+            file,
+            functions,
+            entries,
+        })
+    }
+}
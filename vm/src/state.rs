@@ -0,0 +1,311 @@
+use crate::instance::{Storage, StorageKey};
+use crate::opcode::VMError;
+use crate::receipt::{serialize_bytes, serialize_topic};
+use crate::vm::Value;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// 20-byte account address.
+pub type Address = [u8; 20];
+
+// One account: externally owned when it has no code, a contract otherwise
+#[derive(Debug, Clone, Default)]
+pub struct Account {
+    pub nonce: u64,
+    pub balance: u64,
+    /// Runtime container installed by a deploy; empty for externally owned accounts.
+    pub code: Vec<u8>,
+    pub storage: Storage,
+}
+
+impl Account {
+    pub fn is_contract(&self) -> bool {
+        !self.code.is_empty()
+    }
+}
+
+// One event emitted by a contract: an event id and the emitted values
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+    #[serde(serialize_with = "serialize_bytes")]
+    pub address: Address,
+    #[serde(serialize_with = "serialize_topic")]
+    pub topic: u32,
+    pub data: Vec<Value>,
+}
+
+/// Address of the contract `deployer` creates with its account at `nonce`: the last 20 bytes
+/// of SHA-256 over the deployer address followed by the nonce as 8 big-endian bytes.
+pub fn contract_address(deployer: &Address, nonce: u64) -> Address {
+    let mut hasher = Sha256::new();
+    hasher.update(deployer);
+    hasher.update(nonce.to_be_bytes());
+    let digest = hasher.finalize();
+    digest[12..].try_into().expect("20 bytes")
+}
+
+/// Persistent account store behind a `WorldState`. A node implements it over its database;
+/// `MemoryBackend` keeps everything in a map.
+pub trait StateBackend {
+    /// The committed account at `address`, if it exists.
+    fn account(&self, address: &Address) -> Option<Account>;
+
+    /// Writes a batch of committed changes: `Some` replaces an account, `None` deletes it.
+    fn apply(&mut self, changes: BTreeMap<Address, Option<Account>>) -> Result<(), VMError>;
+}
+
+/// Balances running code can read with `BALANCE`. A call against a `WorldState` sees its
+/// accounts; standalone runs use `()`, where every account is empty.
+pub trait Balances {
+    fn balance(&mut self, address: &Address) -> u64;
+}
+
+impl Balances for () {
+    fn balance(&mut self, _address: &Address) -> u64 {
+        0
+    }
+}
+
+impl<B: StateBackend> Balances for WorldState<B> {
+    fn balance(&mut self, address: &Address) -> u64 {
+        WorldState::balance(self, address)
+    }
+}
+
+// Accounts held in memory, for tests and single-process tools
+#[derive(Debug, Clone, Default)]
+pub struct MemoryBackend {
+    pub accounts: BTreeMap<Address, Account>,
+}
+
+impl StateBackend for MemoryBackend {
+    fn account(&self, address: &Address) -> Option<Account> {
+        self.accounts.get(address).cloned()
+    }
+
+    fn apply(&mut self, changes: BTreeMap<Address, Option<Account>>) -> Result<(), VMError> {
+        for (address, account) in changes {
+            match account {
+                Some(account) => self.accounts.insert(address, account),
+                None => self.accounts.remove(&address),
+            };
+        }
+        Ok(())
+    }
+}
+
+// Undo record for one change made since the last commit
+#[derive(Debug, Clone)]
+enum JournalEntry {
+    /// The account did not exist before this change.
+    Created(Address),
+    Nonce(Address, u64),
+    Balance(Address, u64),
+    Code(Address, Vec<u8>),
+    /// Previous value of a storage slot; `None` if it was never written.
+    Storage(Address, StorageKey, Option<Value>),
+    Log,
+}
+
+/// Journal position to roll back to, from `WorldState::checkpoint`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint(usize);
+
+/// Accounts as seen by execution: reads fall through to the backend, writes stay in an
+/// overlay until `commit`, and every write is journaled so `revert_to` can undo it.
+pub struct WorldState<B: StateBackend> {
+    backend: B,
+    // Accounts read or written since the last commit; `None` caches a missing account
+    accounts: BTreeMap<Address, Option<Account>>,
+    // Logs of the changes since the last commit, in emission order
+    logs: Vec<LogEntry>,
+    journal: Vec<JournalEntry>,
+}
+
+impl<B: StateBackend> WorldState<B> {
+    pub fn new(backend: B) -> Self {
+        WorldState {
+            backend,
+            accounts: BTreeMap::new(),
+            logs: Vec::new(),
+            journal: Vec::new(),
+        }
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// The account at `address` including uncommitted changes, if it exists.
+    pub fn account(&mut self, address: &Address) -> Option<&Account> {
+        self.load(address).as_ref()
+    }
+
+    pub fn exists(&mut self, address: &Address) -> bool {
+        self.account(address).is_some()
+    }
+
+    pub fn nonce(&mut self, address: &Address) -> u64 {
+        self.account(address).map_or(0, |account| account.nonce)
+    }
+
+    pub fn balance(&mut self, address: &Address) -> u64 {
+        self.account(address).map_or(0, |account| account.balance)
+    }
+
+    pub fn code(&mut self, address: &Address) -> Vec<u8> {
+        self.account(address)
+            .map(|account| account.code.clone())
+            .unwrap_or_default()
+    }
+
+    /// Value of storage `slot` of `address`; unwritten slots read as zero.
+    pub fn storage(&mut self, address: &Address, slot: impl Into<StorageKey>) -> Value {
+        let slot = slot.into();
+        self.account(address)
+            .and_then(|account| account.storage.get(&slot).cloned())
+            .unwrap_or(Value::I32(0))
+    }
+
+    pub fn set_nonce(&mut self, address: &Address, nonce: u64) {
+        let account = self.account_mut(address);
+        let previous = std::mem::replace(&mut account.nonce, nonce);
+        self.journal.push(JournalEntry::Nonce(*address, previous));
+    }
+
+    /// Returns the nonce before the increment.
+    pub fn increment_nonce(&mut self, address: &Address) -> Result<u64, VMError> {
+        let nonce = self.nonce(address);
+        let next = nonce.checked_add(1).ok_or_else(|| {
+            VMError::RuntimeError(format!("Nonce of account 0x{} overflowed", hex(address)))
+        })?;
+        self.set_nonce(address, next);
+        Ok(nonce)
+    }
+
+    pub fn set_balance(&mut self, address: &Address, balance: u64) {
+        let account = self.account_mut(address);
+        let previous = std::mem::replace(&mut account.balance, balance);
+        self.journal.push(JournalEntry::Balance(*address, previous));
+    }
+
+    pub fn set_code(&mut self, address: &Address, code: Vec<u8>) {
+        let account = self.account_mut(address);
+        let previous = std::mem::replace(&mut account.code, code);
+        self.journal.push(JournalEntry::Code(*address, previous));
+    }
+
+    pub fn set_storage(&mut self, address: &Address, slot: impl Into<StorageKey>, value: Value) {
+        let slot = slot.into();
+        let account = self.account_mut(address);
+        let previous = account.storage.insert(slot, value);
+        self.journal
+            .push(JournalEntry::Storage(*address, slot, previous));
+    }
+
+    /// Logs recorded since the last commit.
+    pub fn logs(&self) -> &[LogEntry] {
+        &self.logs
+    }
+
+    pub fn push_log(&mut self, log: LogEntry) {
+        self.logs.push(log);
+        self.journal.push(JournalEntry::Log);
+    }
+
+    /// Creates the contract account at `address`, e.g. from `contract_address`. Fails if an
+    /// account with code or a nonce already lives there.
+    pub fn create_contract(&mut self, address: &Address) -> Result<(), VMError> {
+        if self
+            .account(address)
+            .is_some_and(|account| account.is_contract() || account.nonce > 0)
+        {
+            return Err(VMError::RuntimeError(format!(
+                "Contract address 0x{} is already in use",
+                hex(address)
+            )));
+        }
+        // Contract nonces start at 1 so a fresh contract never looks unused.
+        self.set_nonce(address, 1);
+        Ok(())
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.journal.len())
+    }
+
+    /// Undoes every change made since `checkpoint`, newest first.
+    pub fn revert_to(&mut self, checkpoint: Checkpoint) {
+        while self.journal.len() > checkpoint.0 {
+            let entry = self
+                .journal
+                .pop()
+                .expect("journal is longer than checkpoint");
+            match entry {
+                JournalEntry::Created(address) => {
+                    self.accounts.insert(address, None);
+                }
+                JournalEntry::Log => {
+                    self.logs.pop();
+                }
+                JournalEntry::Nonce(address, nonce) => self.account_mut(&address).nonce = nonce,
+                JournalEntry::Balance(address, balance) => {
+                    self.account_mut(&address).balance = balance
+                }
+                JournalEntry::Code(address, code) => self.account_mut(&address).code = code,
+                JournalEntry::Storage(address, slot, value) => {
+                    let storage = &mut self.account_mut(&address).storage;
+                    match value {
+                        Some(value) => storage.insert(slot, value),
+                        None => storage.remove(&slot),
+                    };
+                }
+            }
+        }
+    }
+
+    /// Writes every account changed since the last commit to the backend, clears the journal
+    /// and returns the logs emitted since the last commit.
+    pub fn commit(&mut self) -> Result<Vec<LogEntry>, VMError> {
+        let mut changes = BTreeMap::new();
+        for entry in std::mem::take(&mut self.journal) {
+            let address = match entry {
+                JournalEntry::Created(address)
+                | JournalEntry::Nonce(address, _)
+                | JournalEntry::Balance(address, _)
+                | JournalEntry::Code(address, _)
+                | JournalEntry::Storage(address, _, _) => address,
+                JournalEntry::Log => continue,
+            };
+            changes
+                .entry(address)
+                .or_insert_with(|| self.accounts[&address].clone());
+        }
+        self.accounts.clear();
+        self.backend.apply(changes)?;
+        Ok(std::mem::take(&mut self.logs))
+    }
+
+    fn load(&mut self, address: &Address) -> &Option<Account> {
+        let backend = &self.backend;
+        self.accounts
+            .entry(*address)
+            .or_insert_with(|| backend.account(address))
+    }
+
+    // Returns the account, creating (and journaling) an empty one if it does not exist.
+    fn account_mut(&mut self, address: &Address) -> &mut Account {
+        if self.load(address).is_none() {
+            self.journal.push(JournalEntry::Created(*address));
+        }
+        self.accounts
+            .get_mut(address)
+            .expect("loaded above")
+            .get_or_insert_with(Account::default)
+    }
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -0,0 +1,419 @@
+use crate::opcode::{Immediate, OpCode, VMError};
+use crate::vm::STACK_LIMIT;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+// Decoded operand of one instruction
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    None,
+    U8(u8),
+    I32(i32),
+    U32(u32),
+    Target(u32),
+    GasScope { limit: u32, handler: u32 },
+    Bytes(Vec<u8>),
+}
+
+// One decoded instruction and its offset in the code section
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    pub offset: usize,
+    pub opcode: OpCode,
+    pub operand: Operand,
+}
+
+impl Instruction {
+    /// Encoded size in bytes, including the opcode.
+    pub fn size(&self) -> usize {
+        1 + match &self.operand {
+            Operand::None => 0,
+            Operand::U8(_) => 1,
+            Operand::I32(_) | Operand::U32(_) | Operand::Target(_) => 4,
+            Operand::GasScope { .. } => 8,
+            Operand::Bytes(bytes) => 4 + bytes.len(),
+        }
+    }
+
+    pub fn end(&self) -> usize {
+        self.offset + self.size()
+    }
+
+    /// Values popped and pushed, counting the values a `Log` operand names.
+    pub fn stack_effect(&self) -> (usize, usize) {
+        match (self.opcode, &self.operand) {
+            (OpCode::Log, Operand::U8(count)) => (1 + usize::from(*count), 0),
+            _ => self.opcode.stack_effect(),
+        }
+    }
+}
+
+// Straight-line run of instructions with a single entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasicBlock {
+    pub start: usize,
+    pub end: usize,
+    /// Stack values the block pops below its entry height.
+    pub required: usize,
+    /// Highest stack height reached above the entry height.
+    pub peak: usize,
+    /// Height change from entry to exit.
+    pub delta: isize,
+    /// Lowest height on entry over every path from offset 0, counting calls into the block's
+    /// function; `None` if no path reaches it.
+    pub entry_height: Option<usize>,
+}
+
+// Result of a successful verification
+#[derive(Debug, Clone)]
+pub struct VerifiedCode {
+    pub instructions: Vec<Instruction>,
+    pub blocks: Vec<BasicBlock>,
+}
+
+/// Decodes every instruction, rejecting unknown opcodes and truncated immediates.
+pub fn decode(code: &[u8]) -> Result<Vec<Instruction>, VMError> {
+    let mut instructions = Vec::new();
+    let mut offset = 0;
+    while offset < code.len() {
+        let byte = code[offset];
+        let opcode = OpCode::try_from(byte).map_err(|_| {
+            VMError::InvalidBytecode(format!(
+                "Unknown opcode 0x{:02x} at offset {}",
+                byte, offset
+            ))
+        })?;
+        let operands = &code[offset + 1..];
+        let truncated = |needed: usize| {
+            VMError::InvalidBytecode(format!(
+                "{:?} at offset {} needs {} immediate bytes but only {} remain",
+                opcode,
+                offset,
+                needed,
+                operands.len()
+            ))
+        };
+        let u32_at = |at: usize| -> Result<u32, VMError> {
+            operands
+                .get(at..at + 4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .ok_or_else(|| truncated(at + 4))
+        };
+        let operand = match opcode.immediate() {
+            Immediate::None => Operand::None,
+            Immediate::U8 => Operand::U8(*operands.first().ok_or_else(|| truncated(1))?),
+            Immediate::I32 => Operand::I32(u32_at(0)? as i32),
+            Immediate::U32 => Operand::U32(u32_at(0)?),
+            Immediate::Target => Operand::Target(u32_at(0)?),
+            Immediate::GasScope => Operand::GasScope {
+                limit: u32_at(0)?,
+                handler: u32_at(4)?,
+            },
+            Immediate::Bytes => {
+                let len = u32_at(0)? as usize;
+                let bytes = operands
+                    .get(4..)
+                    .and_then(|rest| rest.get(..len))
+                    .ok_or_else(|| truncated(4 + len))?;
+                Operand::Bytes(bytes.to_vec())
+            }
+        };
+        let instruction = Instruction {
+            offset,
+            opcode,
+            operand,
+        };
+        offset = instruction.end();
+        instructions.push(instruction);
+    }
+    Ok(instructions)
+}
+
+/// Checks a code section before execution: every instruction decodes, every jump, call and
+/// gas-scope handler lands on an instruction boundary, and no block reached from offset 0 can
+/// underflow or overflow the stack.
+pub fn verify(code: &[u8]) -> Result<VerifiedCode, VMError> {
+    let instructions = decode(code)?;
+    let boundaries: BTreeSet<usize> = instructions.iter().map(|i| i.offset).collect();
+
+    let mut leaders = BTreeSet::from([0]);
+    for instruction in &instructions {
+        let target = match instruction.operand {
+            Operand::Target(target) => Some((target as usize, format!("{:?}", instruction.opcode))),
+            Operand::GasScope { handler, .. } => {
+                Some((handler as usize, "Gas scope handler".to_string()))
+            }
+            _ => None,
+        };
+        if let Some((target, kind)) = target {
+            // A handler at the very end of the code halts, as falling off the end does.
+            let halts = instruction.opcode == OpCode::GasLimitBegin && target == code.len();
+            if target >= code.len() && !halts {
+                return Err(VMError::InvalidBytecode(format!(
+                    "{} target {} at offset {} is outside the {}-byte code section",
+                    kind,
+                    target,
+                    instruction.offset,
+                    code.len()
+                )));
+            }
+            if !halts && !boundaries.contains(&target) {
+                return Err(VMError::InvalidBytecode(format!(
+                    "{} target {} at offset {} lands inside an instruction",
+                    kind, target, instruction.offset
+                )));
+            }
+            leaders.insert(target);
+        }
+        if ends_block(instruction.opcode) {
+            leaders.insert(instruction.end());
+        }
+    }
+
+    let mut blocks: Vec<BasicBlock> = Vec::new();
+    for instruction in &instructions {
+        if leaders.contains(&instruction.offset) {
+            blocks.push(BasicBlock {
+                start: instruction.offset,
+                end: instruction.offset,
+                required: 0,
+                peak: 0,
+                delta: 0,
+                entry_height: None,
+            });
+        }
+        let block = blocks.last_mut().expect("offset 0 leads the first block");
+        let (pops, pushes) = instruction.stack_effect();
+        let height = block.delta - pops as isize;
+        block.required = block.required.max((-height).max(0) as usize);
+        block.delta = height + pushes as isize;
+        block.peak = block.peak.max(block.delta.max(0) as usize);
+        block.end = instruction.end();
+    }
+
+    propagate_heights(&instructions, &mut blocks, code.len())?;
+    Ok(VerifiedCode {
+        instructions,
+        blocks,
+    })
+}
+
+fn ends_block(opcode: OpCode) -> bool {
+    matches!(
+        opcode,
+        OpCode::Jump
+            | OpCode::JumpIf
+            | OpCode::Call
+            | OpCode::Return
+            | OpCode::Halt
+            | OpCode::Revert
+            | OpCode::GasLimitBegin
+    )
+}
+
+// Walks the control-flow graph from offset 0, keeping the lowest entry height of each block,
+// and rejects blocks that pop below it or push past the stack limit. Within a function every
+// path must reach a block at the same height, so a loop cannot grow the stack unnoticed. A
+// callee is entered at the caller's height and execution resumes after the `Call` shifted by
+// the callee's net effect.
+fn propagate_heights(
+    instructions: &[Instruction],
+    blocks: &mut [BasicBlock],
+    code_len: usize,
+) -> Result<(), VMError> {
+    let Some(first) = blocks.first_mut() else {
+        return Ok(());
+    };
+    first.entry_height = Some(0);
+    let effects = call_effects(instructions, blocks, code_len);
+    for start in std::iter::once(0).chain(effects.keys().copied()) {
+        walk_function(instructions, blocks, start, code_len, &effects, true)?;
+    }
+
+    let mut queue = VecDeque::from([0usize]);
+    while let Some(index) = queue.pop_front() {
+        let block = &blocks[index];
+        let entry = block.entry_height.expect("queued blocks have a height");
+        if block.required > entry {
+            return Err(underflow(instructions, block, entry));
+        }
+        if entry + block.peak > STACK_LIMIT {
+            return Err(VMError::InvalidBytecode(format!(
+                "Block at offset {} reaches stack height {}, above the limit of {}",
+                block.start,
+                entry + block.peak,
+                STACK_LIMIT
+            )));
+        }
+        let exit = entry as isize + block.delta;
+
+        let last = last_instruction(instructions, block);
+        let mut successors = successors(&last, block.end, exit, &effects);
+        if let (OpCode::Call, Operand::Target(target)) = (last.opcode, &last.operand) {
+            successors.push((*target as usize, exit));
+        }
+
+        for (successor, height) in successors.into_iter().filter(|(s, _)| *s < code_len) {
+            // A callee returning below the empty stack underflows in one of its own blocks.
+            let Ok(height) = usize::try_from(height) else {
+                continue;
+            };
+            let next = blocks
+                .binary_search_by_key(&successor, |b| b.start)
+                .expect("targets start blocks");
+            let lowered = match blocks[next].entry_height {
+                Some(lowest) if lowest <= height => false,
+                _ => {
+                    blocks[next].entry_height = Some(height);
+                    true
+                }
+            };
+            if lowered && !queue.contains(&next) {
+                queue.push_back(next);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Net stack effect of every call target: the lowest height relative to its entry at which it
+// returns, or `None` if it never returns. Recursive calls are settled by iterating to a fixpoint.
+fn call_effects(
+    instructions: &[Instruction],
+    blocks: &[BasicBlock],
+    code_len: usize,
+) -> BTreeMap<usize, Option<isize>> {
+    let mut effects: BTreeMap<usize, Option<isize>> = instructions
+        .iter()
+        .filter(|i| i.opcode == OpCode::Call)
+        .filter_map(|i| match i.operand {
+            Operand::Target(target) if (target as usize) < code_len => {
+                Some((target as usize, None))
+            }
+            _ => None,
+        })
+        .collect();
+    loop {
+        let mut changed = false;
+        for target in effects.keys().copied().collect::<Vec<_>>() {
+            let returned = walk_function(instructions, blocks, target, code_len, &effects, false)
+                .expect("only a strict walk fails");
+            if effects[&target] != returned {
+                effects.insert(target, returned);
+                changed = true;
+            }
+        }
+        if !changed {
+            return effects;
+        }
+    }
+}
+
+// Walks the function starting at `start` with heights relative to its entry and returns the
+// lowest height at which it returns. A strict walk also rejects a block entered at two
+// different heights, such as a loop that grows the stack on every pass.
+fn walk_function(
+    instructions: &[Instruction],
+    blocks: &[BasicBlock],
+    start: usize,
+    code_len: usize,
+    effects: &BTreeMap<usize, Option<isize>>,
+    strict: bool,
+) -> Result<Option<isize>, VMError> {
+    // A callee cannot pop more than the whole stack, which bounds every walk below.
+    let floor = -(STACK_LIMIT as isize);
+    let start = blocks
+        .binary_search_by_key(&start, |b| b.start)
+        .expect("targets start blocks");
+    let mut heights = BTreeMap::from([(start, 0isize)]);
+    let mut queue = VecDeque::from([start]);
+    let mut returned: Option<isize> = None;
+    while let Some(index) = queue.pop_front() {
+        let block = &blocks[index];
+        let exit = heights[&index] + block.delta;
+        let last = last_instruction(instructions, block);
+        if last.opcode == OpCode::Return {
+            returned = Some(returned.map_or(exit, |lowest| lowest.min(exit)));
+        }
+        for (successor, height) in successors(&last, block.end, exit, effects) {
+            if successor >= code_len {
+                continue;
+            }
+            let height = height.max(floor);
+            let next = blocks
+                .binary_search_by_key(&successor, |b| b.start)
+                .expect("targets start blocks");
+            match heights.get(&next) {
+                Some(known) if strict && *known != height => {
+                    return Err(VMError::InvalidBytecode(format!(
+                        "Block at offset {} is entered with {} and with {} values relative to \
+                         its function's entry",
+                        blocks[next].start, known, height
+                    )));
+                }
+                Some(lowest) if *lowest <= height => continue,
+                _ => {}
+            }
+            heights.insert(next, height);
+            if !queue.contains(&next) {
+                queue.push_back(next);
+            }
+        }
+    }
+    Ok(returned)
+}
+
+// Blocks entered from the end of `last` at `exit`, staying within the current function: a call
+// resumes after the callee returns, shifted by its net effect.
+fn successors(
+    last: &Instruction,
+    end: usize,
+    exit: isize,
+    effects: &BTreeMap<usize, Option<isize>>,
+) -> Vec<(usize, isize)> {
+    match (last.opcode, &last.operand) {
+        (OpCode::Jump, Operand::Target(target)) => vec![(*target as usize, exit)],
+        (OpCode::JumpIf, Operand::Target(target)) => {
+            vec![(*target as usize, exit), (end, exit)]
+        }
+        // The scope snapshot is restored before the handler runs.
+        (OpCode::GasLimitBegin, Operand::GasScope { handler, .. }) => {
+            vec![(*handler as usize, exit), (end, exit)]
+        }
+        (OpCode::Call, Operand::Target(target)) => {
+            match effects.get(&(*target as usize)).copied().flatten() {
+                Some(effect) => vec![(end, exit + effect)],
+                None => Vec::new(),
+            }
+        }
+        (OpCode::Return | OpCode::Halt | OpCode::Revert, _) => Vec::new(),
+        _ => vec![(end, exit)],
+    }
+}
+
+fn last_instruction(instructions: &[Instruction], block: &BasicBlock) -> Instruction {
+    instructions
+        .iter()
+        .rev()
+        .find(|i| i.offset < block.end)
+        .filter(|i| i.offset >= block.start)
+        .expect("blocks are never empty")
+        .clone()
+}
+
+fn underflow(instructions: &[Instruction], block: &BasicBlock, entry: usize) -> VMError {
+    let mut height = entry as isize;
+    for instruction in instructions
+        .iter()
+        .filter(|i| i.offset >= block.start && i.offset < block.end)
+    {
+        let (pops, pushes) = instruction.stack_effect();
+        if (pops as isize) > height {
+            return VMError::InvalidBytecode(format!(
+                "{:?} at offset {} pops {} values but only {} are guaranteed on the stack",
+                instruction.opcode, instruction.offset, pops, height
+            ));
+        }
+        height += pushes as isize - pops as isize;
+    }
+    unreachable!("block requires more values than its entry height")
+}
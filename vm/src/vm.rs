@@ -1,6 +1,12 @@
+use super::gas_schedule::GasSchedule;
+use super::instance::{derived_slot, ContractInstance, Storage, StorageKey};
 use super::opcode::{OpCode, VMError};
+use super::receipt::{ExecutionReceipt, ExecutionStatus, GasClass};
+use super::source_map::{SourceLocation, SourceMap};
+use super::state::{contract_address, hex, Address, Balances, LogEntry, StateBackend, WorldState};
+use super::verifier;
 use pqsynq::{DigitalSignature, Kem, KeyEncapsulation, Sign};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 // Value types that can be stored on the stack
 #[derive(Debug, Clone)]
@@ -41,9 +47,90 @@ impl Value {
             _ => Err(VMError::RuntimeError("Expected bool".to_string())),
         }
     }
+
+    /// Whether both values have the same variant and contents; unlike `Eq`, `I32(1)` and
+    /// `I64(1)` differ.
+    pub fn same_as(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::I32(a), Value::I32(b)) => a == b,
+            (Value::I64(a), Value::I64(b)) => a == b,
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    /// Integer result of a conversion, kept as `I32` whenever it fits.
+    pub fn from_i64(value: i64) -> Value {
+        i32::try_from(value).map_or(Value::I64(value), Value::I32)
+    }
+
+    /// Equality used by `Eq`/`Ne`: byte strings compare by content, booleans by truth value,
+    /// and integers numerically across widths.
+    pub fn equals(&self, other: &Value) -> Result<bool, VMError> {
+        match (self, other) {
+            (Value::Bytes(a), Value::Bytes(b)) => Ok(a == b),
+            (Value::Bytes(_), _) | (_, Value::Bytes(_)) => Err(VMError::RuntimeError(
+                "Cannot compare bytes with a non-bytes value".to_string(),
+            )),
+            (Value::Bool(_), _) | (_, Value::Bool(_)) => Ok(self.as_bool()? == other.as_bool()?),
+            _ => Ok(self.as_i64()? == other.as_i64()?),
+        }
+    }
+}
+
+/// Maximum number of values on the operand stack.
+pub const STACK_LIMIT: usize = 1000;
+
+/// Maximum number of gas scopes open at once.
+pub const GAS_SCOPE_LIMIT: usize = 64;
+
+// Sections of a v2 container
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SectionKind {
+    Code = 1,
+    /// Constant pool read by `LoadConst`: entries of a `u32` length followed by the bytes.
+    Data = 2,
+    /// ABI and other tooling metadata; ignored by the VM.
+    Metadata = 3,
+    /// Code offsets mapped to source lines and functions; only used to annotate errors.
+    SourceMap = 4,
+    /// Runtime container installed by `QuantumVM::deploy`; the code section is then init code.
+    Runtime = 5,
+}
+
+impl TryFrom<u8> for SectionKind {
+    type Error = VMError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(SectionKind::Code),
+            2 => Ok(SectionKind::Data),
+            3 => Ok(SectionKind::Metadata),
+            4 => Ok(SectionKind::SourceMap),
+            5 => Ok(SectionKind::Runtime),
+            _ => Err(VMError::InvalidBytecode(format!(
+                "Unknown section kind {}",
+                value
+            ))),
+        }
+    }
+}
+
+// Location of one section within the container
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    pub kind: SectionKind,
+    pub offset: usize,
+    pub length: usize,
 }
 
 // Bytecode header
+//
+// v1: magic, version, header length (u16), code length (u32), data length (u32), then code and data.
+// v2: magic, version, header length (u16), compiler version (3 x u32), source SHA-256 (32 bytes),
+//     section count (u8) and a table of (kind u8, length u32); sections follow in table order.
 #[derive(Debug)]
 pub struct Header {
     pub magic: u32,
@@ -51,13 +138,24 @@ pub struct Header {
     pub header_length: u16,
     pub code_length: u32,
     pub data_length: u32,
+    pub metadata_length: u32,
+    /// `[major, minor, patch]` of the compiler that wrote a v2 container.
+    pub compiler_version: Option<[u32; 3]>,
+    /// SHA-256 of the source text a v2 container was compiled from; all zero when unknown.
+    pub source_hash: Option<[u8; 32]>,
+    pub sections: Vec<Section>,
 }
 
 impl Header {
     pub const MAGIC: u32 = 0x51564D00; // QVM\0
+    pub const VERSION_1: u8 = 1;
+    pub const VERSION_2: u8 = 2;
+    pub const V1_LENGTH: u16 = 15;
+    /// v2 header length before the section table.
+    pub const V2_FIXED_LENGTH: usize = 52;
 
     pub fn parse(bytes: &[u8]) -> Result<Self, VMError> {
-        if bytes.len() < 12 {
+        if bytes.len() < 7 {
             return Err(VMError::InvalidBytecode("Header too short".to_string()));
         }
 
@@ -68,17 +166,182 @@ impl Header {
 
         let version = bytes[4];
         let header_length = u16::from_le_bytes([bytes[5], bytes[6]]);
+        match version {
+            Self::VERSION_1 => Self::parse_v1(bytes, header_length),
+            Self::VERSION_2 => Self::parse_v2(bytes, header_length),
+            _ => Err(VMError::InvalidBytecode(format!(
+                "Unsupported bytecode version {}",
+                version
+            ))),
+        }
+    }
+
+    fn parse_v1(bytes: &[u8], header_length: u16) -> Result<Self, VMError> {
+        if bytes.len() < Self::V1_LENGTH as usize || header_length < Self::V1_LENGTH {
+            return Err(VMError::InvalidBytecode("Header too short".to_string()));
+        }
         let code_length = u32::from_le_bytes([bytes[7], bytes[8], bytes[9], bytes[10]]);
         let data_length = u32::from_le_bytes([bytes[11], bytes[12], bytes[13], bytes[14]]);
 
+        let code_start = header_length as usize;
+        let data_start = code_start + code_length as usize;
+        if bytes.len() < data_start + data_length as usize {
+            return Err(VMError::InvalidBytecode("Bytecode too short".to_string()));
+        }
+
         Ok(Header {
-            magic,
-            version,
+            magic: Self::MAGIC,
+            version: Self::VERSION_1,
             header_length,
             code_length,
             data_length,
+            metadata_length: 0,
+            compiler_version: None,
+            source_hash: None,
+            sections: vec![
+                Section {
+                    kind: SectionKind::Code,
+                    offset: code_start,
+                    length: code_length as usize,
+                },
+                Section {
+                    kind: SectionKind::Data,
+                    offset: data_start,
+                    length: data_length as usize,
+                },
+            ],
+        })
+    }
+
+    fn parse_v2(bytes: &[u8], header_length: u16) -> Result<Self, VMError> {
+        if bytes.len() < Self::V2_FIXED_LENGTH {
+            return Err(VMError::InvalidBytecode("Header too short".to_string()));
+        }
+        let read_u32 = |at: usize| {
+            u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+        };
+        let compiler_version = [read_u32(7), read_u32(11), read_u32(15)];
+        let source_hash: [u8; 32] = bytes[19..51].try_into().expect("32-byte hash");
+
+        let section_count = bytes[51] as usize;
+        let table_end = Self::V2_FIXED_LENGTH + section_count * 5;
+        if header_length as usize != table_end {
+            return Err(VMError::InvalidBytecode(format!(
+                "Header length {} does not match {} sections (expected {})",
+                header_length, section_count, table_end
+            )));
+        }
+        if bytes.len() < table_end {
+            return Err(VMError::InvalidBytecode(
+                "Section table is truncated".to_string(),
+            ));
+        }
+
+        let mut sections: Vec<Section> = Vec::with_capacity(section_count);
+        let mut offset = table_end;
+        for entry in bytes[Self::V2_FIXED_LENGTH..table_end].chunks_exact(5) {
+            let kind = SectionKind::try_from(entry[0])?;
+            if sections.iter().any(|section| section.kind == kind) {
+                return Err(VMError::InvalidBytecode(format!(
+                    "Duplicate {:?} section",
+                    kind
+                )));
+            }
+            let length = u32::from_le_bytes([entry[1], entry[2], entry[3], entry[4]]) as usize;
+            let end = offset
+                .checked_add(length)
+                .filter(|end| *end <= bytes.len())
+                .ok_or_else(|| {
+                    VMError::InvalidBytecode(format!(
+                        "{:?} section of {} bytes at offset {} runs past the {}-byte container",
+                        kind,
+                        length,
+                        offset,
+                        bytes.len()
+                    ))
+                })?;
+            sections.push(Section {
+                kind,
+                offset,
+                length,
+            });
+            offset = end;
+        }
+        if offset != bytes.len() {
+            return Err(VMError::InvalidBytecode(format!(
+                "{} trailing bytes after the last section",
+                bytes.len() - offset
+            )));
+        }
+
+        let length_of = |kind: SectionKind| {
+            sections
+                .iter()
+                .find(|section| section.kind == kind)
+                .map_or(0, |section| section.length as u32)
+        };
+        if !sections
+            .iter()
+            .any(|section| section.kind == SectionKind::Code)
+        {
+            return Err(VMError::InvalidBytecode("Missing Code section".to_string()));
+        }
+
+        Ok(Header {
+            magic: Self::MAGIC,
+            version: Self::VERSION_2,
+            header_length,
+            code_length: length_of(SectionKind::Code),
+            data_length: length_of(SectionKind::Data),
+            metadata_length: length_of(SectionKind::Metadata),
+            compiler_version: Some(compiler_version),
+            source_hash: Some(source_hash),
+            sections,
         })
     }
+
+    /// Bytes of the `kind` section, empty if the container has none.
+    pub fn section_bytes<'a>(&self, bytes: &'a [u8], kind: SectionKind) -> &'a [u8] {
+        self.sections
+            .iter()
+            .find(|section| section.kind == kind)
+            .map_or(&[], |section| {
+                &bytes[section.offset..section.offset + section.length]
+            })
+    }
+
+    /// Splits a constant pool into its entries, rejecting a truncated final entry.
+    pub fn parse_constants(data: &[u8]) -> Result<Vec<Vec<u8>>, VMError> {
+        let mut constants = Vec::new();
+        let mut rest = data;
+        while !rest.is_empty() {
+            let Some((len, tail)) = rest.split_first_chunk::<4>() else {
+                return Err(VMError::InvalidBytecode(format!(
+                    "Constant {} has a truncated length",
+                    constants.len()
+                )));
+            };
+            let len = u32::from_le_bytes(*len) as usize;
+            if tail.len() < len {
+                return Err(VMError::InvalidBytecode(format!(
+                    "Constant {} needs {} bytes but only {} remain",
+                    constants.len(),
+                    len,
+                    tail.len()
+                )));
+            }
+            constants.push(tail[..len].to_vec());
+            rest = &tail[len..];
+        }
+        Ok(constants)
+    }
+}
+
+// Nested gas budget opened by `@gas_limit` functions and `with_gas_limit` blocks
+#[derive(Debug, Clone)]
+pub struct GasScope {
+    pub limit: u64,
+    pub used: u64,
 }
 
 // Gas meter for tracking gas consumption
@@ -87,6 +350,9 @@ pub struct GasMeter {
     pub consumed: u64,
     pub pqc_consumed: u64,
     pub max_pqc_per_tx: u64,
+    pub scopes: Vec<GasScope>,
+    /// Memory size already paid for, in 32-byte words.
+    pub memory_words: u64,
 }
 
 impl GasMeter {
@@ -96,7 +362,26 @@ impl GasMeter {
             consumed: 0,
             pqc_consumed: 0,
             max_pqc_per_tx,
+            scopes: Vec::new(),
+            memory_words: 0,
+        }
+    }
+
+    /// Total cost of `words` words of memory: linear up to a few thousand words, then quadratic.
+    pub fn memory_cost(words: u64) -> u64 {
+        words
+            .saturating_mul(3)
+            .saturating_add(words.saturating_mul(words) / 512)
+    }
+
+    /// Charges for growing memory to `words` words; shrinking or staying the same is free.
+    pub fn expand_memory(&mut self, words: u64) -> Result<(), VMError> {
+        if words <= self.memory_words {
+            return Ok(());
         }
+        self.consume(Self::memory_cost(words) - Self::memory_cost(self.memory_words))?;
+        self.memory_words = words;
+        Ok(())
     }
 
     pub fn consume(&mut self, amount: u64) -> Result<(), VMError> {
@@ -108,9 +393,36 @@ impl GasMeter {
         }
         self.remaining -= amount;
         self.consumed += amount;
+
+        // Every open scope pays for the work done inside it, including nested scopes.
+        for scope in &mut self.scopes {
+            scope.used += amount;
+        }
+        if let Some(depth) = self.exceeded_scope() {
+            let scope = &self.scopes[depth];
+            return Err(VMError::OutOfGas(format!(
+                "Gas scope {} exceeded: limit {}, used {}",
+                depth, scope.limit, scope.used
+            )));
+        }
         Ok(())
     }
 
+    pub fn push_scope(&mut self, limit: u64) {
+        self.scopes.push(GasScope { limit, used: 0 });
+    }
+
+    pub fn pop_scope(&mut self) -> Option<GasScope> {
+        self.scopes.pop()
+    }
+
+    /// Outermost open scope whose budget has been overrun, if any.
+    pub fn exceeded_scope(&self) -> Option<usize> {
+        self.scopes
+            .iter()
+            .position(|scope| scope.used > scope.limit)
+    }
+
     pub fn consume_pqc(&mut self, amount: u64) -> Result<(), VMError> {
         if self.pqc_consumed + amount > self.max_pqc_per_tx {
             return Err(VMError::OutOfGas(format!(
@@ -124,16 +436,62 @@ impl GasMeter {
     }
 }
 
+// VM state captured when a gas scope opens, restored if the scope overruns its budget
+struct GasScopeFrame {
+    handler: usize,
+    call_depth: usize,
+    stack: Vec<Value>,
+    memory: Vec<u8>,
+    // Journal length when the scope opened; an overrun undoes everything after it
+    checkpoint: usize,
+}
+
+// Undo record for one state change made by the running frame
+enum FrameChange {
+    /// Previous value of a storage slot; `None` if it was unset.
+    Storage(StorageKey, Option<Value>),
+    /// Previous balance of an account the frame moved value to or from; `None` if untouched.
+    Balance(Address, Option<u64>),
+    Log,
+}
+
 // The main VM struct
 pub struct QuantumVM {
     pub stack: Vec<Value>,
-    memory: HashMap<usize, Value>,
+    // Linear, byte-addressed memory; it only grows, in whole 32-byte words
+    memory: Vec<u8>,
     code: Vec<u8>,
-    data: Vec<u8>,
+    constants: Vec<Vec<u8>>,
+    metadata: Vec<u8>,
+    source_map: SourceMap,
+    // Persistent slots of the contract being run; `deploy` and `call` move it in and out
+    storage: Storage,
+    // Address the running code is deployed at, and the logs it has emitted
+    address: Address,
+    logs: Vec<LogEntry>,
+    // Account that made the call and the value it sent along
+    caller: Address,
+    call_value: u64,
+    // Balances the frame has changed; `call_contract` writes them back if it succeeds
+    balances: BTreeMap<Address, u64>,
+    // Storage writes, balance changes and logs since the frame started, newest last
+    journal: Vec<FrameChange>,
+    calldata: Vec<u8>,
     pc: usize,
+    // Offset of the instruction that made `execute` fail
+    fault_pc: Option<usize>,
     call_stack: Vec<usize>,
     halted: bool,
     gas_meter: GasMeter,
+    schedule: GasSchedule,
+    gas_scope_frames: Vec<GasScopeFrame>,
+    // Gas and PQC gas consumed before the current run started, and what the run charged
+    // per opcode class; `receipt` reports the difference
+    run_start_gas: (u64, u64),
+    gas_breakdown: BTreeMap<GasClass, u64>,
+    // Message of the `Revert` that ended the last run, and the values it printed
+    revert_data: Option<Vec<u8>>,
+    output: Vec<Value>,
 }
 
 impl Default for QuantumVM {
@@ -144,19 +502,44 @@ impl Default for QuantumVM {
 
 impl QuantumVM {
     pub fn new() -> Self {
-        QuantumVM::with_gas(10_000_000, 300_000) // Default: 10M gas, 300K PQC gas limit
+        QuantumVM::with_schedule(10_000_000, GasSchedule::default()) // Default: 10M gas
     }
 
+    /// VM with the built-in gas schedule and its own PQC gas limit.
     pub fn with_gas(initial_gas: u64, max_pqc_gas: u64) -> Self {
+        let mut schedule = GasSchedule::default();
+        schedule.max_pqc_gas_per_tx = max_pqc_gas;
+        QuantumVM::with_schedule(initial_gas, schedule)
+    }
+
+    /// VM that charges by `schedule`, with its `max_pqc_gas_per_tx` as the PQC gas limit.
+    pub fn with_schedule(initial_gas: u64, schedule: GasSchedule) -> Self {
         QuantumVM {
             stack: Vec::new(),
-            memory: HashMap::new(),
+            memory: Vec::new(),
             code: Vec::new(),
-            data: Vec::new(),
+            constants: Vec::new(),
+            metadata: Vec::new(),
+            source_map: SourceMap::default(),
+            storage: Storage::new(),
+            address: [0; 20],
+            logs: Vec::new(),
+            caller: [0; 20],
+            call_value: 0,
+            balances: BTreeMap::new(),
+            journal: Vec::new(),
+            calldata: Vec::new(),
             pc: 0,
+            fault_pc: None,
             call_stack: Vec::new(),
             halted: false,
-            gas_meter: GasMeter::new(initial_gas, max_pqc_gas),
+            gas_meter: GasMeter::new(initial_gas, schedule.max_pqc_gas_per_tx),
+            schedule,
+            gas_scope_frames: Vec::new(),
+            run_start_gas: (0, 0),
+            gas_breakdown: BTreeMap::new(),
+            revert_data: None,
+            output: Vec::new(),
         }
     }
 
@@ -172,33 +555,415 @@ impl QuantumVM {
         self.gas_meter.pqc_consumed
     }
 
-    pub fn load_bytecode(&mut self, bytecode: &[u8]) -> Result<(), VMError> {
-        let header = Header::parse(bytecode)?;
+    pub fn gas_schedule(&self) -> &GasSchedule {
+        &self.schedule
+    }
 
-        let header_end = header.header_length as usize;
-        let code_end = header_end + header.code_length as usize;
-        let data_end = code_end + header.data_length as usize;
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
 
-        if bytecode.len() < data_end {
-            return Err(VMError::InvalidBytecode("Bytecode too short".to_string()));
+    /// Metadata section of the loaded container, empty for v1 files.
+    pub fn metadata(&self) -> &[u8] {
+        &self.metadata
+    }
+
+    pub fn storage(&self) -> &Storage {
+        &self.storage
+    }
+
+    /// Logs emitted by the last deploy or call; empty if it failed.
+    pub fn logs(&self) -> &[LogEntry] {
+        &self.logs
+    }
+
+    /// Values `Print` wrote during the last run.
+    pub fn output(&self) -> &[Value] {
+        &self.output
+    }
+
+    /// Receipt for the last run (an `execute`, deploy or call) given the result it returned.
+    pub fn receipt<T>(&self, result: &Result<T, VMError>) -> ExecutionReceipt {
+        let status = ExecutionStatus::of(result);
+        let success = status == ExecutionStatus::Success;
+        ExecutionReceipt {
+            status,
+            return_data: if success {
+                self.stack.clone()
+            } else {
+                Vec::new()
+            },
+            revert_data: self.revert_data.clone().filter(|_| !success),
+            error: result.as_ref().err().map(ToString::to_string),
+            logs: if success {
+                self.logs.clone()
+            } else {
+                Vec::new()
+            },
+            gas_used: self.gas_meter.consumed - self.run_start_gas.0,
+            pqc_gas_used: self.gas_meter.pqc_consumed - self.run_start_gas.1,
+            gas_breakdown: self.gas_breakdown.clone(),
+            output: self.output.clone(),
         }
+    }
 
-        self.code = bytecode[header_end..code_end].to_vec();
-        self.data = bytecode[code_end..data_end].to_vec();
+    /// Sets the address logs are attributed to; `deploy_contract` and `call_contract` set it.
+    pub fn set_address(&mut self, address: Address) {
+        self.address = address;
+    }
+
+    /// Sets the address `CALLER` reports; `deploy_contract` and `call_contract` set it.
+    pub fn set_caller(&mut self, caller: Address) {
+        self.caller = caller;
+    }
+
+    /// Sets the input read by the entry dispatcher: a 4-byte selector followed by arguments.
+    pub fn set_calldata(&mut self, calldata: &[u8]) {
+        self.calldata = calldata.to_vec();
+    }
+
+    pub fn load_bytecode(&mut self, bytecode: &[u8]) -> Result<(), VMError> {
+        let header = Header::parse(bytecode)?;
+
+        let data = header.section_bytes(bytecode, SectionKind::Data);
+        // v1 never filled its data section, so only v2 gives it the constant pool layout
+        self.constants = if header.version == Header::VERSION_1 {
+            Vec::new()
+        } else {
+            Header::parse_constants(data)?
+        };
+        let code = header.section_bytes(bytecode, SectionKind::Code);
+        verifier::verify(code)?;
+        self.code = code.to_vec();
+        self.metadata = header
+            .section_bytes(bytecode, SectionKind::Metadata)
+            .to_vec();
+        let source_map = header.section_bytes(bytecode, SectionKind::SourceMap);
+        self.source_map = if source_map.is_empty() {
+            SourceMap::default()
+        } else {
+            SourceMap::decode(source_map)?
+        };
         self.pc = 0;
+        self.fault_pc = None;
         self.halted = false;
 
         Ok(())
     }
 
+    /// Runs the init code of a deploy container once, with `constructor_args` as calldata and
+    /// empty storage, and returns the container's runtime code with the storage it left.
+    pub fn deploy(
+        &mut self,
+        bytecode: &[u8],
+        constructor_args: &[u8],
+    ) -> Result<ContractInstance, VMError> {
+        self.deploy_with(bytecode, constructor_args, &mut ())
+    }
+
+    fn deploy_with(
+        &mut self,
+        bytecode: &[u8],
+        constructor_args: &[u8],
+        balances: &mut dyn Balances,
+    ) -> Result<ContractInstance, VMError> {
+        self.begin_run();
+        let header = Header::parse(bytecode)?;
+        let runtime = header.section_bytes(bytecode, SectionKind::Runtime);
+        if runtime.is_empty() {
+            return Err(VMError::InvalidBytecode(
+                "Container has no Runtime section to deploy".to_string(),
+            ));
+        }
+        // Reject a runtime that could never be called before running any init code.
+        let runtime_header = Header::parse(runtime)?;
+        verifier::verify(runtime_header.section_bytes(runtime, SectionKind::Code))?;
+
+        self.load_bytecode(bytecode)?;
+        self.clear_frame();
+        self.storage = Storage::new();
+        self.set_calldata(constructor_args);
+        self.call_value = 0;
+        self.execute_with(balances)?;
+        Ok(ContractInstance {
+            code: runtime.to_vec(),
+            storage: std::mem::take(&mut self.storage),
+        })
+    }
+
+    /// Runs `instance`'s runtime code with `calldata`. Storage changes are kept only if the
+    /// call succeeds.
+    pub fn call(
+        &mut self,
+        instance: &mut ContractInstance,
+        calldata: &[u8],
+    ) -> Result<(), VMError> {
+        self.call_with(instance, calldata, 0, &mut ())
+    }
+
+    // Moves `value` from the caller to the running contract, then runs the call
+    fn call_with(
+        &mut self,
+        instance: &mut ContractInstance,
+        calldata: &[u8],
+        value: u64,
+        balances: &mut dyn Balances,
+    ) -> Result<(), VMError> {
+        self.begin_run();
+        self.load_bytecode(&instance.code)?;
+        self.clear_frame();
+        self.storage = instance.storage.clone();
+        self.set_calldata(calldata);
+        self.call_value = value;
+        let (caller, address) = (self.caller, self.address);
+        self.move_value(&caller, &address, value, balances)?;
+        self.execute_with(balances)?;
+        instance.storage = std::mem::take(&mut self.storage);
+        Ok(())
+    }
+
+    /// Deploys `bytecode` from `deployer`'s account: the contract address is derived from the
+    /// deployer's nonce, which is incremented even if the init code fails. On success the
+    /// runtime code, storage and balance changes are installed at the returned address; on
+    /// failure every other change is rolled back.
+    pub fn deploy_contract<B: StateBackend>(
+        &mut self,
+        state: &mut WorldState<B>,
+        deployer: &Address,
+        bytecode: &[u8],
+        constructor_args: &[u8],
+    ) -> Result<Address, VMError> {
+        self.begin_run();
+        let nonce = state.increment_nonce(deployer)?;
+        let address = contract_address(deployer, nonce);
+        let checkpoint = state.checkpoint();
+        let result = state.create_contract(&address).and_then(|()| {
+            self.set_address(address);
+            self.set_caller(*deployer);
+            let instance = self.deploy_with(bytecode, constructor_args, state)?;
+            state.set_code(&address, instance.code);
+            for (slot, value) in instance.storage {
+                state.set_storage(&address, slot, value);
+            }
+            for (account, balance) in std::mem::take(&mut self.balances) {
+                state.set_balance(&account, balance);
+            }
+            for log in &self.logs {
+                state.push_log(log.clone());
+            }
+            Ok(address)
+        });
+        if result.is_err() {
+            state.revert_to(checkpoint);
+        }
+        result
+    }
+
+    /// Calls the contract at `address` from `caller` with `calldata`, sending `value` from the
+    /// caller's balance to the contract's before any code runs. Storage slots the call writes
+    /// and balances it moves are updated in `state` only if it succeeds.
+    pub fn call_contract<B: StateBackend>(
+        &mut self,
+        state: &mut WorldState<B>,
+        caller: &Address,
+        address: &Address,
+        calldata: &[u8],
+        value: u64,
+    ) -> Result<(), VMError> {
+        self.begin_run();
+        let Some(account) = state
+            .account(address)
+            .filter(|account| account.is_contract())
+        else {
+            return Err(VMError::RuntimeError(format!(
+                "No contract at address 0x{}",
+                hex(address)
+            )));
+        };
+        let before = account.storage.clone();
+        let mut instance = ContractInstance {
+            code: account.code.clone(),
+            storage: before.clone(),
+        };
+        self.set_address(*address);
+        self.set_caller(*caller);
+        self.call_with(&mut instance, calldata, value, state)?;
+        for (slot, value) in instance.storage {
+            if before
+                .get(&slot)
+                .is_none_or(|previous| !previous.same_as(&value))
+            {
+                state.set_storage(address, slot, value);
+            }
+        }
+        for (account, balance) in std::mem::take(&mut self.balances) {
+            state.set_balance(&account, balance);
+        }
+        for log in &self.logs {
+            state.push_log(log.clone());
+        }
+        Ok(())
+    }
+
+    // Marks the start of a run for `receipt`; calling it again before any code runs is harmless.
+    fn begin_run(&mut self) {
+        self.run_start_gas = (self.gas_meter.consumed, self.gas_meter.pqc_consumed);
+        self.gas_breakdown.clear();
+        self.revert_data = None;
+        self.output.clear();
+    }
+
+    // Each deploy and call starts from an empty stack and memory; only storage carries over.
+    fn clear_frame(&mut self) {
+        self.stack.clear();
+        self.memory.clear();
+        self.call_stack.clear();
+        self.gas_scope_frames.clear();
+        self.logs.clear();
+        self.balances.clear();
+    }
+
+    // Balance of `address` as the running frame sees it
+    fn balance_of(&self, address: &Address, balances: &mut dyn Balances) -> u64 {
+        match self.balances.get(address) {
+            Some(balance) => *balance,
+            None => balances.balance(address),
+        }
+    }
+
+    fn set_balance(&mut self, address: &Address, balance: u64) {
+        let previous = self.balances.insert(*address, balance);
+        self.journal.push(FrameChange::Balance(*address, previous));
+    }
+
+    /// Moves `amount` from `from` to `to` within the frame; fails if `from` has too little.
+    fn move_value(
+        &mut self,
+        from: &Address,
+        to: &Address,
+        amount: u64,
+        balances: &mut dyn Balances,
+    ) -> Result<(), VMError> {
+        if amount == 0 {
+            return Ok(());
+        }
+        let available = self.balance_of(from, balances);
+        let remaining = available.checked_sub(amount).ok_or_else(|| {
+            VMError::RuntimeError(format!(
+                "Account 0x{} has a balance of {}, too little to send {}",
+                hex(from),
+                available,
+                amount
+            ))
+        })?;
+        self.set_balance(from, remaining);
+        let received = self
+            .balance_of(to, balances)
+            .checked_add(amount)
+            .ok_or_else(|| {
+                VMError::RuntimeError(format!("Balance of account 0x{} overflowed", hex(to)))
+            })?;
+        self.set_balance(to, received);
+        Ok(())
+    }
+
+    /// Undoes the storage writes and logs journaled after `checkpoint`, newest first.
+    fn revert_to(&mut self, checkpoint: usize) {
+        for change in self.journal.drain(checkpoint..).rev() {
+            match change {
+                FrameChange::Storage(slot, Some(value)) => {
+                    self.storage.insert(slot, value);
+                }
+                FrameChange::Storage(slot, None) => {
+                    self.storage.remove(&slot);
+                }
+                FrameChange::Balance(address, Some(balance)) => {
+                    self.balances.insert(address, balance);
+                }
+                FrameChange::Balance(address, None) => {
+                    self.balances.remove(&address);
+                }
+                FrameChange::Log => {
+                    self.logs.pop();
+                }
+            }
+        }
+    }
+
+    /// Runs until `Halt` or the end of the code. If execution fails (including `Revert` and
+    /// running out of gas), the storage writes, balance changes and logs it made are undone.
+    /// When the container has a source map, errors name the source line and function of the
+    /// failing instruction.
     pub fn execute(&mut self) -> Result<(), VMError> {
+        self.begin_run();
+        self.execute_with(&mut ())
+    }
+
+    /// Like `execute`, but reports the outcome as a receipt.
+    pub fn execute_receipt(&mut self) -> ExecutionReceipt {
+        let result = self.execute();
+        self.receipt(&result)
+    }
+
+    fn execute_with(&mut self, balances: &mut dyn Balances) -> Result<(), VMError> {
+        // Each execution is one frame: failing undoes its own writes, not earlier runs'.
+        self.journal.clear();
         while !self.halted && self.pc < self.code.len() {
-            self.execute_instruction()?;
+            let pc = self.pc;
+            let consumed = self.gas_meter.consumed;
+            let result = self.execute_instruction(balances);
+            if let Ok(opcode) = OpCode::try_from(self.code[pc]) {
+                *self.gas_breakdown.entry(opcode.gas_class()).or_default() +=
+                    self.gas_meter.consumed - consumed;
+            }
+            if let Err(err) = result {
+                match self.gas_meter.exceeded_scope() {
+                    Some(depth) => self.unwind_gas_scope(depth),
+                    None => {
+                        // A failed execution leaves no storage writes or logs behind.
+                        self.revert_to(0);
+                        self.fault_pc = Some(pc);
+                        return Err(match self.source_map.locate(pc) {
+                            Some(location) => err.with_context(&location.to_string()),
+                            None => err,
+                        });
+                    }
+                }
+            }
         }
         Ok(())
     }
 
-    fn execute_instruction(&mut self) -> Result<(), VMError> {
+    /// Source position of the code at `offset`, from the container's source map.
+    pub fn source_location(&self, offset: usize) -> Option<SourceLocation> {
+        self.source_map.locate(offset)
+    }
+
+    /// Source position of the instruction that made the last `execute` fail.
+    pub fn fault_location(&self) -> Option<SourceLocation> {
+        self.source_location(self.fault_pc?)
+    }
+
+    /// Reverts everything done since scope `depth` opened and resumes at its handler.
+    /// Gas already spent inside the scope stays consumed.
+    fn unwind_gas_scope(&mut self, depth: usize) {
+        self.gas_meter.scopes.truncate(depth);
+        let frame = self
+            .gas_scope_frames
+            .drain(depth..)
+            .next()
+            .expect("gas scope frames track meter scopes");
+        self.stack = frame.stack;
+        // Contents revert, but memory keeps the size that was already paid for.
+        self.memory = frame.memory;
+        self.memory
+            .resize(self.gas_meter.memory_words as usize * 32, 0);
+        self.call_stack.truncate(frame.call_depth);
+        self.revert_to(frame.checkpoint);
+        self.pc = frame.handler;
+    }
+
+    fn execute_instruction(&mut self, balances: &mut dyn Balances) -> Result<(), VMError> {
         if self.pc >= self.code.len() {
             return Err(VMError::InvalidAddress(format!(
                 "Program counter {} exceeds code length {}",
@@ -210,8 +975,13 @@ impl QuantumVM {
         let opcode = OpCode::try_from(self.code[self.pc])?;
         self.pc += 1;
 
-        // Consume base gas for instruction execution
-        self.gas_meter.consume(1)?;
+        // Every instruction pays its base cost up front; PQC operations charge theirs as PQ gas
+        // with the rest of their cost.
+        let base = match opcode.gas_class() {
+            GasClass::Pqc => 0,
+            _ => self.schedule.cost(opcode).base,
+        };
+        self.gas_meter.consume(self.schedule.instruction + base)?;
 
         match opcode {
             OpCode::Push => {
@@ -232,23 +1002,19 @@ impl QuantumVM {
                 self.push(b)?;
             }
             OpCode::Add => {
-                let b = self.pop()?.as_i32()?;
-                let a = self.pop()?.as_i32()?;
-                self.push(Value::I32(a + b))?;
+                let (a, b) = self.pop_operands()?;
+                self.push_arithmetic("Addition", a, b, a.checked_add(b))?;
             }
             OpCode::Sub => {
-                let b = self.pop()?.as_i32()?;
-                let a = self.pop()?.as_i32()?;
-                self.push(Value::I32(a - b))?;
+                let (a, b) = self.pop_operands()?;
+                self.push_arithmetic("Subtraction", a, b, a.checked_sub(b))?;
             }
             OpCode::Mul => {
-                let b = self.pop()?.as_i32()?;
-                let a = self.pop()?.as_i32()?;
-                self.push(Value::I32(a * b))?;
+                let (a, b) = self.pop_operands()?;
+                self.push_arithmetic("Multiplication", a, b, a.checked_mul(b))?;
             }
             OpCode::Div => {
-                let b = self.pop()?.as_i32()?;
-                let a = self.pop()?.as_i32()?;
+                let (a, b) = self.pop_operands()?;
                 if b == 0 {
                     return Err(VMError::RuntimeError(format!(
                         "Division by zero: attempted to divide {} by 0 at PC {}",
@@ -256,42 +1022,36 @@ impl QuantumVM {
                         self.pc - 1
                     )));
                 }
-                self.gas_meter.consume(5)?; // Division is more expensive
-                self.push(Value::I32(a / b))?;
+                self.push_arithmetic("Division", a, b, a.checked_div(b))?;
             }
             OpCode::Eq => {
-                let b = self.pop()?.as_i32()?;
-                let a = self.pop()?.as_i32()?;
-                self.push(Value::Bool(a == b))?;
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.push(Value::Bool(a.equals(&b)?))?;
             }
             OpCode::Ne => {
-                let b = self.pop()?.as_i32()?;
-                let a = self.pop()?.as_i32()?;
-                self.push(Value::Bool(a != b))?;
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.push(Value::Bool(!a.equals(&b)?))?;
             }
             OpCode::Lt => {
-                let b = self.pop()?.as_i32()?;
-                let a = self.pop()?.as_i32()?;
+                let (a, b) = self.pop_operands()?;
                 self.push(Value::Bool(a < b))?;
             }
             OpCode::Le => {
-                let b = self.pop()?.as_i32()?;
-                let a = self.pop()?.as_i32()?;
+                let (a, b) = self.pop_operands()?;
                 self.push(Value::Bool(a <= b))?;
             }
             OpCode::Gt => {
-                let b = self.pop()?.as_i32()?;
-                let a = self.pop()?.as_i32()?;
+                let (a, b) = self.pop_operands()?;
                 self.push(Value::Bool(a > b))?;
             }
             OpCode::Ge => {
-                let b = self.pop()?.as_i32()?;
-                let a = self.pop()?.as_i32()?;
+                let (a, b) = self.pop_operands()?;
                 self.push(Value::Bool(a >= b))?;
             }
             OpCode::Jump => {
                 let addr = self.read_u32()? as usize;
-                self.gas_meter.consume(2)?; // Jump cost
                 if addr >= self.code.len() {
                     return Err(VMError::InvalidAddress(format!(
                         "Jump target {} exceeds code length {} at PC {}",
@@ -305,7 +1065,6 @@ impl QuantumVM {
             OpCode::JumpIf => {
                 let addr = self.read_u32()? as usize;
                 let condition = self.pop()?.as_bool()?;
-                self.gas_meter.consume(2)?; // Conditional jump cost
                 if condition {
                     if addr >= self.code.len() {
                         return Err(VMError::InvalidAddress(format!(
@@ -320,7 +1079,6 @@ impl QuantumVM {
             }
             OpCode::Call => {
                 let addr = self.read_u32()? as usize;
-                self.gas_meter.consume(10)?; // Function call overhead
                 if addr >= self.code.len() {
                     return Err(VMError::InvalidAddress(format!(
                         "Call target {} exceeds code length {} at PC {}",
@@ -332,8 +1090,56 @@ impl QuantumVM {
                 self.call_stack.push(self.pc);
                 self.pc = addr;
             }
+            OpCode::GasLimitBegin => {
+                let limit = self.read_u32()? as u64;
+                let handler = self.read_u32()? as usize;
+                if handler > self.code.len() {
+                    return Err(VMError::InvalidAddress(format!(
+                        "Gas scope handler {} exceeds code length {} at PC {}",
+                        handler,
+                        self.code.len(),
+                        self.pc - 9
+                    )));
+                }
+                if self.gas_scope_frames.len() >= GAS_SCOPE_LIMIT {
+                    return Err(VMError::RuntimeError(format!(
+                        "Gas scopes nest deeper than the limit of {} at PC {}",
+                        GAS_SCOPE_LIMIT,
+                        self.pc - 9
+                    )));
+                }
+                // The snapshot is paid for per word of the memory and stack it copies.
+                let snapshot = self.memory.len()
+                    + self
+                        .stack
+                        .iter()
+                        .map(|value| match value {
+                            Value::Bytes(bytes) => bytes.len().max(32),
+                            _ => 32,
+                        })
+                        .sum::<usize>();
+                self.charge_data(OpCode::GasLimitBegin, snapshot)?;
+                self.gas_scope_frames.push(GasScopeFrame {
+                    handler,
+                    call_depth: self.call_stack.len(),
+                    stack: self.stack.clone(),
+                    memory: self.memory.clone(),
+                    checkpoint: self.journal.len(),
+                });
+                self.gas_meter.push_scope(limit);
+            }
+            OpCode::GasLimitEnd => {
+                self.close_gas_scope()?;
+            }
             OpCode::Return => {
-                self.gas_meter.consume(3)?; // Return cost
+                // Scopes opened by the returning frame close with it.
+                while self
+                    .gas_scope_frames
+                    .last()
+                    .is_some_and(|frame| frame.call_depth >= self.call_stack.len())
+                {
+                    self.close_gas_scope()?;
+                }
                 if let Some(return_addr) = self.call_stack.pop() {
                     self.pc = return_addr;
                 } else {
@@ -342,39 +1148,116 @@ impl QuantumVM {
                 }
             }
             OpCode::Load => {
-                let addr = self.pop()?.as_i32()? as usize;
-                self.gas_meter.consume(3)?; // Memory load cost
-                if let Some(value) = self.memory.get(&addr) {
-                    self.push(value.clone())?;
-                } else {
-                    return Err(VMError::InvalidAddress(format!(
-                        "Memory address {} not found. Valid addresses: {:?}",
-                        addr,
-                        self.memory.keys().take(10).collect::<Vec<_>>()
-                    )));
-                }
+                let addr = self.pop_address()?;
+                self.touch_memory(addr, 32)?;
+                let value = self.read_word(addr);
+                self.push(value)?;
             }
             OpCode::Store => {
-                let addr = self.pop()?.as_i32()? as usize;
+                let addr = self.pop_address()?;
                 let value = self.pop()?;
-                self.gas_meter.consume(5)?; // Memory store cost (higher than load)
-                self.memory.insert(addr, value);
+                // A 32-byte string is stored as the word itself, so wide storage slots fit too.
+                let word = match &value {
+                    Value::Bytes(bytes) if bytes.len() == 32 => {
+                        bytes.as_slice().try_into().expect("32 bytes")
+                    }
+                    value => Self::word_from_value(value)?,
+                };
+                self.touch_memory(addr, 32)?;
+                self.memory[addr..addr + 32].copy_from_slice(&word);
+            }
+            OpCode::Load8 => {
+                let addr = self.pop_address()?;
+                self.touch_memory(addr, 1)?;
+                self.push(Value::I32(i32::from(self.memory[addr])))?;
+            }
+            OpCode::Store8 => {
+                let addr = self.pop_address()?;
+                let value = self.pop()?.as_i64()?;
+                self.touch_memory(addr, 1)?;
+                self.memory[addr] = value as u8;
+            }
+            OpCode::MCopy => {
+                let len = self.pop_address()?;
+                let src = self.pop_address()?;
+                let dst = self.pop_address()?;
+                self.charge_data(OpCode::MCopy, len)?;
+                self.touch_memory(src, len)?;
+                self.touch_memory(dst, len)?;
+                self.memory.copy_within(src..src + len, dst);
+            }
+            OpCode::MSize => {
+                self.push(Value::from_i64(self.memory.len() as i64))?;
+            }
+            OpCode::LoadBytes => {
+                let addr = self.pop_address()?;
+                self.touch_memory(addr, 32)?;
+                let len = self.read_word(addr).as_i64()?;
+                let len = usize::try_from(len).map_err(|_| {
+                    VMError::RuntimeError(format!(
+                        "Byte string at memory address {} has negative length {} at PC {}",
+                        addr,
+                        len,
+                        self.pc - 1
+                    ))
+                })?;
+                self.charge_data(OpCode::LoadBytes, len)?;
+                self.touch_memory(addr + 32, len)?;
+                let bytes = self.memory[addr + 32..addr + 32 + len].to_vec();
+                self.push(Value::Bytes(bytes))?;
+            }
+            OpCode::StoreBytes => {
+                let addr = self.pop_address()?;
+                let value = self.pop()?;
+                let bytes = value.as_bytes()?;
+                self.charge_data(OpCode::StoreBytes, bytes.len())?;
+                // A length word, then the bytes zero-padded to a whole number of words.
+                let end = addr + 32 + bytes.len().div_ceil(32) * 32;
+                self.touch_memory(addr, end - addr)?;
+                let len = Self::word_from_value(&Value::I64(bytes.len() as i64))?;
+                self.memory[addr..addr + 32].copy_from_slice(&len);
+                self.memory[addr + 32..addr + 32 + bytes.len()].copy_from_slice(bytes);
+                self.memory[addr + 32 + bytes.len()..end].fill(0);
+                self.push(Value::from_i64(end as i64))?;
+            }
+            OpCode::BoundsCheck => {
+                let length = self.pop()?.as_i64()?;
+                let index = self.pop()?.as_i64()?;
+                if index < 0 || index >= length {
+                    return Err(VMError::RuntimeError(format!(
+                        "Index {} out of bounds for length {} at PC {}",
+                        index,
+                        length,
+                        self.pc - 1
+                    )));
+                }
+                self.push(Value::from_i64(index))?;
             }
             OpCode::LoadImm => {
                 let len = self.read_u32()? as usize;
                 let bytes = self.read_bytes(len)?;
                 self.push(Value::Bytes(bytes))?;
             }
+            OpCode::LoadConst => {
+                let index = self.read_u32()? as usize;
+                let bytes = self.constants.get(index).cloned().ok_or_else(|| {
+                    VMError::InvalidBytecode(format!(
+                        "Constant {} is outside the {}-entry constant pool",
+                        index,
+                        self.constants.len()
+                    ))
+                })?;
+                self.push(Value::Bytes(bytes))?;
+            }
             OpCode::MLDSAVerify => {
                 let public_key = self.pop()?.as_bytes()?.to_vec();
                 let message = self.pop()?.as_bytes()?.to_vec();
                 let signature = self.pop()?.as_bytes()?.to_vec();
 
-                // Calculate gas cost: base + data cost + compute cost
-                // Base: 6000, Data: ~9 per byte, Compute: 20000
-                let data_cost = (public_key.len() + message.len() + signature.len()) as u64 * 9;
-                let gas_cost = 6000 + data_cost + 20000;
-                self.gas_meter.consume_pqc(gas_cost)?;
+                self.charge_pqc(
+                    OpCode::MLDSAVerify,
+                    public_key.len() + message.len() + signature.len(),
+                )?;
 
                 // Use ML-DSA-65 for verification
                 let signer = Sign::mldsa65();
@@ -384,18 +1267,17 @@ impl QuantumVM {
                 self.push(Value::Bool(result))?;
             }
             OpCode::MLKEMKeyExchange => {
-                self.execute_kem_key_exchange(Kem::mlkem768(), "ML-KEM-768", 5000, 6, 14000)?;
+                self.execute_kem_key_exchange(opcode, Kem::mlkem768(), "ML-KEM-768")?;
             }
             OpCode::FNDSAVerify => {
                 let public_key = self.pop()?.as_bytes()?.to_vec();
                 let message = self.pop()?.as_bytes()?.to_vec();
                 let signature = self.pop()?.as_bytes()?.to_vec();
 
-                // Calculate gas cost: base + data cost + compute cost
-                // Base: 4000, Data: ~6 per byte, Compute: 10000
-                let data_cost = (public_key.len() + message.len() + signature.len()) as u64 * 6;
-                let gas_cost = 4000 + data_cost + 10000;
-                self.gas_meter.consume_pqc(gas_cost)?;
+                self.charge_pqc(
+                    OpCode::FNDSAVerify,
+                    public_key.len() + message.len() + signature.len(),
+                )?;
 
                 // Use FN-DSA-512 for verification
                 let signer = Sign::fndsa512();
@@ -410,17 +1292,286 @@ impl QuantumVM {
                 ));
             }
             OpCode::HQCKEM128KeyExchange => {
-                self.execute_kem_key_exchange(Kem::hqckem128(), "HQC-KEM-128", 6500, 7, 22000)?;
+                self.execute_kem_key_exchange(opcode, Kem::hqckem128(), "HQC-KEM-128")?;
             }
             OpCode::HQCKEM192KeyExchange => {
-                self.execute_kem_key_exchange(Kem::hqckem192(), "HQC-KEM-192", 7000, 7, 26000)?;
+                self.execute_kem_key_exchange(opcode, Kem::hqckem192(), "HQC-KEM-192")?;
             }
             OpCode::HQCKEM256KeyExchange => {
-                self.execute_kem_key_exchange(Kem::hqckem256(), "HQC-KEM-256", 7500, 7, 32000)?;
+                self.execute_kem_key_exchange(opcode, Kem::hqckem256(), "HQC-KEM-256")?;
+            }
+            OpCode::BytesConcat => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                let (a, b) = (a.as_bytes()?, b.as_bytes()?);
+                self.charge_data(OpCode::BytesConcat, a.len() + b.len())?;
+                self.push(Value::Bytes([a, b].concat()))?;
+            }
+            OpCode::BytesSlice => {
+                let end = self.pop()?.as_i64()?;
+                let start = self.pop()?.as_i64()?;
+                let value = self.pop()?;
+                let bytes = value.as_bytes()?;
+                let slice = usize::try_from(start)
+                    .ok()
+                    .zip(usize::try_from(end).ok())
+                    .and_then(|(start, end)| bytes.get(start..end))
+                    .ok_or_else(|| {
+                        VMError::RuntimeError(format!(
+                            "Slice {}..{} is out of bounds for {} bytes at PC {}",
+                            start,
+                            end,
+                            bytes.len(),
+                            self.pc - 1
+                        ))
+                    })?
+                    .to_vec();
+                self.charge_data(OpCode::BytesSlice, slice.len())?;
+                self.push(Value::Bytes(slice))?;
+            }
+            OpCode::BytesEq => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                let (a, b) = (a.as_bytes()?, b.as_bytes()?);
+                self.charge_data(OpCode::BytesEq, a.len().min(b.len()))?;
+                self.push(Value::Bool(a == b))?;
+            }
+            OpCode::BytesLen => {
+                let len = self.pop()?.as_bytes()?.len();
+                let len = i32::try_from(len).map_err(|_| {
+                    VMError::RuntimeError(format!("Byte string of {} bytes is too long", len))
+                })?;
+                self.push(Value::I32(len))?;
+            }
+            OpCode::Mask => {
+                let bits = self.read_u8()?;
+                let value = self.pop()?.as_i64()?;
+                let masked = if bits >= 64 {
+                    value
+                } else {
+                    value & ((1i64 << bits) - 1)
+                };
+                self.push(Value::from_i64(masked))?;
+            }
+            OpCode::SignExtend => {
+                let bits = self.read_u8()?;
+                let value = self.pop()?.as_i64()?;
+                let extended = if bits == 0 || bits >= 64 {
+                    value
+                } else {
+                    let shift = 64 - u32::from(bits);
+                    (value << shift) >> shift
+                };
+                self.push(Value::from_i64(extended))?;
+            }
+            OpCode::IntToBytes => {
+                let width = usize::from(self.read_u8()?);
+                let value = self.pop()?.as_i64()?;
+                // Two's complement, big-endian, sign-filled beyond the 8 value bytes.
+                let fill = if value < 0 { 0xFF } else { 0x00 };
+                let mut bytes = vec![fill; width.saturating_sub(8)];
+                let be = value.to_be_bytes();
+                bytes.extend_from_slice(&be[8 - width.min(8)..]);
+                self.push(Value::Bytes(bytes))?;
+            }
+            OpCode::BytesToInt => {
+                let value = self.pop()?;
+                let bytes = value.as_bytes()?;
+                let significant = &bytes[bytes.iter().take_while(|b| **b == 0).count()..];
+                if significant.len() > 8 || (significant.len() == 8 && significant[0] & 0x80 != 0) {
+                    return Err(VMError::RuntimeError(format!(
+                        "{}-byte value exceeds the VM's 64-bit integer range at PC {}",
+                        bytes.len(),
+                        self.pc - 1
+                    )));
+                }
+                let value = significant
+                    .iter()
+                    .fold(0i64, |acc, byte| (acc << 8) | i64::from(*byte));
+                self.push(Value::from_i64(value))?;
+            }
+            OpCode::BytesResize => {
+                let width = usize::from(self.read_u8()?);
+                let mut bytes = self.pop()?.as_bytes()?.to_vec();
+                bytes.resize(width, 0);
+                self.push(Value::Bytes(bytes))?;
+            }
+            OpCode::CallDataLoad => {
+                let offset = self.pop_address()?;
+                let mut word = [0u8; 32];
+                if offset < self.calldata.len() {
+                    let available = &self.calldata[offset..];
+                    let len = available.len().min(32);
+                    word[..len].copy_from_slice(&available[..len]);
+                }
+                let value = Self::int_from_word(&word).ok_or_else(|| {
+                    VMError::RuntimeError(format!(
+                        "Calldata word at {} exceeds the VM's 64-bit integer range at PC {}",
+                        offset,
+                        self.pc - 1
+                    ))
+                })?;
+                self.push(Value::from_i64(value))?;
+            }
+            OpCode::CallDataSlice => {
+                let len = self.pop_address()?;
+                let offset = self.pop_address()?;
+                let bytes = offset
+                    .checked_add(len)
+                    .and_then(|end| self.calldata.get(offset..end))
+                    .ok_or_else(|| {
+                        VMError::RuntimeError(format!(
+                            "Calldata range {}+{} is outside the {}-byte calldata at PC {}",
+                            offset,
+                            len,
+                            self.calldata.len(),
+                            self.pc - 1
+                        ))
+                    })?
+                    .to_vec();
+                self.charge_data(OpCode::CallDataSlice, len)?;
+                self.push(Value::Bytes(bytes))?;
+            }
+            OpCode::SLoad => {
+                let slot = self.pop_slot()?;
+                let value = self.storage.get(&slot).cloned().unwrap_or(Value::I32(0));
+                self.push(value)?;
+            }
+            OpCode::SlotHash => {
+                let key = self.pop()?;
+                let base = self.pop_slot()?;
+                let slot = match &key {
+                    Value::Bytes(bytes) => derived_slot(base, bytes),
+                    value => derived_slot(base, &Self::word_from_value(value)?),
+                };
+                self.push_slot(slot)?;
+            }
+            OpCode::SlotAdd => {
+                let count = self.pop()?.as_i64()?;
+                let slot = self.pop_slot()?;
+                let count = u64::try_from(count).map_err(|_| {
+                    VMError::InvalidAddress(format!(
+                        "Storage slot offset {} is negative at PC {}",
+                        count,
+                        self.pc - 1
+                    ))
+                })?;
+                self.push_slot(slot.offset(count))?;
+            }
+            OpCode::SLoadBytes => {
+                let slot = self.pop_slot()?;
+                let bytes = match self.storage.get(&slot) {
+                    Some(Value::Bytes(bytes)) => bytes.clone(),
+                    None => Vec::new(),
+                    Some(value) if value.equals(&Value::I32(0))? => Vec::new(),
+                    Some(_) => {
+                        return Err(VMError::RuntimeError(format!(
+                            "Storage slot {} holds an integer, not a byte string, at PC {}",
+                            slot,
+                            self.pc - 1
+                        )))
+                    }
+                };
+                self.charge_data(OpCode::SLoadBytes, bytes.len())?;
+                self.push(Value::Bytes(bytes))?;
+            }
+            OpCode::SStore => {
+                let slot = self.pop_slot()?;
+                let value = self.pop()?;
+                let size = match &value {
+                    Value::Bytes(bytes) => bytes.len(),
+                    _ => 32,
+                };
+                self.charge_data(OpCode::SStore, size)?;
+                self.gas_meter.consume(self.schedule.storage_cost(size))?;
+                let previous = self.storage.insert(slot, value);
+                self.journal.push(FrameChange::Storage(slot, previous));
+            }
+            OpCode::Log => {
+                let count = usize::from(self.read_u8()?);
+                let mut data = (0..count)
+                    .map(|_| self.pop())
+                    .collect::<Result<Vec<_>, _>>()?;
+                data.reverse();
+                let topic = self.pop()?.as_i64()? as u32;
+                let size = data
+                    .iter()
+                    .map(|value| match value {
+                        Value::Bytes(bytes) => bytes.len(),
+                        _ => 32,
+                    })
+                    .sum();
+                self.charge_data(OpCode::Log, size)?;
+                self.logs.push(LogEntry {
+                    address: self.address,
+                    topic,
+                    data,
+                });
+                self.journal.push(FrameChange::Log);
+            }
+            OpCode::Revert => {
+                let message = self.pop()?;
+                let message = message.as_bytes()?;
+                self.revert_data = Some(message.to_vec());
+                return Err(VMError::Revert(format!(
+                    "{} at PC {}",
+                    String::from_utf8_lossy(message),
+                    self.pc - 1
+                )));
+            }
+            OpCode::Address => {
+                self.push(Value::Bytes(self.address.to_vec()))?;
+            }
+            OpCode::Caller => {
+                self.push(Value::Bytes(self.caller.to_vec()))?;
+            }
+            OpCode::CallDataSize => {
+                self.push(Value::I32(self.calldata.len() as i32))?;
+            }
+            OpCode::Selector => {
+                let selector: [u8; 4] = self
+                    .calldata
+                    .get(..4)
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or_else(|| {
+                        VMError::RuntimeError(format!(
+                            "Calldata of {} bytes is too short for a function selector at PC {}",
+                            self.calldata.len(),
+                            self.pc - 1
+                        ))
+                    })?;
+                self.push(Value::I32(u32::from_be_bytes(selector) as i32))?;
+            }
+            OpCode::CallValue => {
+                self.push_balance(self.call_value)?;
+            }
+            OpCode::Balance => {
+                let account = self.pop_account()?;
+                self.push_balance(self.balance_of(&account, balances))?;
+            }
+            OpCode::SelfBalance => {
+                self.push_balance(self.balance_of(&self.address, balances))?;
+            }
+            OpCode::Transfer => {
+                let amount = self.pop()?.as_i64()?;
+                let amount = u64::try_from(amount).map_err(|_| {
+                    VMError::RuntimeError(format!(
+                        "Transfer amount {} is negative at PC {}",
+                        amount,
+                        self.pc - 1
+                    ))
+                })?;
+                let to = self.pop_account()?;
+                self.gas_meter
+                    .consume(self.schedule.transfer_cost(amount))?;
+                let from = self.address;
+                let pc = self.pc - 1;
+                self.move_value(&from, &to, amount, balances)
+                    .map_err(|err| err.with_context(&format!("PC {}", pc)))?;
             }
             OpCode::Print => {
                 let value = self.pop()?;
-                println!("{:?}", value);
+                self.output.push(value);
             }
             OpCode::Halt => {
                 self.halted = true;
@@ -430,22 +1581,27 @@ impl QuantumVM {
         Ok(())
     }
 
+    fn close_gas_scope(&mut self) -> Result<(), VMError> {
+        if self.gas_scope_frames.pop().is_none() || self.gas_meter.pop_scope().is_none() {
+            return Err(VMError::RuntimeError(format!(
+                "GasLimitEnd without an open gas scope at PC {}",
+                self.pc - 1
+            )));
+        }
+        Ok(())
+    }
+
     fn execute_kem_key_exchange(
         &mut self,
+        opcode: OpCode,
         kem: Kem,
         algorithm_name: &str,
-        base_cost: u64,
-        data_multiplier: u64,
-        compute_cost: u64,
     ) -> Result<(), VMError> {
         // This opcode performs decapsulation as per the VM contract interface.
         let private_key = self.pop()?.as_bytes()?.to_vec();
         let ciphertext = self.pop()?.as_bytes()?.to_vec();
 
-        // Dynamic gas model: base + byte-scaled cost + algorithm compute weight.
-        let data_cost = (private_key.len() + ciphertext.len()) as u64 * data_multiplier;
-        let gas_cost = base_cost + data_cost + compute_cost;
-        self.gas_meter.consume_pqc(gas_cost)?;
+        self.charge_pqc(opcode, private_key.len() + ciphertext.len())?;
 
         let shared_secret = kem.decapsulate(&ciphertext, &private_key).map_err(|e| {
             VMError::CryptoError(format!("{algorithm_name} decapsulation failed: {:?}", e))
@@ -454,23 +1610,36 @@ impl QuantumVM {
         Ok(())
     }
 
+    /// Charges the operand-dependent part of `opcode`'s cost for `bytes` bytes of operands.
+    fn charge_data(&mut self, opcode: OpCode, bytes: usize) -> Result<(), VMError> {
+        self.gas_meter
+            .consume(self.schedule.cost(opcode).data_cost(bytes))
+    }
+
+    /// Charges the whole cost of a PQC operation on `bytes` bytes of operands as PQ gas.
+    fn charge_pqc(&mut self, opcode: OpCode, bytes: usize) -> Result<(), VMError> {
+        self.gas_meter
+            .consume_pqc(self.schedule.cost(opcode).total(bytes))
+    }
+
     fn push(&mut self, value: Value) -> Result<(), VMError> {
-        if self.stack.len() >= 1000 {
+        if self.stack.len() >= STACK_LIMIT {
             return Err(VMError::StackOverflow(format!(
-                "Stack overflow: maximum stack size (1000) exceeded. Current size: {}",
+                "Maximum stack size ({}) exceeded. Current size: {}",
+                STACK_LIMIT,
                 self.stack.len()
             )));
         }
-        self.gas_meter.consume(1)?; // Stack push cost
+        self.gas_meter.consume(self.schedule.stack_push)?;
         self.stack.push(value);
         Ok(())
     }
 
     fn pop(&mut self) -> Result<Value, VMError> {
-        self.gas_meter.consume(1)?; // Stack pop cost
+        self.gas_meter.consume(self.schedule.stack_pop)?;
         self.stack.pop().ok_or_else(|| {
             VMError::StackUnderflow(format!(
-                "Stack underflow: attempted to pop from empty stack at PC {}",
+                "Attempted to pop from empty stack at PC {}",
                 self.pc
             ))
         })
@@ -478,13 +1647,164 @@ impl QuantumVM {
 
     fn peek(&self) -> Result<&Value, VMError> {
         self.stack.last().ok_or_else(|| {
-            VMError::StackUnderflow(format!(
-                "Stack underflow: attempted to peek empty stack at PC {}",
-                self.pc
+            VMError::StackUnderflow(format!("Attempted to peek empty stack at PC {}", self.pc))
+        })
+    }
+
+    /// Pops the two integer operands of a binary instruction, left operand first.
+    fn pop_operands(&mut self) -> Result<(i64, i64), VMError> {
+        let b = self.pop()?.as_i64()?;
+        let a = self.pop()?.as_i64()?;
+        Ok((a, b))
+    }
+
+    fn push_arithmetic(
+        &mut self,
+        operation: &str,
+        a: i64,
+        b: i64,
+        result: Option<i64>,
+    ) -> Result<(), VMError> {
+        let value = result.ok_or_else(|| {
+            VMError::RuntimeError(format!(
+                "{} of {} and {} overflows the VM's 64-bit integer range at PC {}",
+                operation,
+                a,
+                b,
+                self.pc - 1
+            ))
+        })?;
+        self.push(Value::from_i64(value))
+    }
+
+    /// Pops a memory address; addresses are non-negative integers.
+    fn pop_address(&mut self) -> Result<usize, VMError> {
+        let addr = self.pop()?.as_i64()?;
+        usize::try_from(addr).map_err(|_| {
+            VMError::InvalidAddress(format!(
+                "Memory address {} is negative at PC {}",
+                addr,
+                self.pc - 1
+            ))
+        })
+    }
+
+    /// Pops an account address: a 20-byte string.
+    fn pop_account(&mut self) -> Result<Address, VMError> {
+        let value = self.pop()?;
+        let bytes = value.as_bytes()?;
+        bytes.try_into().map_err(|_| {
+            VMError::InvalidAddress(format!(
+                "Expected a 20-byte account address but got {} bytes at PC {}",
+                bytes.len(),
+                self.pc - 1
             ))
         })
     }
 
+    fn push_balance(&mut self, balance: u64) -> Result<(), VMError> {
+        let balance = i64::try_from(balance).map_err(|_| {
+            VMError::RuntimeError(format!(
+                "Balance {} exceeds the 64-bit integer range at PC {}",
+                balance,
+                self.pc - 1
+            ))
+        })?;
+        self.push(Value::from_i64(balance))
+    }
+
+    /// Pops a storage slot: a non-negative integer, or a 32-byte word for a slot too large to
+    /// be one.
+    fn pop_slot(&mut self) -> Result<StorageKey, VMError> {
+        match self.pop()? {
+            Value::Bytes(bytes) => bytes.as_slice().try_into().map(StorageKey).map_err(|_| {
+                VMError::InvalidAddress(format!(
+                    "Expected a 32-byte storage slot but got {} bytes at PC {}",
+                    bytes.len(),
+                    self.pc - 1
+                ))
+            }),
+            value => {
+                let slot = value.as_i64()?;
+                u64::try_from(slot)
+                    .map(|slot| StorageKey::default().offset(slot))
+                    .map_err(|_| {
+                        VMError::InvalidAddress(format!(
+                            "Storage slot {} is out of range at PC {}",
+                            slot,
+                            self.pc - 1
+                        ))
+                    })
+            }
+        }
+    }
+
+    /// Pushes a storage slot the way `pop_slot` reads it back: as an integer when it fits one.
+    fn push_slot(&mut self, slot: StorageKey) -> Result<(), VMError> {
+        match slot.as_i64() {
+            Some(slot) => self.push(Value::from_i64(slot)),
+            None => self.push(Value::Bytes(slot.0.to_vec())),
+        }
+    }
+
+    /// Grows memory to cover `addr..addr + len`, charging expansion gas before allocating.
+    fn touch_memory(&mut self, addr: usize, len: usize) -> Result<(), VMError> {
+        if len == 0 {
+            return Ok(());
+        }
+        let end = addr.checked_add(len).ok_or_else(|| {
+            VMError::InvalidAddress(format!(
+                "Memory range {}+{} overflows at PC {}",
+                addr,
+                len,
+                self.pc - 1
+            ))
+        })?;
+        let words = end.div_ceil(32);
+        self.gas_meter.expand_memory(words as u64)?;
+        if self.memory.len() < words * 32 {
+            self.memory.resize(words * 32, 0);
+        }
+        Ok(())
+    }
+
+    /// Big-endian two's complement word for an integer or boolean.
+    fn word_from_value(value: &Value) -> Result<[u8; 32], VMError> {
+        let value = match value {
+            Value::Bool(flag) => i64::from(*flag),
+            Value::I32(v) => i64::from(*v),
+            Value::I64(v) => *v,
+            Value::Bytes(_) => {
+                return Err(VMError::RuntimeError(
+                    "Store expects an integer, a bool or a 32-byte word; use StoreBytes for byte strings"
+                        .to_string(),
+                ))
+            }
+        };
+        let mut word = [if value < 0 { 0xFF } else { 0x00 }; 32];
+        word[24..].copy_from_slice(&value.to_be_bytes());
+        Ok(word)
+    }
+
+    /// Reads the word at `addr` (already in bounds) as a VM integer, or as the 32-byte word
+    /// itself if it is too large for one.
+    fn read_word(&self, addr: usize) -> Value {
+        let word: &[u8; 32] = self.memory[addr..addr + 32]
+            .try_into()
+            .expect("32-byte word");
+        match Self::int_from_word(word) {
+            Some(value) => Value::from_i64(value),
+            None => Value::Bytes(word.to_vec()),
+        }
+    }
+
+    /// Big-endian two's complement word as an `i64`, if it fits.
+    fn int_from_word(word: &[u8; 32]) -> Option<i64> {
+        let value = i64::from_be_bytes(word[24..].try_into().expect("8-byte tail"));
+        let fill = if value < 0 { 0xFF } else { 0x00 };
+        word[..24].iter().all(|byte| *byte == fill).then_some(value)
+    }
+
     fn read_i32(&mut self) -> Result<i32, VMError> {
         if self.pc + 4 > self.code.len() {
             return Err(VMError::InvalidAddress(format!(
@@ -503,6 +1823,17 @@ impl QuantumVM {
         Ok(i32::from_le_bytes(bytes))
     }
 
+    fn read_u8(&mut self) -> Result<u8, VMError> {
+        let byte = *self.code.get(self.pc).ok_or_else(|| {
+            VMError::InvalidAddress(format!(
+                "Cannot read u8: no bytes remaining at PC {}",
+                self.pc
+            ))
+        })?;
+        self.pc += 1;
+        Ok(byte)
+    }
+
     fn read_u32(&mut self) -> Result<u32, VMError> {
         if self.pc + 4 > self.code.len() {
             return Err(VMError::InvalidAddress(format!(
@@ -1,5 +1,11 @@
 use pqsynq::{DigitalSignature, Kem, KeyEncapsulation, Sign};
-use quantumvm::{Assembler, OpCode, QuantumVM};
+use quantumvm::vm::GasMeter;
+use quantumvm::{
+    contract_address, derived_slot, disassembler, verifier, Account, Address, Assembler,
+    BlockExecutor, ContractInstance, ExecutionReceipt, ExecutionStatus, GasClass, GasSchedule,
+    GasScheduleHistory, Header, Label, MemoryBackend, OpCode, QuantumVM, SectionKind, SourceMap,
+    StorageKey, Transaction, TransactionReceipt, VMError, Value, WorldState,
+};
 
 #[test]
 fn test_basic_arithmetic() {
@@ -11,7 +17,7 @@ fn test_basic_arithmetic() {
     assembler.emit_op(OpCode::Add);
     assembler.emit_op(OpCode::Halt);
 
-    let bytecode = assembler.build();
+    let bytecode = assembler.build().unwrap();
     let mut vm = QuantumVM::new();
     vm.load_bytecode(&bytecode).unwrap();
     vm.execute().unwrap();
@@ -20,6 +26,43 @@ fn test_basic_arithmetic() {
     assert_eq!(result, 30);
 }
 
+#[test]
+fn test_arithmetic_widens_to_64_bits_and_rejects_overflow() {
+    let source = r#"
+        push 2000000000
+        push 2000000000
+        add
+        push 3
+        mul
+        dup
+        push 2147483647
+        gt
+        halt
+"#;
+    let bytecode = Assembler::from_qasm(source).unwrap().build().unwrap();
+    let mut vm = QuantumVM::new();
+    vm.load_bytecode(&bytecode).unwrap();
+    vm.execute().unwrap();
+    assert!(vm.stack.pop().unwrap().as_bool().unwrap());
+    assert!(vm.stack.pop().unwrap().same_as(&Value::I64(12_000_000_000)));
+
+    let mut assembler = Assembler::new();
+    assembler.emit_const(&i64::MAX.to_be_bytes());
+    assembler.emit_op(OpCode::BytesToInt);
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_i32(1);
+    assembler.emit_op(OpCode::Add);
+    assembler.emit_op(OpCode::Halt);
+    let mut vm = QuantumVM::new();
+    vm.load_bytecode(&assembler.build().unwrap()).unwrap();
+    let err = vm.execute().unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("overflows the VM's 64-bit integer range"),
+        "{err}"
+    );
+}
+
 #[test]
 fn test_mldsa_verify() {
     let mut assembler = Assembler::new();
@@ -49,7 +92,7 @@ fn test_mldsa_verify() {
     assembler.emit_op(OpCode::MLDSAVerify);
     assembler.emit_op(OpCode::Halt);
 
-    let bytecode = assembler.build();
+    let bytecode = assembler.build().unwrap();
     let mut vm = QuantumVM::new();
     vm.load_bytecode(&bytecode).unwrap();
     vm.execute().unwrap();
@@ -82,7 +125,7 @@ fn test_mlkem_decaps() {
     assembler.emit_op(OpCode::MLKEMKeyExchange);
     assembler.emit_op(OpCode::Halt);
 
-    let bytecode = assembler.build();
+    let bytecode = assembler.build().unwrap();
     let mut vm = QuantumVM::new();
     vm.load_bytecode(&bytecode).unwrap();
     vm.execute().unwrap();
@@ -106,7 +149,7 @@ fn run_hqckem_decaps_test(opcode: OpCode, kem: Kem) {
     assembler.emit_op(opcode);
     assembler.emit_op(OpCode::Halt);
 
-    let bytecode = assembler.build();
+    let bytecode = assembler.build().unwrap();
     let mut vm = QuantumVM::new();
     vm.load_bytecode(&bytecode).unwrap();
     vm.execute().unwrap();
@@ -129,3 +172,1742 @@ fn test_hqckem192_decaps() {
 fn test_hqckem256_decaps() {
     run_hqckem_decaps_test(OpCode::HQCKEM256KeyExchange, Kem::hqckem256());
 }
+
+/// Emits `GasLimitBegin limit <handler>`.
+fn emit_gas_scope_begin(assembler: &mut Assembler, limit: u32, handler: Label) {
+    assembler.emit_op(OpCode::GasLimitBegin);
+    assembler.emit_u32(limit);
+    assembler.emit_label(handler);
+}
+
+fn emit_store(assembler: &mut Assembler, value: i32, addr: i32) {
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_i32(value);
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_i32(addr);
+    assembler.emit_op(OpCode::Store);
+}
+
+#[test]
+fn test_gas_meter_charges_every_open_scope() {
+    let mut meter = GasMeter::new(1_000, 100);
+    meter.push_scope(50);
+    meter.consume(10).unwrap();
+    meter.push_scope(5);
+    meter.consume(4).unwrap();
+    assert_eq!(meter.scopes[0].used, 14);
+    assert_eq!(meter.scopes[1].used, 4);
+
+    assert!(meter.consume(2).is_err());
+    assert_eq!(meter.exceeded_scope(), Some(1));
+    assert_eq!(meter.consumed, 16);
+}
+
+#[test]
+fn test_gas_scope_overrun_reverts_scope_and_resumes_at_handler() {
+    let mut assembler = Assembler::new();
+    emit_store(&mut assembler, 7, 100);
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_i32(42);
+
+    let handler = assembler.new_label();
+    emit_gas_scope_begin(&mut assembler, 30, handler);
+    assembler.emit_op(OpCode::Pop);
+    for value in 0..10 {
+        emit_store(&mut assembler, value, 100);
+    }
+    assembler.emit_op(OpCode::GasLimitEnd);
+    // Falling through reaches the handler with as many values as an overrun restores.
+    emit_push(&mut assembler, 0);
+
+    assembler.bind(handler);
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_i32(100);
+    assembler.emit_op(OpCode::Load);
+    assembler.emit_op(OpCode::Halt);
+
+    let mut vm = QuantumVM::new();
+    vm.load_bytecode(&assembler.build().unwrap()).unwrap();
+    vm.execute().unwrap();
+
+    // The stored value and the popped stack entry are both restored.
+    assert_eq!(vm.stack.pop().unwrap().as_i32().unwrap(), 7);
+    assert_eq!(vm.stack.pop().unwrap().as_i32().unwrap(), 42);
+    assert!(vm.consumed_gas() > 30);
+}
+
+#[test]
+fn test_nested_gas_scope_overrun_only_unwinds_inner_scope() {
+    let mut assembler = Assembler::new();
+    let outer_handler = assembler.new_label();
+    let inner_handler = assembler.new_label();
+    emit_gas_scope_begin(&mut assembler, 10_000, outer_handler);
+    emit_store(&mut assembler, 1, 200);
+
+    emit_gas_scope_begin(&mut assembler, 10, inner_handler);
+    for value in 0..10 {
+        emit_store(&mut assembler, value, 201);
+    }
+    assembler.emit_op(OpCode::GasLimitEnd);
+
+    assembler.bind(inner_handler);
+    emit_store(&mut assembler, 2, 200);
+    assembler.emit_op(OpCode::GasLimitEnd);
+
+    assembler.bind(outer_handler);
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_i32(200);
+    assembler.emit_op(OpCode::Load);
+    assembler.emit_op(OpCode::Halt);
+
+    let mut vm = QuantumVM::new();
+    vm.load_bytecode(&assembler.build().unwrap()).unwrap();
+    vm.execute().unwrap();
+
+    // The outer scope kept running after the inner overrun and its own write survived.
+    assert_eq!(vm.stack.pop().unwrap().as_i32().unwrap(), 2);
+}
+
+#[test]
+fn test_transaction_out_of_gas_is_not_caught_by_gas_scope() {
+    let mut assembler = Assembler::new();
+    let handler = assembler.new_label();
+    emit_gas_scope_begin(&mut assembler, 1_000, handler);
+    for value in 0..10 {
+        emit_store(&mut assembler, value, 300);
+    }
+    assembler.emit_op(OpCode::GasLimitEnd);
+    assembler.bind(handler);
+    assembler.emit_op(OpCode::Halt);
+
+    let mut vm = QuantumVM::with_gas(20, 0);
+    vm.load_bytecode(&assembler.build().unwrap()).unwrap();
+    assert!(matches!(vm.execute(), Err(VMError::OutOfGas(_))));
+}
+
+#[test]
+fn test_gas_scope_snapshot_is_charged_per_word_and_nesting_is_capped() {
+    // 32,000 bytes of memory and an empty stack: opening a scope copies 1,000 words.
+    let gas_used = |scoped: bool| {
+        let mut assembler = Assembler::new();
+        emit_store(&mut assembler, 1, 31_968);
+        if scoped {
+            let handler = assembler.new_label();
+            emit_gas_scope_begin(&mut assembler, 100_000, handler);
+            assembler.emit_op(OpCode::GasLimitEnd);
+            assembler.bind(handler);
+        }
+        assembler.emit_op(OpCode::Halt);
+        run_program(assembler).unwrap().consumed_gas()
+    };
+    // Two instructions, `GasLimitBegin`'s base of 2 and `GasLimitEnd`'s 1, and one per word.
+    assert_eq!(gas_used(true) - gas_used(false), 2 + 2 + 1 + 1_000);
+
+    let mut assembler = Assembler::new();
+    let handler = assembler.new_label();
+    for _ in 0..65 {
+        emit_gas_scope_begin(&mut assembler, 100_000, handler);
+    }
+    assembler.bind(handler);
+    assembler.emit_op(OpCode::Halt);
+    let Err(VMError::RuntimeError(message)) = run_program(assembler) else {
+        panic!("a 65th open gas scope must fail");
+    };
+    assert!(
+        message.contains("Gas scopes nest deeper than the limit of 64"),
+        "{message}"
+    );
+}
+
+fn run_program(assembler: Assembler) -> Result<QuantumVM, VMError> {
+    let mut vm = QuantumVM::new();
+    vm.load_bytecode(&assembler.build().unwrap())?;
+    vm.execute()?;
+    Ok(vm)
+}
+
+fn emit_load_bytes(assembler: &mut Assembler, bytes: &[u8]) {
+    assembler.emit_op(OpCode::LoadImm);
+    assembler.emit_bytes(bytes);
+}
+
+#[test]
+fn test_byte_string_opcodes() {
+    let mut assembler = Assembler::new();
+    emit_load_bytes(&mut assembler, b"post-");
+    emit_load_bytes(&mut assembler, b"quantum");
+    assembler.emit_op(OpCode::BytesConcat);
+    assembler.emit_op(OpCode::Dup);
+    assembler.emit_op(OpCode::BytesLen);
+    assembler.emit_op(OpCode::Swap);
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_i32(5);
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_i32(12);
+    assembler.emit_op(OpCode::BytesSlice);
+    assembler.emit_op(OpCode::Dup);
+    emit_load_bytes(&mut assembler, b"quantum");
+    assembler.emit_op(OpCode::BytesEq);
+    assembler.emit_op(OpCode::Swap);
+    emit_load_bytes(&mut assembler, b"quantum");
+    assembler.emit_op(OpCode::Ne);
+    assembler.emit_op(OpCode::Halt);
+
+    let mut vm = run_program(assembler).unwrap();
+    assert!(!vm.stack.pop().unwrap().as_bool().unwrap());
+    assert!(vm.stack.pop().unwrap().as_bool().unwrap());
+    assert_eq!(vm.stack.pop().unwrap().as_i32().unwrap(), 12);
+}
+
+#[test]
+fn test_byte_slice_out_of_bounds_is_an_error() {
+    let mut assembler = Assembler::new();
+    emit_load_bytes(&mut assembler, b"abc");
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_i32(2);
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_i32(4);
+    assembler.emit_op(OpCode::BytesSlice);
+    assembler.emit_op(OpCode::Halt);
+
+    let err = run_program(assembler)
+        .err()
+        .expect("slice past the end must fail");
+    assert!(err
+        .to_string()
+        .contains("Slice 2..4 is out of bounds for 3 bytes"));
+}
+
+#[test]
+fn test_eq_compares_bools_and_rejects_mixed_bytes() {
+    let mut assembler = Assembler::new();
+    emit_load_bytes(&mut assembler, b"x");
+    emit_load_bytes(&mut assembler, b"x");
+    assembler.emit_op(OpCode::Eq);
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_i32(1);
+    assembler.emit_op(OpCode::Eq);
+    assembler.emit_op(OpCode::Halt);
+    let mut vm = run_program(assembler).unwrap();
+    assert!(vm.stack.pop().unwrap().as_bool().unwrap());
+
+    let mut assembler = Assembler::new();
+    emit_load_bytes(&mut assembler, b"1");
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_i32(1);
+    assembler.emit_op(OpCode::Eq);
+    assembler.emit_op(OpCode::Halt);
+    assert!(run_program(assembler).is_err());
+}
+
+#[test]
+fn test_conversion_opcodes_mask_extend_and_resize() {
+    let mut assembler = Assembler::new();
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_i32(300);
+    assembler.emit_op(OpCode::Mask);
+    assembler.emit_u8(8);
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_i32(200);
+    assembler.emit_op(OpCode::SignExtend);
+    assembler.emit_u8(8);
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_i32(-2);
+    assembler.emit_op(OpCode::IntToBytes);
+    assembler.emit_u8(4);
+    assembler.emit_op(OpCode::Dup);
+    assembler.emit_op(OpCode::BytesToInt);
+    assembler.emit_op(OpCode::Swap);
+    assembler.emit_op(OpCode::BytesResize);
+    assembler.emit_u8(6);
+    assembler.emit_op(OpCode::Halt);
+
+    let mut vm = run_program(assembler).unwrap();
+    assert_eq!(
+        vm.stack.pop().unwrap().as_bytes().unwrap(),
+        &[0xFF, 0xFF, 0xFF, 0xFE, 0x00, 0x00]
+    );
+    assert_eq!(vm.stack.pop().unwrap().as_i64().unwrap(), 0xFFFF_FFFE);
+    assert_eq!(vm.stack.pop().unwrap().as_i32().unwrap(), -56);
+    assert_eq!(vm.stack.pop().unwrap().as_i32().unwrap(), 44);
+}
+
+#[test]
+fn test_bytes_to_int_rejects_values_wider_than_the_vm_word() {
+    let mut assembler = Assembler::new();
+    emit_load_bytes(&mut assembler, &[0x01; 9]);
+    assembler.emit_op(OpCode::BytesToInt);
+    assembler.emit_op(OpCode::Halt);
+
+    let err = run_program(assembler)
+        .err()
+        .expect("nine significant bytes cannot fit an i64");
+    assert!(err
+        .to_string()
+        .contains("9-byte value exceeds the VM's 64-bit integer range"));
+}
+
+#[test]
+fn test_bounds_check_and_unwritten_slots_read_as_zero() {
+    let mut assembler = Assembler::new();
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_u32(77);
+    assembler.emit_op(OpCode::Load);
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_u32(2);
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_u32(3);
+    assembler.emit_op(OpCode::BoundsCheck);
+    assembler.emit_op(OpCode::Halt);
+
+    let mut vm = run_program(assembler).unwrap();
+    assert_eq!(vm.stack.pop().unwrap().as_i64().unwrap(), 2);
+    assert_eq!(vm.stack.pop().unwrap().as_i64().unwrap(), 0);
+
+    let mut assembler = Assembler::new();
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_u32(3);
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_u32(3);
+    assembler.emit_op(OpCode::BoundsCheck);
+    assembler.emit_op(OpCode::Halt);
+
+    let err = run_program(assembler)
+        .err()
+        .expect("index equal to the length is out of bounds");
+    assert!(err
+        .to_string()
+        .contains("Index 3 out of bounds for length 3"));
+}
+
+fn emit_push(assembler: &mut Assembler, value: u32) {
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_u32(value);
+}
+
+#[test]
+fn test_linear_memory_words_bytes_and_copies() {
+    let mut assembler = Assembler::new();
+    // mem[0x20..0x40] = -2 as a word; copy it to 0x80 and read it back.
+    emit_push(&mut assembler, (-2i32) as u32);
+    emit_push(&mut assembler, 0x20);
+    assembler.emit_op(OpCode::Store);
+    emit_push(&mut assembler, 0x80);
+    emit_push(&mut assembler, 0x20);
+    emit_push(&mut assembler, 32);
+    assembler.emit_op(OpCode::MCopy);
+    emit_push(&mut assembler, 0x80);
+    assembler.emit_op(OpCode::Load);
+    // Byte access sees the big-endian encoding.
+    emit_push(&mut assembler, 0x5A);
+    emit_push(&mut assembler, 0x3F);
+    assembler.emit_op(OpCode::Store8);
+    emit_push(&mut assembler, 0x3E);
+    assembler.emit_op(OpCode::Load8);
+    emit_push(&mut assembler, 0x20);
+    assembler.emit_op(OpCode::Load);
+    // Length-prefixed byte strings, padded to whole words.
+    emit_load_bytes(&mut assembler, b"quantum");
+    emit_push(&mut assembler, 0x100);
+    assembler.emit_op(OpCode::StoreBytes);
+    emit_push(&mut assembler, 0x100);
+    assembler.emit_op(OpCode::LoadBytes);
+    assembler.emit_op(OpCode::MSize);
+    assembler.emit_op(OpCode::Halt);
+
+    let mut vm = run_program(assembler).unwrap();
+    assert_eq!(vm.memory().len(), 0x140);
+    assert_eq!(vm.stack.pop().unwrap().as_i64().unwrap(), 0x140);
+    assert_eq!(vm.stack.pop().unwrap().as_bytes().unwrap(), b"quantum");
+    assert_eq!(vm.stack.pop().unwrap().as_i64().unwrap(), 0x140);
+    assert_eq!(vm.stack.pop().unwrap().as_i64().unwrap(), -166);
+    assert_eq!(vm.stack.pop().unwrap().as_i64().unwrap(), 0xFF);
+    assert_eq!(vm.stack.pop().unwrap().as_i64().unwrap(), -2);
+}
+
+#[test]
+fn test_memory_expansion_gas_is_quadratic() {
+    assert_eq!(GasMeter::memory_cost(1), 3);
+    assert_eq!(GasMeter::memory_cost(1024), 3 * 1024 + 2048);
+
+    let mut meter = GasMeter::new(1_000_000, 100);
+    meter.expand_memory(32).unwrap();
+    assert_eq!(meter.consumed, GasMeter::memory_cost(32));
+    meter.expand_memory(16).unwrap();
+    assert_eq!(meter.consumed, GasMeter::memory_cost(32));
+    meter.expand_memory(64).unwrap();
+    assert_eq!(meter.consumed, GasMeter::memory_cost(64));
+
+    // Touching a far address pays for all memory below it before anything is allocated.
+    let mut assembler = Assembler::new();
+    emit_push(&mut assembler, 0x7FFF_FFE0);
+    assembler.emit_op(OpCode::Load);
+    assembler.emit_op(OpCode::Halt);
+    let err = run_program(assembler)
+        .err()
+        .expect("a 2 GiB memory is unaffordable");
+    assert!(matches!(err, VMError::OutOfGas(_)), "{err}");
+}
+
+#[test]
+fn test_memory_rejects_byte_strings_in_words_and_negative_addresses() {
+    let mut assembler = Assembler::new();
+    emit_load_bytes(&mut assembler, b"ab");
+    emit_push(&mut assembler, 0);
+    assembler.emit_op(OpCode::Store);
+    let err = run_program(assembler).err().expect("words hold integers");
+    assert!(err.to_string().contains("use StoreBytes"), "{err}");
+
+    let mut assembler = Assembler::new();
+    emit_push(&mut assembler, (-32i32) as u32);
+    assembler.emit_op(OpCode::Load);
+    let err = run_program(assembler)
+        .err()
+        .expect("addresses are unsigned");
+    assert!(
+        err.to_string().contains("Memory address -32 is negative"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_container_v2_sections_constant_pool_and_metadata() {
+    let mut assembler = Assembler::new();
+    assembler.set_compiler_version(1, 2, 3);
+    assembler.set_source_hash([7; 32]);
+    assembler.set_metadata(b"abi".to_vec());
+    assembler.emit_const(b"pooled");
+    assembler.emit_const(b"other");
+    assembler.emit_const(b"pooled");
+    assembler.emit_op(OpCode::Halt);
+    let bytecode = assembler.build().unwrap();
+
+    let header = Header::parse(&bytecode).unwrap();
+    assert_eq!(header.version, Header::VERSION_2);
+    assert_eq!(header.compiler_version, Some([1, 2, 3]));
+    assert_eq!(header.source_hash, Some([7; 32]));
+    assert_eq!(header.code_length, 16);
+    // Identical literals share one pool entry.
+    assert_eq!(header.data_length, 4 + 6 + 4 + 5);
+    assert_eq!(
+        header.section_bytes(&bytecode, SectionKind::Metadata),
+        b"abi"
+    );
+
+    let mut vm = QuantumVM::new();
+    vm.load_bytecode(&bytecode).unwrap();
+    vm.execute().unwrap();
+    assert_eq!(vm.metadata(), b"abi");
+    assert_eq!(vm.stack.pop().unwrap().as_bytes().unwrap(), b"pooled");
+    assert_eq!(vm.stack.pop().unwrap().as_bytes().unwrap(), b"other");
+    assert_eq!(vm.stack.pop().unwrap().as_bytes().unwrap(), b"pooled");
+}
+
+#[test]
+fn test_container_v1_still_loads() {
+    let code = [OpCode::Push as u8, 9, 0, 0, 0, OpCode::Halt as u8];
+    let mut bytecode = Vec::new();
+    bytecode.extend_from_slice(&Header::MAGIC.to_le_bytes());
+    bytecode.push(Header::VERSION_1);
+    bytecode.extend_from_slice(&Header::V1_LENGTH.to_le_bytes());
+    bytecode.extend_from_slice(&(code.len() as u32).to_le_bytes());
+    bytecode.extend_from_slice(&0u32.to_le_bytes());
+    bytecode.extend_from_slice(&code);
+
+    let header = Header::parse(&bytecode).unwrap();
+    assert_eq!(header.compiler_version, None);
+    assert_eq!(header.source_hash, None);
+
+    let mut vm = QuantumVM::new();
+    vm.load_bytecode(&bytecode).unwrap();
+    vm.execute().unwrap();
+    assert_eq!(vm.stack.pop().unwrap().as_i64().unwrap(), 9);
+
+    bytecode.truncate(bytecode.len() - 1);
+    let err = Header::parse(&bytecode).unwrap_err();
+    assert!(err.to_string().contains("Bytecode too short"), "{err}");
+}
+
+#[test]
+fn test_container_v2_rejects_malformed_sections() {
+    let mut assembler = Assembler::new();
+    assembler.emit_const(b"x");
+    assembler.emit_op(OpCode::Halt);
+    let bytecode = assembler.build().unwrap();
+    let table = Header::V2_FIXED_LENGTH;
+    let parse_err = |bytes: &[u8]| Header::parse(bytes).unwrap_err().to_string();
+
+    let mut trailing = bytecode.clone();
+    trailing.push(0);
+    assert!(parse_err(&trailing).contains("1 trailing bytes"));
+
+    let truncated = &bytecode[..bytecode.len() - 1];
+    assert!(parse_err(truncated).contains("Data section of 5 bytes"));
+
+    let mut duplicate = bytecode.clone();
+    duplicate[table + 5] = SectionKind::Code as u8;
+    assert!(parse_err(&duplicate).contains("Duplicate Code section"));
+
+    let mut unknown = bytecode.clone();
+    unknown[table + 5] = 9;
+    assert!(parse_err(&unknown).contains("Unknown section kind 9"));
+
+    let mut no_code = bytecode.clone();
+    no_code[table] = SectionKind::Metadata as u8;
+    assert!(parse_err(&no_code).contains("Missing Code section"));
+
+    let mut future = bytecode.clone();
+    future[4] = 3;
+    assert!(parse_err(&future).contains("Unsupported bytecode version 3"));
+
+    // A pool entry claiming more bytes than the section holds fails at load time.
+    let mut short_constant = bytecode.clone();
+    let data_start = bytecode.len() - 5;
+    short_constant[data_start] = 2;
+    let err = QuantumVM::new().load_bytecode(&short_constant).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Constant 0 needs 2 bytes but only 1 remain"),
+        "{err}"
+    );
+
+    let mut assembler = Assembler::new();
+    assembler.emit_op(OpCode::LoadConst);
+    assembler.emit_u32(0);
+    let err = run_program(assembler).err().expect("the pool is empty");
+    assert!(
+        err.to_string()
+            .contains("outside the 0-entry constant pool"),
+        "{err}"
+    );
+}
+
+fn load_err(assembler: Assembler) -> String {
+    QuantumVM::new()
+        .load_bytecode(&assembler.build().unwrap())
+        .unwrap_err()
+        .to_string()
+}
+
+#[test]
+fn test_verifier_rejects_bad_targets_truncation_and_unknown_opcodes() {
+    // Jumping to offset 1 lands inside the `Push` immediate.
+    let mut assembler = Assembler::new();
+    emit_push(&mut assembler, 7);
+    assembler.emit_op(OpCode::Jump);
+    assembler.emit_u32(1);
+    let err = load_err(assembler);
+    assert!(
+        err.contains("Jump target 1 at offset 5 lands inside an instruction"),
+        "{err}"
+    );
+
+    let mut assembler = Assembler::new();
+    assembler.emit_op(OpCode::Call);
+    assembler.emit_u32(99);
+    let err = load_err(assembler);
+    assert!(
+        err.contains("Call target 99 at offset 0 is outside the 5-byte code section"),
+        "{err}"
+    );
+
+    let mut assembler = Assembler::new();
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_u8(1);
+    assembler.emit_u8(0);
+    let err = load_err(assembler);
+    assert!(
+        err.contains("Push at offset 0 needs 4 immediate bytes but only 2 remain"),
+        "{err}"
+    );
+
+    let mut assembler = Assembler::new();
+    assembler.emit_op(OpCode::LoadImm);
+    assembler.emit_u32(10);
+    assembler.emit_u8(b'x');
+    let err = load_err(assembler);
+    assert!(
+        err.contains("LoadImm at offset 0 needs 14 immediate bytes but only 5 remain"),
+        "{err}"
+    );
+
+    let mut assembler = Assembler::new();
+    assembler.emit_op(OpCode::Halt);
+    assembler.emit_u8(0x07);
+    let err = load_err(assembler);
+    assert!(err.contains("Unknown opcode 0x07 at offset 1"), "{err}");
+
+    // Input shorter than the v1 header is rejected rather than read past its end.
+    let mut short = Header::MAGIC.to_le_bytes().to_vec();
+    short.extend_from_slice(&[Header::VERSION_1, 15, 0, 0, 0, 0, 0]);
+    let err = Header::parse(&short).unwrap_err();
+    assert!(err.to_string().contains("Header too short"), "{err}");
+}
+
+#[test]
+fn test_verifier_bounds_stack_height_per_block() {
+    let mut assembler = Assembler::new();
+    emit_push(&mut assembler, 1);
+    assembler.emit_op(OpCode::Add);
+    let err = load_err(assembler);
+    assert!(
+        err.contains("Add at offset 5 pops 2 values but only 1 are guaranteed"),
+        "{err}"
+    );
+
+    // Paths that reach a join with different heights are rejected.
+    let mut assembler = Assembler::new();
+    emit_push(&mut assembler, 1);
+    emit_push(&mut assembler, 1);
+    assembler.emit_op(OpCode::JumpIf);
+    assembler.emit_u32(20);
+    emit_push(&mut assembler, 2);
+    assembler.emit_op(OpCode::Pop);
+    assembler.emit_op(OpCode::Halt);
+    let err = load_err(assembler);
+    assert!(
+        err.contains("Block at offset 20 is entered with 1 and with 2 values"),
+        "{err}"
+    );
+
+    // So is a loop that grows the stack on every pass.
+    let mut assembler = Assembler::new();
+    emit_push(&mut assembler, 1);
+    assembler.emit_op(OpCode::Jump);
+    assembler.emit_u32(0);
+    let err = load_err(assembler);
+    assert!(
+        err.contains("Block at offset 0 is entered with 0 and with 1 values"),
+        "{err}"
+    );
+
+    // Unbounded recursion is left to the runtime stack limit.
+    let mut assembler = Assembler::new();
+    emit_push(&mut assembler, 1);
+    assembler.emit_op(OpCode::Call);
+    assembler.emit_u32(0);
+    let err = run_program(assembler).err().expect("the stack overflows");
+    assert!(matches!(err, VMError::StackOverflow(_)), "{err}");
+    assert!(
+        err.to_string()
+            .starts_with("Stack overflow: Maximum stack size"),
+        "{err}"
+    );
+
+    // Both paths reach the join with one value, so only one pop is safe there.
+    let mut assembler = Assembler::new();
+    emit_push(&mut assembler, 1);
+    emit_push(&mut assembler, 1);
+    assembler.emit_op(OpCode::JumpIf);
+    assembler.emit_u32(21);
+    emit_push(&mut assembler, 2);
+    assembler.emit_op(OpCode::Pop);
+    assembler.emit_op(OpCode::Pop);
+    assembler.emit_op(OpCode::Pop);
+    assembler.emit_op(OpCode::Halt);
+    let bytecode = assembler.build().unwrap();
+    let header = Header::parse(&bytecode).unwrap();
+    let code = header.section_bytes(&bytecode, SectionKind::Code);
+    let err = verifier::verify(code).unwrap_err().to_string();
+    assert!(
+        err.contains("Pop at offset 22 pops 1 values but only 0 are guaranteed"),
+        "{err}"
+    );
+
+    let mut fixed = code.to_vec();
+    fixed[22] = OpCode::Halt as u8;
+    let verified = verifier::verify(&fixed).unwrap();
+    let heights: Vec<(usize, Option<usize>, usize, isize)> = verified
+        .blocks
+        .iter()
+        .map(|b| (b.start, b.entry_height, b.required, b.delta))
+        .collect();
+    assert_eq!(
+        heights,
+        vec![
+            (0, Some(0), 0, 1),
+            (15, Some(1), 0, 0),
+            (21, Some(1), 1, -1),
+            (23, None, 0, 0)
+        ]
+    );
+
+    // A callee is entered at the caller's height.
+    let mut assembler = Assembler::new();
+    assembler.emit_op(OpCode::Call);
+    assembler.emit_u32(6);
+    assembler.emit_op(OpCode::Halt);
+    assembler.emit_op(OpCode::Pop);
+    assembler.emit_op(OpCode::Return);
+    let err = load_err(assembler);
+    assert!(
+        err.contains("Pop at offset 6 pops 1 values but only 0 are guaranteed"),
+        "{err}"
+    );
+
+    // Execution resumes after a call shifted by the callee's net effect.
+    let call_then_add = |callee: &[OpCode]| {
+        let mut assembler = Assembler::new();
+        emit_push(&mut assembler, 2);
+        assembler.emit_op(OpCode::Call);
+        assembler.emit_u32(12);
+        assembler.emit_op(OpCode::Add);
+        assembler.emit_op(OpCode::Halt);
+        for opcode in callee {
+            assembler.emit_op(*opcode);
+        }
+        let bytecode = assembler.build().unwrap();
+        let header = Header::parse(&bytecode).unwrap();
+        verifier::verify(header.section_bytes(&bytecode, SectionKind::Code))
+    };
+    let verified = call_then_add(&[OpCode::Dup, OpCode::Return]).unwrap();
+    let heights: Vec<(usize, Option<usize>)> = verified
+        .blocks
+        .iter()
+        .map(|b| (b.start, b.entry_height))
+        .collect();
+    assert_eq!(heights, vec![(0, Some(0)), (10, Some(2)), (12, Some(1))]);
+    let err = call_then_add(&[OpCode::Pop, OpCode::Return])
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("Add at offset 10 pops 2 values but only 0 are guaranteed"),
+        "{err}"
+    );
+    // A callee that never returns leaves the code after the call unreached.
+    let verified = call_then_add(&[OpCode::Halt]).unwrap();
+    assert_eq!(verified.blocks[1].entry_height, None);
+}
+
+#[test]
+fn test_qasm_labels_directives_and_disassembly_round_trip() {
+    let source = r#"
+; counts down from 3, concatenating a pooled string each time
+.version 0.4.2
+.metadata "demo"
+.const "ab"
+        push 3
+loop:   dup
+        push 0
+        eq
+        jumpif done         ; exit once the counter hits zero
+        loadconst 0
+        pop
+        push -1
+        add
+        jump loop
+done:   loadimm "x;\"y\"\x00"
+        loadconst "ab"
+        bytesconcat
+        halt
+"#;
+    let bytecode = Assembler::from_qasm(source).unwrap().build().unwrap();
+    let header = Header::parse(&bytecode).unwrap();
+    assert_eq!(header.compiler_version, Some([0, 4, 2]));
+    assert_eq!(
+        header.section_bytes(&bytecode, SectionKind::Metadata),
+        b"demo"
+    );
+
+    let mut vm = QuantumVM::new();
+    vm.load_bytecode(&bytecode).unwrap();
+    vm.execute().unwrap();
+    assert_eq!(vm.stack.pop().unwrap().as_bytes().unwrap(), b"x;\"y\"\0ab");
+    assert_eq!(vm.stack.pop().unwrap().as_i64().unwrap(), 0);
+
+    let listing = disassembler::disassemble(&bytecode).unwrap();
+    assert!(listing.contains("jumpif L34"), "{listing}");
+    // Control bytes fall back to hex.
+    assert!(listing.contains("loadimm 0x783b22792200"), "{listing}");
+    let reassembled = Assembler::from_qasm(&listing).unwrap().build().unwrap();
+    assert_eq!(reassembled, bytecode);
+}
+
+#[test]
+fn test_qasm_rejects_malformed_lines() {
+    let err = |source: &str| Assembler::from_qasm(source).err().unwrap();
+    assert_eq!(err("a:\na:\n"), "line 2: label `a` is defined twice");
+    assert_eq!(err("  frob 1"), "line 1: unknown mnemonic `frob`");
+    assert_eq!(
+        err("push"),
+        "line 1: `push` takes 1 operands but 0 were given"
+    );
+    assert_eq!(err("mask 300"), "line 1: `300` is not a byte");
+    assert_eq!(err("jump nowhere"), "line 1: undefined label `nowhere`");
+    assert_eq!(
+        err(".const \"a\"\n.const \"a\""),
+        "line 2: constant \"a\" is already pooled"
+    );
+    assert_eq!(
+        err("loadimm 0xabc"),
+        "line 1: `0xabc` is not an even number of hex digits"
+    );
+}
+
+#[test]
+fn test_assembler_labels_resolve_forward_and_backward_jumps() {
+    let mut assembler = Assembler::new();
+    let top = assembler.new_label();
+    let done = assembler.new_label();
+    emit_push(&mut assembler, 2);
+    assembler.bind(top);
+    assembler.emit_op(OpCode::Dup);
+    assembler.emit_op(OpCode::Push);
+    assembler.emit_i32(0);
+    assembler.emit_op(OpCode::Eq);
+    assembler.emit_jump(OpCode::JumpIf, done);
+    emit_push(&mut assembler, (-1i32) as u32);
+    assembler.emit_op(OpCode::Add);
+    assembler.emit_jump(OpCode::Jump, top);
+    assembler.bind(done);
+    assembler.emit_op(OpCode::Halt);
+
+    let mut vm = run_program(assembler).unwrap();
+    assert_eq!(vm.stack.pop().unwrap().as_i64().unwrap(), 0);
+
+    let mut assembler = Assembler::new();
+    let twice = assembler.new_label();
+    assembler.bind(twice);
+    assembler.emit_op(OpCode::Halt);
+    assembler.bind(twice);
+    assert_eq!(
+        assembler.build().unwrap_err(),
+        "Label 0 is bound twice, at offsets 0 and 1"
+    );
+
+    let mut assembler = Assembler::new();
+    let _unused = assembler.new_label();
+    let dangling = assembler.new_label();
+    assembler.emit_op(OpCode::Halt);
+    assembler.emit_jump(OpCode::Jump, dangling);
+    assert_eq!(
+        assembler.build().unwrap_err(),
+        "Label 1 is referenced by the operand at offset 2 but never bound"
+    );
+}
+
+#[test]
+fn test_source_map_annotates_runtime_errors_and_round_trips() {
+    let source = r#"
+.file "Calc.synq"
+.loc 2, 5, "Calc.run"
+        push 10
+.loc 3, 9, "Calc.divide"
+        push 0
+        div
+        halt
+"#;
+    let bytecode = Assembler::from_qasm(source).unwrap().build().unwrap();
+    let header = Header::parse(&bytecode).unwrap();
+    let map = SourceMap::decode(header.section_bytes(&bytecode, SectionKind::SourceMap)).unwrap();
+    assert_eq!(map.file, "Calc.synq");
+    assert_eq!(map.functions, ["Calc.run", "Calc.divide"]);
+
+    let mut vm = QuantumVM::new();
+    vm.load_bytecode(&bytecode).unwrap();
+    let err = vm.execute().unwrap_err();
+    assert!(matches!(err, VMError::RuntimeError(_)), "{err:?}");
+    assert_eq!(
+        err.to_string(),
+        "Runtime error: Division by zero: attempted to divide 10 by 0 at PC 10 \
+         (Calc.synq:3:9 in Calc.divide)"
+    );
+    let location = vm.fault_location().unwrap();
+    assert_eq!((location.line, location.column), (3, 9));
+    assert_eq!(vm.source_location(0).unwrap().function, "Calc.run");
+
+    let listing = disassembler::disassemble(&bytecode).unwrap();
+    assert!(listing.contains(".loc 3, 9, \"Calc.divide\""), "{listing}");
+    let reassembled = Assembler::from_qasm(&listing).unwrap().build().unwrap();
+    assert_eq!(reassembled, bytecode);
+
+    // Without a source map the message is unchanged.
+    let mut vm = QuantumVM::new();
+    vm.load_bytecode(
+        &Assembler::from_qasm("push 1\npush 0\ndiv")
+            .unwrap()
+            .build()
+            .unwrap(),
+    )
+    .unwrap();
+    assert!(vm.execute().unwrap_err().to_string().ends_with("at PC 10"));
+    assert_eq!(vm.fault_location(), None);
+}
+
+#[test]
+fn test_source_map_rejects_malformed_rows() {
+    let mut map = SourceMap::default();
+    map.record(0, 1, 1, "f");
+    map.record(4, 2, 1, "f");
+    // Same offset replaces, repeated position is dropped.
+    map.record(4, 3, 1, "g");
+    map.record(9, 3, 1, "g");
+    assert_eq!(map.entries.len(), 2);
+    assert_eq!(SourceMap::decode(&map.encode()).unwrap(), map);
+
+    let mut bytes = map.encode();
+    bytes.pop();
+    assert!(SourceMap::decode(&bytes)
+        .unwrap_err()
+        .to_string()
+        .contains("not a whole number of 16-byte rows"));
+    let mut bytes = map.encode();
+    let last_function = bytes.len() - 4;
+    bytes[last_function] = 7;
+    assert_eq!(
+        SourceMap::decode(&bytes).unwrap_err().to_string(),
+        "Invalid bytecode: Source map: row at offset 4 names function 7 of 2"
+    );
+}
+
+#[test]
+fn test_deploy_runs_init_once_and_calls_persist_storage() {
+    // Init stores the constructor argument in slot 0; each call increments it, then divides
+    // by its first calldata word so that empty calldata fails after the store.
+    let source = r#"
+        push 0
+        calldataload
+        push 0
+        sstore
+        halt
+.runtime
+        push 0
+        sload
+        push 1
+        add
+        push 0
+        sstore
+        push 1
+        push 0
+        calldataload
+        div
+        halt
+.end
+"#;
+    let bytecode = Assembler::from_qasm(source).unwrap().build().unwrap();
+    let listing = disassembler::disassemble(&bytecode).unwrap();
+    assert!(listing.contains("\n.runtime\n"), "{listing}");
+    assert_eq!(
+        Assembler::from_qasm(&listing).unwrap().build().unwrap(),
+        bytecode
+    );
+
+    let word = |value: u8| {
+        let mut word = [0u8; 32];
+        word[31] = value;
+        word
+    };
+    let mut vm = QuantumVM::new();
+    let mut instance = vm.deploy(&bytecode, &word(41)).unwrap();
+    assert_eq!(instance.storage[&StorageKey::from(0)].as_i64().unwrap(), 41);
+
+    vm.call(&mut instance, &word(1)).unwrap();
+    assert_eq!(instance.storage[&StorageKey::from(0)].as_i64().unwrap(), 42);
+
+    // A failed call leaves the instance's storage as it was.
+    assert!(vm.call(&mut instance, &[]).is_err());
+    assert_eq!(instance.storage[&StorageKey::from(0)].as_i64().unwrap(), 42);
+
+    let decoded = ContractInstance::decode(&instance.encode()).unwrap();
+    assert_eq!(decoded.code, instance.code);
+    assert_eq!(decoded.storage[&StorageKey::from(0)].as_i64().unwrap(), 42);
+    assert!(ContractInstance::decode(&instance.encode()[..10]).is_err());
+
+    // Only deploy containers can be deployed.
+    let runtime = decoded.code;
+    let err = QuantumVM::new().deploy(&runtime, &[]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid bytecode: Container has no Runtime section to deploy"
+    );
+}
+
+#[test]
+fn test_calldata_slice_and_storage_slot_bounds() {
+    let run = |source: &str, calldata: &[u8]| {
+        let bytecode = Assembler::from_qasm(source).unwrap().build().unwrap();
+        let mut vm = QuantumVM::new();
+        vm.load_bytecode(&bytecode).unwrap();
+        vm.set_calldata(calldata);
+        vm.execute().map(|_| vm)
+    };
+
+    let vm = run("push 1\npush 3\ncalldataslice", b"abcdef").unwrap();
+    assert_eq!(vm.stack.last().unwrap().as_bytes().unwrap(), b"bcd");
+    // Reading a word past the end zero-pads it, and unwritten slots read as zero.
+    let mut calldata = [0u8; 34];
+    calldata[33] = 1;
+    let vm = run("push 4\ncalldataload\npush 9\nsload", &calldata).unwrap();
+    assert_eq!(vm.stack[0].as_i64().unwrap(), 1 << 16);
+    assert_eq!(vm.stack[1].as_i64().unwrap(), 0);
+
+    let Err(err) = run("push 4\npush 3\ncalldataslice", b"abcdef") else {
+        panic!("slice past the end should fail");
+    };
+    assert!(
+        err.to_string()
+            .contains("Calldata range 4+3 is outside the 6-byte calldata"),
+        "{err}"
+    );
+    let Err(err) = run("push 1\npush -1\nsstore", &[]) else {
+        panic!("negative slot should fail");
+    };
+    assert!(
+        err.to_string().contains("Storage slot -1 is out of range"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_derived_slots_are_256_bit_and_survive_memory_and_instance_files() {
+    let run = |source: &str| {
+        let bytecode = Assembler::from_qasm(source).unwrap().build().unwrap();
+        let mut vm = QuantumVM::new();
+        vm.load_bytecode(&bytecode).unwrap();
+        vm.execute().map(|_| vm)
+    };
+
+    // storage[SlotAdd(SlotHash(3, 7), 1)] = 5, with the slot stashed in memory on the way;
+    // storage[3] = 9.
+    let vm = run(
+        "push 5\npush 3\npush 7\nslothash\npush 1\nslotadd\npush 64\nstore\n\
+         push 64\nload\nsstore\npush 9\npush 3\nsstore",
+    )
+    .unwrap();
+    let mut key = [0u8; 32];
+    key[31] = 7;
+    let entry = derived_slot(StorageKey::from(3), &key).offset(1);
+    assert_eq!(entry.as_i64(), None, "derived slots are full 256-bit words");
+    assert_eq!(vm.storage().len(), 2);
+    assert_eq!(vm.storage()[&entry].as_i64().unwrap(), 5);
+    assert_eq!(vm.storage()[&StorageKey::from(3)].as_i64().unwrap(), 9);
+
+    let instance = ContractInstance {
+        code: Vec::new(),
+        storage: vm.storage().clone(),
+    };
+    let decoded = ContractInstance::decode(&instance.encode()).unwrap();
+    assert_eq!(decoded.storage[&entry].as_i64().unwrap(), 5);
+
+    let Err(err) = run("push 3\npush -1\nslotadd") else {
+        panic!("negative slot offset should fail");
+    };
+    assert!(
+        err.to_string()
+            .contains("Storage slot offset -1 is negative"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_world_state_deploys_contracts_at_derived_addresses_and_commits() {
+    // Same counter as above: init stores the argument, each call increments slot 0 and then
+    // fails on empty calldata.
+    let source = r#"
+        push 0
+        calldataload
+        push 0
+        sstore
+        halt
+.runtime
+        push 0
+        sload
+        push 1
+        add
+        push 0
+        sstore
+        push 1
+        push 0
+        calldataload
+        div
+        halt
+.end
+"#;
+    let bytecode = Assembler::from_qasm(source).unwrap().build().unwrap();
+    let word = |value: u8| {
+        let mut word = [0u8; 32];
+        word[31] = value;
+        word
+    };
+    let deployer: Address = [0xAA; 20];
+    let mut state = WorldState::new(MemoryBackend::default());
+    state.set_balance(&deployer, 1_000);
+    let mut vm = QuantumVM::new();
+
+    let first = vm
+        .deploy_contract(&mut state, &deployer, &bytecode, &word(10))
+        .unwrap();
+    let second = vm
+        .deploy_contract(&mut state, &deployer, &bytecode, &word(20))
+        .unwrap();
+    assert_eq!(first, contract_address(&deployer, 0));
+    assert_eq!(second, contract_address(&deployer, 1));
+    assert_ne!(first, second);
+    assert_eq!(state.nonce(&deployer), 2);
+    assert_eq!(state.nonce(&first), 1);
+    assert_eq!(state.storage(&first, 0).as_i64().unwrap(), 10);
+    assert_eq!(state.storage(&second, 0).as_i64().unwrap(), 20);
+
+    vm.call_contract(&mut state, &deployer, &second, &word(1), 0)
+        .unwrap();
+    assert_eq!(state.storage(&second, 0).as_i64().unwrap(), 21);
+    assert!(vm
+        .call_contract(&mut state, &deployer, &second, &[], 0)
+        .is_err());
+    assert_eq!(state.storage(&second, 0).as_i64().unwrap(), 21);
+    let err = vm
+        .call_contract(&mut state, &deployer, &deployer, &[], 0)
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("No contract at address 0xaaaa"),
+        "{err}"
+    );
+
+    // A failed deploy keeps the nonce increment but creates nothing.
+    let failing = Assembler::from_qasm("push 1\npush 0\ndiv\n.runtime\nhalt\n.end")
+        .unwrap()
+        .build()
+        .unwrap();
+    assert!(vm
+        .deploy_contract(&mut state, &deployer, &failing, &[])
+        .is_err());
+    assert_eq!(state.nonce(&deployer), 3);
+    assert!(!state.exists(&contract_address(&deployer, 2)));
+
+    // Nothing reaches the backend until commit.
+    assert!(state.backend().accounts.is_empty());
+    state.commit().unwrap();
+    assert_eq!(state.backend().accounts.len(), 3);
+    let mut reopened = WorldState::new(state.backend().clone());
+    assert_eq!(reopened.balance(&deployer), 1_000);
+    assert_eq!(reopened.storage(&second, 0).as_i64().unwrap(), 21);
+    assert!(reopened.account(&first).unwrap().is_contract());
+}
+
+#[test]
+fn test_world_state_checkpoints_revert_newest_changes_first() {
+    let alice: Address = [1; 20];
+    let bob: Address = [2; 20];
+    let mut backend = MemoryBackend::default();
+    backend.accounts.insert(
+        alice,
+        Account {
+            balance: 50,
+            ..Account::default()
+        },
+    );
+    let mut state = WorldState::new(backend);
+
+    let outer = state.checkpoint();
+    state.set_balance(&alice, 40);
+    state.set_storage(&alice, 7, Value::Bool(true));
+    let inner = state.checkpoint();
+    state.set_balance(&alice, 30);
+    state.set_balance(&bob, 10);
+    state.set_storage(&alice, 7, Value::Bool(false));
+
+    state.revert_to(inner);
+    assert_eq!(state.balance(&alice), 40);
+    assert!(state.storage(&alice, 7).as_bool().unwrap());
+    assert!(!state.exists(&bob), "bob was created after the checkpoint");
+
+    state.revert_to(outer);
+    assert_eq!(state.balance(&alice), 50);
+    assert!(state.account(&alice).unwrap().storage.is_empty());
+
+    state.commit().unwrap();
+    assert_eq!(state.backend().accounts[&alice].balance, 50);
+    assert!(!state.backend().accounts.contains_key(&bob));
+}
+
+#[test]
+fn test_revert_and_gas_scope_overrun_undo_storage_writes_and_logs() {
+    // The scope's store overruns its 20 gas, so only the write and log before it survive.
+    let source = r#"
+        push 5
+        push 0
+        sstore
+        push 7
+        push 1
+        log 1
+        gaslimitbegin 20, after
+        push 9
+        push 2
+        log 1
+        push 6
+        push 0
+        sstore
+        gaslimitend
+after:
+        push 0
+        sload
+        halt
+"#;
+    let bytecode = Assembler::from_qasm(source).unwrap().build().unwrap();
+    let mut vm = QuantumVM::new();
+    vm.load_bytecode(&bytecode).unwrap();
+    vm.execute().unwrap();
+    assert_eq!(vm.stack.last().unwrap().as_i64().unwrap(), 5);
+    assert_eq!(vm.storage()[&StorageKey::from(0)].as_i64().unwrap(), 5);
+    assert_eq!(vm.logs().len(), 1);
+    assert_eq!(vm.logs()[0].topic, 7);
+    assert_eq!(vm.logs()[0].data[0].as_i64().unwrap(), 1);
+
+    // A revert after the same writes leaves nothing behind.
+    let bytecode = Assembler::from_qasm(
+        "push 5\npush 0\nsstore\npush 7\npush 1\nlog 1\nloadimm \"not allowed\"\nrevert",
+    )
+    .unwrap()
+    .build()
+    .unwrap();
+    let mut vm = QuantumVM::new();
+    vm.load_bytecode(&bytecode).unwrap();
+    let err = vm.execute().unwrap_err();
+    assert!(matches!(err, VMError::Revert(_)), "{err:?}");
+    assert_eq!(err.to_string(), "Reverted: not allowed at PC 39");
+    assert!(vm.storage().is_empty());
+    assert!(vm.logs().is_empty());
+
+    // Through the world state, logs of successful calls are kept until commit.
+    let bytecode = Assembler::from_qasm(
+        "halt\n.runtime\npush 3\npush 0\ncalldataload\nlog 1\npush 1\npush 0\ncalldataload\ndiv\nhalt\n.end",
+    )
+    .unwrap()
+    .build()
+    .unwrap();
+    let mut state = WorldState::new(MemoryBackend::default());
+    let deployer: Address = [0xAA; 20];
+    let contract = vm
+        .deploy_contract(&mut state, &deployer, &bytecode, &[])
+        .unwrap();
+    let mut word = [0u8; 32];
+    word[31] = 4;
+    vm.call_contract(&mut state, &deployer, &contract, &word, 0)
+        .unwrap();
+    assert!(vm
+        .call_contract(&mut state, &deployer, &contract, &[], 0)
+        .is_err());
+    assert!(vm.logs().is_empty());
+    let logs = state.commit().unwrap();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].address, contract);
+    assert_eq!(logs[0].data[0].as_i64().unwrap(), 4);
+}
+
+#[test]
+fn test_execution_receipts_report_outcome_output_and_gas_by_opcode_class() {
+    let bytecode = Assembler::from_qasm(
+        "push 5\npush 0\nsstore\npush 7\nprint\npush 7\npush 1\nlog 1\npush 42\nhalt",
+    )
+    .unwrap()
+    .build()
+    .unwrap();
+    let mut vm = QuantumVM::new();
+    vm.load_bytecode(&bytecode).unwrap();
+    let receipt = vm.execute_receipt();
+    assert_eq!(receipt.status, ExecutionStatus::Success);
+    assert!(receipt.error.is_none() && receipt.revert_data.is_none());
+    assert_eq!(receipt.return_data.len(), 1);
+    assert_eq!(receipt.return_data[0].as_i64().unwrap(), 42);
+    assert_eq!(receipt.output.len(), 1);
+    assert_eq!(receipt.output[0].as_i64().unwrap(), 7);
+    assert_eq!(receipt.logs.len(), 1);
+    assert_eq!(receipt.gas_used, vm.consumed_gas());
+    assert_eq!(
+        receipt.gas_breakdown.values().sum::<u64>(),
+        receipt.gas_used
+    );
+    assert!(receipt.gas_breakdown[&GasClass::Storage] > receipt.gas_breakdown[&GasClass::Stack]);
+    assert!(receipt.gas_breakdown[&GasClass::Log] > 0);
+    assert!(receipt.gas_breakdown[&GasClass::Utility] > 0);
+    assert!(!receipt.gas_breakdown.contains_key(&GasClass::Pqc));
+
+    let json = receipt.to_json();
+    assert!(json.contains("\"status\": \"success\""), "{json}");
+    assert!(json.contains("\"return_data\": [\n    42\n  ]"), "{json}");
+    assert!(json.contains("\"topic\": \"0x00000007\""), "{json}");
+    assert!(json.contains("\"storage\": "), "{json}");
+
+    // Each run is reported on its own, even when the VM's gas meter carries over.
+    vm.load_bytecode(&bytecode).unwrap();
+    let again = vm.execute_receipt();
+    assert_eq!(again.gas_used, receipt.gas_used);
+    assert_eq!(vm.consumed_gas(), 2 * receipt.gas_used);
+
+    let bytecode = Assembler::from_qasm("push 7\npush 1\nlog 1\nloadimm \"not allowed\"\nrevert")
+        .unwrap()
+        .build()
+        .unwrap();
+    let mut vm = QuantumVM::new();
+    vm.load_bytecode(&bytecode).unwrap();
+    let receipt = vm.execute_receipt();
+    assert_eq!(receipt.status, ExecutionStatus::Reverted);
+    assert_eq!(receipt.revert_data.as_deref(), Some(&b"not allowed"[..]));
+    assert!(receipt.return_data.is_empty() && receipt.logs.is_empty());
+    assert!(receipt.error.unwrap().starts_with("Reverted: not allowed"));
+    assert!(receipt.gas_used > 0);
+
+    let mut vm = QuantumVM::with_gas(3, 0);
+    vm.load_bytecode(
+        &Assembler::from_qasm("push 1\npush 2\nadd\npush 3\nmul")
+            .unwrap()
+            .build()
+            .unwrap(),
+    )
+    .unwrap();
+    let receipt = vm.execute_receipt();
+    assert_eq!(receipt.status, ExecutionStatus::OutOfGas);
+    assert_eq!(receipt.gas_used, 3);
+    assert!(receipt.to_json().contains("\"status\": \"out_of_gas\""));
+
+    // Receipts of world-state calls cover just that call.
+    let bytecode = Assembler::from_qasm("halt\n.runtime\npush 9\nhalt\n.end")
+        .unwrap()
+        .build()
+        .unwrap();
+    let mut state = WorldState::new(MemoryBackend::default());
+    let deployer: Address = [0xAA; 20];
+    let mut vm = QuantumVM::new();
+    let deployed = vm.deploy_contract(&mut state, &deployer, &bytecode, &[]);
+    assert!(vm.receipt(&deployed).is_success());
+    let result = vm.call_contract(&mut state, &deployer, &deployed.unwrap(), &[], 0);
+    let receipt = vm.receipt(&result);
+    assert_eq!(receipt.gas_used, 3);
+    assert_eq!(receipt.return_data[0].as_i64().unwrap(), 9);
+    let missing = vm.call_contract(&mut state, &deployer, &[0xBB; 20], &[], 0);
+    let receipt = vm.receipt(&missing);
+    assert_eq!(receipt.status, ExecutionStatus::Failed);
+    assert_eq!(receipt.gas_used, 0);
+}
+
+#[test]
+fn test_gas_schedule_from_genesis_drives_opcode_costs() {
+    let v1 = GasSchedule::default();
+    assert_eq!(v1.version, 1);
+    assert_eq!(v1.cost(OpCode::SStore).base, 100);
+    assert_eq!(v1.cost(OpCode::MLDSAVerify).total(10), 6_000 + 90 + 20_000);
+    assert_eq!(v1.cost(OpCode::BytesConcat).total(33), 3 + 2);
+    assert_eq!(v1.storage_cost(1024), 50_000);
+
+    // The example of docs/Gas-Model.md §7, with an opcode override and unrelated fields.
+    let genesis = r#"{
+        "chain_id": 7,
+        "pqc_costs": {
+            "verify_mldsa_65": 35000,
+            "verify_fndsa_512": 20000,
+            "mlkem_encapsulate_768": 25000
+        },
+        "opcode_costs": { "sstore": { "base": 1000 } },
+        "storage_cost_per_kb": 60000,
+        "max_pqc_gas_per_tx": 100000,
+        "max_pqc_gas_per_block": 2000000
+    }"#;
+    let schedule = GasSchedule::from_genesis_json(genesis).unwrap();
+    assert_eq!(schedule.version, 1);
+    assert_eq!(schedule.cost(OpCode::MLDSAVerify).total(5_000), 35_000);
+    assert_eq!(schedule.cost(OpCode::MLKEMKeyExchange).total(0), 25_000);
+    assert_eq!(
+        schedule.cost(OpCode::HQCKEM128KeyExchange),
+        v1.cost(OpCode::HQCKEM128KeyExchange)
+    );
+    assert_eq!(schedule.cost(OpCode::SStore).base, 1_000);
+    assert_eq!(schedule.storage_cost_per_kb, 60_000);
+    assert_eq!(schedule.max_pqc_gas_per_tx, 100_000);
+
+    // The VM charges by the schedule it was built with.
+    let source =
+        "loadimm \"s\"\nloadimm \"m\"\nloadimm \"k\"\nmldsaverify\npush 5\npush 0\nsstore\nhalt";
+    let bytecode = Assembler::from_qasm(source).unwrap().build().unwrap();
+    let run = |mut vm: QuantumVM| {
+        vm.load_bytecode(&bytecode).unwrap();
+        vm.execute_receipt()
+    };
+    let default = run(QuantumVM::new());
+    let custom = run(QuantumVM::with_schedule(10_000_000, schedule.clone()));
+    assert_eq!(default.pqc_gas_used, 6_000 + 3 * 9 + 20_000);
+    assert_eq!(custom.pqc_gas_used, 35_000);
+    assert_eq!(
+        custom.gas_breakdown[&GasClass::Storage],
+        default.gas_breakdown[&GasClass::Storage] + 900 + schedule.storage_cost(32)
+            - v1.storage_cost(32)
+    );
+    let mut tight = schedule.clone();
+    tight.max_pqc_gas_per_tx = 30_000;
+    assert_eq!(
+        run(QuantumVM::with_schedule(10_000_000, tight)).status,
+        ExecutionStatus::OutOfGas
+    );
+
+    // Upgrades apply from their activation block, on top of the schedule before them.
+    let history = GasScheduleHistory::from_genesis_json(
+        r#"{
+            "pqc_costs": { "verify_fndsa_512": 20000 },
+            "gas_schedule_upgrades": [
+                { "activation_block": 100, "opcode_costs": { "add": 2 } },
+                { "activation_block": 250, "gas_schedule_version": 7, "storage_cost_per_kb": 1 }
+            ]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(history.at(0).version, 1);
+    assert_eq!(history.at(99).cost(OpCode::Add).base, 0);
+    assert_eq!(history.at(100).version, 2);
+    assert_eq!(history.at(100).cost(OpCode::Add).base, 2);
+    assert_eq!(history.at(1_000).version, 7);
+    assert_eq!(history.at(1_000).cost(OpCode::Add).base, 2);
+    assert_eq!(history.at(1_000).cost(OpCode::FNDSAVerify).total(0), 20_000);
+    assert_eq!(history.at(1_000).storage_cost_per_kb, 1);
+    let mut history = GasScheduleHistory::new(v1.clone());
+    assert!(history.upgrade(0, v1.clone()).is_err());
+    assert!(history.upgrade(10, v1).is_err());
+
+    let error = |json: &str| {
+        GasSchedule::from_genesis_json(json)
+            .unwrap_err()
+            .to_string()
+    };
+    assert!(error(r#"{"pqc_costs": {"verify_rsa": 1}}"#).contains("Unknown PQC cost `verify_rsa`"));
+    assert!(error(r#"{"opcode_costs": {"frobnicate": 1}}"#).contains("Unknown opcode `frobnicate`"));
+    assert!(
+        error(r#"{"pqc_costs": {"mlkem_encapsulate_768": 1, "mlkem_decapsulate_768": 2}}"#)
+            .contains("both set the cost of mlkemkeyexchange")
+    );
+    assert!(error(r#"{"max_pqc_gas_per_tx": 3000000}"#).contains("exceeds max_pqc_gas_per_block"));
+    assert!(
+        error(r#"{"storage_cost_per_kb": "cheap"}"#).starts_with("Invalid gas schedule: Malformed")
+    );
+}
+
+#[test]
+fn test_block_executor_enforces_block_gas_and_pq_gas_limits() {
+    // Each call verifies a signature (26,027 PQ gas) and increments slot 0.
+    let bytecode = Assembler::from_qasm(
+        "halt\n.runtime\nloadimm \"s\"\nloadimm \"m\"\nloadimm \"k\"\nmldsaverify\npush 0\nsload\npush 1\nadd\npush 0\nsstore\nhalt\n.end",
+    )
+    .unwrap()
+    .build()
+    .unwrap();
+    let reverting = Assembler::from_qasm("halt\n.runtime\nloadimm \"no\"\nrevert\n.end")
+        .unwrap()
+        .build()
+        .unwrap();
+    let alice: Address = [0xA1; 20];
+    let counter = contract_address(&alice, 0);
+    let refuser = contract_address(&alice, 1);
+    let call = |contract, gas_limit| Transaction::call(alice, contract, Vec::new(), 0, gas_limit);
+
+    let mut schedule = GasSchedule::default();
+    schedule.max_pqc_gas_per_block = 60_000;
+    schedule.max_pqc_gas_per_tx = 30_000;
+    let executor = BlockExecutor::with_gas_limit(schedule, 1_000_000);
+    let mut state = WorldState::new(MemoryBackend::default());
+    let block = executor.execute(
+        &mut state,
+        &[
+            Transaction::deploy(alice, bytecode, Vec::new(), 100_000),
+            Transaction::deploy(alice, reverting, Vec::new(), 100_000),
+            call(counter, 100_000),
+            call(counter, 100_000),
+            // A third verification would take the block past 60,000 PQ gas, so it runs with
+            // only the 7,946 PQ gas the block has left.
+            call(counter, 100_000),
+            call(refuser, 100_000),
+            // More gas than the block has left.
+            call(counter, 990_000),
+        ],
+    );
+
+    let receipts = &block.transactions;
+    assert_eq!(receipts.len(), 7);
+    let TransactionReceipt::Included {
+        contract_address, ..
+    } = &receipts[0]
+    else {
+        panic!("deploy rejected: {:?}", receipts[0]);
+    };
+    assert_eq!(*contract_address, Some(counter));
+    for receipt in &receipts[2..4] {
+        let execution = receipt.execution().unwrap();
+        assert!(execution.is_success(), "{execution:?}");
+        assert_eq!(execution.pqc_gas_used, 6_000 + 3 * 9 + 20_000);
+    }
+    let capped = receipts[4].execution().unwrap();
+    assert_eq!(capped.status, ExecutionStatus::OutOfGas);
+    assert_eq!(capped.pqc_gas_used, 0);
+    assert!(capped.gas_used > 0);
+    let reverted = receipts[5].execution().unwrap();
+    assert_eq!(reverted.status, ExecutionStatus::Reverted);
+    assert!(reverted.gas_used > 0);
+    let TransactionReceipt::Rejected { reason } = &receipts[6] else {
+        panic!("oversized transaction included: {:?}", receipts[6]);
+    };
+    assert!(
+        reason.starts_with("Gas limit 990000 exceeds the "),
+        "{reason}"
+    );
+
+    // Totals add up the included transactions; the rejected ones left no trace.
+    let included: Vec<&ExecutionReceipt> = receipts
+        .iter()
+        .filter_map(TransactionReceipt::execution)
+        .collect();
+    assert_eq!(included.len(), 6);
+    assert_eq!(
+        block.gas_used,
+        included.iter().map(|r| r.gas_used).sum::<u64>()
+    );
+    assert_eq!(block.pqc_gas_used, 2 * 26_027);
+    let TransactionReceipt::Included {
+        cumulative_gas_used,
+        cumulative_pqc_gas_used,
+        ..
+    } = &receipts[5]
+    else {
+        unreachable!()
+    };
+    assert_eq!(*cumulative_gas_used, block.gas_used);
+    assert_eq!(*cumulative_pqc_gas_used, block.pqc_gas_used);
+    assert_eq!(state.storage(&counter, 0).as_i64().unwrap(), 2);
+    assert_eq!(state.nonce(&alice), 2);
+
+    let json = serde_json::to_string(&block).unwrap();
+    assert!(json.contains(r#""result":"rejected""#), "{json}");
+    assert!(
+        json.contains(r#""cumulative_pqc_gas_used":52054"#),
+        "{json}"
+    );
+}
+
+#[test]
+fn test_value_transfers_move_balances_and_undo_with_their_frame() {
+    // Sends the calldata amount from the contract back to its caller.
+    let refund =
+        Assembler::from_qasm("halt\n.runtime\ncaller\npush 0\ncalldataload\ntransfer\nhalt\n.end")
+            .unwrap()
+            .build()
+            .unwrap();
+    let alice: Address = [0xA1; 20];
+    let mut state = WorldState::new(MemoryBackend::default());
+    state.set_balance(&alice, 1_000);
+    let mut vm = QuantumVM::new();
+    let contract = vm
+        .deploy_contract(&mut state, &alice, &refund, &[])
+        .unwrap();
+    let amount = |value: u8| {
+        let mut word = [0u8; 32];
+        word[31] = value;
+        word
+    };
+
+    vm.call_contract(&mut state, &alice, &contract, &amount(100), 300)
+        .unwrap();
+    assert_eq!(state.balance(&alice), 800);
+    assert_eq!(state.balance(&contract), 200);
+    assert!(
+        vm.consumed_gas() > GasSchedule::default().transfer_cost(1),
+        "{}",
+        vm.consumed_gas()
+    );
+
+    // Failing calls move nothing, including the value they were sent with.
+    let err = vm
+        .call_contract(&mut state, &alice, &contract, &amount(250), 0)
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("has a balance of 200, too little to send 250"),
+        "{err}"
+    );
+    let err = vm
+        .call_contract(&mut state, &alice, &contract, &amount(1), 5_000)
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("has a balance of 800, too little to send 5000"),
+        "{err}"
+    );
+    assert!(vm
+        .call_contract(&mut state, &alice, &contract, &amount(255), 50)
+        .is_err());
+    assert_eq!(state.balance(&alice), 800);
+    assert_eq!(state.balance(&contract), 200);
+
+    // The transfer inside the scope is undone when the store after it overruns the budget.
+    let source = r#"
+        halt
+.runtime
+        gaslimitbegin 6800, after
+        caller
+        push 50
+        transfer
+        push 1
+        push 9
+        sstore
+        gaslimitend
+after:
+        selfbalance
+        push 0
+        sstore
+        caller
+        balance
+        push 1
+        sstore
+        callvalue
+        push 2
+        sstore
+        address
+        push 3
+        sstore
+        halt
+.end
+"#;
+    let bytecode = Assembler::from_qasm(source).unwrap().build().unwrap();
+    let probe = vm
+        .deploy_contract(&mut state, &alice, &bytecode, &[])
+        .unwrap();
+    vm.call_contract(&mut state, &alice, &probe, &[], 80)
+        .unwrap();
+    assert_eq!(state.storage(&probe, 0).as_i64().unwrap(), 80);
+    assert_eq!(state.storage(&probe, 1).as_i64().unwrap(), 720);
+    assert_eq!(state.storage(&probe, 2).as_i64().unwrap(), 80);
+    assert_eq!(state.storage(&probe, 3).as_bytes().unwrap(), &probe[..]);
+    assert_eq!(state.balance(&probe), 80);
+
+    state.commit().unwrap();
+    assert_eq!(state.backend().accounts[&alice].balance, 720);
+}
+
+#[test]
+fn test_abi_encodes_heads_then_tails_and_round_trips() {
+    use quantumvm::abi::{decode, decode_call, encode, encode_call, selector};
+    use quantumvm::{AbiType, AbiValue};
+
+    let types = [AbiType::Int(64), AbiType::Bytes, AbiType::Bool];
+    let values = [
+        AbiValue::int(-2),
+        AbiValue::Bytes(b"hi".to_vec()),
+        AbiValue::Bool(true),
+    ];
+    let data = encode(&types, &values).unwrap();
+    let word = |data: &[u8], n: usize| data[n * 32..(n + 1) * 32].to_vec();
+    assert_eq!(data.len(), 5 * 32);
+    assert_eq!(
+        word(&data, 0),
+        [0xff; 32]
+            .iter()
+            .take(31)
+            .chain(&[0xfe])
+            .copied()
+            .collect::<Vec<_>>()
+    );
+    // The byte string's head is the offset of its tail from the start of the heads.
+    assert_eq!(word(&data, 1)[31], 96);
+    assert_eq!(word(&data, 2)[31], 1);
+    assert_eq!(word(&data, 3)[31], 2);
+    assert_eq!(&word(&data, 4)[..3], b"hi\0");
+    assert_eq!(decode(&types, &data).unwrap(), values);
+
+    let nested = [
+        AbiType::parse("UInt256[2]").unwrap(),
+        AbiType::Array(
+            Box::new(AbiType::Tuple(vec![AbiType::Address, AbiType::String])),
+            None,
+        ),
+        AbiType::parse("MLDSAPublicKey").unwrap(),
+        AbiType::parse("Bytes4").unwrap(),
+    ];
+    let values = [
+        AbiValue::Array(vec![AbiValue::uint(u128::MAX), AbiValue::uint(1)]),
+        AbiValue::Array(vec![
+            AbiValue::Tuple(vec![
+                AbiValue::Address([1; 20]),
+                AbiValue::String("a".into()),
+            ]),
+            AbiValue::Tuple(vec![
+                AbiValue::Address([2; 20]),
+                AbiValue::String(String::new()),
+            ]),
+        ]),
+        AbiValue::Bytes(vec![7; 1952]),
+        AbiValue::FixedBytes(vec![0xde, 0xad, 0xbe, 0xef]),
+    ];
+    let sel = selector("f(UInt256[2],(Address,String)[],MLDSAPublicKey,Bytes4)");
+    let calldata = encode_call(sel, &nested, &values).unwrap();
+    assert_eq!(calldata[..4], sel.to_be_bytes());
+    assert_eq!(
+        decode_call(&nested, &calldata).unwrap(),
+        (sel, values.to_vec())
+    );
+    assert_eq!(nested[1].to_string(), "(Address,String)[]");
+
+    // A VM value is shown as its ABI type; a negative one read as unsigned keeps its low bits.
+    let show = |ty: &str, value: Value| {
+        AbiValue::from_value(&AbiType::parse(ty).unwrap(), &value)
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(show("Int64", Value::I32(-1)), "-1");
+    assert_eq!(show("UInt8", Value::I32(-1)), "255");
+    assert_eq!(
+        show("UInt64", Value::I64(-2)),
+        u64::MAX.wrapping_sub(1).to_string()
+    );
+    assert_eq!(
+        show("UInt256", Value::I32(-1)),
+        "115792089237316195423570985008687907853269984665640564039457584007913129639935"
+    );
+}
+
+#[test]
+fn test_abi_rejects_malformed_values_and_data() {
+    use quantumvm::abi::{decode, encode};
+    use quantumvm::{AbiType, AbiValue};
+
+    fn error<T: std::fmt::Debug>(result: Result<T, VMError>) -> String {
+        match result {
+            Err(VMError::AbiError(msg)) => msg,
+            other => panic!("expected an ABI error, got {:?}", other),
+        }
+    }
+
+    // PQC keys and signatures must have their algorithm's length.
+    let key = AbiType::parse("MLDSAPublicKey").unwrap();
+    let msg = error(encode(
+        std::slice::from_ref(&key),
+        &[AbiValue::Bytes(vec![0; 32])],
+    ));
+    assert!(
+        msg.contains("MLDSAPublicKey must be 1952 bytes but got 32"),
+        "{msg}"
+    );
+    let short_key = encode(&[AbiType::Bytes], &[AbiValue::Bytes(vec![0; 32])]).unwrap();
+    let msg = error(decode(&[key], &short_key));
+    assert!(msg.contains("(value 0)"), "{msg}");
+
+    // Integers must fit their width, on the way in and on the way out.
+    let msg = error(encode(&[AbiType::Int(8)], &[AbiValue::int(128)]));
+    assert!(msg.contains("does not fit in Int8"), "{msg}");
+    let wide = encode(&[AbiType::Int(64)], &[AbiValue::int(-129)]).unwrap();
+    assert!(decode(&[AbiType::Int(16)], &wide).is_ok());
+    let msg = error(decode(&[AbiType::Int(8)], &wide));
+    assert!(msg.contains("is not a valid Int8"), "{msg}");
+
+    let mut word = [0u8; 32];
+    word[31] = 2;
+    let msg = error(decode(&[AbiType::Bool], &word));
+    assert!(msg.contains("is not a valid Bool"), "{msg}");
+
+    let data = encode(&[AbiType::String], &[AbiValue::String("hello".into())]).unwrap();
+    let msg = error(decode(&[AbiType::String], &data[..66]));
+    assert!(msg.contains("run past the end"), "{msg}");
+    let one = encode(&[AbiType::Bool], &[AbiValue::Bool(true)]).unwrap();
+    let msg = error(decode(&[AbiType::Bool, AbiType::Bool], &one));
+    assert!(
+        msg.contains("past the end of the 32-byte data (value 1)"),
+        "{msg}"
+    );
+
+    let msg = error(encode(&[AbiType::Address], &[AbiValue::Bool(true)]));
+    assert!(msg.contains("true is not a valid Address"), "{msg}");
+    assert!(AbiType::parse("UInt512").is_err());
+}